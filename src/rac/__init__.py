@@ -15,6 +15,13 @@ __version__ = "0.2.0"
 
 from datetime import date
 
+from .arrow import (
+    ReadStats,
+    execute_arrow,
+    execute_from_arrow_file,
+    execute_from_parquet_file,
+    execute_polars,
+)
 from .ast import (
     AmendDecl,
     BinOp,
@@ -25,27 +32,143 @@ from .ast import (
     FieldAccess,
     ImportDecl,
     Literal,
+    Lookup,
     Match,
     Module,
     RepealDecl,
+    Scale,
     TemporalValue,
     UnaryOp,
     Var,
     VariableDecl,
 )
+from .autodiff import Dual, derivatives, eval_dual
+from .bytecode import BytecodeError, Program, compile_expr
+from .cache import RowCache, blake2_hasher
+from .categories import Categorical
 from .codegen import generate_javascript, generate_python, generate_rust
-from .compiler import IR, CompileError, Compiler, ResolvedVar
-from .executor import Context, ExecutionError, Executor, Result, run
-from .model import CompareResult, Model, RunResult
+from .compiled import MODEL_FORMAT_VERSION, CompiledModel, ReformComparison
+from .compiler import (
+    IR,
+    IR_VERSION,
+    CompileError,
+    Compiler,
+    Composition,
+    Conflict,
+    CycleError,
+    IRSchemaError,
+    ResolvedVar,
+    compose,
+    critical_path,
+    dependency_closure,
+    dependents,
+    dump_ir,
+    expression_deps,
+    ir_json_schema,
+    load_ir,
+    load_ir_json,
+    required_inputs,
+    topological_order,
+)
+from .config import (
+    ConfigError,
+    EngineConfig,
+    get_default_config,
+    resolve_config,
+    set_default_config,
+)
+from .executor import (
+    Context,
+    EvalError,
+    ExecutionError,
+    Executor,
+    Result,
+    WorkBudgetExceeded,
+    eval_variable,
+    execute_columns,
+    execute_numpy,
+    resolve_scalars,
+    run,
+)
+from .fixed import Fixed, FixedOverflow, to_fixed
+from .formatting import RunStats, format_currency, format_currency_column, run_summary
+from .functions import FunctionError, FunctionStats, register_function, unregister_function
+from .ingest import NumberFormat, ParseReport, format_number, parse_number, read_csv
+from .model import CompareResult, Model, MonteCarloResult, RunResult
 from .native import CompiledBinary, compile_to_binary
+from .parameters import (
+    ParameterError,
+    ParameterRow,
+    ParameterTree,
+    UpratingRule,
+    export_parameters,
+    import_parameter_overrides,
+    load_parameter_tree,
+    parameter_table,
+)
 from .parser import Lexer, ParseError, Parser, parse, parse_file
-from .schema import Data, Entity, Field, ForeignKey, ReverseRelation, Schema
+from .periods import PeriodError, period_start, run_periods
+from .plan import BYTES_PER_VALUE, DryRunReport, Plan, PlanError, PlanStep, build_plan
+from .preflight import PreflightCheck, PreflightReport, preflight
+from .profiling import NodeStats, Profiler
+from .quantiles import (
+    QuantileSketch,
+    quantile_groups,
+    sketch_column,
+    weighted_quantile,
+    weighted_quantiles,
+)
+from .rates import RateError, Violation, check_monotonic, eatr, eatr_column, marginal_rates
+from .rng import RandomManifest, replication_seeds, verify_reproducibility
+from .robustness import PerturbationError, Sensitivity, perturb
+from .sampling import SamplingError, sample_entities, sample_rows
+from .scenarios import (
+    Axis,
+    Decomposition,
+    Outcome,
+    ScenarioComparison,
+    ScenarioError,
+    WinnersLosers,
+    apply_overrides,
+    classify_changes,
+    compare_scenarios,
+    cross_join,
+    decompose,
+    expand_axes,
+    sweep,
+    winners_losers,
+)
+from .schema import Data, Entity, Field, ForeignKey, LinkageError, ReverseRelation, Schema
+from .stats import (
+    Allocation,
+    ColumnStats,
+    StatsError,
+    WeightPolicy,
+    capped_allocate,
+    compensated_sum,
+    crosstab,
+    gini,
+    group_argmax,
+    group_argmin,
+    group_keys,
+    group_sum,
+)
 from .test_runner import TestCase, TestResult, TestResults, load_tests, run_tests
+from .trace import TraceError, TraceNode, trace
+from .typecheck import ValueType, column_problems, variable_types
+
 
+def compile(  # noqa: A001
+    modules: list[Module],
+    as_of: date,
+    features: set[str] | None = None,
+    fixed_point: set[str] | None = None,
+) -> IR:
+    """Compile modules for a specific date, with the given features enabled.
 
-def compile(modules: list[Module], as_of: date) -> IR:  # noqa: A001
-    """Compile modules for a specific date."""
-    return Compiler(modules).compile(as_of)
+    Cycles among ``fixed_point`` variables are allowed and solved by iteration.
+    """
+    return Compiler(modules, features, fixed_point).compile(as_of)
 
 
 def execute(ir: IR, data: dict[str, list[dict]] | Data) -> Result:
@@ -77,6 +200,8 @@ __all__ = [
     "FieldAccess",
     "Match",
     "Cond",
+    "Scale",
+    "Lookup",
     # Schema
     "Schema",
     "Entity",
@@ -84,19 +209,91 @@ __all__ = [
     "ForeignKey",
     "ReverseRelation",
     "Data",
+    "LinkageError",
     # Compile
     "compile",
     "Compiler",
     "CompileError",
+    "CycleError",
     "IR",
     "ResolvedVar",
+    "IR_VERSION",
+    "dump_ir",
+    "load_ir",
+    "load_ir_json",
+    "ir_json_schema",
+    "IRSchemaError",
+    "compose",
+    "Composition",
+    "Conflict",
+    "dependency_closure",
+    "dependents",
+    "critical_path",
+    "required_inputs",
+    "topological_order",
+    "expression_deps",
+    # Config
+    "EngineConfig",
+    "ConfigError",
+    "set_default_config",
+    "get_default_config",
+    "resolve_config",
     # Execute
     "execute",
     "run",
+    "execute_columns",
+    "execute_numpy",
+    "CompiledModel",
+    "MODEL_FORMAT_VERSION",
+    "Categorical",
+    "ReformComparison",
+    "eval_variable",
+    "resolve_scalars",
     "Executor",
     "Context",
     "Result",
     "ExecutionError",
+    "EvalError",
+    "WorkBudgetExceeded",
+    "compile_expr",
+    "Program",
+    "BytecodeError",
+    "Fixed",
+    "FixedOverflow",
+    "to_fixed",
+    "run_periods",
+    "period_start",
+    "PeriodError",
+    # Plan
+    "build_plan",
+    "BYTES_PER_VALUE",
+    "Plan",
+    "PlanStep",
+    "PlanError",
+    "DryRunReport",
+    # Preflight
+    "preflight",
+    "PreflightReport",
+    "PreflightCheck",
+    # Ingest
+    "read_csv",
+    "parse_number",
+    "format_number",
+    "NumberFormat",
+    "ParseReport",
+    # Autodiff
+    "Dual",
+    "eval_dual",
+    "derivatives",
+    # Arrow
+    "execute_arrow",
+    "execute_from_arrow_file",
+    "execute_from_parquet_file",
+    "execute_polars",
+    # Caching
+    "RowCache",
+    "blake2_hasher",
+    "ReadStats",
     # Codegen
     "generate_javascript",
     "generate_python",
@@ -106,12 +303,96 @@ __all__ = [
     "CompiledBinary",
     # High-level
     "Model",
+    "MonteCarloResult",
     "RunResult",
     "CompareResult",
+    # Formatting
+    "format_currency",
+    "format_currency_column",
+    "RunStats",
+    "run_summary",
+    # Registered functions
+    "register_function",
+    "unregister_function",
+    "FunctionError",
+    "FunctionStats",
+    # Parameter tables
+    "ParameterTree",
+    "UpratingRule",
+    "load_parameter_tree",
+    "parameter_table",
+    "export_parameters",
+    "import_parameter_overrides",
+    "ParameterRow",
+    "ParameterError",
+    # Profiling
+    "Profiler",
+    "NodeStats",
+    # Rates
+    "eatr",
+    "eatr_column",
+    "marginal_rates",
+    "check_monotonic",
+    "Violation",
+    "RateError",
+    # Robustness
+    "perturb",
+    "Sensitivity",
+    "PerturbationError",
+    # Quantiles
+    "weighted_quantile",
+    "weighted_quantiles",
+    "quantile_groups",
+    "QuantileSketch",
+    "sketch_column",
+    # Randomness
+    "RandomManifest",
+    "replication_seeds",
+    "verify_reproducibility",
+    # Sampling
+    "sample_rows",
+    "sample_entities",
+    "SamplingError",
+    # Scenarios
+    "apply_overrides",
+    "decompose",
+    "sweep",
+    "cross_join",
+    "Axis",
+    "expand_axes",
+    "winners_losers",
+    "classify_changes",
+    "WinnersLosers",
+    "Outcome",
+    "compare_scenarios",
+    "ScenarioComparison",
+    "Decomposition",
+    "ScenarioError",
+    # Stats
+    "gini",
+    "crosstab",
+    "capped_allocate",
+    "Allocation",
+    "group_argmax",
+    "group_argmin",
+    "group_sum",
+    "group_keys",
+    "compensated_sum",
+    "StatsError",
+    "WeightPolicy",
+    "ColumnStats",
     # Test runner
     "load_tests",
     "run_tests",
     "TestCase",
     "TestResult",
     "TestResults",
+    # Trace
+    "trace",
+    "TraceNode",
+    "TraceError",
+    # Types
+    "variable_types",
+    "column_problems",
+    "ValueType",
 ]
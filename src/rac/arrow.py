@@ -0,0 +1,298 @@
+"""Apache Arrow input/output.
+
+Requires the optional ``pyarrow`` dependency (``pip install rac[arrow]``).
+Batches are evaluated one at a time, so memory stays bounded by the size of
+a single RecordBatch regardless of file size. Only the input columns in the
+dependency closure of the requested outputs are read from the file.
+Parquet files are read and written the same way, a row group's batches at a
+time, without materialising the table. Polars DataFrames (``pip install
+rac[polars]``) cross over as Arrow tables, sharing their buffers.
+"""
+
+from collections.abc import Callable, Mapping, Sequence
+from pathlib import Path
+from typing import Any
+
+from pydantic import BaseModel
+
+from .categories import Categorical
+from .compiler import IR, prune, required_inputs
+from .config import EngineConfig
+from .executor import execute_columns
+from .typecheck import variable_types
+
+ARROW_FILE_MAGIC = b"ARROW1"
+
+
+class ReadStats(BaseModel):
+    """Input columns read versus available, filled in by a run."""
+
+    columns_read: int = 0
+    columns_available: int = 0
+    rows: int = 0
+
+
+def _pyarrow() -> Any:
+    try:
+        import pyarrow as pa
+        import pyarrow.ipc  # noqa: F401
+    except ImportError as e:
+        raise ImportError("Arrow support requires pyarrow: pip install rac[arrow]") from e
+    return pa
+
+
+def _parquet() -> Any:
+    try:
+        import pyarrow.parquet as pq
+    except ImportError as e:
+        raise ImportError("Parquet support requires pyarrow: pip install rac[arrow]") from e
+    return pq
+
+
+def _polars() -> Any:
+    try:
+        import polars as pl
+    except ImportError as e:
+        raise ImportError("Polars support requires polars: pip install rac[polars]") from e
+    return pl
+
+
+def output_columns(ir: IR, entity: str) -> list[str]:
+    """Computed variables for an entity, in execution order."""
+    return [path for path in ir.order if ir.variables[path].entity == entity]
+
+
+def output_schema(ir: IR, entity: str, outputs: list[str] | None = None) -> Any:
+    """Arrow schema of the outputs for an entity: string and date32 for
+    string and date variables, float64 for the rest."""
+    pa = _pyarrow()
+    names = output_columns(ir, entity) if outputs is None else outputs
+    types, _ = variable_types(ir.variables, ir.schema_, ir.order)
+    arrow_types = {"str": pa.string(), "date": pa.date32()}
+    return pa.schema([(path, arrow_types.get(types.get(path), pa.float64())) for path in names])
+
+
+def _values(column: Any) -> Sequence[Any]:
+    """A column's values; dictionary-encoded columns stay encoded."""
+    if hasattr(column, "indices") and hasattr(column, "dictionary"):
+        return Categorical(column.indices.to_pylist(), column.dictionary.to_pylist())
+    return column.to_pylist()
+
+
+def execute_batch(
+    ir: IR,
+    entity: str,
+    batch: Any,
+    outputs: list[str] | None = None,
+    passthrough: Sequence[str] = (),
+    config: EngineConfig | None = None,
+) -> Any:
+    """Evaluate one RecordBatch and return a RecordBatch of outputs.
+
+    Inputs are read column by column, never as rows. ``passthrough`` input
+    columns are copied to the front of the output unchanged.
+    """
+    pa = _pyarrow()
+    if outputs is not None:
+        ir = prune(ir, outputs)
+    names = batch.schema.names
+    fields = {
+        name: _values(batch.column(name))
+        for name in required_inputs(ir, entity)
+        if name in names
+    }
+    columns = execute_columns(ir, entity, fields, config=config, size=batch.num_rows)
+    return _output_batch(ir, entity, batch, columns, outputs, passthrough)
+
+
+def _output_batch(
+    ir: IR,
+    entity: str,
+    batch: Any,
+    columns: Mapping[str, Sequence[Any]],
+    outputs: list[str] | None,
+    passthrough: Sequence[str],
+) -> Any:
+    """The ``passthrough`` columns of ``batch``, then the computed ``columns``
+    typed as output_schema says; numbers are cast to float, None kept null."""
+    pa = _pyarrow()
+    schema = output_schema(ir, entity, outputs)
+    arrays = [batch.column(name) for name in passthrough]
+    for field in schema:
+        values = columns.get(field.name, [])
+        if pa.types.is_floating(field.type):
+            values = [None if v is None else float(v) for v in values]
+        arrays.append(pa.array(values, type=field.type))
+    fields = [batch.schema.field(name) for name in passthrough] + list(schema)
+    return pa.RecordBatch.from_arrays(arrays, schema=pa.schema(fields))
+
+
+def execute_arrow(
+    ir: IR,
+    entity: str,
+    data: Any,
+    outputs: list[str] | None = None,
+    passthrough: Sequence[str] = (),
+    config: EngineConfig | None = None,
+) -> Any:
+    """Evaluate a RecordBatch or Table, returning the same kind of outputs.
+
+    A Table is evaluated one batch at a time, so its chunks are never
+    concatenated.
+    """
+    pa = _pyarrow()
+    if isinstance(data, pa.RecordBatch):
+        return execute_batch(ir, entity, data, outputs, passthrough, config)
+    if not isinstance(data, pa.Table):
+        raise TypeError(f"expected a RecordBatch or Table, got {type(data).__name__}")
+    missing = [name for name in passthrough if name not in data.schema.names]
+    if missing:
+        raise ValueError(f"passthrough columns not in input: {', '.join(missing)}")
+    fields = [data.schema.field(name) for name in passthrough]
+    schema = pa.schema(fields + list(output_schema(ir, entity, outputs)))
+    batches = [
+        execute_batch(ir, entity, batch, outputs, passthrough, config)
+        for batch in data.to_batches()
+    ]
+    return pa.Table.from_batches(batches, schema=schema)
+
+
+def execute_polars(
+    ir: IR,
+    entity: str,
+    frame: Any,
+    outputs: list[str] | None = None,
+    passthrough: Sequence[str] = (),
+    config: EngineConfig | None = None,
+) -> Any:
+    """Evaluate a polars DataFrame, returning a DataFrame of outputs.
+
+    The frame is handed over through Arrow (see execute_arrow), so columns
+    are not copied into Python rows on the way in.
+    """
+    pl = _polars()
+    if not isinstance(frame, pl.DataFrame):
+        raise TypeError(f"expected a polars DataFrame, got {type(frame).__name__}")
+    table = execute_arrow(ir, entity, frame.to_arrow(), outputs, passthrough, config)
+    return pl.from_arrow(table)
+
+
+def _is_file_format(source: Any) -> bool:
+    head = source.read(len(ARROW_FILE_MAGIC))
+    source.seek(0)
+    return head == ARROW_FILE_MAGIC
+
+
+def _read_schema(pa: Any, source: Any) -> Any:
+    """Schema of an IPC file or stream, leaving the source rewound."""
+    open_reader = pa.ipc.open_file if _is_file_format(source) else pa.ipc.open_stream
+    schema = open_reader(source).schema
+    source.seek(0)
+    return schema
+
+
+def _open_reader(pa: Any, source: Any, indices: list[int] | None = None) -> Any:
+    """Open an IPC file or stream, yielding RecordBatches.
+
+    With ``indices``, only those top-level columns are decoded.
+    """
+    options = None if indices is None else pa.ipc.IpcReadOptions(included_fields=indices)
+    if _is_file_format(source):
+        reader = pa.ipc.open_file(source, options=options)
+        return (reader.get_batch(i) for i in range(reader.num_record_batches))
+    return iter(pa.ipc.open_stream(source, options=options))
+
+
+def execute_from_arrow_file(
+    ir: IR,
+    entity: str,
+    input_path: str | Path,
+    output_path: str | Path,
+    outputs: list[str] | None = None,
+    passthrough: Sequence[str] = (),
+    stats: ReadStats | None = None,
+) -> int:
+    """Evaluate an Arrow IPC file (file or stream format) batch by batch.
+
+    Only the inputs needed for ``outputs`` (default: every variable of the
+    entity) and the ``passthrough`` columns are read. Writes one output batch
+    per input batch to ``output_path`` in IPC file format and returns the
+    number of rows processed; ``stats``, if given, records columns read.
+    """
+    pa = _pyarrow()
+    if outputs is None:
+        outputs = output_columns(ir, entity)
+    needed = list(dict.fromkeys([*passthrough, *required_inputs(ir, entity, outputs)]))
+
+    n_rows = 0
+    with pa.OSFile(str(input_path), "rb") as source, pa.OSFile(str(output_path), "wb") as sink:
+        schema = _read_schema(pa, source)
+        missing = [name for name in passthrough if name not in schema.names]
+        if missing:
+            raise ValueError(f"passthrough columns not in input: {', '.join(missing)}")
+        columns = [name for name in needed if name in schema.names]
+        batches = _open_reader(pa, source, sorted(schema.get_field_index(c) for c in columns))
+
+        fields = [schema.field(name) for name in passthrough]
+        out_schema = pa.schema(fields + list(output_schema(ir, entity, outputs)))
+        with pa.ipc.new_file(sink, out_schema) as writer:
+            for batch in batches:
+                writer.write_batch(execute_batch(ir, entity, batch, outputs, passthrough))
+                n_rows += batch.num_rows
+
+    if stats is not None:
+        stats.columns_read = len(columns)
+        stats.columns_available = len(schema.names)
+        stats.rows = n_rows
+    return n_rows
+
+
+def execute_from_parquet_file(
+    ir: IR,
+    entity: str,
+    input_path: str | Path,
+    output_path: str | Path,
+    outputs: list[str] | None = None,
+    passthrough: Sequence[str] = (),
+    stats: ReadStats | None = None,
+    config: EngineConfig | None = None,
+    batch_size: int = 65536,
+    evaluate: Callable[[Any], Mapping[str, Sequence[Any]]] | None = None,
+) -> int:
+    """Evaluate a Parquet file batch by batch, writing a Parquet file of outputs.
+
+    As execute_from_arrow_file: only the needed inputs and ``passthrough``
+    columns are decoded, at most ``batch_size`` rows are held at a time, and
+    the number of rows processed is returned. ``evaluate`` maps a batch to
+    its output columns, in place of execute_columns over its columns.
+    """
+    pa = _pyarrow()
+    pq = _parquet()
+    if outputs is None:
+        outputs = output_columns(ir, entity)
+    needed = list(dict.fromkeys([*passthrough, *required_inputs(ir, entity, outputs)]))
+
+    source = pq.ParquetFile(str(input_path))
+    schema = source.schema_arrow
+    missing = [name for name in passthrough if name not in schema.names]
+    if missing:
+        raise ValueError(f"passthrough columns not in input: {', '.join(missing)}")
+    columns = [name for name in needed if name in schema.names]
+    fields = [schema.field(name) for name in passthrough]
+    out_schema = pa.schema(fields + list(output_schema(ir, entity, outputs)))
+
+    n_rows = 0
+    with pq.ParquetWriter(str(output_path), out_schema) as writer:
+        for batch in source.iter_batches(batch_size=batch_size, columns=columns):
+            if evaluate is None:
+                out = execute_batch(ir, entity, batch, outputs, passthrough, config)
+            else:
+                out = _output_batch(ir, entity, batch, evaluate(batch), outputs, passthrough)
+            writer.write_batch(out)
+            n_rows += batch.num_rows
+
+    if stats is not None:
+        stats.columns_read = len(columns)
+        stats.columns_available = len(schema.names)
+        stats.rows = n_rows
+    return n_rows
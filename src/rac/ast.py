@@ -1,6 +1,8 @@
 """AST nodes for the RAC engine."""
 
+from collections.abc import Callable, Iterable, Iterator
 from datetime import date
+from decimal import Decimal
 from typing import Annotated, Any
 from typing import Literal as TypingLiteral
 
@@ -10,7 +12,7 @@ from pydantic import BaseModel, Field
 # Expressions - using discriminated union for type safety
 class Literal(BaseModel):
     type: TypingLiteral["literal"] = "literal"
-    value: Any  # int, float, str, bool
+    value: Any  # int, float, str, bool, date; a list of them only as the categories of in
 
 
 class Var(BaseModel):
@@ -22,9 +24,10 @@ class Var(BaseModel):
 
 class BinOp(BaseModel):
     type: TypingLiteral["binop"] = "binop"
-    op: str  # +, -, *, /, >, <, >=, <=, ==, !=, and, or
+    op: str  # +, -, *, /, //, %, **, >, <, >=, <=, ==, !=, and, or, xor, in, not in
     left: "Expr"
     right: "Expr"
+    tolerance: float | None = None  # == and != only: overrides the engine's epsilon
 
 
 class UnaryOp(BaseModel):
@@ -67,9 +70,84 @@ class Cond(BaseModel):
     else_expr: "Expr"
 
 
+class Scale(BaseModel):
+    """Bracketed rate schedule applied to a base (e.g., income tax brackets).
+
+    In ``marginal`` mode each slice of the base between consecutive
+    thresholds is charged that bracket's rate; in ``average`` mode the whole
+    base is charged the rate of the highest threshold it reaches. A base
+    below the first threshold gives 0.
+    """
+
+    type: TypingLiteral["scale"] = "scale"
+    base: "Expr"
+    brackets: list[tuple["Expr", "Expr"]]  # [(threshold, rate), ...], thresholds ascending
+    mode: str = "marginal"  # marginal, average
+
+
+SCALE_MODES = ("marginal", "average")
+
+
+class Lookup(BaseModel):
+    """A key mapped through an explicit table (e.g., children to an allowance,
+    council tax band to a multiplier).
+
+    A key the table doesn't hold is handled per ``out_of_range``: ``error``
+    raises, ``default`` gives ``default``, and ``clamp`` (numeric keys only)
+    gives the value of the largest key at or below it, or of the smallest key
+    when it is below them all. A missing key gives a missing result.
+    """
+
+    type: TypingLiteral["lookup"] = "lookup"
+    key: "Expr"
+    table: list[tuple[Any, Any]]  # [(key, value), ...], both literals
+    out_of_range: str = "error"  # error, default, clamp
+    default: Any = None  # the value of an absent key when out_of_range is default
+
+
+LOOKUP_MODES = ("error", "default", "clamp")
+
+
+def lookup_problems(node: Lookup) -> list[str]:
+    """What makes a lookup's table unusable."""
+    problems = []
+    if node.out_of_range not in LOOKUP_MODES:
+        problems.append(f"unknown lookup mode {node.out_of_range}")
+    if not node.table:
+        problems.append("lookup needs at least one entry")
+    keys = [key for key, _ in node.table]
+    scalars = [*keys, *(value for _, value in node.table)]
+    if node.out_of_range == "default" and node.default is None:
+        problems.append('lookup with out_of_range "default" needs a default')
+    elif node.out_of_range == "default":
+        scalars.append(node.default)
+    if not all(isinstance(v, int | float | str) for v in scalars):
+        problems.append("lookup keys and values must be numbers, strings or booleans")
+    elif duplicates := [k for i, k in enumerate(keys) if k in keys[:i]]:
+        problems.append(f"lookup key {duplicates[0]!r} appears more than once")
+    numeric = all(isinstance(k, int | float) and not isinstance(k, bool) for k in keys)
+    if node.out_of_range == "clamp" and not numeric:
+        problems.append("a clamped lookup needs numeric keys")
+    return problems
+
+
+# What a variable's missing (None or NaN) result becomes: stays missing,
+# is replaced by the declared default, or raises
+MISSING_POLICIES = ("propagate", "default", "error")
+
+# Directions a variable's result rounds to a multiple of its round_to;
+# nearest rounds halves up
+ROUND_DIRECTIONS = ("nearest", "down", "up")
+
+
+def round_places(step: float) -> int:
+    """Decimal places in ``step`` as written: 2 for 0.01, 0 for 5."""
+    return max(0, -int(Decimal(str(step)).normalize().as_tuple().exponent))
+
+
 # Expression union type
 Expr = Annotated[
-    Literal | Var | BinOp | UnaryOp | Call | FieldAccess | Match | Cond,
+    Literal | Var | BinOp | UnaryOp | Call | FieldAccess | Match | Cond | Scale | Lookup,
     Field(discriminator="type"),
 ]
 
@@ -92,6 +170,12 @@ class VariableDecl(BaseModel):
     label: str | None = None  # human-readable display name
     description: str | None = None  # longer explanation
     unit: str | None = None  # currency/type hint (e.g., "USD", "percent")
+    requires_feature: str | None = None  # compiled only when this feature is enabled
+    arithmetic: str | None = None  # "exact" evaluates in rational arithmetic
+    missing: str | None = None  # policy for a missing result: propagate, default or error
+    default: Any = None  # value used when no temporal value is in force
+    round_to: int | float | None = None  # round the result to a multiple of this
+    rounding: str | None = None  # direction for round_to: nearest (default), down or up
     values: list[TemporalValue] = []
 
 
@@ -123,6 +207,7 @@ class EntityDecl(BaseModel):
 
     name: str
     fields: list[tuple[str, str]] = []  # [(name, type), ...]
+    nullable: list[str] = []  # fields declared with a trailing ?, which may be missing
     foreign_keys: list[tuple[str, str]] = []  # [(field_name, target_entity), ...]
     reverse_relations: list[tuple[str, str, str]] = []  # [(name, source, source_field), ...]
 
@@ -137,6 +222,7 @@ class Module(BaseModel):
     """A parsed .rac file."""
 
     path: str = ""  # file path
+    namespace: str | None = None  # prefix for the variables this module declares
     imports: list[ImportDecl] = []
     entities: list[EntityDecl] = []
     variables: list[VariableDecl] = []
@@ -151,4 +237,349 @@ Call.model_rebuild()
 FieldAccess.model_rebuild()
 Match.model_rebuild()
 Cond.model_rebuild()
+Scale.model_rebuild()
+Lookup.model_rebuild()
 TemporalValue.model_rebuild()
+
+
+# Built-in function -> (min_args, max_args); None means variadic
+BUILTIN_ARITY: dict[str, tuple[int, int | None]] = {
+    "min": (1, None),
+    "max": (1, None),
+    "abs": (1, 1),
+    "round": (1, 1),
+    "sum": (1, 1),
+    "len": (1, 1),
+    "clip": (3, 3),
+    "where": (3, 3),
+    "floor": (1, 1),
+    "ceil": (1, 1),
+    "trunc": (1, 1),
+    "sign": (1, 1),
+    "any": (1, 1),
+    "all": (1, 1),
+    "coalesce": (1, None),
+    "apportion": (5, 6),
+    "equivalence_scale": (2, 5),
+    "equivalized_income": (3, 6),
+    "age_at": (2, 2),
+    "year_of": (1, 1),
+    "month_of": (1, 1),
+    "months_between": (2, 2),
+    "days_between": (2, 2),
+    "is_missing": (1, 1),
+    "sum_over": (2, 2),
+    "count_over": (1, 1),
+    "any_over": (2, 2),
+    "all_over": (2, 2),
+    "max_over": (2, 2),
+    "min_over": (2, 2),
+    "interp": (3, 3),
+    "uniform": (0, 0),
+    "normal": (2, 2),
+    "bernoulli": (1, 1),
+}
+
+# Operators the executor implements
+BINARY_OPS = (
+    *("+", "-", "*", "/", "//", "%", "**", "<", ">", "<=", ">=", "==", "!="),
+    *("and", "or", "xor", "in", "not in"),
+)
+UNARY_OPS = ("-", "not")
+
+# Built-ins taking a member entity and (except count_over) one of its
+# variables, reduced over the members linked to each row
+AGGREGATES = ("sum_over", "count_over", "any_over", "all_over", "max_over", "min_over")
+
+# Built-ins drawing from the random stream of the row and variable evaluated
+RANDOM = ("uniform", "normal", "bernoulli")
+
+# Default equivalence_scale weights: first adult, each further adult, each child
+OECD_MODIFIED = (1.0, 0.5, 0.3)
+
+
+def arity_error(func: str, n_args: int) -> str | None:
+    """Why ``n_args`` arguments are wrong for a built-in, or None if fine.
+
+    Functions not in BUILTIN_ARITY are not checked.
+    """
+    if func not in BUILTIN_ARITY:
+        return None
+    lo, hi = BUILTIN_ARITY[func]
+    if n_args < lo or (hi is not None and n_args > hi):
+        if hi is None:
+            expected = f"at least {lo}"
+        elif lo == hi:
+            expected = str(lo)
+        else:
+            expected = f"{lo} to {hi}"
+        plural = "s" if (hi if hi is not None else lo) != 1 else ""
+        return f"{func}() takes {expected} argument{plural}, got {n_args}"
+    return None
+
+
+LIST_ERROR = "a bracketed list can only follow in or not in, or be interp() knots"
+
+
+def misplaced_list(expr: Expr) -> bool:
+    """Whether ``expr`` has a literal list anywhere but after ``in`` or
+    ``not in`` or as interp() knots; nothing else can evaluate one."""
+    allowed = set()
+    for node in walk(expr):  # pre-order: each parent before its children
+        if isinstance(node, BinOp) and node.op in ("in", "not in"):
+            allowed.add(id(node.right))
+        elif isinstance(node, Call) and node.func == "interp":
+            allowed.update(id(arg) for arg in node.args[1:])
+        elif isinstance(node, Literal) and isinstance(node.value, list) and id(node) not in allowed:
+            return True
+    return False
+
+
+def knots_error(args: list[Expr]) -> str | None:
+    """Why the knots of ``interp(x, xs, ys)`` are unusable, or None if fine.
+
+    ``xs`` and ``ys`` must be literal lists of numbers of the same length,
+    with ``xs`` strictly ascending.
+    """
+    if len(args) != 3:
+        return None  # arity_error's to report
+    lists = [a.value for a in args[1:] if isinstance(a, Literal) and isinstance(a.value, list)]
+    if len(lists) != 2:
+        return "interp() knots must be literal lists, like [0, 10, 20]"
+    xs, ys = lists
+    if not all(isinstance(v, int | float) and not isinstance(v, bool) for v in xs + ys):
+        return "interp() knots must be numbers"
+    if not xs or len(xs) != len(ys):
+        return f"interp() needs as many ys as xs, and at least one; got {len(xs)} and {len(ys)}"
+    for lower, upper in zip(xs, xs[1:]):
+        if not lower < upper:
+            return f"interp() xs must strictly ascend, got {lower} then {upper}"
+    return None
+
+
+def walk(expr: Expr) -> Iterator[Expr]:
+    """Yield expr and all its subexpressions in pre-order.
+
+    Pre-order position is stable for a given tree, so it doubles as a node id.
+    """
+    yield expr
+    match expr:
+        case BinOp(left=left, right=right):
+            yield from walk(left)
+            yield from walk(right)
+        case UnaryOp(operand=operand):
+            yield from walk(operand)
+        case Call(args=args):
+            for arg in args:
+                yield from walk(arg)
+        case FieldAccess(obj=obj):
+            yield from walk(obj)
+        case Match(subject=subject, cases=cases, default=default):
+            yield from walk(subject)
+            for pattern, result in cases:
+                yield from walk(pattern)
+                yield from walk(result)
+            if default is not None:
+                yield from walk(default)
+        case Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+            yield from walk(cond)
+            yield from walk(then_e)
+            yield from walk(else_e)
+        case Scale(base=base, brackets=brackets):
+            yield from walk(base)
+            for threshold, rate in brackets:
+                yield from walk(threshold)
+                yield from walk(rate)
+        case Lookup(key=key):
+            yield from walk(key)
+
+
+def draw_counters(exprs: Iterable[Expr]) -> dict[int, int]:
+    """Each random call's first draw counter, by node id: twice its pre-order
+    position among its formula's random calls, as normal() takes two draws.
+
+    The counters are fixed by the formula, so the draws a row gets don't
+    depend on which branches it took or on the order rows are evaluated in.
+    """
+    counters = {}
+    for expr in exprs:
+        calls = [n for n in walk(expr) if isinstance(n, Call) and n.func in RANDOM]
+        counters.update((id(call), 2 * k) for k, call in enumerate(calls))
+    return counters
+
+
+# Deepest expression tree accepted; the executor and backends recurse once per level
+MAX_EXPR_DEPTH = 200
+
+
+def children(expr: Expr) -> list[Expr]:
+    """Direct subexpressions of expr, in walk order."""
+    match expr:
+        case BinOp(left=left, right=right):
+            return [left, right]
+        case UnaryOp(operand=operand):
+            return [operand]
+        case Call(args=args):
+            return list(args)
+        case FieldAccess(obj=obj):
+            return [obj]
+        case Match(subject=subject, cases=cases, default=default):
+            out = [subject]
+            for pattern, result in cases:
+                out += [pattern, result]
+            return out if default is None else [*out, default]
+        case Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+            return [cond, then_e, else_e]
+        case Scale(base=base, brackets=brackets):
+            return [base, *(node for bracket in brackets for node in bracket)]
+        case Lookup(key=key):
+            return [key]
+    return []
+
+
+def extent(expr: Expr) -> tuple[int, int]:
+    """Node count and depth of expr, measured without recursion."""
+    nodes = depth = 0
+    stack = [(expr, 1)]
+    while stack:
+        node, level = stack.pop()
+        nodes += 1
+        depth = max(depth, level)
+        stack.extend((child, level + 1) for child in children(node))
+    return nodes, depth
+
+
+def to_source(expr: Expr) -> str:
+    """Render an expression back to single-line .rac syntax."""
+    match expr:
+        case Literal(value=v):
+            if isinstance(v, bool):
+                return "true" if v else "false"
+            if isinstance(v, str):
+                return f'"{v}"'
+            if isinstance(v, list):
+                return f"[{', '.join(to_source(Literal(value=item)) for item in v)}]"
+            if isinstance(v, date):
+                return v.isoformat()
+            return repr(v)
+        case Var(path=path):
+            return path
+        case BinOp(op=op, left=left, right=right, tolerance=tolerance):
+            within = "" if tolerance is None else f" within {tolerance!r}"
+            return f"({to_source(left)} {op} {to_source(right)}{within})"
+        case UnaryOp(op=op, operand=operand):
+            sep = " " if op == "not" else ""
+            return f"{op}{sep}{to_source(operand)}"
+        case Call(func=func, args=args):
+            return f"{func}({', '.join(to_source(a) for a in args)})"
+        case FieldAccess(obj=obj, field=fld):
+            return f"{to_source(obj)}.{fld}"
+        case Match(subject=subject, cases=cases, default=default):
+            arms = [f"{to_source(p)} => {to_source(r)}" for p, r in cases]
+            if default is not None:
+                arms.append(f"_ => {to_source(default)}")
+            return f"match {to_source(subject)}: {'; '.join(arms)}"
+        case Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+            return f"if {to_source(cond)}: {to_source(then_e)} else: {to_source(else_e)}"
+        case Scale(base=base, brackets=brackets, mode=mode):
+            arms = [f"{to_source(t)} => {to_source(r)}" for t, r in brackets]
+            return f"scale {mode} {to_source(base)}: {'; '.join(arms)}"
+        case Lookup(key=key, table=table, out_of_range=mode, default=default):
+            arms = [
+                f"{to_source(Literal(value=k))} => {to_source(Literal(value=v))}"
+                for k, v in table
+            ]
+            if mode == "default":
+                arms.append(f"_ => {to_source(Literal(value=default))}")
+            prefix = "lookup clamp" if mode == "clamp" else "lookup"
+            return f"{prefix} {to_source(key)}: {'; '.join(arms)}"
+    return repr(expr)
+
+
+def map_vars(expr: Expr, fn: Callable[[Var], Expr]) -> Expr:
+    """Copy of expr with every Var node replaced by fn(var)."""
+    match expr:
+        case Var():
+            return fn(expr)
+        case BinOp(left=left, right=right):
+            return expr.model_copy(
+                update={"left": map_vars(left, fn), "right": map_vars(right, fn)}
+            )
+        case UnaryOp(operand=operand):
+            return expr.model_copy(update={"operand": map_vars(operand, fn)})
+        case Call(args=args):
+            return expr.model_copy(update={"args": [map_vars(a, fn) for a in args]})
+        case FieldAccess(obj=obj):
+            return expr.model_copy(update={"obj": map_vars(obj, fn)})
+        case Match(subject=subject, cases=cases, default=default):
+            return expr.model_copy(
+                update={
+                    "subject": map_vars(subject, fn),
+                    "cases": [(map_vars(p, fn), map_vars(r, fn)) for p, r in cases],
+                    "default": map_vars(default, fn) if default is not None else None,
+                }
+            )
+        case Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+            return expr.model_copy(
+                update={
+                    "condition": map_vars(cond, fn),
+                    "then_expr": map_vars(then_e, fn),
+                    "else_expr": map_vars(else_e, fn),
+                }
+            )
+        case Scale(base=base, brackets=brackets):
+            return expr.model_copy(
+                update={
+                    "base": map_vars(base, fn),
+                    "brackets": [(map_vars(t, fn), map_vars(r, fn)) for t, r in brackets],
+                }
+            )
+        case Lookup(key=key):
+            return expr.model_copy(update={"key": map_vars(key, fn)})
+    return expr
+
+
+def rename(expr: Expr, mapping: dict[str, str]) -> Expr:
+    """Copy of expr with variable references renamed per mapping."""
+    return map_vars(expr, lambda v: v.model_copy(update={"path": mapping.get(v.path, v.path)}))
+
+
+def substitute(expr: Expr, mapping: dict[str, Expr]) -> Expr:
+    """Copy of expr with references to the given paths replaced by expressions."""
+    return map_vars(expr, lambda v: mapping.get(v.path, v))
+
+
+def namespaced(module: Module) -> Module:
+    """Copy of a module with its namespace applied.
+
+    Every variable the module declares moves to ``{namespace}/{path}``, and
+    references, amendments and repeals naming those variables follow. Other
+    paths are left alone, so a reference to another module's variable uses
+    its full (prefixed) path.
+    """
+    if not module.namespace:
+        return module
+    mapping = {decl.path: f"{module.namespace}/{decl.path}" for decl in module.variables}
+
+    def values(vals: list[TemporalValue]) -> list[TemporalValue]:
+        return [v.model_copy(update={"expr": rename(v.expr, mapping)}) for v in vals]
+
+    return module.model_copy(
+        update={
+            "namespace": None,
+            "variables": [
+                d.model_copy(update={"path": mapping[d.path], "values": values(d.values)})
+                for d in module.variables
+            ],
+            "amendments": [
+                a.model_copy(
+                    update={"target": mapping.get(a.target, a.target), "values": values(a.values)}
+                )
+                for a in module.amendments
+            ],
+            "repeals": [
+                r.model_copy(update={"target": mapping.get(r.target, r.target)})
+                for r in module.repeals
+            ],
+        }
+    )
@@ -0,0 +1,164 @@
+"""Forward-mode automatic differentiation over the executor.
+
+A Dual carries (value, derivative) and overloads the arithmetic and
+comparison operators, so the ordinary tree walker propagates exact
+derivatives without a separate evaluator. Comparisons and branching use the
+value only. Piecewise builtins take a subgradient: ``min``/``max`` follow the
+selected operand, ``abs`` has slope 0 at 0, and ``round`` is flat. So are
+``//``, which is flat, and ``%``, whose slope is that of ``x - (x // y) * y``.
+"""
+
+import math
+from typing import Any
+
+from . import ast
+from .compiler import IR
+from .executor import Context, Executor, evaluate
+from .schema import Data
+
+
+class Dual:
+    """A value paired with its derivative with respect to one input."""
+
+    __slots__ = ("value", "deriv")
+
+    def __init__(self, value: float, deriv: float = 0.0):
+        self.value = float(value)
+        self.deriv = float(deriv)
+
+    def __repr__(self) -> str:
+        return f"Dual({self.value!r}, {self.deriv!r})"
+
+    def __add__(self, other: Any) -> "Dual":
+        o = lift(other)
+        return Dual(self.value + o.value, self.deriv + o.deriv)
+
+    __radd__ = __add__
+
+    def __sub__(self, other: Any) -> "Dual":
+        o = lift(other)
+        return Dual(self.value - o.value, self.deriv - o.deriv)
+
+    def __rsub__(self, other: Any) -> "Dual":
+        return lift(other) - self
+
+    def __mul__(self, other: Any) -> "Dual":
+        o = lift(other)
+        return Dual(self.value * o.value, self.deriv * o.value + self.value * o.deriv)
+
+    __rmul__ = __mul__
+
+    def __truediv__(self, other: Any) -> "Dual":
+        o = lift(other)
+        return Dual(
+            self.value / o.value,
+            (self.deriv * o.value - self.value * o.deriv) / (o.value * o.value),
+        )
+
+    def __rtruediv__(self, other: Any) -> "Dual":
+        return lift(other) / self
+
+    def __floordiv__(self, other: Any) -> "Dual":
+        return Dual(self.value // lift(other).value, 0.0)
+
+    def __rfloordiv__(self, other: Any) -> "Dual":
+        return lift(other) // self
+
+    def __mod__(self, other: Any) -> "Dual":
+        o = lift(other)
+        return Dual(self.value % o.value, self.deriv - (self.value // o.value) * o.deriv)
+
+    def __rmod__(self, other: Any) -> "Dual":
+        return lift(other) % self
+
+    def __pow__(self, other: Any) -> "Dual":
+        o = lift(other)
+        value = self.value**o.value
+        if isinstance(value, complex):  # a negative base to a fractional power
+            return Dual(math.nan, math.nan)
+        deriv = 0.0
+        if self.deriv:
+            try:
+                deriv += o.value * self.value ** (o.value - 1) * self.deriv
+            except ZeroDivisionError:  # 0 to a power below 1 is vertical
+                deriv += math.copysign(math.inf, o.value * self.deriv)
+        if o.deriv:
+            # u ** v grows by u ** v * ln(u) per unit of v, for u > 0 only
+            deriv += value * math.log(self.value) * o.deriv if self.value > 0 else math.nan
+        return Dual(value, deriv)
+
+    def __rpow__(self, other: Any) -> "Dual":
+        return lift(other) ** self
+
+    def __neg__(self) -> "Dual":
+        return Dual(-self.value, -self.deriv)
+
+    def __abs__(self) -> "Dual":
+        if self.value > 0:
+            return Dual(self.value, self.deriv)
+        if self.value < 0:
+            return Dual(-self.value, -self.deriv)
+        return Dual(0.0, 0.0)
+
+    def __round__(self, ndigits: int | None = None) -> "Dual":
+        return Dual(round(self.value, ndigits), 0.0)
+
+    def __float__(self) -> float:
+        return self.value
+
+    def __bool__(self) -> bool:
+        return self.value != 0
+
+    def __eq__(self, other: Any) -> bool:
+        if not isinstance(other, Dual | int | float):
+            return NotImplemented
+        return self.value == lift(other).value
+
+    def __ne__(self, other: Any) -> bool:
+        if not isinstance(other, Dual | int | float):
+            return NotImplemented
+        return self.value != lift(other).value
+
+    def __lt__(self, other: Any) -> bool:
+        return self.value < lift(other).value
+
+    def __le__(self, other: Any) -> bool:
+        return self.value <= lift(other).value
+
+    def __gt__(self, other: Any) -> bool:
+        return self.value > lift(other).value
+
+    def __ge__(self, other: Any) -> bool:
+        return self.value >= lift(other).value
+
+    __hash__ = None  # type: ignore[assignment]
+
+
+def lift(x: Any) -> Dual:
+    """Treat a plain number as a constant (derivative 0)."""
+    if isinstance(x, Dual):
+        return x
+    return Dual(float(x), 0.0)
+
+
+def eval_dual(expr: ast.Expr, ctx: Context) -> Dual:
+    """Evaluate an expression whose inputs may be Duals."""
+    return lift(evaluate(expr, ctx))
+
+
+def derivatives(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    entity: str,
+    wrt: str,
+) -> dict[str, list[float]]:
+    """Exact per-row derivative of every ``entity`` variable w.r.t. input ``wrt``."""
+    if isinstance(data, Data):
+        data = data.tables
+    seeded = dict(data)
+    seeded[entity] = [{**row, wrt: Dual(row[wrt], 1.0)} for row in data.get(entity, [])]
+    result = Executor(ir).execute(Data(tables=seeded))
+    return {
+        path: [lift(v).deriv for v in values]
+        for path, values in result.entities.get(entity, {}).items()
+    }
@@ -0,0 +1,255 @@
+"""Expressions compiled to flat bytecode for a stack machine.
+
+compile_expr() lowers an expression tree to a postfix instruction list:
+variables become integer slots, resolved once at compile time, and each
+operator becomes the function that applies it, so running a program is a
+loop over a flat list with one reusable stack rather than a recursive walk
+that matches on node types and operator strings at every row.
+
+The tree walker (executor.evaluate) stays the reference implementation:
+a program gives the same value it would, and expressions the bytecode
+doesn't cover (aggregates, random draws, registered functions, field
+access) raise BytecodeError so callers can fall back to it.
+"""
+
+import operator
+from collections.abc import Callable, Sequence
+from dataclasses import dataclass, field
+from functools import partial
+from typing import Any
+
+from . import ast
+from .executor import (
+    BUILTINS,
+    EvalError,
+    _binary,
+    _decides,
+    _interpolator,
+    _lookup,
+    _scale,
+    _unary,
+)
+
+# Opcodes; each instruction is (opcode, argument)
+CONST = 0  # push the argument
+LOAD = 1  # push frame[argument]
+BINARY = 2  # pop right, replace left with argument(left, right)
+UNARY = 3  # replace the top with argument(top)
+CALL = 4  # argument (fn, n): pop n arguments, push fn(*arguments)
+JUMP = 5  # continue at the argument
+BRANCH = 6  # argument (else_pc, end_pc): pop a condition; None pushes None and ends
+CASE = 7  # argument next_pc: pop a pattern; unless the subject under it equals it, go on there
+POP = 8  # drop the top (an unmatched subject, before the default)
+NO_MATCH = 9  # raise: the subject on top matched no case
+SCALE = 10  # argument (mode, n): pop n (threshold, rate) pairs and the base
+SHORT = 11  # argument (op, end_pc): if the top settles the and/or, make it the result and end
+
+_NUMBER = (int, float)
+
+_FAST: dict[str, Callable[[Any, Any], Any]] = {
+    "+": operator.add,
+    "-": operator.sub,
+    "*": operator.mul,
+    "<": operator.lt,
+    ">": operator.gt,
+    "<=": operator.le,
+    ">=": operator.ge,
+}
+
+
+class BytecodeError(Exception):
+    """An expression the bytecode can't express; evaluate it as a tree."""
+
+
+def _operator(op: str, epsilon: float) -> Callable[[Any, Any], Any]:
+    """``op`` as a function of two operands.
+
+    Plain ints and floats take the operator directly; anything else (None,
+    bools, dates, strings, exact fractions) goes through the tree walker's
+    own _binary, so the two agree.
+    """
+    general = partial(_binary, op, epsilon=epsilon)
+    fast = _FAST.get(op)
+    if fast is None:
+        return general
+
+    def apply(left: Any, right: Any) -> Any:
+        if type(left) in _NUMBER and type(right) in _NUMBER:
+            return fast(left, right)
+        return general(left, right)
+
+    return apply
+
+
+@dataclass
+class Program:
+    """A compiled expression: instructions, and the name read by each slot."""
+
+    code: list[tuple[int, Any]] = field(default_factory=list)
+    names: list[str] = field(default_factory=list)
+
+    def run(self, frame: Sequence[Any], stack: list[Any] | None = None) -> Any:
+        """The expression's value, with slot ``i`` reading ``frame[i]``.
+
+        ``stack`` is scratch space, reused across calls when given.
+        """
+        if stack is None:
+            stack = []
+        else:
+            stack.clear()
+        push = stack.append
+        pop = stack.pop
+        code = self.code
+        pc = 0
+        end = len(code)
+        while pc < end:
+            op, arg = code[pc]
+            pc += 1
+            if op == LOAD:
+                push(frame[arg])
+            elif op == CONST:
+                push(arg)
+            elif op == BINARY:
+                right = pop()
+                stack[-1] = arg(stack[-1], right)
+            elif op == UNARY:
+                stack[-1] = arg(stack[-1])
+            elif op == CALL:
+                fn, n = arg
+                args = stack[len(stack) - n :]
+                del stack[len(stack) - n :]
+                push(fn(*args))
+            elif op == BRANCH:
+                condition = pop()
+                if condition is None:
+                    push(None)
+                    pc = arg[1]
+                elif not condition:
+                    pc = arg[0]
+            elif op == JUMP:
+                pc = arg
+            elif op == CASE:
+                pattern = pop()
+                if stack[-1] == pattern:
+                    pop()
+                else:
+                    pc = arg
+            elif op == POP:
+                pop()
+            elif op == NO_MATCH:
+                raise EvalError("no_match", f"no match for: {stack[-1]}")
+            elif op == SHORT:
+                if _decides(arg[0], stack[-1]):
+                    stack[-1] = arg[0] == "or"
+                    pc = arg[1]
+            elif op == SCALE:
+                mode, n = arg
+                flat = stack[len(stack) - 2 * n :]
+                del stack[len(stack) - 2 * n :]
+                stack[-1] = _scale(mode, stack[-1], list(zip(flat[::2], flat[1::2])))
+        return stack[-1]
+
+    def run_column(self, columns: Sequence[Sequence[Any]], size: int) -> list[Any]:
+        """The value at each of ``size`` rows, slot ``i`` reading ``columns[i]``.
+
+        An EvalError records the row it arose on.
+        """
+        stack: list[Any] = []
+        values = []
+        run = self.run
+        row = 0
+        try:
+            for row, frame in enumerate(zip(*columns) if columns else [()] * size):
+                values.append(run(frame, stack))
+        except EvalError as e:
+            e.row = row if e.row is None else e.row
+            raise
+        return values
+
+
+def compile_expr(expr: ast.Expr, epsilon: float = 0.0) -> Program:
+    """``expr`` as a program; ``epsilon`` is the == and != tolerance where
+    a comparison sets none."""
+    program = Program()
+    slots: dict[str, int] = {}
+    code = program.code
+
+    def emit(node: ast.Expr) -> None:
+        match node:
+            case ast.Literal(value=v):
+                code.append((CONST, v))
+            case ast.Var(path=path):
+                if path not in slots:
+                    slots[path] = len(program.names)
+                    program.names.append(path)
+                code.append((LOAD, slots[path]))
+            case ast.BinOp(op="and" | "or" as op, left=left, right=right):
+                emit(left)
+                short = len(code)
+                code.append((SHORT, None))
+                emit(right)
+                code.append((BINARY, _operator(op, epsilon)))
+                code[short] = (SHORT, (op, len(code)))
+            case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
+                emit(left)
+                emit(right)
+                code.append((BINARY, _operator(op, epsilon if tolerance is None else tolerance)))
+            case ast.UnaryOp(op=op, operand=operand):
+                emit(operand)
+                code.append((UNARY, partial(_unary, op)))
+            case ast.Call(
+                func="interp", args=[x, ast.Literal(value=list(xs)), ast.Literal(value=list(ys))]
+            ):
+                emit(x)
+                code.append((UNARY, _interpolator(xs, ys)))
+            case ast.Call(func=func, args=args):
+                if func not in BUILTINS or func in ast.AGGREGATES or func in ast.RANDOM:
+                    raise BytecodeError(f"{func}() needs the tree walker")
+                if ast.arity_error(func, len(args)):
+                    raise BytecodeError(f"{func}() has the wrong number of arguments")
+                for arg in args:
+                    emit(arg)
+                code.append((CALL, (BUILTINS[func], len(args))))
+            case ast.Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+                emit(cond)
+                branch = len(code)
+                code.append((BRANCH, None))
+                emit(then_e)
+                jump = len(code)
+                code.append((JUMP, None))
+                else_pc = len(code)
+                emit(else_e)
+                code[branch] = (BRANCH, (else_pc, len(code)))
+                code[jump] = (JUMP, len(code))
+            case ast.Match(subject=subject, cases=cases, default=default):
+                emit(subject)
+                jumps = []
+                for pattern, result in cases:
+                    emit(pattern)
+                    case = len(code)
+                    code.append((CASE, None))
+                    emit(result)
+                    jumps.append(len(code))
+                    code.append((JUMP, None))
+                    code[case] = (CASE, len(code))
+                if default is not None:
+                    code.append((POP, None))
+                    emit(default)
+                else:
+                    code.append((NO_MATCH, None))
+                for jump in jumps:
+                    code[jump] = (JUMP, len(code))
+            case ast.Scale(base=base, brackets=brackets, mode=mode):
+                emit(base)
+                for threshold, rate in brackets:
+                    emit(threshold)
+                    emit(rate)
+                code.append((SCALE, (mode, len(brackets))))
+            case ast.Lookup(key=key):
+                emit(key)
+                code.append((UNARY, _lookup(node)))
+            case _:
+                raise BytecodeError(f"{type(node).__name__} needs the tree walker")
+
+    emit(expr)
+    return program
@@ -0,0 +1,50 @@
+"""Dictionary-encoded (categorical) columns.
+
+A column of a few distinct strings, like a region or filing status, is
+stored as integer codes into its distinct values. The executor reads it as
+an ordinary sequence of values, but compares it against literal categories
+(``region == "SCOTLAND"``, ``status in ["single", "widowed"]``) once per
+distinct value rather than once per row.
+"""
+
+from collections.abc import Callable, Iterable, Sequence
+from typing import Any, overload
+
+
+class Categorical(Sequence[Any]):
+    """A column held as ``codes`` into ``categories``; a code of None is missing."""
+
+    def __init__(self, codes: Sequence[int | None], categories: Sequence[Any]):
+        self.codes = codes
+        self.categories = list(categories)
+
+    @classmethod
+    def encode(cls, values: Iterable[Any]) -> "Categorical":
+        """Dictionary-encode ``values``, categories in order of first appearance."""
+        lookup: dict[Any, int] = {}
+        codes: list[int | None] = []
+        for value in values:
+            if value is None:
+                codes.append(None)
+                continue
+            codes.append(lookup.setdefault(value, len(lookup)))
+        return cls(codes, list(lookup))
+
+    def __len__(self) -> int:
+        return len(self.codes)
+
+    @overload
+    def __getitem__(self, i: int) -> Any: ...
+    @overload
+    def __getitem__(self, i: slice) -> list[Any]: ...
+    def __getitem__(self, i: int | slice) -> Any:
+        if isinstance(i, slice):
+            return [self[k] for k in range(*i.indices(len(self)))]
+        code = self.codes[i]
+        return None if code is None else self.categories[code]
+
+    def map(self, fn: Callable[[Any], Any], index: Sequence[int]) -> list[Any]:
+        """``fn`` of the value at each row of ``index``, called once per category."""
+        results = [fn(category) for category in self.categories]
+        missing = fn(None) if any(self.codes[i] is None for i in index) else None
+        return [missing if (code := self.codes[i]) is None else results[code] for i in index]
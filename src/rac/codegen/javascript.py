@@ -3,8 +3,16 @@
 Compiles IR to standalone JavaScript that works in browsers and Node.js.
 """
 
+from datetime import date
+
 from .. import ast
-from ..compiler import IR
+from ..compiler import (
+    IR,
+    CompileError,
+    ResolvedVar,
+    cross_entity_variables,
+    random_variables,
+)
 
 
 def generate_javascript(ir: IR, module_name: str = "calculator") -> str:
@@ -17,6 +25,14 @@ class JavaScriptGenerator:
     """Generates JavaScript code from compiled IR."""
 
     def __init__(self, ir: IR, module_name: str):
+        if ir.fixed_point:
+            raise CompileError("fixed-point groups are only supported by the executor")
+        if cross := cross_entity_variables(ir):
+            raise CompileError(f"{cross[0]} reads another entity, which only the executor supports")
+        if random := random_variables(ir):
+            raise CompileError(
+                f"{random[0]} draws random numbers, which only the executor supports"
+            )
         self.ir = ir
         self.module_name = module_name
         self.entity_vars: dict[str, list[str]] = {}
@@ -26,9 +42,138 @@ class JavaScriptGenerator:
                 self.entity_vars.setdefault(var.entity, []).append(path)
 
     def generate(self) -> str:
+        first, other, child = ast.OECD_MODIFIED
         lines = [
             "// Auto-generated by RAC compiler",
             "",
+            "function racFloorDiv(a, b) {",
+            "  return b !== 0 ? Math.floor(a / b) : 0;",
+            "}",
+            "",
+            "// Takes the sign of the divisor, as in Python",
+            "function racMod(a, b) {",
+            "  if (b === 0) return 0;",
+            "  const r = a % b;",
+            "  return r !== 0 && r < 0 !== b < 0 ? r + b : r;",
+            "}",
+            "",
+            "function racPow(a, b) {",
+            "  return a === 0 && b < 0 ? 0 : Math.pow(a, b);",
+            "}",
+            "",
+            "function racApportion(amount, start, end, lo, hi, inclusive = true) {",
+            "  const extra = inclusive ? 1 : 0;",
+            "  const length = hi - lo + extra;",
+            "  if (length <= 0) return 0;",
+            "  const covered = Math.max(0, Math.min(end, hi) - Math.max(start, lo) + extra);",
+            "  return (amount * covered) / length;",
+            "}",
+            "",
+            "function racEquivalenceScale(",
+            f"  adults, children, first = {first}, other = {other}, child = {child}",
+            ") {",
+            "  if (adults > 0) return first + other * (adults - 1) + child * children;",
+            "  if (children > 0) return first + child * (children - 1);",
+            "  return Number.isNaN(adults) || Number.isNaN(children) ? NaN : 0;",
+            "}",
+            "",
+            "function racEquivalizedIncome(income, adults, children, ...weights) {",
+            "  const total = Array.isArray(income) ? racSum(income) : income;",
+            "  const scale = racEquivalenceScale(adults, children, ...weights);",
+            "  return scale === 0 ? 0 : total / scale;",
+            "}",
+            "",
+            "// Dates are ISO strings (YYYY-MM-DD)",
+            "function racYmd(d) {",
+            "  return String(d).split(\"-\").map(Number);",
+            "}",
+            "",
+            "function racAgeAt(on, born) {",
+            "  const [y1, m1, d1] = racYmd(on);",
+            "  const [y0, m0, d0] = racYmd(born);",
+            "  return y1 - y0 - (m1 < m0 || (m1 === m0 && d1 < d0) ? 1 : 0);",
+            "}",
+            "",
+            "function racMonthsBetween(start, end) {",
+            "  const [y0, m0, d0] = racYmd(start);",
+            "  const [y1, m1, d1] = racYmd(end);",
+            "  const months = (y1 - y0) * 12 + m1 - m0;",
+            "  if (months > 0 && d1 < d0) return months - 1;",
+            "  if (months < 0 && d1 > d0) return months + 1;",
+            "  return months;",
+            "}",
+            "",
+            "function racDaysBetween(start, end) {",
+            "  return Math.round((Date.parse(end) - Date.parse(start)) / 86400000);",
+            "}",
+            "",
+            "function racScale(mode, base, brackets) {",
+            "  if (Number.isNaN(base)) return NaN;",
+            '  if (mode === "average") {',
+            "    let rate = 0;",
+            "    for (const [threshold, bracketRate] of brackets) {",
+            "      if (base >= threshold) rate = bracketRate;",
+            "    }",
+            "    return base * rate;",
+            "  }",
+            "  let total = 0;",
+            "  for (let k = 0; k < brackets.length && base > brackets[k][0]; k++) {",
+            "    const top = k + 1 < brackets.length ? Math.min(base, brackets[k + 1][0]) : base;",
+            "    total += (top - brackets[k][0]) * brackets[k][1];",
+            "  }",
+            "  return total;",
+            "}",
+            "",
+            "// Neumaier-compensated, added in array order",
+            "function racSum(values) {",
+            "  let total = 0;",
+            "  let compensation = 0;",
+            "  for (const x of values) {",
+            "    const t = total + x;",
+            "    if (Number.isFinite(t)) {",
+            "      compensation += Math.abs(total) >= Math.abs(x) ? total - t + x : x - t + total;",
+            "    }",
+            "    total = t;",
+            "  }",
+            "  return total + compensation;",
+            "}",
+            "",
+            "// A quotient within 1e-9 of a whole step counts as one",
+            "function racRoundTo(x, step, places, direction) {",
+            "  if (x === null || !Number.isFinite(x)) return x;",
+            "  const q = Math.round((x / step) * 1e9) / 1e9;",
+            "  let n = Math.floor(q + 0.5);",
+            '  if (direction === "down") n = Math.floor(q);',
+            '  if (direction === "up") n = Math.ceil(q);',
+            "  return Number((n * step).toFixed(places));",
+            "}",
+            "",
+            "// Flat beyond the first and last knots; xs strictly ascend",
+            "function racInterp(x, xs, ys) {",
+            "  if (x === null || Number.isNaN(x)) return x;",
+            "  let k = 0;",
+            "  while (k < xs.length && xs[k] <= x) k++;",
+            "  if (k === 0) return ys[0];",
+            "  if (k === xs.length) return ys[k - 1];",
+            "  return ys[k - 1] + ((ys[k] - ys[k - 1]) * (x - xs[k - 1])) / (xs[k] - xs[k - 1]);",
+            "}",
+            "",
+            "// A clamped table is sorted by key",
+            "function racLookup(key, table, mode, fallback) {",
+            "  if (key === null || Number.isNaN(key)) return key;",
+            "  const found = table.find(([k]) => k === key);",
+            "  if (found) return found[1];",
+            '  if (mode === "default") return fallback;',
+            '  if (mode !== "clamp") throw new Error(`no lookup entry for: ${key}`);',
+            "  let value = table[0][1];",
+            "  for (const [k, v] of table) if (k <= key) value = v;",
+            "  return value;",
+            "}",
+            "",
+            "function racNoMatch(value) {",
+            "  throw new Error(`no match for: ${value}`);",
+            "}",
+            "",
         ]
 
         # Entity input classes
@@ -69,7 +214,7 @@ class JavaScriptGenerator:
             var = self.ir.variables[path]
             if var.entity is None:
                 safe_name = self._js_ident(path.replace("/", "_"))
-                expr_code = self._gen_expr(var.expr)
+                expr_code = self._rounded(var, self._gen_expr(var.expr))
                 lines.append(f"  const {safe_name} = {expr_code};")
                 scalar_names.append(safe_name)
 
@@ -95,6 +240,7 @@ class JavaScriptGenerator:
             expr_code = self._gen_expr(
                 var.expr, entity_var="input", scalars_var="scalars", computed=computed_names.copy()
             )
+            expr_code = self._rounded(var, expr_code)
             lines.append(f"  const {safe_name} = {expr_code};")
             computed_names.append((path, safe_name))
 
@@ -105,6 +251,13 @@ class JavaScriptGenerator:
         lines.append("}")
         return lines
 
+    def _rounded(self, var: ResolvedVar, code: str) -> str:
+        """``code`` rounded as the variable says, if it says."""
+        if var.round_to is None:
+            return code
+        places = ast.round_places(var.round_to)
+        return f'racRoundTo({code}, {var.round_to!r}, {places}, "{var.rounding or "nearest"}")'
+
     def _gen_expr(
         self,
         expr: ast.Expr,
@@ -117,10 +270,15 @@ class JavaScriptGenerator:
 
         match expr:
             case ast.Literal(value=v):
+                if isinstance(v, date):  # dates travel as ISO strings
+                    v = v.isoformat()
                 if isinstance(v, bool):
                     return "true" if v else "false"
                 if isinstance(v, str):
                     return f'"{v}"'
+                if isinstance(v, list):
+                    items = [self._gen_expr(ast.Literal(value=item)) for item in v]
+                    return f"[{', '.join(items)}]"
                 return str(v)
 
             case ast.Var(path=path):
@@ -135,9 +293,20 @@ class JavaScriptGenerator:
                     return f"{entity_var}.{self._js_ident(path)}"
                 return self._js_ident(path)
 
-            case ast.BinOp(op=op, left=left, right=right):
+            case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
                 left_val = self._gen_expr(left, entity_var, scalars_var, computed)
                 right_val = self._gen_expr(right, entity_var, scalars_var, computed)
+                if tolerance is not None and op in ("==", "!="):
+                    close = f"Math.abs({left_val} - {right_val}) <= {tolerance!r}"
+                    return f"({close})" if op == "==" else f"!({close})"
+                if op in ("in", "not in"):
+                    member = f"{right_val}.includes({left_val})"
+                    return member if op == "in" else f"!{member}"
+                if op == "xor":
+                    return f"(Boolean({left_val}) !== Boolean({right_val}))"
+                helper = {"//": "racFloorDiv", "%": "racMod", "**": "racPow"}.get(op)
+                if helper:
+                    return f"{helper}({left_val}, {right_val})"
                 js_op = self._js_op(op)
                 return f"({left_val} {js_op} {right_val})"
 
@@ -166,8 +335,27 @@ class JavaScriptGenerator:
             case ast.Match(subject=subject, cases=cases, default=default):
                 return self._gen_match(subject, cases, default, entity_var, scalars_var, computed)
 
+            case ast.Scale(base=base, brackets=brackets, mode=mode):
+                b = self._gen_expr(base, entity_var, scalars_var, computed)
+                pairs = ", ".join(
+                    f"[{self._gen_expr(t, entity_var, scalars_var, computed)}, "
+                    f"{self._gen_expr(r, entity_var, scalars_var, computed)}]"
+                    for t, r in brackets
+                )
+                return f'racScale("{mode}", {b}, [{pairs}])'
+
+            case ast.Lookup(key=key, table=table, out_of_range=mode, default=default):
+                k = self._gen_expr(key, entity_var, scalars_var, computed)
+                entries = ", ".join(
+                    f"[{self._gen_expr(ast.Literal(value=tk))}, "
+                    f"{self._gen_expr(ast.Literal(value=v))}]"
+                    for tk, v in (sorted(table) if mode == "clamp" else table)
+                )
+                fallback = "null" if default is None else self._gen_expr(ast.Literal(value=default))
+                return f'racLookup({k}, [{entries}], "{mode}", {fallback})'
+
             case _:
-                return "0"
+                raise CompileError(f"can't generate JavaScript for {type(expr).__name__}")
 
     def _gen_match(
         self,
@@ -181,7 +369,9 @@ class JavaScriptGenerator:
         subj = self._gen_expr(subject, entity_var, scalars_var, computed)
         # Build nested ternary
         result = (
-            "0" if default is None else self._gen_expr(default, entity_var, scalars_var, computed)
+            f"racNoMatch({subj})"
+            if default is None
+            else self._gen_expr(default, entity_var, scalars_var, computed)
         )
         for pattern, value in reversed(cases):
             p = self._gen_expr(pattern, entity_var, scalars_var, computed)
@@ -200,13 +390,45 @@ class JavaScriptGenerator:
             case "round":
                 return f"Math.round({args[0]})"
             case "sum":
-                return f"{args[0]}.reduce((a, b) => a + b, 0)"
+                return f"racSum({args[0]})"
             case "len":
                 return f"{args[0]}.length"
             case "clip":
                 return f"Math.min(Math.max({args[0]}, {args[1]}), {args[2]})"
+            case "where":
+                return f"({args[0]} ? {args[1]} : {args[2]})"
+            case "floor" | "ceil" | "trunc" | "sign":
+                return f"Math.{func}({args[0]})"
+            case "interp":
+                return f"racInterp({', '.join(args)})"
+            case "coalesce":
+                return f"({' ?? '.join(args)})"
+            case "apportion":
+                return f"racApportion({', '.join(args)})"
+            case "equivalence_scale":
+                return f"racEquivalenceScale({', '.join(args)})"
+            case "equivalized_income":
+                return f"racEquivalizedIncome({', '.join(args)})"
+            case "is_missing":
+                return f"({args[0]} == null || Number.isNaN({args[0]}))"
+            case "age_at":
+                return f"racAgeAt({args[0]}, {args[1]})"
+            case "year_of":
+                return f"racYmd({args[0]})[0]"
+            case "month_of":
+                return f"racYmd({args[0]})[1]"
+            case "months_between":
+                return f"racMonthsBetween({args[0]}, {args[1]})"
+            case "days_between":
+                return f"racDaysBetween({args[0]}, {args[1]})"
+            case "any":
+                return f"{args[0]}.some(Boolean)"
+            case "all":
+                return f"{args[0]}.every(Boolean)"
             case _:
-                return f"/* unknown: {func} */ 0"
+                raise CompileError(
+                    f"{func} has no JavaScript translation; only the executor runs it"
+                )
 
     def _js_op(self, op: str) -> str:
         return {
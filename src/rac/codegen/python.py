@@ -3,8 +3,16 @@
 Compiles IR to standalone Python source code.
 """
 
+from datetime import date
+
 from .. import ast
-from ..compiler import IR
+from ..compiler import (
+    IR,
+    CompileError,
+    ResolvedVar,
+    cross_entity_variables,
+    random_variables,
+)
 
 
 def generate_python(ir: IR, module_name: str = "calculator") -> str:
@@ -17,6 +25,14 @@ class PythonGenerator:
     """Generates Python code from compiled IR."""
 
     def __init__(self, ir: IR, module_name: str):
+        if ir.fixed_point:
+            raise CompileError("fixed-point groups are only supported by the executor")
+        if cross := cross_entity_variables(ir):
+            raise CompileError(f"{cross[0]} reads another entity, which only the executor supports")
+        if random := random_variables(ir):
+            raise CompileError(
+                f"{random[0]} draws random numbers, which only the executor supports"
+            )
         self.ir = ir
         self.module_name = module_name
         self.entity_vars: dict[str, list[str]] = {}
@@ -26,10 +42,191 @@ class PythonGenerator:
                 self.entity_vars.setdefault(var.entity, []).append(path)
 
     def generate(self) -> str:
+        first, other, child = ast.OECD_MODIFIED
         lines = [
             '"""Auto-generated by RAC compiler."""',
             "",
+            "import bisect",
+            "import math",
             "from dataclasses import dataclass",
+            "from datetime import date",
+            "",
+            "",
+            "def _has_nan(values):",
+            "    return any(isinstance(v, float) and math.isnan(v) for v in values)",
+            "",
+            "",
+            "def _min(*args):",
+            "    values = args[0] if len(args) == 1 else args",
+            "    return math.nan if _has_nan(values) else min(values)",
+            "",
+            "",
+            "def _max(*args):",
+            "    values = args[0] if len(args) == 1 else args",
+            "    return math.nan if _has_nan(values) else max(values)",
+            "",
+            "",
+            "def _round(x):",
+            "    return _whole(round, x)",
+            "",
+            "",
+            "# Neumaier-compensated, added in list order",
+            "def _sum(values):",
+            "    if None in values:",
+            "        return None",
+            "    total, compensation = 0.0, 0.0",
+            "    for x in values:",
+            "        t = total + x",
+            "        if math.isfinite(t):",
+            "            if abs(total) >= abs(x):",
+            "                compensation += (total - t) + x",
+            "            else:",
+            "                compensation += (x - t) + total",
+            "        total = t",
+            "    return total + compensation if compensation else total",
+            "",
+            "",
+            "def _round_to(x, step, places, direction):",
+            "    if x is None or isinstance(x, bool) or not math.isfinite(x):",
+            "        return x",
+            "    q = round(x / step, 9)",
+            "    if direction == \"down\":",
+            "        n = math.floor(q)",
+            "    elif direction == \"up\":",
+            "        n = math.ceil(q)",
+            "    else:",
+            "        n = math.floor(q + 0.5)",
+            "    return round(n * step, places)",
+            "",
+            "",
+            "def _floordiv(a, b):",
+            "    return a // b if b != 0 else 0",
+            "",
+            "",
+            "def _mod(a, b):",
+            "    return a % b if b != 0 else 0",
+            "",
+            "",
+            "def _pow(a, b):",
+            "    if a == 0 and b < 0:",
+            "        return 0",
+            "    if a < 0 and math.isfinite(b) and b != math.floor(b):",
+            "        return math.nan",
+            "    try:",
+            "        return (float(a) if abs(b) > 64 else a) ** b",
+            "    except OverflowError:",
+            "        return -math.inf if a < 0 and b % 2 == 1 else math.inf",
+            "",
+            "",
+            "def _whole(fn, x):",
+            "    return x if _has_nan([x]) or x in (math.inf, -math.inf) else fn(x)",
+            "",
+            "",
+            "def _sign(x):",
+            "    return x if _has_nan([x]) else (x > 0) - (x < 0)",
+            "",
+            "",
+            "def _interp(x, xs, ys):",
+            "    if x is None or _has_nan([x]):",
+            "        return x",
+            "    k = bisect.bisect_right(xs, x)",
+            "    if k == 0 or k == len(xs):",
+            "        return float(ys[min(k, len(xs) - 1)])",
+            "    return ys[k - 1] + (ys[k] - ys[k - 1]) * (x - xs[k - 1]) / (xs[k] - xs[k - 1])",
+            "",
+            "",
+            "def _apportion(amount, start, end, lo, hi, inclusive=True):",
+            "    if _has_nan([amount, start, end, lo, hi]):",
+            "        return math.nan",
+            "    extra = 1 if inclusive else 0",
+            "    length = hi - lo + extra",
+            "    if length <= 0:",
+            "        return 0.0",
+            "    return amount * max(0, min(end, hi) - max(start, lo) + extra) / length",
+            "",
+            "",
+            "def _equivalence_scale(",
+            f"    adults, children, first={first}, other={other}, child={child}",
+            "):",
+            "    if _has_nan([adults, children, first, other, child]):",
+            "        return math.nan",
+            "    if adults > 0:",
+            "        return first + other * (adults - 1) + child * children",
+            "    if children > 0:",
+            "        return first + child * (children - 1)",
+            "    return 0.0",
+            "",
+            "",
+            "def _equivalized_income(income, adults, children, *weights):",
+            "    total = _sum(income) if isinstance(income, list) else income",
+            "    scale = _equivalence_scale(adults, children, *weights)",
+            "    return total if _has_nan([scale]) else (0.0 if scale == 0 else total / scale)",
+            "",
+            "",
+            "def _dated(fn):",
+            "    def wrapped(*args):",
+            "        if any(a is None or _has_nan([a]) for a in args):",
+            "            return None",
+            "        dates = (date.fromisoformat(a) if isinstance(a, str) else a for a in args)",
+            "        return fn(*dates)",
+            "",
+            "    return wrapped",
+            "",
+            "",
+            "@_dated",
+            "def _age_at(on, born):",
+            "    return on.year - born.year - ((on.month, on.day) < (born.month, born.day))",
+            "",
+            "",
+            "@_dated",
+            "def _months_between(start, end):",
+            "    months = (end.year - start.year) * 12 + end.month - start.month",
+            "    if months > 0 and end.day < start.day:",
+            "        return months - 1",
+            "    if months < 0 and end.day > start.day:",
+            "        return months + 1",
+            "    return months",
+            "",
+            "",
+            "_year_of = _dated(lambda d: d.year)",
+            "_month_of = _dated(lambda d: d.month)",
+            "_days_between = _dated(lambda start, end: end.toordinal() - start.toordinal())",
+            "",
+            "",
+            "def _scale(mode, base, brackets):",
+            "    if _has_nan([base]):",
+            "        return math.nan",
+            "    if mode == 'average':",
+            "        rate = 0",
+            "        for threshold, bracket_rate in brackets:",
+            "            if base >= threshold:",
+            "                rate = bracket_rate",
+            "        return base * rate",
+            "    total = 0",
+            "    for k, (threshold, rate) in enumerate(brackets):",
+            "        if base <= threshold:",
+            "            break",
+            "        top = base if k + 1 == len(brackets) else min(base, brackets[k + 1][0])",
+            "        total += (top - threshold) * rate",
+            "    return total",
+            "",
+            "",
+            "def _lookup(key, table, mode, default):",
+            "    if key is None or _has_nan([key]):",
+            "        return key",
+            "    if key in table:",
+            "        return table[key]",
+            "    if mode == 'default':",
+            "        return default",
+            "    if mode == 'clamp':",
+            "        below = [k for k in table if k <= key]",
+            "        return table[max(below) if below else min(table)]",
+            "    raise ValueError(f'no lookup entry for: {key!r}')",
+            "",
+            "",
+            "def _no_match(value):",
+            "    raise ValueError(f'no match for: {value!r}')",
+            "",
             "",
         ]
 
@@ -73,7 +270,7 @@ class PythonGenerator:
             var = self.ir.variables[path]
             if var.entity is None:
                 safe_name = self._py_ident(path.replace("/", "_"))
-                expr_code = self._gen_expr(var.expr)
+                expr_code = self._rounded(var, self._gen_expr(var.expr))
                 lines.append(f"    {safe_name} = {expr_code}")
                 scalar_names.append(safe_name)
                 has_scalars = True
@@ -105,6 +302,7 @@ class PythonGenerator:
                 scalars_var="scalars",
                 computed=computed_names.copy(),
             )
+            expr_code = self._rounded(var, expr_code)
             lines.append(f"    {safe_name} = {expr_code}")
             computed_names.append((path, safe_name))
 
@@ -114,6 +312,13 @@ class PythonGenerator:
         lines.append("    }")
         return lines
 
+    def _rounded(self, var: ResolvedVar, code: str) -> str:
+        """``code`` rounded as the variable says, if it says."""
+        if var.round_to is None:
+            return code
+        places = ast.round_places(var.round_to)
+        return f'_round_to({code}, {var.round_to!r}, {places}, "{var.rounding or "nearest"}")'
+
     def _gen_expr(
         self,
         expr: ast.Expr,
@@ -126,6 +331,8 @@ class PythonGenerator:
 
         match expr:
             case ast.Literal(value=v):
+                if isinstance(v, date):  # dates travel as ISO strings
+                    v = v.isoformat()
                 if isinstance(v, bool):
                     return "True" if v else "False"
                 if isinstance(v, str):
@@ -144,9 +351,17 @@ class PythonGenerator:
                     return f"{entity_var}.{self._py_ident(path)}"
                 return self._py_ident(path)
 
-            case ast.BinOp(op=op, left=left, right=right):
+            case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
                 left_val = self._gen_expr(left, entity_var, scalars_var, computed)
                 right_val = self._gen_expr(right, entity_var, scalars_var, computed)
+                if tolerance is not None and op in ("==", "!="):
+                    close = f"abs({left_val} - {right_val}) <= {tolerance!r}"
+                    return f"({close})" if op == "==" else f"(not {close})"
+                if op == "xor":
+                    return f"(bool({left_val}) != bool({right_val}))"
+                helper = {"//": "_floordiv", "%": "_mod", "**": "_pow"}.get(op)
+                if helper:
+                    return f"{helper}({left_val}, {right_val})"
                 py_op = self._py_op(op)
                 return f"({left_val} {py_op} {right_val})"
 
@@ -175,8 +390,27 @@ class PythonGenerator:
             case ast.Match(subject=subject, cases=cases, default=default):
                 return self._gen_match(subject, cases, default, entity_var, scalars_var, computed)
 
+            case ast.Scale(base=base, brackets=brackets, mode=mode):
+                b = self._gen_expr(base, entity_var, scalars_var, computed)
+                pairs = ", ".join(
+                    f"({self._gen_expr(t, entity_var, scalars_var, computed)}, "
+                    f"{self._gen_expr(r, entity_var, scalars_var, computed)})"
+                    for t, r in brackets
+                )
+                return f'_scale("{mode}", {b}, [{pairs}])'
+
+            case ast.Lookup(key=key, table=table, out_of_range=mode, default=default):
+                k = self._gen_expr(key, entity_var, scalars_var, computed)
+                entries = ", ".join(
+                    f"{self._gen_expr(ast.Literal(value=tk))}: "
+                    f"{self._gen_expr(ast.Literal(value=v))}"
+                    for tk, v in table
+                )
+                fallback = self._gen_expr(ast.Literal(value=default))
+                return f'_lookup({k}, {{{entries}}}, "{mode}", {fallback})'
+
             case _:
-                return "0"
+                raise CompileError(f"can't generate Python for {type(expr).__name__}")
 
     def _gen_match(
         self,
@@ -190,7 +424,9 @@ class PythonGenerator:
         subj = self._gen_expr(subject, entity_var, scalars_var, computed)
         # Build nested ternary
         result = (
-            "0" if default is None else self._gen_expr(default, entity_var, scalars_var, computed)
+            f"_no_match({subj})"
+            if default is None
+            else self._gen_expr(default, entity_var, scalars_var, computed)
         )
         for pattern, value in reversed(cases):
             p = self._gen_expr(pattern, entity_var, scalars_var, computed)
@@ -201,21 +437,41 @@ class PythonGenerator:
     def _gen_builtin_call(self, func: str, args: list[str]) -> str:
         match func:
             case "min":
-                return f"min({', '.join(args)})"
+                return f"_min({', '.join(args)})"
             case "max":
-                return f"max({', '.join(args)})"
+                return f"_max({', '.join(args)})"
             case "abs":
                 return f"abs({args[0]})"
             case "round":
-                return f"round({args[0]})"
+                return f"_round({args[0]})"
             case "sum":
-                return f"sum({args[0]})"
+                return f"_sum({args[0]})"
             case "len":
                 return f"len({args[0]})"
             case "clip":
-                return f"min(max({args[0]}, {args[1]}), {args[2]})"
+                return f"_min(_max({args[0]}, {args[1]}), {args[2]})"
+            case "where":
+                return f"({args[1]} if {args[0]} else {args[2]})"
+            case "floor" | "ceil" | "trunc":
+                return f"_whole(math.{func}, {args[0]})"
+            case "sign":
+                return f"_sign({args[0]})"
+            case "interp":
+                return f"_interp({', '.join(args)})"
+            case "coalesce":
+                return f"next((v for v in ({', '.join(args)},) if v is not None), None)"
+            case "apportion":
+                return f"_apportion({', '.join(args)})"
+            case "equivalence_scale" | "equivalized_income":
+                return f"_{func}({', '.join(args)})"
+            case "is_missing":
+                return f"({args[0]} is None or _has_nan([{args[0]}]))"
+            case "age_at" | "year_of" | "month_of" | "months_between" | "days_between":
+                return f"_{func}({', '.join(args)})"
+            case "any" | "all":
+                return f"{func}({args[0]})"
             case _:
-                return f"0  # unknown: {func}"
+                raise CompileError(f"{func} has no Python translation; only the executor runs it")
 
     def _py_op(self, op: str) -> str:
         return {
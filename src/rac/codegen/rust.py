@@ -1,10 +1,27 @@
 """Rust code generator.
 
 Compiles IR to Rust source code that can be built with cargo.
+
+A model that draws random numbers gets a BLAKE2b port of rac.rng, and its
+compute functions take the master seed and the row's key (its id, or its
+index), so each draw is bit-for-bit the one the executor makes.
+
+Dates are numbers of days since 1970-01-01, NaN when missing.
 """
 
+from datetime import date
+
 from .. import ast
-from ..compiler import IR
+from ..compiler import (
+    IR,
+    CompileError,
+    ResolvedVar,
+    cross_entity_variables,
+    random_variables,
+)
+from ..typecheck import expression_type, variable_types
+
+EPOCH = date(1970, 1, 1)  # day 0 of a date
 
 
 def generate_rust(ir: IR, module_name: str = "rules") -> str:
@@ -17,7 +34,16 @@ class RustGenerator:
     """Generates Rust code from compiled IR."""
 
     def __init__(self, ir: IR, module_name: str):
+        if ir.fixed_point:
+            raise CompileError("fixed-point groups are only supported by the executor")
+        if cross := cross_entity_variables(ir):
+            raise CompileError(f"{cross[0]} reads another entity, which only the executor supports")
         self.ir = ir
+        self.random = set(random_variables(ir))
+        self.draws = ast.draw_counters(ir.variables[path].expr for path in self.random)
+        self.stream = "stream"  # the variable holding the current formula's stream
+        self.types, _ = variable_types(ir.variables, ir.schema_, ir.order)
+        self.entity: str | None = None  # the entity whose formulas are being generated
         self.module_name = module_name
         self.entity_vars: dict[str, list[str]] = {}
         for path in ir.order:
@@ -29,8 +55,181 @@ class RustGenerator:
         lines = [
             "//! Auto-generated by RAC compiler",
             "",
+            "// min/max propagate NaN (f64::min/max would drop it)",
+            "#[allow(dead_code)]",
+            "fn rac_min(a: f64, b: f64) -> f64 {",
+            "    if a.is_nan() || b.is_nan() { f64::NAN } else { a.min(b) }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_max(a: f64, b: f64) -> f64 {",
+            "    if a.is_nan() || b.is_nan() { f64::NAN } else { a.max(b) }",
+            "}",
+            "",
+            "// A zero divisor gives 0, and % takes the sign of the divisor",
+            "#[allow(dead_code)]",
+            "fn rac_div(a: f64, b: f64) -> f64 {",
+            "    if b == 0.0 { 0.0 } else { a / b }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_floor_div(a: f64, b: f64) -> f64 {",
+            "    if b == 0.0 { 0.0 } else { (a / b).floor() }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_mod(a: f64, b: f64) -> f64 {",
+            "    if b == 0.0 { return 0.0; }",
+            "    let r = a % b;",
+            "    if r == 0.0 { return 0.0_f64.copysign(b); }",
+            "    if (r < 0.0) != (b < 0.0) { r + b } else { r }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_pow(a: f64, b: f64) -> f64 {",
+            "    if a == 0.0 && b < 0.0 { 0.0 } else { a.powf(b) }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_apportion(",
+            "    amount: f64, start: f64, end: f64, lo: f64, hi: f64, inclusive: bool,",
+            ") -> f64 {",
+            "    let extra = if inclusive { 1.0 } else { 0.0 };",
+            "    let length = hi - lo + extra;",
+            "    if length <= 0.0 { return 0.0; }",
+            "    let covered = rac_max(0.0, rac_min(end, hi) - rac_max(start, lo) + extra);",
+            "    amount * covered / length",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_equivalence_scale(",
+            "    adults: f64, children: f64, first: f64, other: f64, child: f64,",
+            ") -> f64 {",
+            "    if adults > 0.0 { return first + other * (adults - 1.0) + child * children; }",
+            "    if children > 0.0 { return first + child * (children - 1.0); }",
+            "    if adults.is_nan() || children.is_nan() { f64::NAN } else { 0.0 }",
+            "}",
+            "",
+            "// Dates are days since 1970-01-01 in the proleptic Gregorian calendar",
+            "#[allow(dead_code)]",
+            "fn rac_ymd(days: f64) -> (i64, i64, i64) {",
+            "    let z = days as i64 + 719468;",
+            "    let era = z.div_euclid(146097);",
+            "    let doe = z - era * 146097;",
+            "    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;",
+            "    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);",
+            "    let mp = (5 * doy + 2) / 153;",
+            "    let m = if mp < 10 { mp + 3 } else { mp - 9 };",
+            "    (yoe + era * 400 + i64::from(m <= 2), m, doy - (153 * mp + 2) / 5 + 1)",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_age_at(on: f64, born: f64) -> f64 {",
+            "    if on.is_nan() || born.is_nan() { return f64::NAN; }",
+            "    let ((y1, m1, d1), (y0, m0, d0)) = (rac_ymd(on), rac_ymd(born));",
+            "    (y1 - y0 - i64::from((m1, d1) < (m0, d0))) as f64",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_months_between(start: f64, end: f64) -> f64 {",
+            "    if start.is_nan() || end.is_nan() { return f64::NAN; }",
+            "    let ((y0, m0, d0), (y1, m1, d1)) = (rac_ymd(start), rac_ymd(end));",
+            "    let months = (y1 - y0) * 12 + m1 - m0;",
+            "    let months = if months > 0 && d1 < d0 {",
+            "        months - 1",
+            "    } else if months < 0 && d1 > d0 {",
+            "        months + 1",
+            "    } else {",
+            "        months",
+            "    };",
+            "    months as f64",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_year_of(d: f64) -> f64 {",
+            "    if d.is_nan() { f64::NAN } else { rac_ymd(d).0 as f64 }",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_month_of(d: f64) -> f64 {",
+            "    if d.is_nan() { f64::NAN } else { rac_ymd(d).1 as f64 }",
+            "}",
+            "",
+            "// A clamped table is sorted by key; without a default or clamp, a key",
+            "// the table doesn't hold panics",
+            "#[allow(dead_code)]",
+            "fn rac_lookup(",
+            "    key: f64, table: &[(f64, f64)], clamp: bool, default: Option<f64>,",
+            ") -> f64 {",
+            "    if key.is_nan() { return f64::NAN; }",
+            "    if let Some(&(_, value)) = table.iter().find(|e| e.0 == key) { return value; }",
+            "    if let Some(value) = default { return value; }",
+            "    if !clamp { panic!(\"no lookup entry for: {}\", key); }",
+            "    table.iter().filter(|e| e.0 <= key).last().map_or(table[0].1, |e| e.1)",
+            "}",
+            "",
+            "// Neumaier-compensated, added in slice order",
+            "#[allow(dead_code)]",
+            "fn rac_sum(values: &[f64]) -> f64 {",
+            "    let (mut total, mut compensation) = (0.0_f64, 0.0_f64);",
+            "    for &x in values {",
+            "        let t = total + x;",
+            "        if t.is_finite() && total.abs() >= x.abs() {",
+            "            compensation += (total - t) + x;",
+            "        } else if t.is_finite() {",
+            "            compensation += (x - t) + total;",
+            "        }",
+            "        total = t;",
+            "    }",
+            "    total + compensation",
+            "}",
+            "",
+            "// A quotient within 1e-9 of a whole step counts as one",
+            "#[allow(dead_code)]",
+            "fn rac_round_to(x: f64, step: f64, places: i32, direction: &str) -> f64 {",
+            "    if !x.is_finite() { return x; }",
+            "    let q = (x / step * 1e9).round() / 1e9;",
+            "    let n = match direction {",
+            '        "down" => q.floor(),',
+            '        "up" => q.ceil(),',
+            "        _ => (q + 0.5).floor(),",
+            "    };",
+            "    let scale = 10_f64.powi(places);",
+            "    (n * step * scale).round() / scale",
+            "}",
+            "",
+            "// Flat beyond the first and last knots; xs strictly ascend",
+            "#[allow(dead_code)]",
+            "fn rac_interp(x: f64, xs: &[f64], ys: &[f64]) -> f64 {",
+            "    if x.is_nan() { return f64::NAN; }",
+            "    let k = xs.partition_point(|&knot| knot <= x);",
+            "    if k == 0 { return ys[0]; }",
+            "    if k == xs.len() { return ys[k - 1]; }",
+            "    ys[k - 1] + (ys[k] - ys[k - 1]) * (x - xs[k - 1]) / (xs[k] - xs[k - 1])",
+            "}",
+            "",
+            "#[allow(dead_code)]",
+            "fn rac_scale(average: bool, base: f64, brackets: &[(f64, f64)]) -> f64 {",
+            "    if base.is_nan() { return f64::NAN; }",
+            "    if average {",
+            "        let reached = brackets.iter().filter(|b| base >= b.0).last();",
+            "        let rate = reached.map_or(0.0, |b| b.1);",
+            "        return base * rate;",
+            "    }",
+            "    let mut total = 0.0;",
+            "    for (k, &(threshold, rate)) in brackets.iter().enumerate() {",
+            "        if base <= threshold { break; }",
+            "        let top = brackets.get(k + 1).map_or(base, |next| base.min(next.0));",
+            "        total += (top - threshold) * rate;",
+            "    }",
+            "    total",
+            "}",
+            "",
         ]
 
+        if self.random:
+            lines.extend(RANDOM_PRELUDE)
+
         for entity in self.ir.schema_.entities.values():
             lines.extend(self._gen_input_struct(entity))
             lines.append("")
@@ -87,9 +286,11 @@ class RustGenerator:
         return lines
 
     def _gen_compute_scalars(self) -> list[str]:
+        params = "seed: &str" if self.random else ""
+        self.entity = None
         lines = [
             "impl Scalars {",
-            "    pub fn compute() -> Self {",
+            f"    pub fn compute({params}) -> Self {{",
         ]
 
         scalar_names = []
@@ -97,13 +298,14 @@ class RustGenerator:
             var = self.ir.variables[path]
             if var.entity is None:
                 safe_name = self._rust_ident(path.replace("/", "_"))
-                expr_code = self._gen_expr(var.expr)
+                lines.extend(self._gen_stream(path, "        ", '"None"'))
+                expr_code = self._rounded(var, self._gen_expr(var.expr))
                 lines.append(f"        let {safe_name} = {expr_code};")
-                scalar_names.append(safe_name)
+                scalar_names.append((path, safe_name))
 
         lines.append("        Self {")
-        for name in scalar_names:
-            lines.append(f"            {name},")
+        for path, name in scalar_names:
+            lines.append(f"            {self._field_init(path, name)},")
         lines.append("        }")
         lines.append("    }")
         lines.append("}")
@@ -113,30 +315,57 @@ class RustGenerator:
         type_name = self._rust_type_name(entity_name)
         var_paths = self.entity_vars[entity_name]
 
+        params = f"input: &{type_name}Input, scalars: &Scalars"
+        if self.random:
+            params += ", seed: &str, key: &str"
         lines = [
             f"impl {type_name}Output {{",
             "    #[inline]",
-            f"    pub fn compute(input: &{type_name}Input, scalars: &Scalars) -> Self {{",
+            f"    pub fn compute({params}) -> Self {{",
         ]
 
         computed_names = []
+        self.entity = entity_name
         for path in var_paths:
             safe_name = self._rust_ident(path.replace("/", "_"))
             var = self.ir.variables[path]
+            lines.extend(self._gen_stream(path, "        ", "key"))
             expr_code = self._gen_expr(
                 var.expr, entity_var="input", scalars_var="scalars", computed=computed_names.copy()
             )
+            expr_code = self._rounded(var, expr_code)
             lines.append(f"        let {safe_name} = {expr_code};")
             computed_names.append((path, safe_name))
 
         lines.append("        Self {")
-        for _, name in computed_names:
-            lines.append(f"            {name},")
+        for path, name in computed_names:
+            lines.append(f"            {self._field_init(path, name)},")
         lines.append("        }")
         lines.append("    }")
         lines.append("}")
         return lines
 
+    def _field_init(self, path: str, name: str) -> str:
+        """An output field from its local; outputs are f64, so a bool counts 1 or 0."""
+        return f"{name}: f64::from({name})" if self.types.get(path) == "bool" else name
+
+    def _type(self, expr: ast.Expr) -> str:
+        return expression_type(expr, self.entity, self.ir.schema_, self.types)
+
+    def _gen_stream(self, path: str, indent: str, key: str) -> list[str]:
+        """The stream a random variable's draws come from, as rac.rng.stream_id."""
+        if path not in self.random:
+            return []
+        return [f'{indent}let {self.stream} = rac_stream(seed, "{path}", {key});']
+
+    def _rounded(self, var: ResolvedVar, code: str) -> str:
+        """``code`` rounded as the variable says, if it says."""
+        if var.round_to is None:
+            return code
+        step, places = float(var.round_to), ast.round_places(var.round_to)
+        direction = var.rounding or "nearest"
+        return f'rac_round_to(({code}) as f64, {step!r}_f64, {places}, "{direction}")'
+
     def _gen_expr(
         self,
         expr: ast.Expr,
@@ -149,10 +378,16 @@ class RustGenerator:
 
         match expr:
             case ast.Literal(value=v):
+                if isinstance(v, date):
+                    days = (v - EPOCH).days
+                    return f"{days}_f64" if days >= 0 else f"({days}_f64)"
                 if isinstance(v, bool):
                     return "true" if v else "false"
                 if isinstance(v, str):
                     return f'"{v}"'
+                if isinstance(v, list):
+                    items = [self._gen_expr(ast.Literal(value=item)) for item in v]
+                    return f"[{', '.join(items)}]"
                 return f"{v}_f64"
 
             case ast.Var(path=path):
@@ -167,9 +402,25 @@ class RustGenerator:
                     return f"{entity_var}.{self._rust_ident(path)}"
                 return self._rust_ident(path)
 
-            case ast.BinOp(op=op, left=left, right=right):
+            case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
                 left_val = self._gen_expr(left, entity_var, scalars_var, computed)
                 right_val = self._gen_expr(right, entity_var, scalars_var, computed)
+                if tolerance is not None and op in ("==", "!="):
+                    close = f"({left_val} - {right_val}).abs() <= {tolerance!r}_f64"
+                    return f"({close})" if op == "==" else f"!({close})"
+                if op in ("in", "not in"):
+                    # &str compares with String and &str alike; numbers as f64
+                    item = left_val if self._type(left) == "str" else f"({left_val}) as f64"
+                    member = f"{right_val}.iter().any(|c| *c == {item})"
+                    return member if op == "in" else f"!{member}"
+                helper = {
+                    "/": "rac_div",
+                    "//": "rac_floor_div",
+                    "%": "rac_mod",
+                    "**": "rac_pow",
+                }.get(op)
+                if helper:
+                    return f"{helper}({left_val}, {right_val})"
                 rust_op = self._rust_op(op)
                 return f"({left_val} {rust_op} {right_val})"
 
@@ -183,6 +434,11 @@ class RustGenerator:
 
             case ast.Call(func=func, args=args):
                 arg_strs = [self._gen_expr(a, entity_var, scalars_var, computed) for a in args]
+                if func in ast.RANDOM:
+                    numbers = "".join(f", ({a}) as f64" for a in arg_strs)
+                    return f"rac_{func}(&{self.stream}, {self.draws[id(expr)]}{numbers})"
+                if func == "is_missing":
+                    return self._is_missing(args[0], arg_strs[0])
                 return self._gen_builtin_call(func, arg_strs)
 
             case ast.FieldAccess(obj=obj, field=field):
@@ -195,36 +451,129 @@ class RustGenerator:
                 e = self._gen_expr(else_e, entity_var, scalars_var, computed)
                 return f"(if {c} {{ {t} }} else {{ {e} }})"
 
+            case ast.Scale(base=base, brackets=brackets, mode=mode):
+                b = self._gen_expr(base, entity_var, scalars_var, computed)
+                pairs = ", ".join(
+                    f"({self._gen_expr(t, entity_var, scalars_var, computed)}, "
+                    f"{self._gen_expr(r, entity_var, scalars_var, computed)})"
+                    for t, r in brackets
+                )
+                return f"rac_scale({str(mode == 'average').lower()}, {b}, &[{pairs}])"
+
+            case ast.Lookup(key=key, table=table, out_of_range=mode, default=default):
+                k = self._gen_expr(key, entity_var, scalars_var, computed)
+                entries = ", ".join(
+                    f"({self._number(tk)}, {self._number(v)})"
+                    for tk, v in (sorted(table) if mode == "clamp" else table)
+                )
+                fallback = "None" if mode != "default" else f"Some({self._number(default)})"
+                clamp = str(mode == "clamp").lower()
+                return f"rac_lookup(({k}) as f64, &[{entries}], {clamp}, {fallback})"
+
+            case ast.Match(subject=subject, cases=cases, default=default):
+                subj = self._gen_expr(subject, entity_var, scalars_var, computed)
+                if self._type(subject) != "str":
+                    subj = f"(({subj}) as f64)"
+                result = (
+                    f'panic!("no match for: {{:?}}", {subj})'
+                    if default is None
+                    else self._gen_expr(default, entity_var, scalars_var, computed)
+                )
+                for pattern, value in reversed(cases):
+                    p = self._gen_expr(pattern, entity_var, scalars_var, computed)
+                    v = self._gen_expr(value, entity_var, scalars_var, computed)
+                    result = f"(if {subj} == {p} {{ {v} }} else {{ {result} }})"
+                return result
+
             case _:
-                return "0.0_f64"
+                raise CompileError(f"can't generate Rust for {type(expr).__name__}")
+
+    def _number(self, value) -> str:
+        """A lookup entry as an f64 literal; booleans count as 1 and 0."""
+        if isinstance(value, str):
+            raise CompileError(f"lookup entry {value!r} is a string, which Rust can't compute with")
+        return f"{float(value)!r}_f64"
 
     def _gen_builtin_call(self, func: str, args: list[str]) -> str:
         match func:
             case "min":
                 if len(args) == 2:
-                    return f"{args[0]}.min({args[1]})"
-                return f"[{', '.join(args)}].iter().cloned().fold(f64::INFINITY, f64::min)"
+                    return f"rac_min({args[0]}, {args[1]})"
+                return f"[{', '.join(args)}].iter().cloned().fold(f64::INFINITY, rac_min)"
             case "max":
                 if len(args) == 2:
-                    return f"{args[0]}.max({args[1]})"
-                return f"[{', '.join(args)}].iter().cloned().fold(f64::NEG_INFINITY, f64::max)"
+                    return f"rac_max({args[0]}, {args[1]})"
+                return f"[{', '.join(args)}].iter().cloned().fold(f64::NEG_INFINITY, rac_max)"
             case "abs":
                 return f"{args[0]}.abs()"
             case "round":
-                return f"{args[0]}.round()"
+                # Halves to even, as Python's round; + 0.0 turns -0 into the 0 its int gives
+                return f"({args[0]}.round_ties_even() + 0.0)"
             case "sum":
-                return f"{args[0]}.iter().sum::<f64>()"
+                return f"rac_sum(&{args[0]})"
             case "len":
                 return f"({args[0]}.len() as f64)"
             case "clip":
-                return f"{args[0]}.max({args[1]}).min({args[2]})"
+                return f"rac_min(rac_max({args[0]}, {args[1]}), {args[2]})"
+            case "where":
+                return f"(if {args[0]} {{ {args[1]} }} else {{ {args[2]} }})"
+            case "floor" | "ceil" | "trunc":
+                return f"({args[0]}.{func}() + 0.0)"
+            case "interp":
+                return f"rac_interp(({args[0]}) as f64, &{args[1]}, &{args[2]})"
+            case "sign":
+                # 0 for zero, where f64::signum gives 1
+                return (
+                    f"{{ let v = {args[0]}; "
+                    f"if v > 0.0 {{ 1.0 }} else if v < 0.0 {{ -1.0 }} else {{ v * 0.0 }} }}"
+                )
+            case "coalesce":
+                # Missing inputs are NaN in the native representation
+                result = args[-1]
+                for arg in reversed(args[:-1]):
+                    result = f"{{ let v = {arg}; if v.is_nan() {{ {result} }} else {{ v }} }}"
+                return result
+            case "apportion":
+                inclusive = args[5] if len(args) > 5 else "true"
+                return f"rac_apportion({', '.join(args[:5])}, {inclusive})"
+            case "equivalence_scale":
+                return f"rac_equivalence_scale({', '.join(self._with_weights(args, 2))})"
+            case "equivalized_income":
+                # Native inputs are per-row numbers: pass sum(members.income) for a group
+                weighted = ", ".join(self._with_weights(args[1:], 2))
+                return (
+                    f"{{ let s = rac_equivalence_scale({weighted}); "
+                    f"if s == 0.0 {{ 0.0 }} else {{ {args[0]} / s }} }}"
+                )
+            case "age_at" | "months_between" | "year_of" | "month_of":
+                return f"rac_{func}({', '.join(args)})"
+            case "days_between":
+                return f"({args[1]} - {args[0]})"
+            case "any":
+                return f"{args[0]}.iter().any(|v| *v != 0.0)"
+            case "all":
+                return f"{args[0]}.iter().all(|v| *v != 0.0)"
             case _:
-                return f"/* unknown: {func} */ 0.0_f64"
+                raise CompileError(f"{func} has no Rust translation; only the executor runs it")
+
+    def _is_missing(self, arg: ast.Expr, code: str) -> str:
+        """Missing numbers are NaN in the native representation; other values
+        can't be missing there."""
+        kind = self._type(arg)
+        if kind in ("str", "bool"):
+            raise CompileError(f"is_missing of a {kind} has no Rust translation")
+        return f"{code}.is_nan()" if kind != "int" else f"(({code}) as f64).is_nan()"
+
+    def _with_weights(self, args: list[str], given: int) -> list[str]:
+        """``args`` padded with the default equivalence_scale weights."""
+        defaults = [f"{w}_f64" for w in ast.OECD_MODIFIED]
+        return args + defaults[len(args) - given :]
 
     def _rust_op(self, op: str) -> str:
         return {
             "and": "&&",
             "or": "||",
+            "xor": "^",
             "==": "==",
             "!=": "!=",
         }.get(op, op)
@@ -243,6 +592,99 @@ class RustGenerator:
             "int": "i64",
             "float": "f64",
             "str": "String",
+            "category": "String",
             "bool": "bool",
-            "date": "String",
         }.get(dtype, "f64")
+
+
+# BLAKE2b with an 8-byte digest (RFC 7693), as hashlib.blake2b(digest_size=8)
+RANDOM_PRELUDE = [
+    "const RAC_IV: [u64; 8] = [",
+    "    0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,",
+    "    0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179,",
+    "];",
+    "",
+    "const RAC_SIGMA: [[usize; 16]; 12] = [",
+    "    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],",
+    "    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],",
+    "    [11, 8, 12, 0, 5, 2, 15, 13, 10, 14, 3, 6, 7, 1, 9, 4],",
+    "    [7, 9, 3, 1, 13, 12, 11, 14, 2, 6, 5, 10, 4, 0, 15, 8],",
+    "    [9, 0, 5, 7, 2, 4, 10, 15, 14, 1, 11, 12, 6, 8, 3, 13],",
+    "    [2, 12, 6, 10, 0, 11, 8, 3, 4, 13, 7, 5, 15, 14, 1, 9],",
+    "    [12, 5, 1, 15, 14, 13, 4, 10, 0, 7, 6, 3, 9, 2, 8, 11],",
+    "    [13, 11, 7, 14, 12, 1, 3, 9, 5, 0, 15, 4, 8, 6, 2, 10],",
+    "    [6, 15, 14, 9, 11, 3, 0, 8, 12, 2, 13, 7, 1, 4, 10, 5],",
+    "    [10, 2, 8, 4, 7, 6, 1, 5, 15, 11, 9, 14, 3, 12, 13, 0],",
+    "    [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],",
+    "    [14, 10, 4, 8, 9, 15, 13, 6, 1, 12, 0, 2, 11, 7, 5, 3],",
+    "];",
+    "",
+    "fn rac_blake2b_64(data: &[u8]) -> u64 {",
+    "    let mut h = RAC_IV;",
+    "    h[0] ^= 0x0101_0008; // no key, 8-byte digest",
+    "    let blocks = data.len().max(1).div_ceil(128);",
+    "    for b in 0..blocks {",
+    "        let chunk = &data[b * 128..data.len().min(b * 128 + 128)];",
+    "        let mut block = [0u8; 128];",
+    "        block[..chunk.len()].copy_from_slice(chunk);",
+    "        let m: Vec<u64> = block",
+    "            .chunks(8)",
+    "            .map(|w| u64::from_le_bytes(w.try_into().unwrap()))",
+    "            .collect();",
+    "        let last = b + 1 == blocks;",
+    "        let mut v = [0u64; 16];",
+    "        v[..8].copy_from_slice(&h);",
+    "        v[8..].copy_from_slice(&RAC_IV);",
+    "        v[12] ^= (if last { data.len() } else { (b + 1) * 128 }) as u64;",
+    "        if last { v[14] = !v[14]; }",
+    "        for s in RAC_SIGMA.iter() {",
+    "            for (i, &(a, b, c, d)) in [",
+    "                (0, 4, 8, 12), (1, 5, 9, 13), (2, 6, 10, 14), (3, 7, 11, 15),",
+    "                (0, 5, 10, 15), (1, 6, 11, 12), (2, 7, 8, 13), (3, 4, 9, 14),",
+    "            ].iter().enumerate() {",
+    "                let (x, y) = (m[s[2 * i]], m[s[2 * i + 1]]);",
+    "                v[a] = v[a].wrapping_add(v[b]).wrapping_add(x);",
+    "                v[d] = (v[d] ^ v[a]).rotate_right(32);",
+    "                v[c] = v[c].wrapping_add(v[d]);",
+    "                v[b] = (v[b] ^ v[c]).rotate_right(24);",
+    "                v[a] = v[a].wrapping_add(v[b]).wrapping_add(y);",
+    "                v[d] = (v[d] ^ v[a]).rotate_right(16);",
+    "                v[c] = v[c].wrapping_add(v[d]);",
+    "                v[b] = (v[b] ^ v[c]).rotate_right(63);",
+    "            }",
+    "        }",
+    "        for i in 0..8 { h[i] ^= v[i] ^ v[i + 8]; }",
+    "    }",
+    "    h[0]",
+    "}",
+    "",
+    "// rac.rng.stream_id: key is the row's id or index as Python repr()s it",
+    "#[allow(dead_code)]",
+    "fn rac_stream(seed: &str, feature: &str, key: &str) -> String {",
+    '    let digest = rac_blake2b_64(format!("{}:{}:{}", seed, feature, key).as_bytes());',
+    '    format!("{:016x}", digest.swap_bytes())',
+    "}",
+    "",
+    "// rac.rng.uniform_at",
+    "fn rac_uniform(stream: &str, counter: u64) -> f64 {",
+    '    let digest = rac_blake2b_64(format!("{}:{}", stream, counter).as_bytes());',
+    "    digest as f64 / 18446744073709551616.0",
+    "}",
+    "",
+    "// A missing (NaN) probability fails: the executor's missing draw has no bool",
+    "#[allow(dead_code)]",
+    "fn rac_bernoulli(stream: &str, counter: u64, p: f64) -> bool {",
+    '    if p.is_nan() { panic!("bernoulli probability is missing"); }',
+    '    if p < 0.0 || p > 1.0 { panic!("bernoulli probability {} is not in [0, 1]", p); }',
+    "    rac_uniform(stream, counter) < p",
+    "}",
+    "",
+    "// Box-Muller, from the counter-th and next uniforms",
+    "#[allow(dead_code)]",
+    "fn rac_normal(stream: &str, counter: u64, mu: f64, sigma: f64) -> f64 {",
+    '    if sigma < 0.0 { panic!("normal standard deviation {} is negative", sigma); }',
+    "    let (u, v) = (rac_uniform(stream, counter), rac_uniform(stream, counter + 1));",
+    "    mu + sigma * (-2.0 * (1.0 - u).ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()",
+    "}",
+    "",
+]
@@ -0,0 +1,354 @@
+"""CompiledModel: check and prepare an IR once, then run it many times.
+
+run() on a bare IR builds an executor and evaluates every scalar on each
+call, though scalars never depend on the data. A CompiledModel loads and
+checks the IR once, resolves its scalars once, and keeps an executor per
+set of requested outputs, so thousands of scenario runs pay only for the
+entity variables.
+
+run_with_reform() overlays a reform on the same model: overridden
+variables take new values or expressions, and only they and the variables
+downstream of them are evaluated again. run_batches() streams a dataset
+through the model a chunk at a time, run_parquet() a Parquet file,
+run_csv() a CSV file with its column types inferred, and run_polars() a
+polars DataFrame.
+
+save() writes a model to a versioned binary file and load() reads it back,
+so a service can start from a file rather than parsing rule sources.
+
+The backend picks how entity formulas run: "tree" walks each expression
+a column at a time, "bytecode" compiles each formula to a flat program
+once per model (see bytecode.py) and runs it row by row, and "native"
+builds the model into a Rust binary once (see native.py; needs numpy and
+cargo) and runs every row there. A config with ``decimal_places`` set runs
+the model in fixed-point decimal arithmetic instead of binary floating
+point (see fixed.py), on the tree walker.
+"""
+
+import json
+import math
+import struct
+import zlib
+from collections.abc import Iterable, Iterator, Mapping, Sequence
+from pathlib import Path
+from typing import TYPE_CHECKING, Any
+
+from pydantic import BaseModel
+
+from . import ast
+from .arrow import ReadStats, execute_from_parquet_file, execute_polars
+from .compiler import (
+    IR,
+    MAX_IR_BYTES,
+    CompileError,
+    dependents,
+    expression_deps,
+    load_ir,
+    prune,
+    topological_order,
+)
+from .config import EngineConfig, resolve_config
+from .executor import Executor, Result
+from .ingest import US, NumberFormat, read_csv
+from .plan import ir_problems
+from .schema import Data
+from .typecheck import variable_types
+
+if TYPE_CHECKING:
+    from .native import CompiledBinary
+
+BACKENDS = ("tree", "bytecode", "native")
+
+# A saved model is a header, then the zlib-compressed JSON of the IR, the
+# config and the backend. The IR inside carries its own version, so files
+# written before an IR layout change still load through upgrade_ir.
+MODEL_MAGIC = b"RACMODEL"
+MODEL_FORMAT_VERSION = 1
+_HEADER = struct.Struct(">8sHI")  # magic, format version, CRC-32 of the payload
+
+
+class ReformComparison(BaseModel):
+    """Baseline and reform results of one dataset, side by side."""
+
+    baseline: Result
+    reform: Result
+    changed: list[str]  # overridden variables and everything downstream, in order
+
+
+class CompiledModel:
+    """An IR checked once and ready to run against any number of datasets."""
+
+    def __init__(self, ir: IR, config: EngineConfig | None = None, backend: str = "tree"):
+        if backend not in BACKENDS:
+            raise CompileError(
+                f"unknown backend {backend!r}: expected one of {', '.join(BACKENDS)}"
+            )
+        if problems := ir_problems(ir):
+            raise CompileError(problems[0])
+        self.ir = ir
+        self.backend = backend
+        if backend == "bytecode":
+            config = (config or EngineConfig()).overlay(EngineConfig(bytecode=True))
+        self.config = config
+        self._binary: CompiledBinary | None = None
+        if backend == "native":
+            if resolve_config(config).decimal_places is not None:
+                raise CompileError("the native backend has no fixed-point arithmetic")
+            from .native import compile_to_binary  # deferred: needs numpy
+
+            self._binary = compile_to_binary(ir)
+            self._types, _ = variable_types(ir.variables, ir.schema_, ir.order)
+        self._executors: dict[tuple[str, ...] | None, Executor] = {}
+        empty = Data(tables={}, schema_=ir.schema_)
+        # Fixed-point groups are solved afresh on every run, so only the
+        # plain scalars are carried over
+        scalars = self._executor(None).execute(empty).scalars
+        grouped = {path for group in ir.fixed_point for path in group}
+        self._scalars = Result(
+            scalars={path: v for path, v in scalars.items() if path not in grouped},
+            entities={},
+        )
+
+    @classmethod
+    def compile(
+        cls,
+        source: IR | str | bytes | Mapping[str, Any],
+        config: EngineConfig | None = None,
+        backend: str = "tree",
+    ) -> "CompiledModel":
+        """From an IR, or its JSON text or dict as accepted by load_ir."""
+        if isinstance(source, IR):
+            return cls(source, config, backend)
+        ir = load_ir(dict(source) if isinstance(source, Mapping) else source)
+        return cls(ir, config, backend)
+
+    def save(self, path: str | Path) -> None:
+        """Write the model to ``path`` in the binary format load() reads."""
+        envelope = {
+            "backend": self.backend,
+            "config": None if self.config is None else self.config.model_dump(mode="json"),
+            "ir": self.ir.model_dump(mode="json"),
+        }
+        payload = zlib.compress(json.dumps(envelope, separators=(",", ":")).encode())
+        header = _HEADER.pack(MODEL_MAGIC, MODEL_FORMAT_VERSION, zlib.crc32(payload))
+        Path(path).write_bytes(header + payload)
+
+    @classmethod
+    def load(cls, path: str | Path) -> "CompiledModel":
+        """Read a model written by save().
+
+        The IR is validated as by load_ir; a file that isn't a saved
+        model, is truncated or corrupt, or has a newer format version
+        raises CompileError.
+        """
+        blob = Path(path).read_bytes()
+        if len(blob) < _HEADER.size or not blob.startswith(MODEL_MAGIC):
+            raise CompileError(f"{path} is not a saved model")
+        _, version, checksum = _HEADER.unpack_from(blob)
+        if version > MODEL_FORMAT_VERSION:
+            raise CompileError(
+                f"{path}: model format {version} is newer than supported "
+                f"({MODEL_FORMAT_VERSION})"
+            )
+        payload = blob[_HEADER.size :]
+        if zlib.crc32(payload) != checksum:
+            raise CompileError(f"{path}: checksum mismatch, the file is corrupt")
+        decompressor = zlib.decompressobj()
+        text = decompressor.decompress(payload, MAX_IR_BYTES)
+        if decompressor.unconsumed_tail:
+            raise CompileError(f"{path}: model is larger than {MAX_IR_BYTES} bytes")
+        try:
+            envelope = json.loads(text)
+            config = envelope["config"]
+            return cls(
+                load_ir(envelope["ir"]),
+                None if config is None else EngineConfig.model_validate(config),
+                envelope["backend"],
+            )
+        except (ValueError, KeyError, TypeError) as e:
+            raise CompileError(f"{path}: malformed model: {e}") from None
+
+    def _executor(self, outputs: list[str] | None) -> Executor:
+        key = None if outputs is None else tuple(outputs)
+        if key not in self._executors:
+            ir = self.ir if outputs is None else prune(self.ir, outputs)
+            self._executors[key] = Executor(ir, config=self.config)
+        return self._executors[key]
+
+    @property
+    def scalars(self) -> dict[str, Any]:
+        return dict(self._scalars.scalars)
+
+    def run(
+        self,
+        data: Data | dict[str, list[dict]],
+        outputs: list[str] | None = None,
+    ) -> Result:
+        """Evaluate the entity variables (only ``outputs`` and their
+        dependencies, if given) against ``data``, reusing the scalars."""
+        if self._binary is not None:
+            tables = data.tables if isinstance(data, Data) else data
+            return self._run_native(self._binary, tables, outputs)
+        executor = self._executor(outputs)
+        if isinstance(data, dict):
+            data = Data(tables=data, schema_=self.ir.schema_)
+        return executor.execute(data, reuse=self._scalars)
+
+    def _run_native(
+        self, binary: "CompiledBinary", tables: dict[str, list[dict]], outputs: list[str] | None
+    ) -> Result:
+        """The entity variables as ``binary`` computes them, its f64 columns
+        read back as the tree walker gives them: bools, ints, and None for
+        NaN."""
+        cfg = resolve_config(self.config)
+        arrays = binary.run(
+            tables,
+            cfg.number_format,
+            num_threads=cfg.num_threads,
+            chunk_size=cfg.chunk_size,
+            parallel=cfg.parallel,
+            deterministic=cfg.deterministic,
+            seed=cfg.seed,
+        )
+        wanted = set(self.ir.order if outputs is None else prune(self.ir, outputs).order)
+        entities = {}
+        for entity, array in arrays.items():
+            paths = binary.entity_outputs[entity]
+            entities[entity] = {
+                path: [self._native_value(path, v) for v in array[:, j].tolist()]
+                for j, path in enumerate(paths)
+                if path in wanted
+            }
+        return Result(scalars=self.scalars, entities=entities)
+
+    def _native_value(self, path: str, value: float) -> Any:
+        if math.isnan(value):
+            return None
+        match self._types.get(path):
+            case "bool":
+                return value != 0
+            case "int":
+                return int(value)
+        return value
+
+    def run_batches(
+        self,
+        batches: Iterable[Data | dict[str, list[dict]]],
+        outputs: list[str] | None = None,
+    ) -> Iterator[Result]:
+        """Run each batch in turn, yielding its result before the next batch
+        is read.
+
+        Only one batch and its result are held at a time, so a dataset larger
+        than memory can be run from a generator of chunks. Each batch must be
+        self-contained: rows linked across entities (a household and its
+        members) belong in the same batch.
+        """
+        for batch in batches:
+            yield self.run(batch, outputs)
+
+    def run_parquet(
+        self,
+        input_path: str | Path,
+        output_path: str | Path,
+        entity: str,
+        columns: list[str] | None = None,
+        passthrough: Sequence[str] = (),
+        stats: ReadStats | None = None,
+    ) -> int:
+        """Evaluate ``entity`` over a Parquet file, writing the ``columns``
+        (default: every variable of the entity) to a Parquet file.
+
+        Each batch goes through run(), so it reuses the model's scalars and
+        executors. See arrow.execute_from_parquet_file; requires pyarrow.
+        Returns the number of rows processed.
+        """
+
+        def evaluate(batch: Any) -> dict[str, list[Any]]:
+            return self.run({entity: batch.to_pylist()}, columns).entities.get(entity, {})
+
+        return execute_from_parquet_file(
+            self.ir,
+            entity,
+            input_path,
+            output_path,
+            outputs=columns,
+            passthrough=passthrough,
+            stats=stats,
+            evaluate=evaluate,
+        )
+
+    def run_polars(
+        self,
+        frame: Any,
+        entity: str,
+        outputs: list[str] | None = None,
+        passthrough: Sequence[str] = (),
+    ) -> Any:
+        """Evaluate ``entity`` over a polars DataFrame, returning a DataFrame
+        of the ``passthrough`` columns then the outputs.
+
+        See arrow.execute_polars; requires polars and pyarrow.
+        """
+        return execute_polars(self.ir, entity, frame, outputs, passthrough, self.config)
+
+    def run_csv(
+        self,
+        path: str | Path,
+        entity: str,
+        outputs: list[str] | None = None,
+        delimiter: str = ",",
+        number_format: NumberFormat | None = None,
+    ) -> dict[str, list[Any]]:
+        """Evaluate ``entity`` over the rows of a CSV file, returning a column
+        per computed variable.
+
+        Column types are inferred as by read_csv: numeric, boolean, or
+        string. ``number_format`` defaults to the config's, else US.
+        """
+        fmt = number_format or resolve_config(self.config).number_format or US
+        rows, _ = read_csv(path, number_format=fmt, delimiter=delimiter)
+        return self.run({entity: rows}, outputs).entities.get(entity, {})
+
+    def reformed(self, overrides: Mapping[str, Any]) -> IR:
+        """The IR with each override applied: an expression (an ast node)
+        replaces the variable's formula, any other value fixes it."""
+        variables = dict(self.ir.variables)
+        for path, override in overrides.items():
+            if path not in variables:
+                raise CompileError(f"unknown variable: {path}")
+            expr = override if isinstance(override, BaseModel) else ast.Literal(value=override)
+            variables[path] = variables[path].model_copy(
+                update={"expr": expr, "deps": expression_deps(expr)}
+            )
+        order = topological_order(variables, self.ir.fixed_point)
+        ir = self.ir.model_copy(update={"variables": variables, "order": order})
+        if problems := ir_problems(ir):
+            raise CompileError(problems[0])
+        return ir
+
+    def run_with_reform(
+        self,
+        data: Data | dict[str, list[dict]],
+        overrides: Mapping[str, Any],
+        outputs: list[str] | None = None,
+        compare: bool = False,
+    ) -> Result | ReformComparison:
+        """Evaluate ``data`` under ``overrides`` (see reformed) without
+        recompiling the model.
+
+        Variables the overrides can't affect keep their baseline values:
+        the scalars always, and with ``compare`` the entity columns too, as
+        the baseline is run first and returned alongside the reform.
+        """
+        ir = self.reformed(overrides)
+        changed = dependents(ir, list(overrides))
+        if isinstance(data, dict):
+            data = Data(tables=data, schema_=self.ir.schema_)
+        baseline = self.run(data, outputs) if compare else self._scalars
+        executor = Executor(ir if outputs is None else prune(ir, outputs), config=self.config)
+        reform = executor.execute(data, reuse=baseline, recompute=changed)
+        if not compare:
+            return reform
+        order = [path for path in ir.order if path in changed]
+        return ReformComparison(baseline=baseline, reform=reform, changed=order)
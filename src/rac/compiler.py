@@ -4,12 +4,19 @@ Takes parsed modules and an as_of date, resolves which temporal values apply,
 and produces a flat variable graph.
 """
 
+import json
+import math
+from collections import Counter
+from collections.abc import Callable
 from datetime import date
+from typing import Any
 
-from pydantic import BaseModel, ConfigDict
+from pydantic import BaseModel, ConfigDict, ValidationError
 
 from . import ast
+from .functions import get_function
 from .schema import Entity, Field, ForeignKey, ReverseRelation, Schema
+from .typecheck import expression_type, variable_types
 
 
 class ResolvedVar(BaseModel):
@@ -23,24 +30,67 @@ class ResolvedVar(BaseModel):
     label: str | None = None
     description: str | None = None
     unit: str | None = None
+    requires_feature: str | None = None
+    arithmetic: str | None = None
+    missing: str | None = None  # policy for a missing result; None propagates it
+    default: Any = None  # declared default, which missing: "default" substitutes
+    round_to: int | float | None = None  # the result rounds to a multiple of this
+    rounding: str | None = None  # direction for round_to; None rounds to the nearest
+    effective: date | None = None  # start of the temporal value in force; None for a default
     expr: ast.Expr
     deps: set[str] = set()
 
 
+# Bump when the serialized IR layout changes, and teach upgrade_ir the old one.
+IR_VERSION = 1
+
+# Limits on IR accepted by load_ir, which may come from untrusted callers
+MAX_IR_BYTES = 64 * 1024 * 1024
+MAX_IR_NODES = 1_000_000
+MAX_IR_NESTING = 250  # JSON containers; pydantic will not serialize past 255
+LITERAL_TYPES = (int, float, str, bool, date, type(None))
+ZEROS = {"bool": False, "float": 0.0, "str": ""}  # stand-ins by type; others read 0
+
+
+def _literal_ok(value: Any) -> bool:
+    """A scalar, or a list of scalars (the categories of ``in``)."""
+    if isinstance(value, list):
+        return all(isinstance(item, LITERAL_TYPES) for item in value)
+    return isinstance(value, LITERAL_TYPES)
+
+
 class IR(BaseModel):
     """Intermediate representation: resolved variable graph + schema."""
 
     model_config = ConfigDict(arbitrary_types_allowed=True)
 
+    version: int = IR_VERSION
     schema_: Schema
     variables: dict[str, ResolvedVar]
     order: list[str]  # topologically sorted variable paths
+    fixed_point: list[list[str]] = []  # circular groups solved by iteration, as ordered
 
 
 class CompileError(Exception):
     pass
 
 
+class CycleError(CompileError):
+    """Variables that depend on each other in a loop."""
+
+    def __init__(self, cycle: list[str]):
+        self.cycle = cycle  # from a variable back round to itself
+        super().__init__(f"circular dependency: {' -> '.join(cycle)}")
+
+
+class IRSchemaError(CompileError):
+    """A serialized IR that doesn't match the IR schema, and where."""
+
+    def __init__(self, path: str, message: str):
+        self.path = path  # JSON path of the offending value, from "$"
+        super().__init__(f"invalid IR at {path}: {message}")
+
+
 class TemporalLayer:
     """Tracks temporal values for a variable, with amendment stacking."""
 
@@ -52,6 +102,12 @@ class TemporalLayer:
         label: str | None = None,
         description: str | None = None,
         unit: str | None = None,
+        requires_feature: str | None = None,
+        arithmetic: str | None = None,
+        missing: str | None = None,
+        default: Any = None,
+        round_to: int | float | None = None,
+        rounding: str | None = None,
     ):
         self.path = path
         self.entity = entity
@@ -59,6 +115,12 @@ class TemporalLayer:
         self.label = label
         self.description = description
         self.unit = unit
+        self.requires_feature = requires_feature
+        self.arithmetic = arithmetic
+        self.missing = missing
+        self.default = default
+        self.round_to = round_to
+        self.rounding = rounding
         self.values: list[ast.TemporalValue] = []
         self.repealed_after: date | None = None
 
@@ -73,46 +135,88 @@ class TemporalLayer:
 
     def resolve(self, as_of: date) -> ast.Expr | None:
         """Get the applicable expression for a date. Later values win."""
+        tv = self.resolve_value(as_of)
+        return tv.expr if tv is not None else None
+
+    def resolve_value(self, as_of: date) -> ast.TemporalValue | None:
+        """The temporal value in force on a date, with its bounds."""
         if self.repealed_after and as_of >= self.repealed_after:
             return None
 
         result = None
         for tv in self.values:
             if tv.start <= as_of and (tv.end is None or as_of <= tv.end):
-                result = tv.expr
+                result = tv
         return result
 
 
 class Compiler:
     """Compiles parsed modules into IR."""
 
-    def __init__(self, modules: list[ast.Module]):
+    def __init__(
+        self,
+        modules: list[ast.Module],
+        features: set[str] | None = None,
+        fixed_point: set[str] | None = None,
+    ):
         self.modules = modules
+        self.features = set(features or ())
+        self.fixed_point = set(fixed_point or ())
         self.schema = Schema()
         self.layers: dict[str, TemporalLayer] = {}
+        self._collected = False
 
     def compile(self, as_of: date) -> IR:
-        for module in self.modules:
-            self._collect_entities(module)
-            self._collect_variables(module)
-            self._apply_amendments(module)
-            self._apply_repeals(module)
+        """Resolve every variable in force on ``as_of``.
+
+        Variables tagged ``requires_feature`` are left out entirely unless
+        their feature is enabled, and references to them compile to 0.
+        Modules with a ``namespace`` are compiled under that prefix.
+        Circular dependencies are errors, except among the ``fixed_point``
+        variables: each cycle of those becomes a group the executor solves
+        by iteration.
+
+        The modules are collected on the first call, so compiling again for
+        another date only resolves the temporal values anew.
+        """
+        if not self._collected:
+            for module in map(ast.namespaced, self.modules):
+                self._collect_entities(module)
+                self._collect_variables(module)
+                self._apply_amendments(module)
+                self._apply_repeals(module)
+            self.schema.infer_reverse_relations()
+            self._collected = True
 
-        self.schema.infer_reverse_relations()
         resolved = self._resolve_temporal(as_of)
+        self._exclude_disabled(resolved)
 
+        check_expressions(resolved)
         for var in resolved.values():
             self._walk_deps(var.expr, var.deps)
 
-        order = self._topo_sort(resolved)
+        for problem in link_problems(resolved, self.schema):
+            raise CompileError(problem)
+
+        groups = self._fixed_point_groups(resolved)
+        order = self._topo_sort(resolved, groups)
 
-        return IR(schema_=self.schema, variables=resolved, order=order)
+        _, problems = variable_types(resolved, self.schema, order)
+        for problem in problems:
+            raise CompileError(problem)
+
+        return IR(
+            schema_=self.schema,
+            variables=resolved,
+            order=order,
+            fixed_point=_ordered_groups(groups, order),
+        )
 
     def _collect_entities(self, module: ast.Module) -> None:
         for decl in module.entities:
             entity = Entity(name=decl.name)
             for name, dtype in decl.fields:
-                entity.fields[name] = Field(name=name, dtype=dtype)
+                entity.fields[name] = Field(name=name, dtype=dtype, nullable=name in decl.nullable)
             for name, target in decl.foreign_keys:
                 entity.foreign_keys[name] = ForeignKey(name=name, target=target)
             for name, source, source_field in decl.reverse_relations:
@@ -125,6 +229,10 @@ class Compiler:
         for decl in module.variables:
             if decl.path in self.layers:
                 raise CompileError(f"duplicate variable: {decl.path}")
+            if decl.missing == "default" and decl.default is None:
+                raise CompileError(f'{decl.path}: missing: "default" needs a declared default')
+            if decl.rounding is not None and decl.round_to is None:
+                raise CompileError(f"{decl.path}: rounding needs a round_to")
             layer = TemporalLayer(
                 decl.path,
                 entity=decl.entity,
@@ -132,6 +240,12 @@ class Compiler:
                 label=decl.label,
                 description=decl.description,
                 unit=decl.unit,
+                requires_feature=decl.requires_feature,
+                arithmetic=decl.arithmetic,
+                missing=decl.missing,
+                default=decl.default,
+                round_to=decl.round_to,
+                rounding=decl.rounding,
             )
             layer.add_values(decl.values)
             self.layers[decl.path] = layer
@@ -150,19 +264,52 @@ class Compiler:
     def _resolve_temporal(self, as_of: date) -> dict[str, ResolvedVar]:
         resolved = {}
         for path, layer in self.layers.items():
-            expr = layer.resolve(as_of)
-            if expr is not None:
-                resolved[path] = ResolvedVar(
-                    path=path,
-                    entity=layer.entity,
-                    source=layer.source,
-                    label=layer.label,
-                    description=layer.description,
-                    unit=layer.unit,
-                    expr=expr,
-                )
+            tv = layer.resolve_value(as_of)
+            if tv is not None:
+                effective, expr = tv.start, tv.expr
+            elif layer.default is not None:
+                # Nothing in force: the declared default stands in
+                effective, expr = None, ast.Literal(value=layer.default)
+            else:
+                continue
+            resolved[path] = ResolvedVar(
+                path=path,
+                entity=layer.entity,
+                source=layer.source,
+                label=layer.label,
+                description=layer.description,
+                unit=layer.unit,
+                requires_feature=layer.requires_feature,
+                arithmetic=layer.arithmetic,
+                missing=layer.missing,
+                default=layer.default,
+                round_to=layer.round_to,
+                rounding=layer.rounding,
+                effective=effective,
+                expr=expr,
+            )
         return resolved
 
+    def _exclude_disabled(self, resolved: dict[str, ResolvedVar]) -> None:
+        """Drop variables whose feature is off; readers of one get its
+        declared default, or else a zero of its formula's type."""
+        disabled = {
+            path
+            for path, var in resolved.items()
+            if var.requires_feature is not None and var.requires_feature not in self.features
+        }
+        defaults: dict[str, ast.Expr] = {}
+        for path in disabled:
+            var = resolved.pop(path)
+            if var.default is None:
+                dtype = expression_type(var.expr, var.entity, self.schema)
+                defaults[path] = ast.Literal(value=ZEROS.get(dtype, 0))
+            else:
+                defaults[path] = ast.Literal(value=var.default)
+        if defaults:
+            for var in resolved.values():
+                var.expr = ast.substitute(var.expr, defaults)
+
     def _walk_deps(self, expr: ast.Expr, deps: set[str]) -> None:
         match expr:
             case ast.Literal():
@@ -182,7 +329,8 @@ class Compiler:
                 self._walk_deps(obj, deps)
             case ast.Match(subject=subject, cases=cases, default=default):
                 self._walk_deps(subject, deps)
-                for _, result in cases:
+                for pattern, result in cases:
+                    self._walk_deps(pattern, deps)
                     self._walk_deps(result, deps)
                 if default:
                     self._walk_deps(default, deps)
@@ -190,26 +338,716 @@ class Compiler:
                 self._walk_deps(cond, deps)
                 self._walk_deps(then_e, deps)
                 self._walk_deps(else_e, deps)
+            case ast.Scale(base=base, brackets=brackets):
+                self._walk_deps(base, deps)
+                for threshold, rate in brackets:
+                    self._walk_deps(threshold, deps)
+                    self._walk_deps(rate, deps)
+            case ast.Lookup(key=key):
+                self._walk_deps(key, deps)
+
+    def _fixed_point_groups(self, variables: dict[str, ResolvedVar]) -> list[list[str]]:
+        """Cycles among the fixed_point variables, each listed in declaration order."""
+        for path in sorted(self.fixed_point - variables.keys()):
+            raise CompileError(f"unknown fixed-point variable: {path}")
+        reach: dict[str, set[str]] = {}
+        for start in self.fixed_point:
+            seen: set[str] = set()
+            stack = [start]
+            while stack:
+                for dep in variables[stack.pop()].deps & self.fixed_point:
+                    if dep not in seen:
+                        seen.add(dep)
+                        stack.append(dep)
+            reach[start] = seen
+        groups, grouped = [], set()
+        for path in variables:
+            if path not in self.fixed_point or path in grouped or path not in reach[path]:
+                continue
+            group = [p for p in variables if p in reach[path] and path in reach[p]]
+            entities = {variables[p].entity for p in group}
+            if len(entities) > 1:
+                raise CompileError(
+                    f"fixed-point group {', '.join(group)} spans several entities"
+                )
+            groups.append(group)
+            grouped.update(group)
+        return groups
+
+    def _topo_sort(
+        self, variables: dict[str, ResolvedVar], groups: list[list[str]] | None = None
+    ) -> list[str]:
+        """Depth-first post-order over deps, kept on an explicit stack so a
+        long dependency chain cannot exhaust the interpreter's.
+
+        Deps with nothing in force and no default are left out of the order;
+        reading one is an error in strict mode and 0 otherwise. Each of
+        ``groups`` is sorted as one node and its members kept together. A
+        cycle raises CycleError naming every variable on it."""
+        group_of = {path: group[0] for group in groups or () for path in group}
+        members = {group[0]: group for group in groups or ()}
+
+        def deps(node: str) -> set[str]:
+            out: set[str] = set()
+            for path in members.get(node, [node]):
+                if path in variables:
+                    out.update(group_of.get(dep, dep) for dep in variables[path].deps)
+            if node in members:
+                out.discard(node)  # reads within a group are solved by iteration
+            return out
 
-    def _topo_sort(self, variables: dict[str, ResolvedVar]) -> list[str]:
         visited: set[str] = set()
         order: list[str] = []
-        temp: set[str] = set()
-
-        def visit(path: str) -> None:
-            if path in temp:
-                raise CompileError(f"circular dependency involving {path}")
-            if path in visited:
-                return
-            temp.add(path)
-            if path in variables:
-                for dep in variables[path].deps:
-                    visit(dep)
-            temp.remove(path)
-            visited.add(path)
-            order.append(path)
 
-        for path in variables:
-            visit(path)
+        for root in variables:
+            root = group_of.get(root, root)
+            if root in visited:
+                continue
+            temp = {root}
+            stack = [(root, iter(sorted(deps(root))))]
+            while stack:
+                node, pending = stack[-1]
+                for dep in pending:
+                    if dep in temp:
+                        path = [n for n, _ in stack]
+                        raise CycleError(path[path.index(dep) :] + [dep])
+                    if dep not in visited:
+                        temp.add(dep)
+                        stack.append((dep, iter(sorted(deps(dep)))))
+                        break
+                else:
+                    stack.pop()
+                    temp.remove(node)
+                    visited.add(node)
+                    order.extend(p for p in members.get(node, [node]) if p in variables)
 
         return order
+
+
+def topological_order(
+    variables: dict[str, ResolvedVar], fixed_point: list[list[str]] | None = None
+) -> list[str]:
+    """An evaluation order for ``variables``, each after its deps; raises
+    CycleError on a cycle outside the ``fixed_point`` groups."""
+    return Compiler([])._topo_sort(variables, fixed_point)
+
+
+def expression_deps(expr: ast.Expr) -> set[str]:
+    """Variable paths an expression reads, as compiled into ``deps``."""
+    deps: set[str] = set()
+    Compiler([])._walk_deps(expr, deps)
+    return deps
+
+
+def _ordered_groups(groups: list[list[str]], order: list[str]) -> list[list[str]]:
+    position = {path: i for i, path in enumerate(order)}
+    return sorted(groups, key=lambda group: position[group[0]])
+
+
+def dependency_closure(ir: IR, outputs: list[str]) -> set[str]:
+    """``outputs`` plus every variable they transitively depend on.
+
+    Deps with nothing in force (see Compiler._topo_sort) are not included.
+    """
+    for path in outputs:
+        if path not in ir.variables:
+            raise CompileError(f"unknown variable: {path}")
+    closure: set[str] = set()
+    stack = list(outputs)
+    while stack:
+        path = stack.pop()
+        if path in closure or path not in ir.variables:
+            continue
+        closure.add(path)
+        stack.extend(ir.variables[path].deps)
+    return closure
+
+
+def prune(ir: IR, outputs: list[str]) -> IR:
+    """Copy of ir restricted to the dependency closure of ``outputs``."""
+    closure = dependency_closure(ir, outputs)
+    return ir.model_copy(
+        update={
+            "variables": {p: v for p, v in ir.variables.items() if p in closure},
+            "order": [p for p in ir.order if p in closure],
+        }
+    )
+
+
+def dependents(ir: IR, paths: list[str]) -> set[str]:
+    """``paths`` plus every variable that transitively depends on them."""
+    users: dict[str, list[str]] = {}
+    for path, var in ir.variables.items():
+        for dep in var.deps:
+            users.setdefault(dep, []).append(path)
+    closure: set[str] = set()
+    stack = list(paths)
+    while stack:
+        path = stack.pop()
+        if path in closure:
+            continue
+        if path not in ir.variables:
+            raise CompileError(f"unknown variable: {path}")
+        closure.add(path)
+        stack.extend(users.get(path, []))
+    return closure
+
+
+def critical_path(
+    variables: dict[str, ResolvedVar], fixed_point: list[list[str]] | None = None
+) -> tuple[int, list[str]]:
+    """Longest dependency chain, as (number of variables, paths from first to last).
+
+    The length bounds the number of sequential evaluation steps, however many
+    variables could otherwise run in parallel. Among equally long chains the
+    end is the first in topological order and each step back takes the
+    alphabetically first dependency. Reads within one of the ``fixed_point``
+    groups are solved by iteration and are not steps of a chain.
+    """
+    order = Compiler([])._topo_sort(variables, fixed_point)
+    group_of = {path: i for i, group in enumerate(fixed_point or ()) for path in group}
+    depth: dict[str, int] = {}
+    prev: dict[str, str | None] = {}
+    for path in order:
+        if path not in variables:
+            continue
+        best = None
+        for dep in sorted(variables[path].deps):
+            if path in group_of and group_of.get(dep) == group_of[path]:
+                continue
+            if dep in depth and (best is None or depth[dep] > depth[best]):
+                best = dep
+        depth[path] = 1 + (depth[best] if best is not None else 0)
+        prev[path] = best
+
+    if not depth:
+        return 0, []
+    end = max(depth, key=lambda p: depth[p])
+    chain = [end]
+    while prev[chain[-1]] is not None:
+        chain.append(prev[chain[-1]])
+    return depth[end], chain[::-1]
+
+
+def required_inputs(ir: IR, entity: str, outputs: list[str] | None = None) -> list[str]:
+    """Input fields of ``entity`` read by the dependency closure of ``outputs``.
+
+    ``outputs`` defaults to every variable of the entity. Fields are returned
+    in first-reference order along ``ir.order``.
+    """
+    if outputs is None:
+        outputs = [path for path in ir.order if ir.variables[path].entity == entity]
+    closure = dependency_closure(ir, outputs)
+    fields: dict[str, None] = {}
+    for path in ir.order:
+        var = ir.variables[path]
+        if path not in closure or var.entity != entity:
+            continue
+        members = {
+            id(node.args[0])
+            for node in ast.walk(var.expr)
+            if isinstance(node, ast.Call) and node.func in ast.AGGREGATES and node.args
+        }
+        for node in ast.walk(var.expr):
+            if isinstance(node, ast.Var) and "/" not in node.path and id(node) not in members:
+                fields.setdefault(node.path)
+    return list(fields)
+
+
+def dump_ir(ir: IR) -> str:
+    """Serialize IR to JSON."""
+    return ir.model_dump_json()
+
+
+def check_expressions(variables: dict[str, ResolvedVar]) -> None:
+    """Reject expressions too deep to evaluate, or too many nodes in total."""
+    nodes = 0
+    for path, var in variables.items():
+        count, depth = ast.extent(var.expr)
+        if depth > ast.MAX_EXPR_DEPTH:
+            raise CompileError(
+                f"{path}: expression nested {depth} levels deep (limit {ast.MAX_EXPR_DEPTH})"
+            )
+        nodes += count
+    if nodes > MAX_IR_NODES:
+        raise CompileError(f"IR has {nodes} expression nodes (limit {MAX_IR_NODES})")
+
+
+def _link_problem(path: str, schema: Schema, source: str, target: str) -> list[str]:
+    links = schema.links(source, target)
+    if len(links) == 1:
+        return []
+    return [f"{path}: {source} has {'several links' if links else 'no link'} to {target}"]
+
+
+def node_problems(variables: dict[str, ResolvedVar]) -> list[str]:
+    """Operators, functions and argument counts the executor would reject.
+
+    Functions must be built in or registered (see register_function) by
+    the time this runs.
+    """
+    problems: list[str] = []
+    for path, var in variables.items():
+        for node in ast.walk(var.expr):
+            match node:
+                case ast.BinOp(op=op) if op not in ast.BINARY_OPS:
+                    problems.append(f"{path}: unknown operator {op!r}")
+                case ast.UnaryOp(op=op) if op not in ast.UNARY_OPS:
+                    problems.append(f"{path}: unknown unary operator {op!r}")
+                case ast.Call(func=func, args=args):
+                    if func not in ast.BUILTIN_ARITY and get_function(func) is None:
+                        problems.append(f"{path}: unknown function {func}()")
+                    elif error := ast.arity_error(func, len(args)):
+                        problems.append(f"{path}: {error}")
+    return problems
+
+
+def link_problems(variables: dict[str, ResolvedVar], schema: Schema) -> list[str]:
+    """Cross-entity reads that no single foreign key resolves.
+
+    An entity variable may reduce a member entity's variable over the rows
+    linked to it (sum_over and the like), or read a variable of the entity
+    its rows link to, broadcast down to each linked row. Either needs
+    exactly one foreign key from the member entity to the other.
+    """
+    problems: list[str] = []
+    for path, var in variables.items():
+        for node in ast.walk(var.expr):
+            if not isinstance(node, ast.Call) or node.func not in ast.AGGREGATES:
+                continue
+            if ast.arity_error(node.func, len(node.args)):
+                continue  # reported by the parser, or by the executor when strict
+            members = node.args[0]
+            if not isinstance(members, ast.Var) or "/" in members.path:
+                problems.append(f"{path}: {node.func} takes an entity name first")
+                continue
+            if var.entity is None:
+                problems.append(f"{path}: {node.func} needs an entity variable")
+                continue
+            problems.extend(_link_problem(path, schema, members.path, var.entity))
+            for value in node.args[1:]:
+                target = variables.get(value.path) if isinstance(value, ast.Var) else None
+                if target is None or target.entity != members.path:
+                    problems.append(f"{path}: {node.func} takes a variable of {members.path}")
+        for dep in broadcast_reads(var, variables):
+            problems.extend(_link_problem(path, schema, var.entity, variables[dep].entity))
+    return list(dict.fromkeys(problems))
+
+
+def cross_entity_variables(ir: IR) -> list[str]:
+    """Variables that aggregate or broadcast another entity's variables."""
+    return [
+        path
+        for path, var in ir.variables.items()
+        if broadcast_reads(var, ir.variables)
+        or any(isinstance(n, ast.Call) and n.func in ast.AGGREGATES for n in ast.walk(var.expr))
+    ]
+
+
+def random_variables(ir: IR) -> list[str]:
+    """Variables that draw random numbers (uniform, normal, bernoulli)."""
+    return [
+        path
+        for path, var in ir.variables.items()
+        if any(isinstance(n, ast.Call) and n.func in ast.RANDOM for n in ast.walk(var.expr))
+    ]
+
+
+def broadcast_reads(var: ResolvedVar, variables: dict[str, ResolvedVar]) -> list[str]:
+    """Variables of other entities that ``var`` reads outside any aggregate."""
+    if var.entity is None:
+        return []
+    aggregated = {
+        id(arg)
+        for node in ast.walk(var.expr)
+        if isinstance(node, ast.Call) and node.func in ast.AGGREGATES
+        for arg in node.args
+    }
+    reads: dict[str, None] = {}
+    for node in ast.walk(var.expr):
+        if isinstance(node, ast.Var) and id(node) not in aggregated and node.path in variables:
+            if variables[node.path].entity not in (None, var.entity):
+                reads.setdefault(node.path)
+    return list(reads)
+
+
+def structure_problems(ir: IR, declared_entities: bool = True) -> list[str]:
+    """Inconsistencies between the variables, their deps and the order.
+
+    Call after check_expressions, which bounds the depth walked here. With
+    ``declared_entities=False`` a variable may name an entity the schema
+    doesn't declare, as compiled IR allows.
+    """
+    problems = []
+    seen: set[str] = set()
+    for path in ir.order:
+        if path not in ir.variables:
+            problems.append(f"order lists unknown variable: {path}")
+        elif path in seen:
+            problems.append(f"order lists {path} more than once")
+        seen.add(path)
+    if missing := ir.variables.keys() - seen:
+        problems.append(f"order omits variables: {', '.join(sorted(missing))}")
+    group_of = {path: i for i, group in enumerate(ir.fixed_point) for path in group}
+    for path in group_of:
+        if path not in ir.variables:
+            problems.append(f"fixed-point group lists unknown variable: {path}")
+    position = {path: i for i, path in enumerate(ir.order)}
+    for path, var in ir.variables.items():
+        if var.path != path:
+            problems.append(f"variable {path} is keyed under a different path: {var.path}")
+        if declared_entities and var.entity not in (None, *ir.schema_.entities):
+            problems.append(f"{path}: unknown entity {var.entity}")
+        if var.missing not in (None, *ast.MISSING_POLICIES):
+            problems.append(f"{path}: unknown missing policy {var.missing}")
+        if var.missing == "default" and var.default is None:
+            problems.append(f'{path}: missing: "default" needs a declared default')
+        if var.round_to is not None and not (
+            isinstance(var.round_to, int | float)
+            and not isinstance(var.round_to, bool)
+            and 0 < var.round_to < math.inf
+        ):
+            problems.append(f"{path}: round_to must be a positive number")
+        if var.rounding not in (None, *ast.ROUND_DIRECTIONS):
+            problems.append(f"{path}: unknown rounding direction {var.rounding}")
+        elif var.rounding is not None and var.round_to is None:
+            problems.append(f"{path}: rounding needs a round_to")
+        if unlisted := expression_deps(var.expr) - var.deps:
+            problems.append(f"{path} reads {', '.join(sorted(unlisted))}, missing from its deps")
+        for dep in sorted(var.deps):
+            if path in group_of and group_of.get(dep) == group_of[path]:
+                continue  # solved together by iteration
+            if path in position and position.get(dep, -1) >= position[path]:
+                problems.append(f"order puts {path} before its dependency {dep}")
+        for node in ast.walk(var.expr):
+            if isinstance(node, ast.Literal) and not _literal_ok(node.value):
+                problems.append(f"{path}: literal must be a number, string or boolean")
+                break
+            if isinstance(node, ast.Scale) and node.mode not in ast.SCALE_MODES:
+                problems.append(f"{path}: unknown scale mode {node.mode}")
+            if isinstance(node, ast.Scale) and not node.brackets:
+                problems.append(f"{path}: scale needs at least one bracket")
+            if isinstance(node, ast.Lookup):
+                problems.extend(f"{path}: {problem}" for problem in ast.lookup_problems(node))
+            if isinstance(node, ast.Call) and node.func == "interp":
+                if error := ast.knots_error(node.args):
+                    problems.append(f"{path}: {error}")
+        if ast.misplaced_list(var.expr):
+            problems.append(f"{path}: {ast.LIST_ERROR}")
+    problems.extend(link_problems(ir.variables, ir.schema_))
+    if not problems:
+        problems.extend(variable_types(ir.variables, ir.schema_, ir.order)[1])
+    return problems
+
+
+def _check_nesting(raw: Any) -> None:
+    stack = [(raw, 1)]
+    while stack:
+        value, level = stack.pop()
+        if level > MAX_IR_NESTING:
+            raise CompileError(f"IR is nested more than {MAX_IR_NESTING} levels deep")
+        if isinstance(value, dict):
+            stack.extend((v, level + 1) for v in value.values())
+        elif isinstance(value, list):
+            stack.extend((v, level + 1) for v in value)
+
+
+def _validation_message(error: ValidationError) -> str:
+    first = error.errors()[0]
+    if first["type"] == "recursion_loop":
+        return "IR is nested too deeply"
+    loc = ".".join(map(str, first["loc"]))
+    if len(loc) > 80:
+        loc = f"{loc[:37]}...{loc[-40:]}"
+    more = error.error_count() - 1
+    return f"invalid IR at {loc or '<root>'}: {first['msg']}" + (
+        f" (and {more} more errors)" if more else ""
+    )
+
+
+def _json_path(raw: Any, loc: tuple[Any, ...]) -> str:
+    """A pydantic error location as a JSON path into ``raw``.
+
+    Tagged unions put the tag in the location ("expr.binop.left"); those
+    segments name no key of the document, so they are dropped.
+    """
+    path = "$"
+    for segment in loc:
+        if isinstance(raw, dict) and segment not in raw and segment == raw.get("type"):
+            continue
+        if isinstance(segment, int):
+            path += f"[{segment}]"
+        elif str(segment).isidentifier():
+            path += f".{segment}"
+        else:
+            path += f"[{json.dumps(str(segment))}]"
+        try:
+            raw = raw[segment]
+        except (KeyError, IndexError, TypeError):
+            raw = None
+    return path
+
+
+def _schema_error(error: ValidationError, raw: dict[str, Any]) -> CompileError:
+    first = error.errors()[0]
+    if first["type"] == "recursion_loop":
+        return CompileError("IR is nested too deeply")
+    loc, message = first["loc"], first["msg"]
+    if first["type"] in ("union_tag_invalid", "union_tag_not_found"):
+        loc = (*loc, "type")
+        if first["type"] == "union_tag_invalid":
+            message = f"unknown node type {first['ctx']['tag']!r}"
+    more = error.error_count() - 1
+    message += f" (and {more} more errors)" if more else ""
+    return IRSchemaError(_json_path(raw, loc), message)
+
+
+def _unique_keys(pairs: list[tuple[str, Any]]) -> dict[str, Any]:
+    counts = Counter(key for key, _ in pairs)
+    if duplicates := sorted(key for key, n in counts.items() if n > 1):
+        raise ValueError(f"duplicate key {duplicates[0]!r}")
+    return dict(pairs)
+
+
+def _no_constant(name: str) -> Any:
+    raise ValueError(f"{name} is not a JSON number")
+
+
+def ir_json_schema() -> dict[str, Any]:
+    """JSON Schema of the current IR layout, as load_ir_json checks it."""
+    return IR.model_json_schema()
+
+
+def load_ir_json(source: str | bytes, strict: bool = True) -> IR:
+    """Load IR from a JSON document, checked strictly against the IR schema.
+
+    Stricter than load_ir: duplicate object keys and the non-standard
+    NaN/Infinity constants are rejected rather than silently resolved, and
+    a value that doesn't fit the schema raises IRSchemaError with the JSON
+    path to it (``$.variables["person/tax"].expr.left.type``). ``strict``
+    is as for load_ir.
+    """
+    if len(source) > MAX_IR_BYTES:
+        raise CompileError(f"IR is {len(source)} bytes (limit {MAX_IR_BYTES})")
+    try:
+        raw = json.loads(source, object_pairs_hook=_unique_keys, parse_constant=_no_constant)
+    except RecursionError:
+        raise CompileError("IR JSON is nested too deeply") from None
+    except ValueError as e:
+        raise CompileError(f"IR is not valid JSON: {e}") from None
+    if not isinstance(raw, dict):
+        raise IRSchemaError("$", f"expected an object, got {type(raw).__name__}")
+    return _load(raw, lambda e: _schema_error(e, raw), strict)
+
+
+def load_ir(source: str | bytes | dict[str, Any], strict: bool = True) -> IR:
+    """Load IR from JSON text or a dict, upgrading older layouts.
+
+    The IR may come from outside the process (server mode accepts it over
+    the network), so anything malformed or over the size limits raises
+    CompileError rather than failing later in the executor. An IR without
+    an ``order`` is given one derived from its deps. ``strict`` also
+    rejects unknown operators and functions and wrong argument counts (see
+    node_problems); without it they fail only when evaluated.
+    """
+    if isinstance(source, str | bytes):
+        if len(source) > MAX_IR_BYTES:
+            raise CompileError(f"IR is {len(source)} bytes (limit {MAX_IR_BYTES})")
+        try:
+            raw = json.loads(source)
+        except RecursionError:
+            raise CompileError("IR JSON is nested too deeply") from None
+        except ValueError as e:
+            raise CompileError(f"IR is not valid JSON: {e}") from None
+    else:
+        raw = source
+    if not isinstance(raw, dict):
+        raise CompileError(f"IR must be a JSON object, got {type(raw).__name__}")
+    return _load(raw, lambda e: CompileError(_validation_message(e)), strict)
+
+
+def _load(
+    raw: dict[str, Any],
+    invalid: Callable[[ValidationError], CompileError],
+    strict: bool,
+) -> IR:
+    _check_nesting(raw)
+    try:
+        upgraded = upgrade_ir(raw)
+        derive = "order" not in upgraded
+        ir = IR.model_validate({**upgraded, "order": []} if derive else upgraded)
+    except ValidationError as e:
+        raise invalid(e) from None
+    except RecursionError:
+        raise CompileError("IR is nested too deeply") from None
+    check_expressions(ir.variables)
+    if derive:
+        ir.order = topological_order(ir.variables, ir.fixed_point)
+    if problems := structure_problems(ir) or (node_problems(ir.variables) if strict else []):
+        raise CompileError(problems[0])
+    return ir
+
+
+def upgrade_ir(raw: dict[str, Any]) -> dict[str, Any]:
+    """Rewrite a serialized IR of any supported version to the current layout.
+
+    Unversioned dicts are version 0: the layout emitted before versioning,
+    where ``deps`` and ``order`` may be omitted and are recomputed.
+    """
+    version = raw.get("version", 0)
+    if not isinstance(version, int) or isinstance(version, bool) or version < 0:
+        raise CompileError(f"IR version must be a non-negative integer, got {version!r}")
+    if version > IR_VERSION:
+        raise CompileError(f"IR version {version} is newer than supported ({IR_VERSION})")
+    if version == 0:
+        raw = _upgrade_v0(raw)
+    return raw
+
+
+def _upgrade_v0(raw: dict[str, Any]) -> dict[str, Any]:
+    compiler = Compiler([])
+    variables = {}
+    raw_variables = raw.get("variables", {})
+    if not isinstance(raw_variables, dict):
+        raise CompileError("IR variables must be an object")
+    for path, var in raw_variables.items():
+        if not isinstance(var, dict):
+            raise CompileError(f"IR variable {path} must be an object")
+        resolved = ResolvedVar.model_validate({"path": path, **var})
+        check_expressions({path: resolved})
+        if "deps" not in var:
+            compiler._walk_deps(resolved.expr, resolved.deps)
+        variables[path] = resolved
+    order = raw.get("order")
+    if order is None:
+        order = compiler._topo_sort(variables)
+    return {
+        "version": IR_VERSION,
+        "schema_": raw.get("schema_", raw.get("schema", {})),
+        "variables": variables,
+        "order": order,
+    }
+
+
+class Conflict(BaseModel):
+    """A variable path defined by more than one composed bundle."""
+
+    path: str
+    sources: list[str]  # names of the defining bundles, in order
+    identical: bool  # all definitions are structurally the same
+
+
+class Composition(BaseModel):
+    """Result of composing several IR bundles into one."""
+
+    ir: IR
+    conflicts: list[Conflict] = []
+
+
+def _signature(var: ResolvedVar) -> tuple:
+    return (var.entity, var.expr.model_dump_json())
+
+
+def _merge_schemas(schemas: list[Schema]) -> Schema:
+    merged = Schema()
+    for schema in schemas:
+        for name, entity in schema.entities.items():
+            if name not in merged.entities:
+                merged.entities[name] = entity.model_copy(deep=True)
+                continue
+            target = merged.entities[name]
+            for field_name, fld in entity.fields.items():
+                existing = target.fields.get(field_name)
+                if existing is not None and existing.dtype != fld.dtype:
+                    raise CompileError(
+                        f"entity {name} field {field_name}: {existing.dtype} vs {fld.dtype}"
+                    )
+                target.fields.setdefault(field_name, fld)
+            for fk_name, fk in entity.foreign_keys.items():
+                target.foreign_keys.setdefault(fk_name, fk)
+            for rel_name, rel in entity.reverse_relations.items():
+                target.reverse_relations.setdefault(rel_name, rel)
+    return merged
+
+
+def _merge_groups(groups: list[list[str]]) -> list[list[str]]:
+    """Groups with any overlapping ones joined, members in first-seen order."""
+    merged: list[list[str]] = []
+    for group in groups:
+        overlapping = [g for g in merged if not set(g).isdisjoint(group)]
+        joined: dict[str, None] = {}
+        for g in overlapping + [group]:
+            joined.update(dict.fromkeys(g))
+        merged = [g for g in merged if g not in overlapping] + [list(joined)]
+    return merged
+
+
+def compose(bundles: list[IR] | dict[str, IR], on_conflict: str = "error") -> Composition:
+    """Merge separately compiled IR bundles into one IR.
+
+    Paths defined identically in several bundles are merged silently. For
+    differing definitions, ``on_conflict`` decides: "error" raises,
+    "last_wins" keeps the last bundle's definition, and "namespace" prefixes
+    each colliding path with its bundle name ("federal/tax/credit") and
+    rewrites that bundle's references. Dependencies and execution order are
+    recomputed across the union; fixed-point groups are carried over,
+    renamed with their variables, and groups sharing a variable are merged.
+    """
+    if on_conflict not in ("error", "last_wins", "namespace"):
+        raise CompileError(f"unknown on_conflict policy: {on_conflict}")
+    if isinstance(bundles, dict):
+        named = dict(bundles)
+    else:
+        named = {str(i): b for i, b in enumerate(bundles)}
+
+    definers: dict[str, list[str]] = {}
+    for name, ir in named.items():
+        for path in ir.variables:
+            definers.setdefault(path, []).append(name)
+
+    conflicts = []
+    for path, names in definers.items():
+        if len(names) > 1:
+            sigs = {_signature(named[n].variables[path]) for n in names}
+            conflicts.append(Conflict(path=path, sources=names, identical=len(sigs) == 1))
+    differing = {c.path for c in conflicts if not c.identical}
+
+    if differing and on_conflict == "error":
+        detail = ", ".join(f"{p} ({', '.join(definers[p])})" for p in sorted(differing))
+        raise CompileError(f"conflicting definitions: {detail}")
+
+    compiler = Compiler([])
+    variables: dict[str, ResolvedVar] = {}
+    groups: list[list[str]] = []
+    for name, ir in named.items():
+        mapping: dict[str, str] = {}
+        if on_conflict == "namespace":
+            mapping = {p: f"{name}/{p}" for p in differing if p in ir.variables}
+        groups.extend([mapping.get(p, p) for p in group] for group in ir.fixed_point)
+        for path, var in ir.variables.items():
+            if on_conflict == "namespace":
+                ambiguous = (var.deps & differing) - ir.variables.keys()
+                if ambiguous:
+                    raise CompileError(
+                        f"{name}: {path} references {', '.join(sorted(ambiguous))}, "
+                        "which is defined differently by several other bundles"
+                    )
+            elif path in variables and path not in differing:
+                continue  # identical duplicate: keep the first
+            new_path = mapping.get(path, path)
+            expr = ast.rename(var.expr, mapping) if mapping else var.expr
+            variables[new_path] = var.model_copy(
+                update={"path": new_path, "expr": expr, "deps": set()}
+            )
+
+    for var in variables.values():
+        compiler._walk_deps(var.expr, var.deps)
+    groups = _merge_groups(groups)
+    order = compiler._topo_sort(variables, groups)
+    schema = _merge_schemas([ir.schema_ for ir in named.values()])
+    fixed_point = _ordered_groups(groups, order)
+    return Composition(
+        ir=IR(schema_=schema, variables=variables, order=order, fixed_point=fixed_point),
+        conflicts=conflicts,
+    )
+
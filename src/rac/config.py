@@ -0,0 +1,154 @@
+"""Engine configuration with layered defaults.
+
+Each option resolves from the first layer that sets it, highest precedence
+first:
+
+1. per-call arguments (e.g. ``Model.run(..., sequential_threshold=0)``)
+2. an EngineConfig passed to the call or held by a Model
+3. the process-wide default set with ``set_default_config()``
+4. environment variables ``RAC_<OPTION>`` (e.g. ``RAC_STRICT=0``,
+   ``RAC_APPROX_QUANTILES=1``, ``RAC_NUMBER_FORMAT=EU``)
+5. the built-in defaults in ``BUILTIN_CONFIG``
+"""
+
+import os
+from collections.abc import Mapping
+from typing import Any
+
+from pydantic import BaseModel, ConfigDict, ValidationError, field_validator
+
+from .fixed import MAX_PLACES
+from .ingest import EU, US, NumberFormat
+
+ENV_PREFIX = "RAC_"
+NUMBER_FORMATS = {"US": US, "EU": EU}
+
+
+class ConfigError(Exception):
+    pass
+
+
+class EngineConfig(BaseModel):
+    """Execution options; None means "not set at this layer"."""
+
+    model_config = ConfigDict(extra="forbid")
+
+    strict: bool | None = None  # missing dependencies raise instead of reading 0
+    approx_quantiles: bool | None = None  # quantiles from a sketch, not a full sort
+    quantile_accuracy: float | None = None  # relative error bound of the sketch
+    sequential_threshold: int | None = None  # rows x outputs below which Model runs in-process
+    number_format: NumberFormat | None = None  # for numeric strings in input data
+    work_budget: int | None = None  # max expression nodes evaluated per run; unset is unlimited
+    epsilon: float | None = None  # == and != treat numbers at most this far apart as equal
+    decimal_places: int | None = None  # evaluate in i64 fixed point with this many decimals
+    max_iterations: int | None = None  # sweeps allowed to solve a fixed-point group
+    iteration_tolerance: float | None = None  # largest change at which a group has converged
+    columnar: bool | None = None  # evaluate entity variables a column at a time, not per row
+    bytecode: bool | None = None  # run entity formulas as compiled bytecode, row by row
+    parallel: bool | None = None  # the native binary spreads rows over threads
+    num_threads: int | None = None  # its thread pool's size; 0 lets it match the cores
+    chunk_size: int | None = None  # fewest rows a thread takes at a time
+    deterministic: bool | None = None  # split rows into fixed chunk_size blocks, not by threads
+    seed: int | None = None  # master seed of uniform(), normal() and bernoulli()
+
+    @field_validator("number_format", mode="before")
+    @classmethod
+    def _preset(cls, value: Any) -> Any:
+        if isinstance(value, str):
+            if value.upper() not in NUMBER_FORMATS:
+                raise ValueError(f"unknown number format: {value}")
+            return NUMBER_FORMATS[value.upper()]
+        return value
+
+    @field_validator("num_threads")
+    @classmethod
+    def _threads(cls, value: int | None) -> int | None:
+        if value is not None and value < 0:
+            raise ValueError("num_threads must be 0 (automatic) or more")
+        return value
+
+    @field_validator("decimal_places")
+    @classmethod
+    def _places(cls, value: int | None) -> int | None:
+        if value is not None and not 0 <= value <= MAX_PLACES:
+            raise ValueError(f"decimal_places must be from 0 to {MAX_PLACES}")
+        return value
+
+    @field_validator("chunk_size")
+    @classmethod
+    def _chunk(cls, value: int | None) -> int | None:
+        if value is not None and value < 1:
+            raise ValueError("chunk_size must be at least 1")
+        return value
+
+    @classmethod
+    def from_dict(cls, values: Mapping[str, Any]) -> "EngineConfig":
+        try:
+            return cls.model_validate(dict(values))
+        except ValidationError as e:
+            raise ConfigError(str(e)) from None
+
+    @classmethod
+    def from_env(cls, environ: Mapping[str, str] | None = None) -> "EngineConfig":
+        """Options set as ``RAC_<OPTION>`` environment variables."""
+        environ = os.environ if environ is None else environ
+        values = {
+            name: environ[ENV_PREFIX + name.upper()]
+            for name in cls.model_fields
+            if ENV_PREFIX + name.upper() in environ
+        }
+        return cls.from_dict(values)
+
+    def overlay(self, other: "EngineConfig | None") -> "EngineConfig":
+        """This config with every option ``other`` sets taking precedence."""
+        if other is None:
+            return self
+        values = {name: getattr(other, name) for name in type(other).model_fields}
+        return self.model_copy(update={k: v for k, v in values.items() if v is not None})
+
+
+BUILTIN_CONFIG = EngineConfig(
+    strict=True,
+    approx_quantiles=False,
+    quantile_accuracy=0.01,
+    sequential_threshold=2_000,
+    epsilon=0.0,
+    max_iterations=100,
+    iteration_tolerance=1e-9,
+    columnar=True,
+    bytecode=False,
+    parallel=True,
+    num_threads=0,
+    chunk_size=1,
+    deterministic=False,
+    seed=0,
+)
+
+# Per interpreter: each subinterpreter imports its own copy of this module
+_default_config: EngineConfig | None = None
+
+
+def set_default_config(config: EngineConfig | Mapping[str, Any] | None) -> None:
+    """Set (or with None, clear) the process-wide default configuration."""
+    global _default_config
+    if config is not None and not isinstance(config, EngineConfig):
+        config = EngineConfig.from_dict(config)
+    _default_config = config
+
+
+def get_default_config() -> EngineConfig | None:
+    return _default_config
+
+
+def resolve_config(*configs: EngineConfig | None, **call_args: Any) -> EngineConfig:
+    """The effective configuration for one call.
+
+    ``configs`` are instance-level layers, later ones taking precedence;
+    ``call_args`` are per-call options, where None means "not given".
+    """
+    effective = BUILTIN_CONFIG.overlay(EngineConfig.from_env()).overlay(_default_config)
+    for config in configs:
+        effective = effective.overlay(config)
+    return effective.overlay(
+        EngineConfig.from_dict({k: v for k, v in call_args.items() if v is not None})
+    )
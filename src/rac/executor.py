@@ -1,18 +1,101 @@
 """Executor: evaluates compiled IR against input data."""
 
-from typing import Any
+import math
+from bisect import bisect_right
+from collections.abc import Callable, Mapping, Sequence
+from datetime import date, datetime
+from fnmatch import fnmatchcase
+from fractions import Fraction
+from functools import partial
+from numbers import Real
+from typing import Any, Literal
 
 from pydantic import BaseModel, ConfigDict
 
 from . import ast
-from .compiler import IR
-from .schema import Data
+from .categories import Categorical
+from .compiler import IR, CompileError, ResolvedVar, broadcast_reads, prune
+from .config import EngineConfig, resolve_config
+from .fixed import Fixed, FixedOverflow, to_fixed
+from .functions import CallLog, FunctionStats, call_function, get_function
+from .ingest import MissingSpec, apply_missing
+from .plan import DryRunReport, Plan, PlanError, build_plan, entity_inputs
+from .rng import stream_id, uniform_at
+from .schema import Data, LinkageError, _examples
+from .stats import ColumnStats, compensated_sum
 
 
 class ExecutionError(Exception):
     pass
 
 
+EvalErrorKind = Literal[
+    "undefined",  # a name with no value: not computed, not an input field
+    "unknown_function",
+    "unknown_operator",
+    "arity",  # a built-in called with the wrong number of arguments
+    "no_match",  # a match with no arm for the subject and no default
+    "invalid_scale",
+    "out_of_range",  # a lookup key its table doesn't hold, with out_of_range: error
+    "invalid_date",  # a date argument that isn't a date or an ISO-date string
+    "missing",  # a missing result of a variable declared missing: "error"
+    "overflow",  # a fixed-point result outside the i64 range
+    "invalid_argument",  # a probability outside [0, 1] or a negative standard deviation
+]
+
+
+class EvalError(ExecutionError):
+    """An expression that can't be evaluated, rather than a silent zero.
+
+    ``variable`` and ``row`` (None for scalars, or a whole column) say where
+    evaluation stopped, and ``expr`` is the source of the innermost
+    expression that failed.
+    """
+
+    def __init__(
+        self,
+        kind: EvalErrorKind,
+        message: str,
+        variable: str | None = None,
+        row: int | None = None,
+        expr: str | None = None,
+    ):
+        super().__init__(message)
+        self.kind = kind
+        self.message = message
+        self.variable = variable
+        self.row = row
+        self.expr = expr
+
+    def locate(self, expr: ast.Expr, variable: str, row: int | None) -> None:
+        """Record where the error arose, once, at the innermost expression."""
+        if self.expr is not None:
+            return
+        self.expr = ast.to_source(expr)
+        self.variable = self.variable or variable or None
+        self.row = row if self.row is None else self.row
+        where = self.variable or "expression"
+        if self.row is not None:
+            where += f" row {self.row}"
+        self.args = (f"{self.message} (in {where}: {self.expr})",)
+
+
+class WorkBudgetExceeded(ExecutionError):
+    """A run evaluated more expression nodes than its work budget allows."""
+
+    def __init__(self, budget: int, variable: str, row: int | None, completed: int, total: int):
+        self.budget = budget
+        self.variable = variable
+        self.row = row
+        self.completed = completed  # variables fully evaluated
+        self.total = total
+        at = variable if row is None else f"{variable} row {row}"
+        super().__init__(
+            f"work budget of {budget} nodes exceeded at {at} "
+            f"({completed} of {total} variables completed)"
+        )
+
+
 class Context(BaseModel):
     """Runtime context for evaluation."""
 
@@ -22,13 +105,32 @@ class Context(BaseModel):
     computed: dict[str, Any] = {}
     current_row: dict | None = None
     current_entity: str | None = None
+    current_path: str = ""  # variable being evaluated
+    current_index: int | None = None  # its row, None for scalars
+    profiler: Any = None  # profiling.Profiler, when deep profiling is on
+    strict: bool = True  # built-in arity is checked and undefined variables raise
+    epsilon: float = 0.0  # == and != tolerance where the comparison sets none
+    calls: CallLog = CallLog()  # registered function retries and failures
+    # How numbers are read: exactly (Fractions) for variables declared
+    # arithmetic: "exact", as Fixed under decimal_places, or as given (None)
+    numbers: Callable[[Any], Any] | None = None
+    work_budget: int | None = None  # max nodes to evaluate, None for unlimited
+    work: int = 0  # nodes evaluated so far, counted only under a budget
+    completed: int = 0  # variables fully evaluated, for progress reports
+    total: int = 0
+    entities: dict[str, dict[str, list[Any]]] = {}  # entity columns evaluated so far
+    seed: int = 0  # master seed of the random built-ins' streams
+    draws: dict[int, int] = {}  # each random call's first draw counter, by node id
+    memberships: dict[tuple[str, str], dict[Any, list[int]]] = {}  # member rows by link value
 
     def get(self, path: str) -> Any:
         if path in self.computed:
             return self.computed[path]
         if self.current_row and path in self.current_row:
             return self.current_row[path]
-        raise ExecutionError(f"undefined: {path}")
+        if not self.strict and "/" in path:
+            return 0  # a variable with nothing in force and no declared default
+        raise EvalError("undefined", f"undefined: {path}")
 
     def get_related(self, entity: str, fk_field: str) -> list[dict]:
         if self.current_row is None:
@@ -40,84 +142,386 @@ class Context(BaseModel):
         return self.data.get_row(target_entity, fk_value)
 
 
+def _within(old: Any, new: Any, tolerance: float) -> bool:
+    if isinstance(old, Real) and isinstance(new, Real):
+        return abs(new - old) <= tolerance
+    return old == new
+
+
+def _is_nan(value: Any) -> bool:
+    return isinstance(value, float) and math.isnan(value)
+
+
+def _nan_propagating(fn: Any) -> Any:
+    """Wrap min/max-style builtins and sums so any NaN argument yields NaN.
+
+    Python's min/max (like f64::min/max) otherwise keep or drop a NaN
+    depending on argument order, so a missing input could silently pass a
+    ``max(0, x)`` guard. Comparisons keep IEEE semantics: false with a NaN
+    operand, except ``!=``, which is true. A None argument yields None.
+    """
+
+    def wrapped(*args: Any) -> Any:
+        values = args[0] if len(args) == 1 and isinstance(args[0], list | tuple) else args
+        if any(_is_nan(v) for v in values):
+            return math.nan
+        if any(v is None for v in values):
+            return None
+        return fn(*args)
+
+    return wrapped
+
+
+def _day(value: Any) -> Any:
+    return value.toordinal() if isinstance(value, date) else value
+
+
+def _apportion(
+    amount: Any,
+    start_day: Any,
+    end_day: Any,
+    period_start: Any,
+    period_end: Any,
+    inclusive: bool = True,
+) -> Any:
+    """``amount`` scaled by the share of the period that ``start_day..end_day`` covers.
+
+    Days are day numbers or dates. End days count as part of the span
+    unless ``inclusive`` is false; the span is clamped to the period, so
+    the factor stays within [0, 1]. An empty period apportions nothing.
+    """
+    start, end, lo, hi = map(_day, (start_day, end_day, period_start, period_end))
+    extra = 1 if inclusive else 0
+    length = hi - lo + extra
+    if length <= 0:
+        return amount * 0
+    covered = max(0, min(end, hi) - max(start, lo) + extra)
+    return amount * covered / length
+
+
+def _as_date(value: Any) -> date:
+    """A date, read from an ISO-date string (as Python callers often pass) if need be."""
+    if isinstance(value, datetime):
+        return value.date()
+    if isinstance(value, date):
+        return value
+    if isinstance(value, str):
+        try:
+            return date.fromisoformat(value)
+        except ValueError:
+            pass
+    raise EvalError("invalid_date", f"expected a date, got {value!r}")
+
+
+def _dates(left: Any, right: Any) -> tuple[Any, Any]:
+    """Both sides as dates when one is a date and the other an ISO-date string."""
+    if isinstance(left, date) and isinstance(right, str):
+        return left, _as_date(right)
+    if isinstance(right, date) and isinstance(left, str):
+        return _as_date(left), right
+    return left, right
+
+
+def _dated(fn: Any) -> Any:
+    """Wrap a date function: its arguments read as dates, a missing one gives None."""
+
+    def wrapped(*args: Any) -> Any:
+        if any(_is_missing(a) for a in args):
+            return None
+        return fn(*map(_as_date, args))
+
+    return wrapped
+
+
+def _age_at(on: date, born: date) -> int:
+    """Whole years from ``born`` to ``on``; a birthday on ``on`` counts."""
+    return on.year - born.year - ((on.month, on.day) < (born.month, born.day))
+
+
+def _months_between(start: date, end: date) -> int:
+    """Whole calendar months from ``start`` to ``end``; negative if ``end`` is earlier."""
+    months = (end.year - start.year) * 12 + end.month - start.month
+    if months > 0 and end.day < start.day:
+        return months - 1
+    if months < 0 and end.day > start.day:
+        return months + 1
+    return months
+
+
+def _equivalence_scale(
+    num_adults: Any,
+    num_children: Any,
+    first_adult: Any = ast.OECD_MODIFIED[0],
+    other_adult: Any = ast.OECD_MODIFIED[1],
+    child: Any = ast.OECD_MODIFIED[2],
+) -> Any:
+    """Household size in adult equivalents; the OECD-modified scale by default.
+
+    The first adult weighs ``first_adult``, each further adult
+    ``other_adult`` and each child ``child``. A household of children only
+    counts its first child as the first adult; an empty household is 0.
+    """
+    if num_adults > 0:
+        return first_adult + other_adult * (num_adults - 1) + child * num_children
+    if num_children > 0:
+        return first_adult + child * (num_children - 1)
+    return first_adult * 0
+
+
+def _equivalized_income(income: Any, num_adults: Any, num_children: Any, *weights: Any) -> Any:
+    """Household income over its equivalence scale.
+
+    ``income`` is a number or a list of members' incomes, which is summed.
+    An empty household has no equivalized income: 0.
+    """
+    total = compensated_sum(income) if isinstance(income, list) else income
+    scale = _equivalence_scale(num_adults, num_children, *weights)
+    return total * 0 if scale == 0 else total / scale
+
+
+def _interpolator(xs: Sequence[Any], ys: Sequence[Any]) -> Callable[[Any], Any]:
+    """Piecewise-linear interpolation through the knots ``(xs[i], ys[i])``,
+    flat beyond the first and last; ``xs`` strictly ascend."""
+
+    def interp(x: Any) -> Any:
+        if _is_missing(x):
+            return x
+        k = bisect_right(xs, x)
+        if k == 0:
+            return float(ys[0])
+        if k == len(xs):
+            return float(ys[-1])
+        x0, x1, y0, y1 = xs[k - 1], xs[k], ys[k - 1], ys[k]
+        return y0 + (y1 - y0) * (x - x0) / (x1 - x0)
+
+    return interp
+
+
+def _draw(func: str, stream: str, counter: int, args: list[Any]) -> Any:
+    """A random built-in's value from ``stream``, starting at ``counter``.
+
+    uniform() is on [0, 1); bernoulli(p) is true with probability p;
+    normal(mu, sigma) comes from two uniforms by the Box-Muller transform.
+    A missing argument gives a missing result, as elsewhere.
+    """
+    if any(_is_nan(v) for v in args):
+        return math.nan
+    if any(v is None for v in args):
+        return None
+    u = uniform_at(stream, counter)
+    if func == "uniform":
+        return u
+    if func == "bernoulli":
+        if not 0 <= args[0] <= 1:
+            raise EvalError("invalid_argument", f"bernoulli probability {args[0]} is not in [0, 1]")
+        return u < args[0]
+    mu, sigma = args
+    if sigma < 0:
+        raise EvalError("invalid_argument", f"normal standard deviation {sigma} is negative")
+    v = uniform_at(stream, counter + 1)
+    return mu + sigma * math.sqrt(-2 * math.log(1 - u)) * math.cos(2 * math.pi * v)
+
+
 BUILTINS = {
-    "min": min,
-    "max": max,
-    "abs": abs,
-    "round": round,
-    "sum": sum,
+    "min": _nan_propagating(min),
+    "max": _nan_propagating(max),
+    "abs": lambda x: x if x is None else abs(x),
+    "round": lambda x: _whole(round, x),
+    "sum": _nan_propagating(compensated_sum),
     "len": len,
-    "clip": lambda x, lo, hi: max(lo, min(hi, x)),
+    "clip": _nan_propagating(lambda x, lo, hi: max(lo, min(hi, x))),
+    # Both branches are evaluated, unlike if/else; a missing condition is missing
+    "where": lambda cond, a, b: None if cond is None else (a if cond else b),
+    "floor": lambda x: _whole(math.floor, x),
+    "ceil": lambda x: _whole(math.ceil, x),
+    "trunc": lambda x: _whole(math.trunc, x),
+    "sign": lambda x: x if _is_missing(x) else (x > 0) - (x < 0),
     "any": any,
     "all": all,
+    "coalesce": lambda *args: next((a for a in args if not _is_missing(a)), None),
+    "is_missing": lambda x: _is_missing(x),
+    "apportion": _nan_propagating(_apportion),
+    "equivalence_scale": _nan_propagating(_equivalence_scale),
+    "equivalized_income": _nan_propagating(_equivalized_income),
+    "age_at": _dated(_age_at),
+    "year_of": _dated(lambda d: d.year),
+    "month_of": _dated(lambda d: d.month),
+    "months_between": _dated(_months_between),
+    "days_between": _dated(lambda start, end: end.toordinal() - start.toordinal()),
+    "interp": lambda x, xs, ys: _interpolator(xs, ys)(x),
+    # Aggregates: the executor passes each row's member values as one list,
+    # in the members' input row order, so a sum adds them in that order
+    "sum_over": _nan_propagating(compensated_sum),
+    "count_over": len,
+    "any_over": any,
+    "all_over": all,
+    "max_over": lambda values: BUILTINS["max"](values) if values else 0,
+    "min_over": lambda values: BUILTINS["min"](values) if values else 0,
+    # Random draws: the executor passes the row's stream and the call's counter
+    "uniform": partial(_draw, "uniform"),
+    "normal": partial(_draw, "normal"),
+    "bernoulli": partial(_draw, "bernoulli"),
 }
 
 
+def _aggregate(func: str, args: list[ast.Expr], ctx: Context, rows: list[dict]) -> list[Any]:
+    """``func`` over the member rows linked to each of ``rows``, which
+    belong to ``ctx.current_entity``."""
+    member = args[0].path if isinstance(args[0], ast.Var) else None
+    entity = ctx.current_entity
+    links = ctx.data.schema_.links(member, entity) if ctx.data.schema_ and member else []
+    if len(links) != 1:
+        raise ExecutionError(f"{func}: {member} has no single link to {entity}")
+    fk = links[0]
+    by_key = ctx.memberships.get((member, fk.name))
+    if by_key is None:
+        by_key = {}
+        for i, row in enumerate(ctx.data.get_rows(member)):
+            by_key.setdefault(row.get(fk.name), []).append(i)
+        ctx.memberships[(member, fk.name)] = by_key
+    if len(args) > 1:
+        path = args[1].path if isinstance(args[1], ast.Var) else None
+        column = ctx.entities.get(member, {}).get(path)
+        if column is None:
+            raise ExecutionError(f"{func}: {path} is not a variable of {member}")
+        if ctx.numbers:
+            column = [ctx.numbers(v) for v in column]
+    else:
+        column = [None] * len(ctx.data.get_rows(member))
+    reduce = BUILTINS[func]
+    return [
+        reduce([column[i] for i in by_key.get(row.get(fk.target_field), [])]) for row in rows
+    ]
+
+
+def _whole(fn: Callable[[Any], int], x: Any) -> Any:
+    """``fn(x)``, a whole number; missing values and infinities, which have
+    no whole number, come back unchanged."""
+    if _is_missing(x) or (isinstance(x, float) and math.isinf(x)):
+        return x
+    return fn(x)
+
+
+def _is_missing(value: Any) -> bool:
+    """None, or NaN (how the native backend represents a missing input)."""
+    return value is None or _is_nan(value)
+
+
+def _exact(value: Any) -> Any:
+    """A number as a Fraction; floats are read as the decimal they print as."""
+    if isinstance(value, Fixed):
+        return Fraction(value)
+    if isinstance(value, bool) or not isinstance(value, int | float):
+        return value
+    if isinstance(value, float):
+        return Fraction(repr(value)) if math.isfinite(value) else value
+    return Fraction(value)
+
+
+def _equal(a: Any, b: Any, tolerance: float) -> bool:
+    """``a == b``, with numbers up to ``tolerance`` apart counting as equal.
+
+    NaN equals nothing whatever the tolerance, so ``!=`` stays true for it.
+    Integers and booleans (counts, flags, codes) always compare exactly.
+    """
+    if (
+        tolerance
+        and isinstance(a, Real)
+        and isinstance(b, Real)
+        and not (isinstance(a, int) and isinstance(b, int))
+    ):
+        return abs(a - b) <= tolerance
+    return a == b
+
+
+def _inexact(value: Any) -> Any:
+    """Fractions and Fixed back to float, for results."""
+    return float(value) if isinstance(value, Fraction | Fixed) else value
+
+
+def _numbers(var: ResolvedVar, places: int | None) -> Callable[[Any], Any] | None:
+    """How ``var`` reads numbers (see Context.numbers)."""
+    if var.arithmetic == "exact":
+        return _exact
+    if places is not None:
+        return partial(to_fixed, places=places)
+    return None
+
+
 def evaluate(expr: ast.Expr, ctx: Context) -> Any:
     """Evaluate an expression in context."""
+    if ctx.work_budget is not None:
+        ctx.work += 1
+        if ctx.work > ctx.work_budget:
+            raise WorkBudgetExceeded(
+                ctx.work_budget, ctx.current_path, ctx.current_index, ctx.completed, ctx.total
+            )
+    try:
+        if ctx.profiler is not None and ctx.profiler.active:
+            return ctx.profiler.time(expr, ctx, _evaluate)
+        return _evaluate(expr, ctx)
+    except EvalError as e:
+        e.locate(expr, ctx.current_path, ctx.current_index)
+        raise
+    except FixedOverflow as e:
+        error = EvalError("overflow", str(e))
+        error.locate(expr, ctx.current_path, ctx.current_index)
+        raise error from None
+
+
+def _evaluate(expr: ast.Expr, ctx: Context) -> Any:
     match expr:
         case ast.Literal(value=v):
-            return v
+            return ctx.numbers(v) if ctx.numbers else v
 
         case ast.Var(path=path):
-            return ctx.get(path)
+            return ctx.numbers(ctx.get(path)) if ctx.numbers else ctx.get(path)
+
+        case ast.BinOp(op="and" | "or" as op, left=left, right=right):
+            left_val = evaluate(left, ctx)
+            if _decides(op, left_val):
+                return op == "or"
+            return _binary(op, left_val, evaluate(right, ctx), ctx.epsilon)
 
-        case ast.BinOp(op=op, left=left, right=right):
+        case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
             left_val = evaluate(left, ctx)
             right_val = evaluate(right, ctx)
-            match op:
-                case "+":
-                    return left_val + right_val
-                case "-":
-                    return left_val - right_val
-                case "*":
-                    return left_val * right_val
-                case "/":
-                    return left_val / right_val if right_val != 0 else 0
-                case "<":
-                    return left_val < right_val
-                case ">":
-                    return left_val > right_val
-                case "<=":
-                    return left_val <= right_val
-                case ">=":
-                    return left_val >= right_val
-                case "==":
-                    return left_val == right_val
-                case "!=":
-                    return left_val != right_val
-                case "and":
-                    return left_val and right_val
-                case "or":
-                    return left_val or right_val
-                case _:
-                    raise ExecutionError(f"unknown op: {op}")
+            epsilon = ctx.epsilon if tolerance is None else tolerance
+            return _binary(op, left_val, right_val, epsilon)
 
         case ast.UnaryOp(op=op, operand=operand):
-            v = evaluate(operand, ctx)
-            match op:
-                case "-":
-                    return -v
-                case "not":
-                    return not v
-                case _:
-                    raise ExecutionError(f"unknown unary op: {op}")
+            return _unary(op, evaluate(operand, ctx))
 
         case ast.Call(func=func, args=args):
             if func not in BUILTINS:
-                raise ExecutionError(f"unknown function: {func}")
+                spec = get_function(func)
+                if spec is None:
+                    raise EvalError("unknown_function", f"unknown function: {func}")
+                arg_vals = [evaluate(a, ctx) for a in args]
+                return call_function(
+                    func,
+                    spec,
+                    arg_vals,
+                    ctx.calls,
+                    ctx.current_path,
+                    ctx.current_index,
+                    ctx.profiler,
+                    ctx.current_entity,
+                )
+            if ctx.strict and (error := ast.arity_error(func, len(args))):
+                raise EvalError("arity", error)
+            if func in ast.AGGREGATES:
+                return _aggregate(func, args, ctx, [ctx.current_row or {}])[0]
             arg_vals = [evaluate(a, ctx) for a in args]
+            if func in ast.RANDOM:
+                row = ctx.current_row
+                key = None if row is None else row.get("id", ctx.current_index)
+                stream = stream_id(ctx.seed, ctx.current_path, key)
+                return BUILTINS[func](stream, ctx.draws.get(id(expr), 0), arg_vals)
             return BUILTINS[func](*arg_vals)
 
         case ast.FieldAccess(obj=obj, field=fld):
-            o = evaluate(obj, ctx)
-            if isinstance(o, dict):
-                return o.get(fld)
-            if isinstance(o, list):
-                return [
-                    item.get(fld) if isinstance(item, dict) else getattr(item, fld) for item in o
-                ]
-            return getattr(o, fld)
+            return _field(evaluate(obj, ctx), fld)
 
         case ast.Match(subject=subject, cases=cases, default=default):
             val = evaluate(subject, ctx)
@@ -127,13 +531,457 @@ def evaluate(expr: ast.Expr, ctx: Context) -> Any:
                     return evaluate(result, ctx)
             if default:
                 return evaluate(default, ctx)
-            raise ExecutionError(f"no match for: {val}")
+            raise EvalError("no_match", f"no match for: {val}")
 
         case ast.Cond(condition=cond, then_expr=then_e, else_expr=else_e):
-            if evaluate(cond, ctx):
+            condition = evaluate(cond, ctx)
+            if condition is None:
+                return None  # neither branch applies to a missing condition
+            if condition:
                 return evaluate(then_e, ctx)
             return evaluate(else_e, ctx)
 
+        case ast.Scale(base=base, brackets=brackets, mode=mode):
+            base_val = evaluate(base, ctx)
+            pairs = [(evaluate(t, ctx), evaluate(r, ctx)) for t, r in brackets]
+            return _scale(mode, base_val, pairs)
+
+        case ast.Lookup(key=key):
+            value = _lookup(expr)(evaluate(key, ctx))
+            return ctx.numbers(value) if ctx.numbers else value
+
+        case _:
+            raise ExecutionError(f"unknown expr type: {type(expr)}")
+
+
+def _binary(op: str, left_val: Any, right_val: Any, epsilon: float) -> Any:
+    if left_val is None or right_val is None:
+        return _missing_binary(op, left_val, right_val)
+    left_val, right_val = _dates(left_val, right_val)
+    match op:
+        case "+":
+            return left_val + right_val
+        case "-":
+            return left_val - right_val
+        case "*":
+            return left_val * right_val
+        case "/":
+            return left_val / right_val if right_val != 0 else 0
+        case "//":
+            return left_val // right_val if right_val != 0 else 0
+        case "%":
+            return left_val % right_val if right_val != 0 else 0
+        case "**":
+            return _power(left_val, right_val)
+        case "<":
+            return left_val < right_val
+        case ">":
+            return left_val > right_val
+        case "<=":
+            return left_val <= right_val
+        case ">=":
+            return left_val >= right_val
+        case "==":
+            return _equal(left_val, right_val, epsilon)
+        case "!=":
+            return not _equal(left_val, right_val, epsilon)
+        case "and":
+            return bool(left_val) and bool(right_val)
+        case "or":
+            return bool(left_val) or bool(right_val)
+        case "xor":
+            return bool(left_val) != bool(right_val)
+        case "in":
+            return left_val in right_val
+        case "not in":
+            return left_val not in right_val
+        case _:
+            raise EvalError("unknown_operator", f"unknown op: {op}")
+
+
+# Integer and exact powers above this go through floats rather than
+# building ever larger numbers
+_MAX_EXACT_EXPONENT = 64
+
+
+def _power(base: Any, exponent: Any) -> Any:
+    """``base ** exponent``, kept real: 0 to a negative power is 0 (as
+    ``x / 0`` is), a negative base to a fractional power is NaN, and
+    overflow is infinite."""
+    if base == 0 and exponent < 0:
+        return 0
+    if base < 0 and math.isfinite(exponent) and exponent != math.floor(exponent):
+        return math.nan
+    if abs(exponent) > _MAX_EXACT_EXPONENT:
+        base = float(base)
+    try:
+        return base ** exponent
+    except OverflowError:
+        odd = isinstance(exponent, int) and exponent % 2 == 1
+        return -math.inf if base < 0 and odd else math.inf
+
+
+def _decides(op: str, left_val: Any) -> bool:
+    """Whether the left operand alone settles ``and`` (false) or ``or``
+    (true), so the right isn't evaluated."""
+    return left_val is not None and bool(left_val) == (op == "or")
+
+
+def _missing_binary(op: str, left_val: Any, right_val: Any) -> Any:
+    """An operation on a missing (None) operand: missing, except where the
+    other side decides it (``false and x`` is false, ``true or x`` true)."""
+    known = right_val if left_val is None else left_val
+    if op == "and" and known is not None and not known:
+        return False
+    if op == "or" and known is not None and known:
+        return True
+    if op not in ast.BINARY_OPS:
+        raise EvalError("unknown_operator", f"unknown op: {op}")
+    return None
+
+
+def _unary(op: str, v: Any) -> Any:
+    if v is None and op in ("-", "not"):
+        return None
+    match op:
+        case "-":
+            return -v
+        case "not":
+            return not v
+        case _:
+            raise EvalError("unknown_operator", f"unknown unary op: {op}")
+
+
+def _lookup(node: ast.Lookup) -> Callable[[Any], Any]:
+    """The table of ``node`` as a function of its key; see ast.Lookup."""
+    table = dict(node.table)
+    keys = sorted(table) if node.out_of_range == "clamp" else []
+
+    def look(key: Any) -> Any:
+        if _is_missing(key):
+            return key
+        if key in table:
+            return table[key]
+        if node.out_of_range == "default":
+            return node.default
+        if keys and isinstance(key, Real) and not isinstance(key, bool):
+            return table[keys[max(bisect_right(keys, key) - 1, 0)]]
+        raise EvalError("out_of_range", f"no lookup entry for: {key!r}")
+
+    return look
+
+
+def _scale(mode: str, base: Any, brackets: list[tuple[Any, Any]]) -> Any:
+    """A bracket schedule applied to ``base``; see ast.Scale."""
+    for (lower, _), (upper, _) in zip(brackets, brackets[1:]):
+        if upper < lower:
+            raise EvalError(
+                "invalid_scale", f"scale thresholds must ascend, got {lower} then {upper}"
+            )
+    if base is None or base != base:  # missing stays missing
+        return base
+    if mode == "average":
+        rate = 0
+        for threshold, bracket_rate in brackets:
+            if base >= threshold:
+                rate = bracket_rate
+        return base * rate
+    if mode != "marginal":
+        raise EvalError("invalid_scale", f"unknown scale mode: {mode}")
+    total = 0
+    for k, (threshold, rate) in enumerate(brackets):
+        if base <= threshold:
+            break
+        top = base
+        if k + 1 < len(brackets) and brackets[k + 1][0] < base:
+            top = brackets[k + 1][0]
+        total += (top - threshold) * rate
+    return total
+
+
+def _field(o: Any, fld: str) -> Any:
+    if isinstance(o, dict):
+        return o.get(fld)
+    if isinstance(o, list):
+        return [item.get(fld) if isinstance(item, dict) else getattr(item, fld) for item in o]
+    return getattr(o, fld)
+
+
+class Columns:
+    """One entity's inputs and computed columns, for column-at-a-time evaluation.
+
+    Inputs are either ``rows`` of dicts, as held by Data, or ``fields`` of
+    one sequence per input column. Both are held by reference, never copied.
+    """
+
+    def __init__(
+        self,
+        computed: dict[str, list[Any]],
+        rows: list[dict] | None = None,
+        fields: Mapping[str, Sequence[Any]] | None = None,
+        size: int | None = None,
+    ):
+        self.computed = computed  # this entity's variables evaluated so far
+        self.rows = rows
+        self.fields = fields
+        if size is not None:
+            self.size = size
+        elif rows is not None:
+            self.size = len(rows)
+        else:
+            self.size = len(next(iter(fields.values()), ())) if fields else 0
+
+    def row_id(self, i: int) -> Any:
+        """Row ``i``'s id, or ``i`` where it has none, as its random streams are keyed."""
+        if self.rows is not None:
+            return self.rows[i].get("id", i)
+        if self.fields is not None and "id" in self.fields:
+            return self.fields["id"][i]
+        return i
+
+    def categorical(self, path: str, ctx: Context) -> Categorical | None:
+        """The input column ``path``, if it is dictionary-encoded."""
+        if path in ctx.computed or path in self.computed or self.fields is None:
+            return None
+        column = self.fields.get(path)
+        return column if isinstance(column, Categorical) else None
+
+    def get(self, path: str, ctx: Context, index: list[int]) -> list[Any]:
+        """``path`` at rows ``index``, resolved as Context.get would per row."""
+        if path in ctx.computed:
+            return [ctx.computed[path]] * len(index)
+        if path in self.computed:
+            column = self.computed[path]
+            return [column[i] for i in index]
+        if self.rows is None:
+            if self.fields is not None and path in self.fields:
+                column = self.fields[path]
+                return [column[i] for i in index]
+            return [_undefined(path, ctx, index[0])] * len(index)
+        values = []
+        for i in index:
+            row = self.rows[i]
+            values.append(row[path] if path in row else _undefined(path, ctx, i))
+        return values
+
+
+def _settle(var: ResolvedVar, value: Any, row: int | None = None) -> Any:
+    """``value`` rounded as the variable says, under its missing-value policy."""
+    if var.round_to is not None:
+        value = round_to(value, var.round_to, var.rounding or "nearest")
+    if var.missing in (None, "propagate") or not _is_missing(value):
+        return value
+    if var.missing == "default":
+        return var.default
+    error = EvalError("missing", "missing value")
+    error.locate(var.expr, var.path, row)
+    raise error
+
+
+def _settle_column(var: ResolvedVar, values: list[Any]) -> list[Any]:
+    if var.missing in (None, "propagate") and var.round_to is None:
+        return values
+    return [_settle(var, value, i) for i, value in enumerate(values)]
+
+
+def round_to(value: Any, step: float, direction: str = "nearest") -> Any:
+    """``value`` rounded to a multiple of ``step``: to the nearest (halves
+    up), down or up. Missing, infinite and non-numeric values pass through.
+    """
+    if isinstance(value, bool) or not isinstance(value, Real) or not math.isfinite(value):
+        return value
+    if isinstance(value, Fraction | Fixed):
+        exact_step = Fraction(str(step))
+        rounded = _whole_steps(Fraction(value) / exact_step, direction) * exact_step
+        return to_fixed(rounded, value.places) if isinstance(value, Fixed) else rounded
+    # A quotient within 1e-9 of a whole (or half) step counts as one, so
+    # float noise (0.29 / 0.01 is 28.999999999999996) costs no step
+    steps = _whole_steps(round(value / step, 9), direction)
+    return round(steps * step, ast.round_places(step))
+
+
+def _whole_steps(steps: Any, direction: str) -> int:
+    if direction == "down":
+        return math.floor(steps)
+    if direction == "up":
+        return math.ceil(steps)
+    return math.floor(steps + Fraction(1, 2))
+
+
+def _undefined(path: str, ctx: Context, row: int) -> Any:
+    if not ctx.strict and "/" in path:
+        return 0  # a variable with nothing in force and no declared default
+    raise EvalError("undefined", f"undefined: {path}", row=row)
+
+
+def evaluate_column(
+    expr: ast.Expr, ctx: Context, columns: Columns, index: list[int] | None = None
+) -> list[Any]:
+    """Evaluate an expression over whole columns: its value at each row of
+    ``index`` (by default every row), as evaluate() would give row by row.
+
+    Each node is dispatched once per column rather than once per row, and
+    rows are never copied. A conditional evaluates each branch only on the
+    rows that take it, so a branch never sees rows it would not run on.
+    """
+    if index is None:
+        index = list(range(columns.size))
+    if not index:
+        return []
+    try:
+        return _evaluate_column(expr, ctx, columns, index)
+    except EvalError as e:
+        e.locate(expr, ctx.current_path, None)
+        raise
+    except FixedOverflow as e:
+        error = EvalError("overflow", str(e))
+        error.locate(expr, ctx.current_path, None)
+        raise error from None
+
+
+def _evaluate_column(expr: ast.Expr, ctx: Context, columns: Columns, index: list[int]) -> list[Any]:
+    match expr:
+        case ast.Literal(value=v):
+            return [ctx.numbers(v) if ctx.numbers else v] * len(index)
+
+        case ast.Var(path=path):
+            values = columns.get(path, ctx, index)
+            return list(map(ctx.numbers, values)) if ctx.numbers else values
+
+        case ast.BinOp(
+            op="==" | "!=" | "in" | "not in" as op,
+            left=ast.Var(path=path),
+            right=ast.Literal(value=v),
+            tolerance=tolerance,
+        ) if not ctx.numbers and (encoded := columns.categorical(path, ctx)) is not None:
+            # Compared once per category rather than once per row
+            epsilon = ctx.epsilon if tolerance is None else tolerance
+            return encoded.map(lambda category: _binary(op, category, v, epsilon), index)
+
+        case ast.BinOp(op="and" | "or" as op, left=left, right=right):
+            lefts = evaluate_column(left, ctx, columns, index)
+            # The right side is evaluated only on the rows the left leaves open
+            open_rows = [i for i, v in zip(index, lefts) if not _decides(op, v)]
+            rights = dict(zip(open_rows, evaluate_column(right, ctx, columns, open_rows)))
+            return [
+                _binary(op, v, rights[i], ctx.epsilon) if i in rights else op == "or"
+                for i, v in zip(index, lefts)
+            ]
+
+        case ast.BinOp(op=op, left=left, right=right, tolerance=tolerance):
+            left_vals = evaluate_column(left, ctx, columns, index)
+            right_vals = evaluate_column(right, ctx, columns, index)
+            epsilon = ctx.epsilon if tolerance is None else tolerance
+            return [_binary(op, a, b, epsilon) for a, b in zip(left_vals, right_vals)]
+
+        case ast.UnaryOp(op=op, operand=operand):
+            return [_unary(op, v) for v in evaluate_column(operand, ctx, columns, index)]
+
+        case ast.Call(
+            func="interp", args=[x, ast.Literal(value=list(xs)), ast.Literal(value=list(ys))]
+        ):
+            # The knots are set up once for the column rather than once per row
+            return list(map(_interpolator(xs, ys), evaluate_column(x, ctx, columns, index)))
+
+        case ast.Call(func=func, args=args):
+            if func not in BUILTINS:
+                spec = get_function(func)
+                if spec is None:
+                    raise EvalError("unknown_function", f"unknown function: {func}")
+                arg_cols = [evaluate_column(a, ctx, columns, index) for a in args]
+                return [
+                    call_function(
+                        func,
+                        spec,
+                        [col[k] for col in arg_cols],
+                        ctx.calls,
+                        ctx.current_path,
+                        i,
+                        ctx.profiler,
+                        ctx.current_entity,
+                    )
+                    for k, i in enumerate(index)
+                ]
+            if ctx.strict and (error := ast.arity_error(func, len(args))):
+                raise EvalError("arity", error)
+            if func in ast.AGGREGATES:
+                if columns.rows is None:
+                    raise ExecutionError(f"{func} reads other entities, which needs run()")
+                return _aggregate(func, args, ctx, [columns.rows[i] for i in index])
+            arg_cols = [evaluate_column(a, ctx, columns, index) for a in args]
+            if func in ast.RANDOM:
+                counter = ctx.draws.get(id(expr), 0)
+                draws = []
+                for k, i in enumerate(index):
+                    stream = stream_id(ctx.seed, ctx.current_path, columns.row_id(i))
+                    try:
+                        draws.append(BUILTINS[func](stream, counter, [col[k] for col in arg_cols]))
+                    except EvalError as e:
+                        e.row = i
+                        raise
+                return draws
+            fn = BUILTINS[func]
+            if not arg_cols:
+                return [fn() for _ in index]
+            if len(arg_cols) == 1:
+                return list(map(fn, arg_cols[0]))
+            return [fn(*arg_vals) for arg_vals in zip(*arg_cols)]
+
+        case ast.FieldAccess(obj=obj, field=fld):
+            return [_field(o, fld) for o in evaluate_column(obj, ctx, columns, index)]
+
+        case ast.Match(subject=subject, cases=cases, default=default):
+            subjects = dict(zip(index, evaluate_column(subject, ctx, columns, index)))
+            results: dict[int, Any] = {}
+            remaining = index
+            for pattern, result in cases:
+                if not remaining:
+                    break
+                patterns = evaluate_column(pattern, ctx, columns, remaining)
+                hit = [i for i, p in zip(remaining, patterns) if subjects[i] == p]
+                results.update(zip(hit, evaluate_column(result, ctx, columns, hit)))
+                remaining = [i for i in remaining if i not in results]
+            if remaining:
+                if not default:
+                    raise EvalError(
+                        "no_match", f"no match for: {subjects[remaining[0]]}", row=remaining[0]
+                    )
+                results.update(zip(remaining, evaluate_column(default, ctx, columns, remaining)))
+            return [results[i] for i in index]
+
+        case ast.Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+            conditions = evaluate_column(cond, ctx, columns, index)
+            taken = [i for i, c in zip(index, conditions) if c]
+            skipped = [i for i, c in zip(index, conditions) if c is not None and not c]
+            results = {i: None for i, c in zip(index, conditions) if c is None}
+            results.update(zip(taken, evaluate_column(then_e, ctx, columns, taken)))
+            results.update(zip(skipped, evaluate_column(else_e, ctx, columns, skipped)))
+            return [results[i] for i in index]
+
+        case ast.Scale(base=base, brackets=brackets, mode=mode):
+            bases = evaluate_column(base, ctx, columns, index)
+            pairs = [
+                list(
+                    zip(
+                        evaluate_column(t, ctx, columns, index),
+                        evaluate_column(r, ctx, columns, index),
+                    )
+                )
+                for t, r in brackets
+            ]
+            return [_scale(mode, b, [col[k] for col in pairs]) for k, b in enumerate(bases)]
+
+        case ast.Lookup(key=key):
+            look = _lookup(expr)
+            values = []
+            try:
+                for k in evaluate_column(key, ctx, columns, index):
+                    values.append(look(k))
+            except EvalError as e:
+                e.row = index[len(values)]
+                raise
+            return list(map(ctx.numbers, values)) if ctx.numbers else values
+
         case _:
             raise ExecutionError(f"unknown expr type: {type(expr)}")
 
@@ -145,29 +993,324 @@ class Result(BaseModel):
 
     scalars: dict[str, Any]
     entities: dict[str, dict[str, list[Any]]]
+    function_stats: dict[str, FunctionStats] = {}  # registered functions called
+    warnings: list[str] = []
+    order: list[str] = []  # variables in the order they were evaluated
+    reused: list[str] = []  # variables copied from an earlier result instead
+    column_stats: dict[str, ColumnStats] = {}  # monitored variables (collect_stats)
+    plan: Plan | None = None  # what the run laid out before evaluating
+    row_ids: dict[str, list[Any]] = {}  # primary key of each row, per entity with outputs
+
+    def ranges(self) -> dict[str, tuple[Any, Any]]:
+        """Observed (min, max) of each entity variable over all rows.
+
+        Missing values (None or NaN) are ignored; a variable with no observed
+        values reports ``(None, None)``.
+        """
+        out = {}
+        for columns in self.entities.values():
+            for path, values in columns.items():
+                present = [v for v in values if not _is_missing(v)]
+                out[path] = (min(present), max(present)) if present else (None, None)
+        return out
+
+    def to_long(self, entity: str | None = None) -> list[dict[str, Any]]:
+        """Entity outputs as tidy records, one per (row, variable).
+
+        Each record is ``{"entity", "entity_id", "variable", "value"}``;
+        records run by entity, then row, then variable in evaluation order.
+        ``entity`` restricts them to one entity.
+        """
+        if entity is not None and entity not in self.entities:
+            raise ExecutionError(f"no outputs for entity: {entity}")
+        names = [entity] if entity is not None else list(self.entities)
+        records = []
+        for name in names:
+            columns = self.entities[name]
+            for i, row_id in enumerate(self.row_ids.get(name, [])):
+                for path, values in columns.items():
+                    records.append(
+                        {"entity": name, "entity_id": row_id, "variable": path, "value": values[i]}
+                    )
+        return records
+
+    def to_matrix(self, entity: str) -> tuple[Any, list[str]]:
+        """One entity's outputs as a C-contiguous float64 array (rows x variables).
+
+        Columns follow evaluation order, which is fixed for a given IR, and
+        are named by the returned list; missing values are NaN and booleans
+        1.0/0.0, as in the native backend's arrays. Needs numpy.
+        """
+        import numpy as np
+
+        if entity not in self.entities:
+            raise ExecutionError(f"no outputs for entity: {entity}")
+        columns = self.entities[entity]
+        names = list(columns)
+        rows = len(next(iter(columns.values()), []))
+        out = np.empty((rows, len(names)), dtype=np.float64)
+        for j, name in enumerate(names):
+            out[:, j] = _floats(name, columns[name])
+        return out, names
+
+
+def _floats(name: str, values: list[Any]) -> list[float]:
+    try:
+        return [math.nan if v is None else float(v) for v in values]
+    except (TypeError, ValueError):
+        raise ExecutionError(f"{name} has non-numeric values") from None
 
 
 class Executor:
-    """Executes compiled IR against data."""
+    """Executes compiled IR against data.
+
+    Variables are evaluated once each, in dependency order. Entity variables
+    store their per-row values and downstream references read the stored
+    value, so a shared intermediate is never recomputed.
+    """
 
-    def __init__(self, ir: IR):
+    def __init__(
+        self,
+        ir: IR,
+        profiler: Any = None,
+        config: EngineConfig | None = None,
+        collect_stats: list[str] | None = None,
+    ):
         self.ir = ir
+        self.profiler = profiler
+        cfg = resolve_config(config)
+        self.strict = cfg.strict
+        self.epsilon = cfg.epsilon
+        self.places = cfg.decimal_places  # None evaluates in binary floating point
+        self.seed = cfg.seed
+        self.draws = ast.draw_counters(var.expr for var in ir.variables.values())
+        self.work_budget = cfg.work_budget
+        self.max_iterations = cfg.max_iterations
+        self.iteration_tolerance = cfg.iteration_tolerance
+        # Profiling and work budgets account per row, so they keep the row path
+        self.columnar = cfg.columnar and profiler is None and self.work_budget is None
+        self.bytecode = cfg.bytecode and profiler is None and self.work_budget is None
+        self.programs: dict[str, Any] = {}  # bytecode.Program by path, None where unsupported
+        self.monitored = monitored_paths(ir, collect_stats or [])
+        self.groups = {path: group for group in ir.fixed_point for path in group}
+        self.inputs: dict[str, list[str]] | None = None  # set once the IR passes build_plan
+        self.broadcasts = {
+            path: reads
+            for path, var in ir.variables.items()
+            if (reads := broadcast_reads(var, ir.variables))
+        }
+
+    def _broadcast(
+        self, var: ResolvedVar, data: Data, entities: dict[str, dict[str, list[Any]]]
+    ) -> dict[str, list[Any]]:
+        """Other entities' variables that ``var`` reads, each value carried
+        down to the rows linked to it; unlinked rows read None."""
+        linked = {}
+        for dep in self.broadcasts.get(var.path, []):
+            parent = self.ir.variables[dep].entity
+            links = data.schema_.links(var.entity, parent) if data.schema_ else []
+            if len(links) != 1:
+                raise ExecutionError(f"{var.path} reads {dep}: no single link to {parent}")
+            fk = links[0]
+            position = {row.get(fk.target_field): i for i, row in enumerate(data.get_rows(parent))}
+            column = entities.get(parent, {}).get(dep, [])
+            linked[dep] = [
+                column[position[key]] if (key := row.get(fk.name)) in position else None
+                for row in data.get_rows(var.entity)
+            ]
+        return linked
+
+    def _iterate(self, group: list[str], ctx: Context, store: dict[str, Any]) -> bool:
+        """Gauss-Seidel sweeps over ``group`` starting from 0, writing each new
+        value to ``store``; whether every change fell within tolerance."""
+        for path in group:
+            store[path] = 0
+        for _ in range(self.max_iterations):
+            settled = True
+            for path in group:
+                var = self.ir.variables[path]
+                ctx.current_path = path
+                ctx.numbers = _numbers(var, self.places)
+                value = evaluate(var.expr, ctx)
+                settled = settled and _within(store[path], value, self.iteration_tolerance)
+                store[path] = value
+            if settled:
+                return True
+        return False
+
+    def _solve(
+        self,
+        group: list[str],
+        ctx: Context,
+        data: Data,
+        entities: dict[str, dict[str, list[Any]]],
+        column_stats: dict[str, ColumnStats],
+    ) -> list[str]:
+        """Solve a fixed-point group, per row for entity variables; returns
+        a diagnostic if any row failed to converge."""
+        entity = self.ir.variables[group[0]].entity
+        failed: list[Any] = []
+        if entity is None:
+            if self.profiler is not None:
+                self.profiler.start_row(None)
+            if not self._iterate(group, ctx, ctx.computed):
+                failed.append("scalar")
+            solved = {path: [ctx.computed[path]] for path in group}
+        else:
+            columns = entities.setdefault(entity, {})
+            prior = dict(columns)
+            solved = {path: [] for path in group}
+            for i, row in enumerate(data.get_rows(entity)):
+                augmented = dict(row)
+                for prev_path, prev_vals in prior.items():
+                    augmented[prev_path] = prev_vals[i]
+                ctx.current_row = augmented
+                ctx.current_entity = entity
+                ctx.current_index = i
+                if self.profiler is not None:
+                    self.profiler.start_row(i)
+                if not self._iterate(group, ctx, augmented):
+                    failed.append(row.get("id", i))
+                for path in group:
+                    solved[path].append(augmented[path])
+            ctx.current_row = None
+            ctx.current_entity = None
+            ctx.current_index = None
+            columns.update(solved)
+        for path, values in solved.items():
+            if acc := column_stats.get(path):
+                for value in values:
+                    acc.add(value)
+        if not failed:
+            return []
+        where = "" if entity is None else f" for {entity} rows {_examples(failed)}"
+        return [
+            f"{', '.join(group)} did not converge within "
+            f"{self.max_iterations} iterations{where}"
+        ]
+
+    def _evaluate_column(self, var: ResolvedVar, ctx: Context, columns: Columns) -> list[Any]:
+        """``var`` over every row: as bytecode when enabled and the formula
+        compiles to it, otherwise by walking the tree a column at a time."""
+        program = self._program(var) if self.bytecode else None
+        if program is None:
+            return evaluate_column(var.expr, ctx, columns)
+        index = list(range(columns.size))
+        try:
+            slots = [columns.get(name, ctx, index) for name in program.names]
+        except EvalError:
+            # An input missing from some rows: only the tree walk knows
+            # whether those rows ever read it
+            return evaluate_column(var.expr, ctx, columns)
+        try:
+            return program.run_column(slots, columns.size)
+        except EvalError as e:
+            e.locate(var.expr, var.path, None)
+            raise
 
-    def execute(self, data: Data) -> Result:
-        ctx = Context(data=data)
+    def _program(self, var: ResolvedVar) -> Any:
+        if var.path not in self.programs:
+            from .bytecode import BytecodeError, compile_expr  # deferred: imports this module
+
+            program = None
+            # Bytecode runs on floats, not fractions or fixed point
+            if var.arithmetic != "exact" and self.places is None:
+                try:
+                    program = compile_expr(var.expr, self.epsilon)
+                except BytecodeError:
+                    pass
+            self.programs[var.path] = program
+        return self.programs[var.path]
+
+    def execute(
+        self,
+        data: Data,
+        reuse: Result | None = None,
+        recompute: set[str] | None = None,
+        plan: Plan | None = None,
+    ) -> Result:
+        """Evaluate every variable; raises WorkBudgetExceeded past the work budget.
+
+        With ``reuse``, variables not in ``recompute`` take their values from
+        that earlier result (on the same data) instead of being evaluated;
+        fixed-point groups are always solved afresh. ``plan`` is one already
+        built by build_plan for this IR and data.
+        """
+        if plan is None:
+            plan = build_plan(self.ir, data, self.inputs)
+            if self.inputs is None:
+                self.inputs = entity_inputs(self.ir)
+        ctx = Context(
+            data=data,
+            profiler=self.profiler,
+            strict=self.strict,
+            epsilon=self.epsilon,
+            work_budget=self.work_budget,
+            total=len(self.ir.order),
+            seed=self.seed,
+            draws=self.draws,
+        )
         entities: dict[str, dict[str, list[Any]]] = {}
+        ctx.entities = entities
+        reused = []
+        unsettled: list[str] = []
+        column_stats = {path: ColumnStats() for path in self.monitored}
 
-        for path in self.ir.order:
+        for completed, step in enumerate(plan.steps):
+            path = step.path
             var = self.ir.variables[path]
+            ctx.current_path = path
+            ctx.completed = completed
+            ctx.numbers = _numbers(var, self.places)
+            acc = column_stats.get(path)
+
+            if group := self.groups.get(path):
+                if path == group[0]:
+                    unsettled.extend(self._solve(group, ctx, data, entities, column_stats))
+                continue
+
+            if reuse is not None and path not in (recompute or ()):
+                if var.entity is None and path in reuse.scalars:
+                    ctx.computed[path] = reuse.scalars[path]
+                    reused.append(path)
+                    if acc is not None:
+                        acc.add(ctx.computed[path])
+                    continue
+                columns = reuse.entities.get(var.entity or "", {})
+                if path in columns:
+                    entities.setdefault(var.entity, {})[path] = list(columns[path])
+                    reused.append(path)
+                    if acc is not None:
+                        for value in columns[path]:
+                            acc.add(value)
+                    continue
 
             if var.entity is None:
-                ctx.computed[path] = evaluate(var.expr, ctx)
+                if self.profiler is not None:
+                    self.profiler.start_row(None)
+                ctx.computed[path] = _settle(var, evaluate(var.expr, ctx))
+                if acc is not None:
+                    acc.add(ctx.computed[path])
             else:
                 entity_name = var.entity
                 rows = data.get_rows(entity_name)
 
                 if entity_name not in entities:
                     entities[entity_name] = {}
+                linked = self._broadcast(var, data, entities)
+                if self.columnar or (self.bytecode and self._program(var) is not None):
+                    computed = entities[entity_name]
+                    if linked:
+                        computed = {**computed, **linked}
+                    ctx.current_entity = entity_name
+                    values = self._evaluate_column(var, ctx, Columns(computed, rows=rows))
+                    values = _settle_column(var, values)
+                    ctx.current_entity = None
+                    entities[entity_name][path] = values
+                    if acc is not None:
+                        for val in values:
+                            acc.add(val)
+                    continue
                 entities[entity_name][path] = []
 
                 for i, row in enumerate(rows):
@@ -175,18 +1318,245 @@ class Executor:
                     for prev_path, prev_vals in entities.get(entity_name, {}).items():
                         if len(prev_vals) > i:
                             augmented[prev_path] = prev_vals[i]
+                    for dep, values in linked.items():
+                        augmented[dep] = values[i]
                     ctx.current_row = augmented
                     ctx.current_entity = entity_name
-                    val = evaluate(var.expr, ctx)
+                    ctx.current_index = i
+                    if self.profiler is not None:
+                        self.profiler.start_row(i)
+                    val = _settle(var, evaluate(var.expr, ctx), i)
                     entities[entity_name][path].append(val)
+                    if acc is not None:
+                        acc.add(val)
                     ctx.current_row = None
                     ctx.current_entity = None
+                    ctx.current_index = None
+
+        if self.places is not None or any(
+            var.arithmetic == "exact" for var in self.ir.variables.values()
+        ):
+            ctx.computed = {path: _inexact(v) for path, v in ctx.computed.items()}
+            for columns in entities.values():
+                for values in columns.values():
+                    values[:] = map(_inexact, values)
+
+        return Result(
+            scalars=ctx.computed,
+            entities=entities,
+            function_stats=ctx.calls.stats,
+            warnings=ctx.calls.warnings() + unsettled,
+            order=list(self.ir.order),
+            reused=reused,
+            column_stats=column_stats,
+            plan=plan,
+            row_ids={entity: _row_ids(data, entity) for entity in entities},
+        )
+
+
+def _row_ids(data: Data, entity: str) -> list[Any]:
+    decl = data.schema_.entities.get(entity) if data.schema_ else None
+    key = decl.primary_key if decl else "id"
+    return [row.get(key, i) for i, row in enumerate(data.get_rows(entity))]
+
+
+def monitored_paths(ir: IR, patterns: list[str]) -> list[str]:
+    """Variables selected by ``collect_stats``, in evaluation order.
+
+    Patterns are variable paths or globs (``person/*``); a plain path that
+    names no variable is an error, a glob matching nothing is not.
+    """
+    for pattern in patterns:
+        if not any(c in pattern for c in "*?[") and pattern not in ir.variables:
+            raise ExecutionError(f"unknown variable: {pattern}")
+    return [path for path in ir.order if any(fnmatchcase(path, p) for p in patterns)]
+
+
+def resolve_scalars(
+    ir: IR, overrides: Mapping[str, Any] | None = None, config: EngineConfig | None = None
+) -> dict[str, Any]:
+    """Evaluate only the scalar (non-entity) variables, without any data.
+
+    ``overrides`` fixes variables to values, as a reform would. The result
+    depends only on ``ir``, ``overrides`` and the seed, so callers may cache it.
+    """
+    overrides = dict(overrides or {})
+    for path in overrides:
+        if path not in ir.variables:
+            raise ExecutionError(f"unknown variable: {path}")
+    cfg = resolve_config(config)
+    ctx = Context(
+        data=Data(tables={}),
+        epsilon=cfg.epsilon,
+        seed=cfg.seed,
+        draws=ast.draw_counters(var.expr for var in ir.variables.values()),
+    )
+    for path in ir.order:
+        var = ir.variables[path]
+        if var.entity is not None:
+            continue
+        ctx.current_path = path
+        ctx.numbers = _numbers(var, None)
+        if path in overrides:
+            ctx.computed[path] = overrides[path]
+        else:
+            ctx.computed[path] = _settle(var, evaluate(var.expr, ctx))
+    return {path: _inexact(v) for path, v in ctx.computed.items()}
+
+
+def execute_columns(
+    ir: IR,
+    entity: str,
+    fields: Mapping[str, Sequence[Any]],
+    outputs: list[str] | None = None,
+    config: EngineConfig | None = None,
+    size: int | None = None,
+) -> dict[str, list[Any]]:
+    """Evaluate one entity's variables over input columns rather than rows.
+
+    ``fields`` maps input names to equal-length sequences, read in place;
+    ``size``, the number of rows, is needed only if no inputs are given.
+    Returns a column per variable of ``entity`` (only ``outputs`` and their
+    dependencies, if given); other entities' variables are not evaluated.
+    """
+    if outputs is not None:
+        ir = prune(ir, outputs)
+    sizes = {len(column) for column in fields.values()}
+    if size is not None:
+        sizes.add(size)
+    if len(sizes) > 1:
+        raise ExecutionError(f"input columns differ in length: {sorted(sizes)}")
+    for group in ir.fixed_point:
+        if ir.variables[group[0]].entity in (None, entity):
+            raise ExecutionError(f"fixed-point group {', '.join(group)} needs run()")
+    cfg = resolve_config(config)
+    ctx = Context(
+        data=Data(tables={}),
+        strict=cfg.strict,
+        epsilon=cfg.epsilon,
+        seed=cfg.seed,
+        draws=ast.draw_counters(var.expr for var in ir.variables.values()),
+    )
+    columns = Columns({}, fields=fields, size=size)
+    for path in ir.order:
+        var = ir.variables[path]
+        ctx.current_path = path
+        ctx.numbers = _numbers(var, cfg.decimal_places)
+        if var.entity is None:
+            ctx.computed[path] = _settle(var, evaluate(var.expr, ctx))
+        elif var.entity == entity:
+            columns.computed[path] = _settle_column(var, evaluate_column(var.expr, ctx, columns))
+    return {path: list(map(_inexact, values)) for path, values in columns.computed.items()}
+
+
+def execute_numpy(
+    ir: IR,
+    entity: str,
+    columns: Mapping[str, Any],
+    outputs: list[str] | None = None,
+    config: EngineConfig | None = None,
+) -> dict[str, Any]:
+    """execute_columns over one-dimensional NumPy arrays, returning float64 arrays.
+
+    NaN inputs read as missing; in the outputs missing values are NaN and
+    booleans 1.0/0.0, as in Result.to_matrix.
+    """
+    import numpy as np
+
+    fields = {}
+    for name, array in columns.items():
+        array = np.asarray(array)
+        if array.ndim != 1:
+            raise ExecutionError(f"{name} must be one-dimensional, got shape {array.shape}")
+        fields[name] = array.tolist()
+    values = execute_columns(ir, entity, fields, outputs, config)
+    return {path: np.array(_floats(path, vals), dtype=np.float64) for path, vals in values.items()}
+
+
+def run(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    profiler: Any = None,
+    missing: Mapping[str, MissingSpec] | None = None,
+    config: EngineConfig | None = None,
+    dedupe: str = "error",
+    collect_stats: list[str] | None = None,
+    outputs: list[str] | None = None,
+    dry_run: bool = False,
+) -> Result | DryRunReport:
+    """Execute IR against data.
+
+    ``missing`` maps entity -> column -> sentinel values (e.g. ``[-999]`` or
+    ``[float("nan")]``) that are read as missing (None) before evaluation.
+    Table dicts are checked against the IR's entity links (see Data);
+    ``dedupe="first"`` drops exact-duplicate rows instead of raising.
+    ``collect_stats`` lists variable paths or globs whose min, max, mean,
+    standard deviation and null count are gathered while they are
+    evaluated, onto ``Result.column_stats``.
+    ``outputs`` restricts the run to those variables and everything they
+    transitively depend on; other variables are never evaluated and are
+    absent from the result.
+
+    ``dry_run=True`` stops at the evaluation boundary: every check a real
+    run makes (IR structure, input fields, entity linkage) runs and the
+    plan is built, but no expression is evaluated. A DryRunReport with the
+    plan, or with the problems found, is returned instead of a Result.
+    """
+    try:
+        if outputs is not None:
+            ir = prune(ir, outputs)
+        if isinstance(data, dict):
+            data = Data(tables=data, schema_=ir.schema_, dedupe=dedupe)
+        if missing:
+            tables = dict(data.tables)
+            for entity, spec in missing.items():
+                if entity in tables:
+                    tables[entity] = apply_missing(tables[entity], spec)
+            data = Data(tables=tables, schema_=data.schema_, dedupe=data.dedupe)
+        executor = Executor(ir, profiler=profiler, config=config, collect_stats=collect_stats)
+        plan = build_plan(ir, data)
+    except PlanError as e:
+        if dry_run:
+            return DryRunReport(problems=e.problems)
+        raise
+    except (CompileError, ExecutionError, LinkageError) as e:
+        if dry_run:
+            return DryRunReport(problems=[str(e)])
+        raise
+    if dry_run:
+        return DryRunReport(plan=plan)
+    return executor.execute(data, plan=plan)
 
-        return Result(scalars=ctx.computed, entities=entities)
 
+def eval_variable(
+    var: ResolvedVar,
+    inputs: dict[str, Any],
+    strict: bool | None = None,
+    config: EngineConfig | None = None,
+) -> Any:
+    """Evaluate one variable's expression against hand-specified inputs.
 
-def run(ir: IR, data: Data | dict[str, list[dict]]) -> Result:
-    """Execute IR against data."""
-    if isinstance(data, dict):
-        data = Data(tables=data)
-    return Executor(ir).execute(data)
+    ``inputs`` supplies values for the expression's direct references, both
+    computed variables and row fields. In strict mode (the default) a missing
+    reference raises ExecutionError; in lenient mode it evaluates as 0.
+    """
+    values = dict(inputs)
+    cfg = resolve_config(config, strict=strict)
+    strict = cfg.strict
+    if not strict:
+        for node in ast.walk(var.expr):
+            if isinstance(node, ast.Var):
+                values.setdefault(node.path, 0)
+    ctx = Context(
+        data=Data(tables={}),
+        computed=values,
+        current_row=values,
+        current_entity=var.entity,
+        current_path=var.path,
+        strict=strict,
+        epsilon=cfg.epsilon,
+        numbers=_numbers(var, cfg.decimal_places),
+        seed=cfg.seed,
+        draws=ast.draw_counters([var.expr]),
+    )
+    return _inexact(_settle(var, evaluate(var.expr, ctx)))
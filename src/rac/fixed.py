@@ -0,0 +1,234 @@
+"""Fixed-point decimal arithmetic, for runs with ``decimal_places`` set.
+
+A Fixed is a whole number of units of 10**-places (milli-pence with
+places=3 on amounts in pounds) held as a signed 64-bit integer, so 0.1 +
+0.2 is exactly 0.3. Sums and differences are exact; a product or quotient
+rounds to the nearest unit, halves to even. A result outside the i64 range
+raises FixedOverflow rather than wrapping or losing precision.
+
+Fixed overloads the arithmetic and comparison operators, so the ordinary
+evaluator runs on it unchanged. Ints and floats meeting a Fixed are read as
+the decimal they print as; NaN and infinities stay floats, and so does a
+fractional power.
+"""
+
+import math
+import numbers
+from fractions import Fraction
+from typing import Any
+
+I64_MIN, I64_MAX = -(2**63), 2**63 - 1
+MAX_PLACES = 18  # 10**18 units still leaves whole values up to about 9.2
+
+
+class FixedOverflow(ArithmeticError):
+    pass
+
+
+class Fixed:
+    """A decimal held as ``units`` of 10**-``places``."""
+
+    __slots__ = ("units", "places")
+
+    def __init__(self, units: int, places: int):
+        if not I64_MIN <= units <= I64_MAX:
+            value = Fraction(units, 10**places)
+            raise FixedOverflow(
+                f"{float(value):g} is out of fixed-point range with {places} decimal places"
+            )
+        self.units = units
+        self.places = places
+
+    def __repr__(self) -> str:
+        return f"Fixed('{self}')"
+
+    def __str__(self) -> str:
+        sign = "-" if self.units < 0 else ""
+        whole, part = divmod(abs(self.units), 10**self.places)
+        return f"{sign}{whole}.{part:0{self.places}d}" if self.places else f"{sign}{whole}"
+
+    @property
+    def numerator(self) -> int:
+        return self._fraction().numerator
+
+    @property
+    def denominator(self) -> int:
+        return self._fraction().denominator
+
+    def _fraction(self) -> Fraction:
+        return Fraction(self.units, 10**self.places)
+
+    def _with(self, value: Fraction | int) -> "Fixed":
+        """``value``, in units, rounded to a whole unit."""
+        return Fixed(round(value), self.places)
+
+    def __add__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) + o
+        return Fixed(self.units + o.units, self.places)
+
+    __radd__ = __add__
+
+    def __sub__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) - o
+        return Fixed(self.units - o.units, self.places)
+
+    def __rsub__(self, other: Any) -> Any:
+        return -self + other
+
+    def __mul__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) * o
+        return self._with(Fraction(self.units * o.units, 10**self.places))
+
+    __rmul__ = __mul__
+
+    def __truediv__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) / o
+        return self._with(Fraction(self.units * 10**self.places, o.units))
+
+    def __rtruediv__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else o / float(self)
+        return o / self
+
+    def __floordiv__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) // o
+        return Fixed(self.units // o.units * 10**self.places, self.places)
+
+    def __rfloordiv__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else o // float(self)
+        return o // self
+
+    def __mod__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) % o
+        return Fixed(self.units % o.units, self.places)
+
+    def __rmod__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else o % float(self)
+        return o % self
+
+    def __pow__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else float(self) ** o
+        if o.units % 10**o.places:
+            return float(self) ** float(o)  # a fractional power isn't a decimal
+        return self._with(self._fraction() ** (o.units // 10**o.places) * 10**self.places)
+
+    def __rpow__(self, other: Any) -> Any:
+        o = _operand(other, self.places)
+        if not isinstance(o, Fixed):
+            return o if o is NotImplemented else o ** float(self)
+        return o**self
+
+    def __neg__(self) -> "Fixed":
+        return Fixed(-self.units, self.places)
+
+    def __pos__(self) -> "Fixed":
+        return self
+
+    def __abs__(self) -> "Fixed":
+        return Fixed(abs(self.units), self.places)
+
+    def __round__(self, ndigits: int | None = None) -> Any:
+        if ndigits is None:
+            return round(self._fraction())
+        return to_fixed(round(self._fraction(), ndigits), self.places)
+
+    def __floor__(self) -> int:
+        return self.units // 10**self.places
+
+    def __ceil__(self) -> int:
+        return -(-self.units // 10**self.places)
+
+    def __trunc__(self) -> int:
+        return math.trunc(self._fraction())
+
+    def __int__(self) -> int:
+        return math.trunc(self._fraction())
+
+    def __float__(self) -> float:
+        return self.units / 10**self.places
+
+    def __bool__(self) -> bool:
+        return self.units != 0
+
+    def __hash__(self) -> int:
+        return hash(float(self))  # that of the int or float it equals, so lookups find it
+
+    def __eq__(self, other: Any) -> bool:
+        o = _comparable(other)
+        return o if o is NotImplemented else self._fraction() == o
+
+    def __lt__(self, other: Any) -> bool:
+        o = _comparable(other)
+        return o if o is NotImplemented else self._fraction() < o
+
+    def __le__(self, other: Any) -> bool:
+        o = _comparable(other)
+        return o if o is NotImplemented else self._fraction() <= o
+
+    def __gt__(self, other: Any) -> bool:
+        o = _comparable(other)
+        return o if o is NotImplemented else self._fraction() > o
+
+    def __ge__(self, other: Any) -> bool:
+        o = _comparable(other)
+        return o if o is NotImplemented else self._fraction() >= o
+
+
+numbers.Rational.register(Fixed)
+
+
+def to_fixed(value: Any, places: int) -> Any:
+    """A number as a Fixed with ``places`` decimals; floats are read as the
+    decimal they print as. Anything else (booleans, strings, dates, NaN and
+    infinities) is returned as it is."""
+    if isinstance(value, Fixed):
+        if value.places == places:
+            return value
+        value = value._fraction()
+    elif isinstance(value, bool) or not isinstance(value, int | float | Fraction):
+        return value
+    elif isinstance(value, float):
+        if not math.isfinite(value):
+            return value
+        value = Fraction(repr(value))
+    return Fixed(round(value * 10**places), places)
+
+
+def _operand(other: Any, places: int) -> Any:
+    """The other side of an arithmetic operator: a Fixed, a float that
+    can't be one (NaN or infinite), or NotImplemented."""
+    if isinstance(other, bool):
+        other = int(other)
+    if isinstance(other, float) and not math.isfinite(other):
+        return other
+    converted = to_fixed(other, places)
+    return converted if isinstance(converted, Fixed) else NotImplemented
+
+
+def _comparable(other: Any) -> Any:
+    if isinstance(other, Fixed):
+        return other._fraction()
+    if isinstance(other, float) and math.isfinite(other):
+        return Fraction(repr(other))  # as the decimal it prints as, like an operand
+    if isinstance(other, int | float | Fraction):
+        return other
+    return NotImplemented
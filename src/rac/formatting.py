@@ -0,0 +1,122 @@
+"""Locale-aware formatting of monetary outputs and run summaries for reports.
+
+Formatting is table-driven and independent of the process locale, so the
+same call produces the same string on every machine.
+"""
+
+from collections.abc import Iterable, Mapping
+
+from pydantic import BaseModel
+
+from .ingest import NumberFormat, format_number
+
+
+class LocaleSpec(BaseModel):
+    """Number and currency-placement conventions for a locale."""
+
+    number: NumberFormat
+    symbol_first: bool = True
+    symbol_space: bool = False
+
+
+LOCALES: dict[str, LocaleSpec] = {
+    "en_GB": LocaleSpec(number=NumberFormat()),
+    "en_US": LocaleSpec(number=NumberFormat()),
+    "en_IE": LocaleSpec(number=NumberFormat()),
+    "de_DE": LocaleSpec(
+        number=NumberFormat(decimal=",", thousands="."), symbol_first=False, symbol_space=True
+    ),
+    "fr_FR": LocaleSpec(
+        number=NumberFormat(decimal=",", thousands="\u202f"), symbol_first=False, symbol_space=True
+    ),
+    "nl_NL": LocaleSpec(number=NumberFormat(decimal=",", thousands="."), symbol_space=True),
+}
+
+CURRENCY_SYMBOLS: dict[str, str] = {
+    "GBP": "£",
+    "EUR": "€",
+    "USD": "$",
+}
+
+
+def format_currency(value: float, locale: str, currency: str, decimals: int = 2) -> str:
+    """Format a monetary value, e.g. "£1,234.56" (en_GB) or "1.234,56 €" (de_DE).
+
+    Negative values carry a leading minus sign in every locale.
+    """
+    if locale not in LOCALES:
+        raise ValueError(f"unknown locale: {locale}")
+    spec = LOCALES[locale]
+    symbol = CURRENCY_SYMBOLS.get(currency, currency)
+    body = format_number(abs(value), spec.number, decimals)
+    sep = " " if spec.symbol_space else ""
+    text = f"{symbol}{sep}{body}" if spec.symbol_first else f"{body}{sep}{symbol}"
+    # Rounding may turn a tiny negative into zero; don't print "-0.00"
+    negative = value < 0 and any(c not in "0.," for c in f"{abs(value):.{decimals}f}")
+    return f"-{text}" if negative else text
+
+
+def format_currency_column(
+    values: Iterable[float], locale: str, currency: str, decimals: int = 2
+) -> list[str]:
+    """Format every value in a column."""
+    return [format_currency(v, locale, currency, decimals) for v in values]
+
+
+# Summaries always use "," thousands and "." decimals, whatever the locale
+SUMMARY_FORMAT = NumberFormat(parens_negative=False)
+
+
+class RunStats(BaseModel):
+    """What a run did, for run_summary()."""
+
+    rows: int
+    wall_time: float  # seconds
+    variables: int
+    warnings: int = 0
+    variable_times: dict[str, float] = {}  # seconds per variable, e.g. Profiler.variable_times()
+
+
+def _number(value: float) -> str:
+    decimals = 0 if float(value).is_integer() else 2
+    return format_number(value, SUMMARY_FORMAT, decimals)
+
+
+def _seconds(value: float) -> str:
+    return f"{value:.2f} s"
+
+
+def run_summary(
+    stats: RunStats,
+    aggregates: Mapping[str, float] | None = None,
+    markdown: bool = False,
+) -> str:
+    """A compact, pasteable summary of a run.
+
+    Lists rows processed, wall time, throughput, variables evaluated, warning
+    count and the three slowest variables, then each requested aggregate.
+    ``markdown=True`` renders a two-column table for notebooks instead of
+    aligned plain text.
+    """
+    throughput = (
+        f"{_number(round(stats.rows / stats.wall_time))} rows/s" if stats.wall_time > 0 else "n/a"
+    )
+    slowest = sorted(stats.variable_times.items(), key=lambda kv: kv[1], reverse=True)[:3]
+    lines = [
+        ("rows processed", _number(stats.rows)),
+        ("wall time", _seconds(stats.wall_time)),
+        ("throughput", throughput),
+        ("variables evaluated", _number(stats.variables)),
+        ("warnings", _number(stats.warnings)),
+    ]
+    if slowest:
+        lines.append(
+            ("slowest variables", ", ".join(f"{name} ({_seconds(t)})" for name, t in slowest))
+        )
+    lines += [(name, _number(value)) for name, value in (aggregates or {}).items()]
+
+    if markdown:
+        rows = [f"| {label} | {value} |" for label, value in lines]
+        return "\n".join(["| metric | value |", "| --- | --- |", *rows])
+    width = max(len(label) for label, _ in lines)
+    return "\n".join(f"{label:<{width}}  {value}" for label, value in lines)
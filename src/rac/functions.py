@@ -0,0 +1,213 @@
+"""User-registered Python functions callable from RAC expressions.
+
+A registered function is called like a builtin. Functions that can fail
+transiently (e.g. a lookup service timing out) may be retried with
+exponential backoff, and once retries are exhausted either re-raise (the
+default), evaluate as missing (None), or evaluate to a fixed default.
+Retries and failures are counted per function and reported on the result.
+
+A function registered with ``memoize=True`` is taken to be pure: within one
+row, a call repeating an earlier successful call's arguments exactly (floats
+compared bit for bit) reuses its result, so several variables applying the
+same lookup to the row's value pay for it once. Each row keeps at most
+MEMO_ROW_SIZE results, and they last only as long as the run's call log.
+"""
+
+import time
+from collections.abc import Callable
+from typing import Any
+
+from pydantic import BaseModel, ConfigDict
+
+FAILURE_POLICIES = ("error", "missing", "default")
+MEMO_ROW_SIZE = 64  # memoized results per row; later distinct calls in the row aren't kept
+
+
+class FunctionError(Exception):
+    pass
+
+
+class FunctionSpec(BaseModel):
+    """A registered function and how to handle it raising."""
+
+    model_config = ConfigDict(arbitrary_types_allowed=True)
+
+    fn: Callable[..., Any]
+    retries: int = 0  # extra attempts after the first failure
+    retry_backoff_ms: float = 0  # delay before the first retry, doubled for each further one
+    on_failure: str = "error"  # "error" re-raises, "missing" yields None, "default" yields default
+    default: Any = None
+    memoize: bool = False  # reuse results for repeated arguments within a row
+
+
+class FunctionStats(BaseModel):
+    """Call counts for one registered function over a run."""
+
+    calls: int = 0
+    retries: int = 0
+    failures: int = 0  # calls that still raised after every retry
+    memo_hits: int = 0  # calls answered from earlier results
+
+
+class FailureSpan(BaseModel):
+    """Consecutive failed rows of one variable, for the warning message."""
+
+    function: str
+    variable: str
+    first_row: int | None
+    last_row: int | None
+    count: int
+    error: str
+
+
+class CallLog(BaseModel):
+    """Retries, failures and memoized results of registered functions in one run."""
+
+    stats: dict[str, FunctionStats] = {}
+    failures: list[FailureSpan] = []
+    memo: dict[tuple[str | None, int | None], dict[tuple, Any]] = {}  # by (entity, row)
+
+    def record_failure(self, function: str, variable: str, row: int | None, error: str) -> None:
+        last = self.failures[-1] if self.failures else None
+        if (
+            last is not None
+            and (last.function, last.variable) == (function, variable)
+            and last.last_row is not None
+            and row is not None
+            and row <= last.last_row + 1
+        ):
+            last.last_row = row
+            last.count += 1
+            return
+        self.failures.append(
+            FailureSpan(
+                function=function,
+                variable=variable,
+                first_row=row,
+                last_row=row,
+                count=1,
+                error=error,
+            )
+        )
+
+    def warnings(self) -> list[str]:
+        out = []
+        for span in self.failures:
+            rows = (
+                ""
+                if span.first_row is None
+                else f" row {span.first_row}"
+                if span.first_row == span.last_row
+                else f" rows {span.first_row}-{span.last_row}"
+            )
+            out.append(
+                f"{span.function}() failed for {span.variable}{rows}"
+                f" ({span.count} {'call' if span.count == 1 else 'calls'}): {span.error}"
+            )
+        for name, stats in self.stats.items():
+            if stats.retries:
+                out.append(f"{name}() retried {stats.retries} times over {stats.calls} calls")
+        return out
+
+
+# Per interpreter: each subinterpreter imports its own copy of this module
+_registry: dict[str, FunctionSpec] = {}
+
+
+def register_function(
+    name: str,
+    fn: Callable[..., Any],
+    retries: int = 0,
+    retry_backoff_ms: float = 0,
+    on_failure: str = "error",
+    default: Any = None,
+    memoize: bool = False,
+) -> None:
+    """Make ``fn`` callable as ``name(...)`` from expressions.
+
+    By default a call that raises fails the run. ``retries`` re-attempts a
+    failing call after ``retry_backoff_ms``, doubling the delay each time;
+    ``on_failure="missing"`` or ``"default"`` then substitutes None or
+    ``default`` for calls that still fail. ``memoize=True`` reuses a
+    result for repeated arguments within a row, for pure functions only.
+    """
+    from .executor import BUILTINS  # deferred: executor imports this module
+
+    if name in BUILTINS:
+        raise FunctionError(f"cannot register over built-in function: {name}")
+    if on_failure not in FAILURE_POLICIES:
+        raise FunctionError(f"on_failure must be one of {FAILURE_POLICIES}, got {on_failure!r}")
+    if retries < 0 or retry_backoff_ms < 0:
+        raise FunctionError("retries and retry_backoff_ms must be non-negative")
+    _registry[name] = FunctionSpec(
+        fn=fn,
+        retries=retries,
+        retry_backoff_ms=retry_backoff_ms,
+        on_failure=on_failure,
+        default=default,
+        memoize=memoize,
+    )
+
+
+def unregister_function(name: str) -> None:
+    _registry.pop(name, None)
+
+
+def get_function(name: str) -> FunctionSpec | None:
+    return _registry.get(name)
+
+
+def exact_key(value: Any) -> Any:
+    """Exact-bits key: 0.1 + 0.2 and 0.3 differ, as do 0.0 and -0.0, and 1 and 1.0."""
+    if isinstance(value, float):
+        return ("float", value.hex())
+    try:
+        hash(value)
+    except TypeError:
+        return (type(value).__name__, repr(value))
+    return (type(value).__name__, value)
+
+
+def call_function(
+    name: str,
+    spec: FunctionSpec,
+    args: list[Any],
+    log: CallLog,
+    variable: str,
+    row: int | None,
+    profiler: Any = None,
+    entity: str | None = None,
+) -> Any:
+    """Call a registered function under its retry, failure and memo policy;
+    ``entity`` and ``row`` scope the memo."""
+    stats = log.stats.setdefault(name, FunctionStats())
+    memo = log.memo.setdefault((entity, row), {}) if spec.memoize else None
+    key = (name, *map(exact_key, args))
+    if memo is not None:
+        hit = key in memo
+        if profiler is not None:
+            profiler.record_memo(name, hit)
+        if hit:
+            stats.memo_hits += 1
+            return memo[key]
+    stats.calls += 1
+    delay = spec.retry_backoff_ms / 1000
+    for attempt in range(spec.retries + 1):
+        try:
+            value = spec.fn(*args)
+        except Exception as e:
+            if attempt < spec.retries:
+                stats.retries += 1
+                if delay:
+                    time.sleep(delay)
+                    delay *= 2
+                continue
+            stats.failures += 1
+            if spec.on_failure == "error":
+                raise
+            log.record_failure(name, variable, row, f"{type(e).__name__}: {e}")
+        else:
+            if memo is not None and len(memo) < MEMO_ROW_SIZE:
+                memo[key] = value
+            return value
+    return None if spec.on_failure == "missing" else spec.default
@@ -0,0 +1,188 @@
+"""Input ingestion: CSV reading and locale-tolerant numeric coercion.
+
+Partner extracts arrive with different number conventions ("1,234.56" vs
+"1.234,56", currency symbols, accounting-style "(500)" negatives). A
+NumberFormat describes one convention; the CSV reader applies it per column
+and reports every cell that still failed to parse.
+"""
+
+import csv
+import math
+from collections.abc import Iterable, Mapping, Sequence
+from pathlib import Path
+from typing import Any
+
+from pydantic import BaseModel
+
+
+class NumberFormat(BaseModel):
+    """How numbers are written in string cells."""
+
+    decimal: str = "."
+    thousands: str | None = ","
+    currency_symbols: tuple[str, ...] = ("$", "£", "€")
+    parens_negative: bool = True
+
+
+US = NumberFormat()
+EU = NumberFormat(decimal=",", thousands=".")
+
+BOOLEANS = {"true": True, "false": False}  # cell text, matched case-insensitively
+
+
+def parse_number(text: str, fmt: NumberFormat = US) -> float:
+    """Parse a string as a number under a format. Raises ValueError."""
+    s = text.strip()
+    negative = False
+    if fmt.parens_negative and s.startswith("(") and s.endswith(")"):
+        negative = True
+        s = s[1:-1].strip()
+    for symbol in fmt.currency_symbols:
+        s = s.replace(symbol, "")
+    s = s.strip()
+    if s.startswith("-"):
+        negative = not negative
+        s = s[1:].strip()
+    int_part, _, frac = s.partition(fmt.decimal)
+    if fmt.thousands and fmt.thousands in int_part:
+        # Thousands separators must form proper groups, so "1.234,56" is
+        # rejected under US conventions rather than misread as 1.23456
+        groups = int_part.split(fmt.thousands)
+        if not (1 <= len(groups[0]) <= 3 and all(len(g) == 3 for g in groups[1:])):
+            raise ValueError(f"invalid number: {text!r}")
+        int_part = "".join(groups)
+    if (fmt.thousands and fmt.thousands in frac) or not s or s[0] in "+-":
+        raise ValueError(f"invalid number: {text!r}")
+    try:
+        value = float(f"{int_part}.{frac}" if frac else int_part)
+    except ValueError:
+        raise ValueError(f"invalid number: {text!r}") from None
+    return -value if negative else value
+
+
+def format_number(value: float, fmt: NumberFormat = US, decimals: int = 2) -> str:
+    """Format a number under a format (inverse of parse_number)."""
+    body = f"{abs(value):,.{decimals}f}"
+    body = body.replace(",", "\0").replace(".", fmt.decimal).replace("\0", fmt.thousands or "")
+    if value < 0:
+        return f"({body})" if fmt.parens_negative else f"-{body}"
+    return body
+
+
+def coerce_number(value: Any, fmt: NumberFormat | None = None) -> float:
+    """Coerce a cell to float, parsing strings with the given format."""
+    if isinstance(value, str):
+        if fmt is None:
+            return float(value)
+        return parse_number(value, fmt)
+    return float(value)
+
+
+class CellError(BaseModel):
+    """A cell that could not be parsed as a number."""
+
+    row: int  # 0-based data row (header excluded)
+    column: str
+    value: str
+
+
+class ParseReport(BaseModel):
+    """Cells that failed numeric parsing."""
+
+    errors: list[CellError] = []
+
+    @property
+    def ok(self) -> bool:
+        return not self.errors
+
+
+MissingSpec = Mapping[str, Sequence[Any]]  # column -> values meaning "missing"
+
+
+def is_sentinel(value: Any, sentinels: Sequence[Any]) -> bool:
+    """Whether value is one of the sentinels; a NaN sentinel matches any NaN."""
+    if isinstance(value, float) and math.isnan(value):
+        return any(isinstance(s, float) and math.isnan(s) for s in sentinels)
+    return any(value == s for s in sentinels if not (isinstance(s, float) and math.isnan(s)))
+
+
+def apply_missing(rows: list[dict[str, Any]], missing: MissingSpec) -> list[dict[str, Any]]:
+    """Copy of rows with declared sentinels, and absent cells of declared
+    columns, replaced by None."""
+    out = []
+    for row in rows:
+        row = dict(row)
+        for name, sentinels in missing.items():
+            if name not in row or is_sentinel(row[name], sentinels):
+                row[name] = None
+        out.append(row)
+    return out
+
+
+def read_csv(
+    path: str | Path,
+    numeric: Iterable[str] | None = None,
+    number_format: NumberFormat = US,
+    column_formats: dict[str, NumberFormat] | None = None,
+    delimiter: str = ",",
+    missing: MissingSpec | None = None,
+) -> tuple[list[dict[str, Any]], ParseReport]:
+    """Read a CSV file into rows, coercing numeric columns.
+
+    Numeric columns are those listed in ``numeric`` plus any with a
+    per-column format. When ``numeric`` is None, columns whose non-empty
+    cells all parse are also treated as numeric, and columns whose non-empty
+    cells are all "true" or "false" (any case) as boolean; every other
+    column is read as strings. Empty cells and a column's
+    ``missing`` sentinels (matched as text, e.g. "NA", or as parsed numbers,
+    e.g. -999) become None; cells in numeric columns that fail to parse are
+    kept as strings and recorded in the report.
+    """
+    column_formats = column_formats or {}
+    missing = missing or {}
+    with open(path, newline="") as f:
+        raw = list(csv.DictReader(f, delimiter=delimiter))
+
+    columns = list(raw[0].keys()) if raw else []
+    declared = set(numeric or []) | set(column_formats)
+    booleans: set[str] = set()
+    if numeric is None:
+        for name in columns:
+            fmt = column_formats.get(name, number_format)
+            sentinels = missing.get(name, ())
+            cells = [c for r in raw if (c := r[name].strip()) and not is_sentinel(c, sentinels)]
+            if all(_parses(c, fmt) for c in cells):
+                declared.add(name)
+            elif name not in declared and all(c.lower() in BOOLEANS for c in cells):
+                booleans.add(name)
+
+    report = ParseReport()
+    rows: list[dict[str, Any]] = []
+    for i, r in enumerate(raw):
+        row: dict[str, Any] = {}
+        for name, cell in r.items():
+            sentinels = missing.get(name, ())
+            if not cell.strip() or is_sentinel(cell.strip(), sentinels):
+                row[name] = None
+            elif name in declared:
+                try:
+                    value = parse_number(cell, column_formats.get(name, number_format))
+                except ValueError:
+                    row[name] = cell
+                    report.errors.append(CellError(row=i, column=name, value=cell))
+                else:
+                    row[name] = None if is_sentinel(value, sentinels) else value
+            elif name in booleans:
+                row[name] = BOOLEANS[cell.strip().lower()]
+            else:
+                row[name] = cell
+        rows.append(row)
+    return rows, report
+
+
+def _parses(text: str, fmt: NumberFormat) -> bool:
+    try:
+        parse_number(text, fmt)
+    except ValueError:
+        return False
+    return True
@@ -2,18 +2,24 @@
 
 from __future__ import annotations
 
+import math
 from concurrent.futures import ThreadPoolExecutor
-from dataclasses import dataclass
+from dataclasses import dataclass, field
 from datetime import date
 from pathlib import Path
 
 import numpy as np
 
 from .compiler import IR, Compiler
-from .executor import Context, evaluate
+from .config import EngineConfig, resolve_config
+from .executor import Executor, Result, monitored_paths, resolve_scalars
+from .ingest import NumberFormat, coerce_number
 from .native import CompiledBinary, compile_to_binary
 from .parser import parse
+from .rng import RandomManifest, replication_seeds
+from .scenarios import WinnersLosers, classify_changes
 from .schema import Data
+from .stats import ColumnStats
 
 
 @dataclass
@@ -22,16 +28,76 @@ class RunResult:
 
     arrays: dict[str, np.ndarray]
     output_names: dict[str, list[str]]
+    config: EngineConfig | None = None  # effective configuration of the run
+    column_stats: dict[str, ColumnStats] = field(default_factory=dict)  # from collect_stats
 
     def __getitem__(self, entity: str) -> np.ndarray:
         return self.arrays[entity]
 
+    def ranges(self) -> dict[str, tuple[float, float]]:
+        """Observed (min, max) of each output over all rows, ignoring NaN."""
+        out = {}
+        for entity, arr in self.arrays.items():
+            if not len(arr):
+                continue
+            lows, highs = np.nanmin(arr, axis=0), np.nanmax(arr, axis=0)
+            for j, name in enumerate(self.output_names[entity]):
+                out[name] = (float(lows[j]), float(highs[j]))
+        return out
+
     def to_dict(self, entity: str) -> list[dict[str, float]]:
         arr = self.arrays[entity]
         names = self.output_names[entity]
         return [{name: arr[i, j] for j, name in enumerate(names)} for i in range(len(arr))]
 
 
+@dataclass
+class MonteCarloResult:
+    """Per-row means and standard errors of each output over replications.
+
+    The standard error is the sample standard deviation over replications
+    divided by sqrt(n_replications); NaN with a single replication.
+    """
+
+    means: dict[str, np.ndarray]
+    standard_errors: dict[str, np.ndarray]
+    output_names: dict[str, list[str]]
+    n_replications: int
+    seed: int
+    scalar_means: dict[str, float] = field(default_factory=dict)
+    scalar_standard_errors: dict[str, float] = field(default_factory=dict)
+    draws: dict[str, np.ndarray] | None = None  # (replications, rows, outputs), if kept
+
+    def mean(self, entity: str, variable: str) -> np.ndarray:
+        return self.means[entity][:, self.output_names[entity].index(variable)]
+
+    def standard_error(self, entity: str, variable: str) -> np.ndarray:
+        return self.standard_errors[entity][:, self.output_names[entity].index(variable)]
+
+
+class _Moments:
+    """Running means and squared deviations (Welford), folded in replication
+    order with the same arithmetic as the native binary, so both agree."""
+
+    def __init__(self) -> None:
+        self.n = 0
+        self.mean: dict[str, np.ndarray] = {}
+        self.m2: dict[str, np.ndarray] = {}
+
+    def add(self, values: dict[str, np.ndarray | float]) -> None:
+        self.n += 1
+        for key, x in values.items():
+            mean = self.mean.get(key, np.zeros(np.shape(x)))
+            delta = x - mean
+            self.mean[key] = mean + delta / self.n
+            self.m2[key] = self.m2.get(key, np.zeros(np.shape(x))) + delta * (x - self.mean[key])
+
+    def standard_errors(self) -> dict[str, np.ndarray]:
+        if self.n < 2:
+            return {key: np.full(np.shape(m2), math.nan) for key, m2 in self.m2.items()}
+        return {key: np.sqrt(m2 / (self.n - 1)) / np.sqrt(self.n) for key, m2 in self.m2.items()}
+
+
 @dataclass
 class CompareResult:
     """Result of comparing baseline vs reform."""
@@ -47,6 +113,17 @@ class CompareResult:
         r_idx = r_names.index(variable)
         return self.reform.arrays[entity][:, r_idx] - self.baseline.arrays[entity][:, b_idx]
 
+    def winners_losers(
+        self,
+        entity: str,
+        variable: str,
+        weights: np.ndarray | None = None,
+        tolerance: float = 1.0,
+    ) -> WinnersLosers:
+        """Weighted counts and totals of rows gaining, losing and unchanged."""
+        gain = self.gain(entity, variable).tolist()
+        return classify_changes(gain, None if weights is None else weights.tolist(), tolerance)
+
     def summary(self, entity: str, variable: str, income_col: np.ndarray | None = None) -> dict:
         gain = self.gain(entity, variable)
         n = len(gain)
@@ -82,23 +159,44 @@ class CompareResult:
 
 
 class Model:
-    """A compiled RAC model ready for execution."""
+    """A compiled RAC model ready for execution.
 
-    def __init__(self, ir: IR, binary: CompiledBinary):
+    Below ``sequential_threshold`` rows x outputs, run() evaluates
+    in-process: spawning the native binary and round-tripping through temp
+    files costs far more than the arithmetic for a single household. The
+    in-process executor is kept between runs, so its IR is checked, its
+    formulas compiled to bytecode and its scalars evaluated once.
+    """
+
+    def __init__(self, ir: IR, binary: CompiledBinary, config: EngineConfig | None = None):
         self._ir = ir
         self._binary = binary
+        self.config = config
+        self._executor: tuple[tuple, Executor, Result] | None = None  # see _in_process_executor
 
     @classmethod
-    def from_source(cls, *sources: str, as_of: date) -> Model:
+    def from_source(
+        cls,
+        *sources: str,
+        as_of: date,
+        features: set[str] | None = None,
+        config: EngineConfig | None = None,
+    ) -> Model:
         modules = [parse(s) for s in sources]
-        ir = Compiler(modules).compile(as_of)
+        ir = Compiler(modules, features).compile(as_of)
         binary = compile_to_binary(ir)
-        return cls(ir, binary)
+        return cls(ir, binary, config)
 
     @classmethod
-    def from_file(cls, *paths: str | Path, as_of: date) -> Model:
+    def from_file(
+        cls,
+        *paths: str | Path,
+        as_of: date,
+        features: set[str] | None = None,
+        config: EngineConfig | None = None,
+    ) -> Model:
         sources = [Path(p).read_text() for p in paths]
-        return cls.from_source(*sources, as_of=as_of)
+        return cls.from_source(*sources, as_of=as_of, features=features, config=config)
 
     @property
     def entities(self) -> list[str]:
@@ -106,12 +204,7 @@ class Model:
 
     @property
     def scalars(self) -> dict[str, float]:
-        ctx = Context(data=Data(tables={}))
-        for path in self._ir.order:
-            var = self._ir.variables[path]
-            if var.entity is None:
-                ctx.computed[path] = evaluate(var.expr, ctx)
-        return dict(ctx.computed)
+        return resolve_scalars(self._ir, config=self.config)
 
     def outputs(self, entity: str) -> list[str]:
         return self._binary.entity_outputs.get(entity, [])
@@ -119,12 +212,228 @@ class Model:
     def inputs(self, entity: str) -> list[str]:
         return self._binary.entity_schemas.get(entity, [])
 
-    def run(self, data: dict[str, list[dict] | np.ndarray]) -> RunResult:
-        arrays = self._binary.run(data)
+    def run(
+        self,
+        data: dict[str, list[dict] | np.ndarray],
+        number_format: NumberFormat | None = None,
+        sequential_threshold: int | None = None,
+        config: EngineConfig | None = None,
+        manifest: RandomManifest | None = None,
+        collect_stats: list[str] | None = None,
+        parallel: bool | None = None,
+        num_threads: int | None = None,
+        chunk_size: int | None = None,
+        deterministic: bool | None = None,
+    ) -> RunResult:
+        """Run the model; the effective configuration is recorded on the
+        result and, if given, in the reproducibility ``manifest``.
+
+        ``collect_stats`` lists output paths or globs to summarise (see
+        executor.run); the statistics go on the result and in the manifest.
+        ``parallel``, ``num_threads``, ``chunk_size`` and ``deterministic``
+        set how the native binary spreads rows over its own thread pool (see
+        CompiledBinary.run); the outputs are the same for any thread count.
+        """
+        cfg = resolve_config(
+            self.config,
+            config,
+            number_format=number_format,
+            sequential_threshold=sequential_threshold,
+            parallel=parallel,
+            num_threads=num_threads,
+            chunk_size=chunk_size,
+            deterministic=deterministic,
+        )
+        monitored = monitored_paths(self._ir, collect_stats or [])
+        column_stats: dict[str, ColumnStats] = {}
+        if self._work(data) < cfg.sequential_threshold:
+            arrays = self._run_in_process(
+                data, cfg.number_format, monitored, column_stats, seed=cfg.seed
+            )
+        else:
+            arrays = self._binary.run(
+                data,
+                cfg.number_format,
+                num_threads=cfg.num_threads,
+                chunk_size=cfg.chunk_size,
+                parallel=cfg.parallel,
+                deterministic=cfg.deterministic,
+                seed=cfg.seed,
+            )
+            column_stats = self._column_stats(arrays, monitored)
+        if manifest is not None:
+            manifest.config = cfg
+            manifest.column_stats = column_stats
         return RunResult(
             arrays=arrays,
             output_names={e: self._binary.entity_outputs[e] for e in arrays},
+            config=cfg,
+            column_stats=column_stats,
+        )
+
+    def run_monte_carlo(
+        self,
+        data: dict[str, list[dict] | np.ndarray],
+        n_replications: int,
+        seed: int | None = None,
+        keep_draws: bool = False,
+        number_format: NumberFormat | None = None,
+        sequential_threshold: int | None = None,
+        config: EngineConfig | None = None,
+        parallel: bool | None = None,
+        num_threads: int | None = None,
+    ) -> MonteCarloResult:
+        """Run the model ``n_replications`` times with different random
+        streams, and summarise each output per row.
+
+        Replication r draws with master seed replication_seeds(seed, n)[r]
+        (``seed`` defaults to the configured one), so any replication can be
+        rerun alone with Model.run. The native binary runs replications in
+        parallel on its own threads; below ``sequential_threshold`` rows x
+        outputs x replications they run in-process, with the same results.
+        """
+        if n_replications < 1:
+            raise ValueError(f"n_replications must be at least 1, got {n_replications}")
+        cfg = resolve_config(
+            self.config,
+            config,
+            number_format=number_format,
+            sequential_threshold=sequential_threshold,
+            parallel=parallel,
+            num_threads=num_threads,
+            seed=seed,
         )
+        seeds = replication_seeds(cfg.seed, n_replications)
+        scalars = _Moments()
+        for s in seeds:
+            values = resolve_scalars(self._ir, config=EngineConfig(seed=s))
+            scalars.add({p: float(v) for p, v in values.items() if isinstance(v, int | float)})
+
+        outputs = self._binary.entity_outputs
+        if self._work(data) * n_replications < cfg.sequential_threshold:
+            rows = _Moments()
+            kept: dict[str, list[np.ndarray]] = {}
+            for s in seeds:
+                arrays = self._run_in_process(data, cfg.number_format, seed=s)
+                rows.add(arrays)
+                if keep_draws:
+                    for entity, arr in arrays.items():
+                        kept.setdefault(entity, []).append(arr)
+            means, standard_errors = rows.mean, rows.standard_errors()
+            draws = {e: np.stack(arrs) for e, arrs in kept.items()} if keep_draws else None
+        else:
+            summaries = self._binary.run_monte_carlo(
+                data,
+                seeds,
+                cfg.number_format,
+                num_threads=cfg.num_threads,
+                parallel=cfg.parallel,
+                keep_draws=keep_draws,
+            )
+            means = {e: summary[0] for e, summary in summaries.items()}
+            standard_errors = {e: summary[1] for e, summary in summaries.items()}
+            draws = {e: summary[2] for e, summary in summaries.items()} if keep_draws else None
+
+        return MonteCarloResult(
+            means=means,
+            standard_errors=standard_errors,
+            output_names={e: outputs[e] for e in means},
+            n_replications=n_replications,
+            seed=cfg.seed,
+            scalar_means={p: float(m) for p, m in scalars.mean.items()},
+            scalar_standard_errors={p: float(se) for p, se in scalars.standard_errors().items()},
+            draws=draws,
+        )
+
+    def _column_stats(
+        self, arrays: dict[str, np.ndarray], monitored: list[str]
+    ) -> dict[str, ColumnStats]:
+        """Statistics of monitored outputs, read back from the binary's arrays."""
+        out = {}
+        for path in monitored:
+            entity = self._ir.variables[path].entity
+            acc = ColumnStats()
+            if entity is None:
+                acc.add(self.scalars[path])
+            elif entity in arrays:
+                for value in arrays[entity][:, self._binary.entity_outputs[entity].index(path)]:
+                    acc.add(float(value))
+            else:
+                continue
+            out[path] = acc
+        return out
+
+    def _work(self, data: dict[str, list[dict] | np.ndarray]) -> int:
+        outputs = self._binary.entity_outputs
+        return sum(len(rows) * len(outputs[e]) for e, rows in data.items() if e in outputs)
+
+    def _in_process_executor(
+        self, seed: int | None, monitored: list[str] | None
+    ) -> tuple[Executor, Result]:
+        """The executor for ``seed`` and ``monitored``, with its scalars
+        already evaluated; the last one is kept for the next run."""
+        key = (seed, tuple(monitored or ()))
+        if self._executor is None or self._executor[0] != key:
+            config = EngineConfig(seed=seed, bytecode=True)
+            executor = Executor(self._ir, config=config, collect_stats=monitored)
+            # Fixed-point groups are solved afresh on every run, as in CompiledModel
+            scalars = executor.execute(Data(tables={}, schema_=self._ir.schema_)).scalars
+            grouped = {path for group in self._ir.fixed_point for path in group}
+            reuse = Result(
+                scalars={path: v for path, v in scalars.items() if path not in grouped},
+                entities={},
+            )
+            self._executor = (key, executor, reuse)
+        return self._executor[1], self._executor[2]
+
+    def _run_in_process(
+        self,
+        data: dict[str, list[dict] | np.ndarray],
+        number_format: NumberFormat | None,
+        monitored: list[str] | None = None,
+        column_stats: dict[str, ColumnStats] | None = None,
+        seed: int | None = None,
+    ) -> dict[str, np.ndarray]:
+        """Evaluate with the Python executor, reading inputs as the binary does.
+
+        Inputs are restricted to declared fields, missing fields read as 0.0
+        and int fields are truncated, so both paths see identical values.
+        A row's id is kept even where it isn't declared, so random draws are
+        keyed as ``run`` keys them: by the id, or else the row index.
+        """
+        tables: dict[str, list[dict]] = {}
+        for entity_name, rows in data.items():
+            if entity_name not in self._binary.entity_outputs:
+                continue
+            fields = self._binary.entity_schemas.get(entity_name, [])
+            schema = self._ir.schema_.entities.get(entity_name)
+            declared = schema.fields if schema else {}
+            ints = {f for f in fields if f in declared and declared[f].dtype == "int"}
+            if isinstance(rows, np.ndarray):
+                rows = [dict(zip(fields, r)) for r in rows.astype(np.float64, copy=False).tolist()]
+            table = []
+            for row in rows:
+                values = {f: coerce_number(row.get(f, 0.0), number_format) for f in fields}
+                for f in ints:
+                    values[f] = int(values[f])
+                if "id" in row and "id" not in values:
+                    values["id"] = row["id"]
+                table.append(values)
+            tables[entity_name] = table
+
+        executor, scalars = self._in_process_executor(seed, monitored)
+        result = executor.execute(Data(tables=tables, schema_=self._ir.schema_), reuse=scalars)
+        if column_stats is not None:
+            column_stats.update(result.column_stats)
+        arrays = {}
+        for entity_name, table in tables.items():
+            names = self._binary.entity_outputs[entity_name]
+            out = np.empty((len(table), len(names)), dtype=np.float64)
+            columns = result.entities.get(entity_name, {})
+            for j, name in enumerate(names):
+                out[:, j] = columns[name]
+            arrays[entity_name] = out
+        return arrays
 
     def compare(self, reform: Model, data: dict[str, list[dict] | np.ndarray]) -> CompareResult:
         with ThreadPoolExecutor(max_workers=2) as executor:
@@ -3,10 +3,24 @@
 Compiles IR to native Rust binary. Auto-installs Rust toolchain if needed.
 
 Performance: ~40M rows/sec with numpy arrays.
+
+A run converts the inputs to an array under the GIL, computes in the
+binary's own process, and reads the outputs back. While the binary
+computes, this process only waits on it, which releases the GIL, so
+other Python threads keep running through a large run.
+
+Each row is computed on its own and written to its own output slot, and
+reductions over the outputs (column statistics, totals) run afterwards in
+row order on one thread, so the outputs are the same for any thread count
+or split. ``deterministic=True`` changes only the scheduling: rows go out
+in fixed blocks of ``chunk_size``, each computed in row order by one
+thread, rather than however the pool's work stealing divides them, so
+which thread computed which row is reproducible too (for profiling).
 """
 
 import hashlib
 import json
+import math
 import os
 import platform
 import shutil
@@ -14,13 +28,17 @@ import struct
 import subprocess
 import tempfile
 from pathlib import Path
+from typing import Any
 
 import numpy as np
 
-from .codegen.rust import generate_rust
-from .compiler import IR
+from .codegen.rust import EPOCH, generate_rust
+from .compiler import IR, random_variables
+from .executor import _as_date
+from .ingest import NumberFormat, coerce_number
 
 CACHE_DIR = Path.home() / ".cache" / "rac"
+BINARY_FORMAT = 4  # bumped when the binary's arguments or output change, to rebuild
 RUSTUP_URL = "https://sh.rustup.rs"
 
 
@@ -69,7 +87,11 @@ def ensure_cargo() -> Path:
 
 def _ir_hash(ir: IR) -> str:
     data = json.dumps(
-        {"order": ir.order, "vars": {k: str(v.expr) for k, v in ir.variables.items()}},
+        {
+            "format": BINARY_FORMAT,
+            "order": ir.order,
+            "vars": {k: str(v.expr) for k, v in ir.variables.items()},
+        },
         sort_keys=True,
     )
     return hashlib.sha256(data.encode()).hexdigest()[:16]
@@ -90,57 +112,198 @@ class CompiledBinary:
         self.entity_schemas = entity_schemas
         self.entity_outputs = entity_outputs
 
-    def run(self, data: dict[str, list[dict]] | dict[str, np.ndarray]) -> dict[str, np.ndarray]:
+    def run(
+        self,
+        data: dict[str, list[dict]] | dict[str, np.ndarray],
+        number_format: NumberFormat | None = None,
+        num_threads: int = 0,
+        chunk_size: int = 1,
+        parallel: bool = True,
+        deterministic: bool = False,
+        seed: int = 0,
+    ) -> dict[str, np.ndarray]:
+        """Outputs per entity. Rows are spread over a thread pool of
+        ``num_threads`` (0 for one per core), private to this run, in
+        chunks of at least ``chunk_size`` rows; ``parallel=False`` runs
+        them on one thread, and ``deterministic=True`` in blocks of exactly
+        ``chunk_size`` rows whatever the thread count. Rows are independent,
+        so the outputs are the same either way; only the split is pinned.
+        ``seed`` is the master seed of random draws. The GIL is free while
+        the binary computes."""
         results = {}
+        threads = str(num_threads if parallel else 1)
 
         for entity_name, rows in data.items():
             if entity_name not in self.entity_outputs:
                 continue
-
-            input_fields = self.entity_schemas.get(entity_name, [])
             output_fields = self.entity_outputs[entity_name]
-
-            if isinstance(rows, np.ndarray):
-                input_arr = rows.astype(np.float64, copy=False)
-                n_rows = len(input_arr)
-            else:
-                n_rows = len(rows)
-                if n_rows == 0:
-                    results[entity_name] = np.zeros((0, len(output_fields)), dtype=np.float64)
-                    continue
-                input_arr = np.array(
-                    [[float(row.get(field, 0.0)) for field in input_fields] for row in rows],
-                    dtype=np.float64,
-                )
-
-            input_path = tempfile.mktemp(suffix=".bin")
-            with open(input_path, "wb") as f:
-                f.write(struct.pack("<Q", n_rows))
-                input_arr.tofile(f)
-
+            input_path = self._write_input(entity_name, rows, number_format)
+            if input_path is None:
+                results[entity_name] = np.zeros((0, len(output_fields)), dtype=np.float64)
+                continue
             output_path = tempfile.mktemp(suffix=".bin")
-
             try:
-                result = subprocess.run(
-                    [str(self.binary_path), entity_name, input_path, output_path],
-                    capture_output=True,
-                    text=True,
+                self._call(
+                    entity_name,
+                    input_path,
+                    output_path,
+                    threads,
+                    str(chunk_size),
+                    str(int(deterministic)),
+                    str(seed),
                 )
-                if result.returncode != 0:
-                    raise RuntimeError(f"Binary failed for {entity_name}: {result.stderr}")
-
                 with open(output_path, "rb") as f:
                     out_n = struct.unpack("<Q", f.read(8))[0]
                     output_arr = np.fromfile(f, dtype=np.float64).reshape(out_n, len(output_fields))
 
                 results[entity_name] = output_arr
             finally:
-                os.unlink(input_path)
+                self._remove_input(input_path)
                 if os.path.exists(output_path):
                     os.unlink(output_path)
 
         return results
 
+    def run_monte_carlo(
+        self,
+        data: dict[str, list[dict]] | dict[str, np.ndarray],
+        seeds: list[int],
+        number_format: NumberFormat | None = None,
+        num_threads: int = 0,
+        parallel: bool = True,
+        keep_draws: bool = False,
+    ) -> dict[str, tuple[np.ndarray, np.ndarray, np.ndarray | None]]:
+        """Per entity, the mean and standard error of each output cell over
+        one replication per seed in ``seeds``, and with ``keep_draws`` every
+        replication's outputs, shaped (replications, rows, outputs).
+
+        The binary runs whole replications on its threads, in batches of a
+        fixed size, and folds them into the moments in ``seeds`` order, so
+        the results are the same for any thread count."""
+        results = {}
+        threads = str(num_threads if parallel else 1)
+        seeds_path = tempfile.mktemp(suffix=".txt")
+        Path(seeds_path).write_text("".join(f"{seed}\n" for seed in seeds))
+
+        try:
+            for entity_name, rows in data.items():
+                if entity_name not in self.entity_outputs:
+                    continue
+                n_outputs = len(self.entity_outputs[entity_name])
+                input_path = self._write_input(entity_name, rows, number_format)
+                if input_path is None:
+                    empty = np.zeros((0, n_outputs), dtype=np.float64)
+                    draws = np.zeros((len(seeds), 0, n_outputs)) if keep_draws else None
+                    results[entity_name] = (empty, empty.copy(), draws)
+                    continue
+                output_path = tempfile.mktemp(suffix=".bin")
+                draws_path = tempfile.mktemp(suffix=".bin")
+                try:
+                    self._call(
+                        entity_name,
+                        input_path,
+                        output_path,
+                        threads,
+                        "1",
+                        "0",
+                        "0",
+                        seeds_path,
+                        *([draws_path] if keep_draws else []),
+                    )
+                    with open(output_path, "rb") as f:
+                        out_n = struct.unpack("<Q", f.read(8))[0]
+                        moments = np.fromfile(f, dtype=np.float64).reshape(2, out_n, n_outputs)
+                    draws = None
+                    if keep_draws:
+                        draws = np.fromfile(draws_path, dtype=np.float64)
+                        draws = draws.reshape(len(seeds), out_n, n_outputs)
+                    results[entity_name] = (moments[0], moments[1], draws)
+                finally:
+                    self._remove_input(input_path)
+                    for path in (output_path, draws_path):
+                        if os.path.exists(path):
+                            os.unlink(path)
+        finally:
+            os.unlink(seeds_path)
+
+        return results
+
+    def _write_input(
+        self,
+        entity_name: str,
+        rows: list[dict] | np.ndarray,
+        number_format: NumberFormat | None,
+    ) -> str | None:
+        """A temp file of the entity's input rows as the binary reads them;
+        None when there are no rows to write."""
+        input_fields = self.entity_schemas.get(entity_name, [])
+        if isinstance(rows, np.ndarray):
+            input_arr = rows.astype(np.float64, copy=False)
+            n_rows = len(input_arr)
+        else:
+            n_rows = len(rows)
+            if n_rows == 0:
+                return None
+            schema = self.ir.schema_.entities.get(entity_name)
+            fields = schema.fields if schema else {}
+            dates = {name for name, f in fields.items() if f.dtype == "date"}
+            input_arr = np.array(
+                [
+                    [
+                        _day_number(row.get(field))
+                        if field in dates
+                        else coerce_number(row.get(field, 0.0), number_format)
+                        for field in input_fields
+                    ]
+                    for row in rows
+                ],
+                dtype=np.float64,
+            )
+
+        input_path = tempfile.mktemp(suffix=".bin")
+        with open(input_path, "wb") as f:
+            f.write(struct.pack("<Q", n_rows))
+            input_arr.tofile(f)
+        if random_variables(self.ir):
+            keys = self._stream_keys(entity_name, rows, input_arr)
+            Path(f"{input_path}.keys").write_text("".join(f"{key!r}\n" for key in keys))
+        return input_path
+
+    def _stream_keys(
+        self, entity_name: str, rows: list[dict] | np.ndarray, input_arr: np.ndarray
+    ) -> list:
+        """Each row's random stream key, as the in-process run keys it: a
+        declared id as the binary reads it, else the row's own id, else its
+        index."""
+        input_fields = self.entity_schemas.get(entity_name, [])
+        if "id" in input_fields:
+            ids = input_arr[:, input_fields.index("id")].tolist()
+            schema = self.ir.schema_.entities[entity_name]
+            return [int(v) for v in ids] if schema.fields["id"].dtype == "int" else ids
+        if isinstance(rows, np.ndarray):
+            return list(range(len(rows)))
+        return [row.get("id", i) for i, row in enumerate(rows)]
+
+    @staticmethod
+    def _remove_input(input_path: str) -> None:
+        for path in (input_path, f"{input_path}.keys"):
+            if os.path.exists(path):
+                os.unlink(path)
+
+    def _call(self, entity_name: str, *args: str) -> None:
+        result = subprocess.run(
+            [str(self.binary_path), entity_name, *args],
+            capture_output=True,
+            text=True,
+        )
+        if result.returncode != 0:
+            raise RuntimeError(f"Binary failed for {entity_name}: {result.stderr}")
+
+
+def _day_number(value: Any) -> float:
+    """A date as the binary reads it: days since 1970-01-01, NaN when missing."""
+    return math.nan if value is None else float((_as_date(value) - EPOCH).days)
+
 
 def compile_to_binary(ir: IR, cache: bool = True) -> CompiledBinary:
     cargo = ensure_cargo()
@@ -223,10 +386,22 @@ def _generate_main(
         entity_schema = ir.schema_.entities.get(entity_name)
         field_reads = []
         for i, f in enumerate(input_fields):
-            is_int = entity_schema and f in entity_schema.fields and entity_schema.fields[f].dtype == "int"
-            cast = " as i64" if is_int else ""
+            dtype = entity_schema.fields[f].dtype if entity_schema and f in entity_schema.fields else None
+            cast = {"int": " as i64", "bool": " != 0.0"}.get(dtype, "")
             field_reads.append(f"                    {f}: row[{i}]{cast},")
 
+        random = bool(random_variables(ir))
+        seeded = ", seed, &keys[i]" if random else ""
+        # Each row's stream key, as repr() gives it, one per line beside the input
+        keys = ""
+        if random:
+            keys = """
+            let keys: Vec<String> = std::fs::read_to_string(format!("{}.keys", args[2]))
+                .expect("Failed to read keys")
+                .lines()
+                .map(str::to_string)
+                .collect();"""
+
         output_writes = [
             f"            out[{i}] = o.{path.replace('/', '_')};"
             for i, path in enumerate(output_fields)
@@ -241,20 +416,96 @@ def _generate_main(
             for i in 0..n_rows * n_input_fields {{
                 file.read_exact(&mut buf8).expect("Failed to read");
                 input_data[i] = f64::from_le_bytes(buf8);
-            }}
-
-            let mut output_data = vec![0.0f64; n_rows * n_output_fields];
+            }}{keys}
 
-            input_data
-                .par_chunks(n_input_fields)
-                .zip(output_data.par_chunks_mut(n_output_fields))
-                .for_each(|(row, out)| {{
-                    let input = {type_name}Input {{
+            let compute_row = |scalars: &Scalars, seed: &str, i: usize, row: &[f64], out: &mut [f64]| {{
+                let input = {type_name}Input {{
 {chr(10).join(field_reads)}
-                    }};
-                    let o = {type_name}Output::compute(&input, &scalars);
+                }};
+                let o = {type_name}Output::compute(&input, scalars{seeded});
 {chr(10).join(output_writes)}
+            }};
+
+            if let Some(replications) = &replications {{
+                // Each replication runs whole on one thread, in fixed batches; results
+                // fold into the running moments in replication order
+                let cells = n_rows * n_output_fields;
+                let replicate = |seed: &String| {{
+                    let scalars = Scalars::compute({"seed" if random else ""});
+                    let mut outs = vec![0.0f64; cells];
+                    input_data
+                        .chunks(n_input_fields)
+                        .zip(outs.chunks_mut(n_output_fields))
+                        .enumerate()
+                        .for_each(|(i, (row, out))| compute_row(&scalars, seed, i, row, out));
+                    outs
+                }};
+                let (mut mean, mut m2, mut n) = (vec![0.0f64; cells], vec![0.0f64; cells], 0.0f64);
+                for batch in replications.chunks(REPLICATION_BATCH) {{
+                    let results: Vec<Vec<f64>> = if threads == 1 {{
+                        batch.iter().map(&replicate).collect()
+                    }} else {{
+                        pool.install(|| batch.par_iter().map(&replicate).collect())
+                    }};
+                    for outs in results {{
+                        n += 1.0;
+                        for k in 0..cells {{
+                            let delta = outs[k] - mean[k];
+                            mean[k] += delta / n;
+                            m2[k] += delta * (outs[k] - mean[k]);
+                        }}
+                        if let Some(draws_file) = draws_file.as_mut() {{
+                            for v in outs {{
+                                draws_file.write_all(&v.to_le_bytes()).unwrap();
+                            }}
+                        }}
+                    }}
+                }}
+                out_file.write_all(&(n_rows as u64).to_le_bytes()).unwrap();
+                for v in &mean {{
+                    out_file.write_all(&v.to_le_bytes()).unwrap();
+                }}
+                for v in m2 {{
+                    let se = if n > 1.0 {{ (v / (n - 1.0)).sqrt() / n.sqrt() }} else {{ f64::NAN }};
+                    out_file.write_all(&se.to_le_bytes()).unwrap();
+                }}
+                return;
+            }}
+
+            let scalars = Scalars::compute({"seed" if random else ""});
+            let mut output_data = vec![0.0f64; n_rows * n_output_fields];
+            if threads == 1 {{
+                input_data
+                    .chunks(n_input_fields)
+                    .zip(output_data.chunks_mut(n_output_fields))
+                    .enumerate()
+                    .for_each(|(i, (row, out))| compute_row(&scalars, seed, i, row, out));
+            }} else if deterministic {{
+                // Fixed blocks, each in row order: the split ignores the pool's size
+                pool.install(|| {{
+                    input_data
+                        .par_chunks(n_input_fields * chunk_size)
+                        .zip(output_data.par_chunks_mut(n_output_fields * chunk_size))
+                        .enumerate()
+                        .for_each(|(b, (rows, outs))| {{
+                            rows.chunks(n_input_fields)
+                                .zip(outs.chunks_mut(n_output_fields))
+                                .enumerate()
+                                .for_each(|(k, (row, out))| {{
+                                    compute_row(&scalars, seed, b * chunk_size + k, row, out)
+                                }});
+                        }});
                 }});
+            }} else {{
+                pool.install(|| {{
+                    input_data
+                        .par_chunks(n_input_fields)
+                        .zip(output_data.par_chunks_mut(n_output_fields))
+                        .with_min_len(chunk_size)
+                        .enumerate()
+                        .for_each(|(i, (row, out))| compute_row(&scalars, seed, i, row, out));
+                }});
+            }}
 
             // Write output
             out_file.write_all(&(n_rows as u64).to_le_bytes()).unwrap();
@@ -269,12 +520,40 @@ use std::env;
 use std::fs::File;
 use std::io::{{Read, Write, BufReader, BufWriter}};
 
+// Monte Carlo replications in flight at once; fixed, so the thread count
+// never changes which replications are folded together
+const REPLICATION_BATCH: usize = 64;
+
 fn main() {{
     let args: Vec<String> = env::args().collect();
-    if args.len() != 4 {{
-        eprintln!("Usage: {{}} <entity> <input.bin> <output.bin>", args[0]);
+    if args.len() < 4 || args.len() > 10 {{
+        eprintln!(
+            "Usage: {{}} <entity> <input.bin> <output.bin> [threads] [chunk] [deterministic] \\
+             [seed] [replications.txt] [draws.bin]",
+            args[0]
+        );
         std::process::exit(1);
     }}
+    // 0 threads lets rayon match the cores; 1 runs without a pool
+    let threads: usize = args.get(4).map_or(0, |a| a.parse().expect("Invalid thread count"));
+    let chunk_size: usize = args.get(5).map_or(1, |a| a.parse().expect("Invalid chunk size"));
+    let deterministic = args.get(6).is_some_and(|a| a == "1");
+    // The master seed of random draws, as the decimal Python formats it
+    let seed: &str = args.get(7).map_or("0", |a| a.as_str());
+    // With a file of replication seeds, one per line, the output is each
+    // cell's mean then its standard error, and draws.bin gets every result
+    let replications: Option<Vec<String>> = args.get(8).map(|path| {{
+        let text = std::fs::read_to_string(path).expect("Failed to read replications");
+        text.lines().map(str::to_string).collect()
+    }});
+    let mut draws_file = args
+        .get(9)
+        .map(|path| BufWriter::new(File::create(path).expect("Failed to create draws")));
+    // A pool private to this run, so the global rayon pool is never touched
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .expect("Failed to build thread pool");
 
     let entity = &args[1];
     let mut file = BufReader::new(File::open(&args[2]).expect("Failed to open input"));
@@ -284,8 +563,6 @@ fn main() {{
     file.read_exact(&mut buf8).expect("Failed to read count");
     let n_rows = u64::from_le_bytes(buf8) as usize;
 
-    let scalars = Scalars::compute();
-
     match entity.as_str() {{
 {chr(10).join(entity_handlers)}
         _ => {{
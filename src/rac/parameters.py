@@ -0,0 +1,348 @@
+"""Parameter trees, and export of resolved parameter values.
+
+A parameter is a scalar (non-entity) variable. A parameter tree holds the
+dated values of many parameters in one nested JSON or YAML document; its
+module() declares each leaf as a variable with one temporal value per date,
+so compiling the rules with it resolves every parameter as of the period.
+
+A parameter may be uprated: given an index series (CPI, average earnings)
+held in the same tree, its values are projected forward past the last
+legislated one as the index grows, rounded as its UpratingRule says.
+
+The exported table lists each one's path, resolved value, where the value
+came from and the date the value took effect, so a partner can audit a run
+without reading IR, and feed the file back as overrides to reproduce it.
+"""
+
+import csv
+import json
+import re
+from collections.abc import Mapping
+from datetime import date, timedelta
+from pathlib import Path
+from typing import Any
+
+import yaml
+from pydantic import BaseModel, PrivateAttr, ValidationError
+
+from . import ast
+from .compiler import IR, Compiler
+from .executor import resolve_scalars, round_to
+
+PARAMETER_FORMATS = ("csv", "json")
+COLUMNS = ("path", "value", "source", "effective")
+ROUNDINGS = ("none", *ast.ROUND_DIRECTIONS)
+
+
+class ParameterError(Exception):
+    pass
+
+
+SEGMENT = re.compile(r"[a-zA-Z_][a-zA-Z0-9_]*")
+
+
+class UpratingRule(BaseModel):
+    """How a parameter follows an index past its last legislated value.
+
+    On each date the index changes after that value, the parameter becomes
+    the value times the index's growth since it took effect, rounded to
+    the nearest ``multiple``, or down or up to one (``none`` leaves it
+    unrounded). Each projection starts from the legislated value, so
+    rounding never compounds.
+    """
+
+    index: str  # path of the index series, a parameter in the same tree
+    rounding: str = "none"  # none, nearest, down, up
+    multiple: float = 1
+
+
+class ParameterTree(BaseModel):
+    """Dated values of each parameter, keyed by path."""
+
+    leaves: dict[str, list[tuple[date, Any]]]  # start dates ascending
+    uprating: dict[str, UpratingRule] = {}  # parameter path -> rule
+    _projected: dict[str, list[tuple[date, Any]]] | None = PrivateAttr(default=None)
+
+    def value(self, path: str, as_of: date) -> Any:
+        """The value of ``path`` in force on ``as_of``, uprated if it has a rule."""
+        leaves = self._dated()
+        if path not in leaves:
+            raise ParameterError(f"unknown parameter: {path}")
+        in_force = [value for start, value in leaves[path] if start <= as_of]
+        if not in_force:
+            raise ParameterError(f"{path} has no value in force on {as_of}")
+        return in_force[-1]
+
+    def at(self, as_of: date) -> dict[str, Any]:
+        """Every parameter with a value in force on ``as_of``, by path."""
+        return {
+            path: self.value(path, as_of)
+            for path, dated in self.leaves.items()
+            if dated[0][0] <= as_of
+        }
+
+    def uprated(self) -> "ParameterTree":
+        """A tree without uprating rules, holding each uprated parameter's
+        projected values as dated values."""
+        return ParameterTree(leaves=self._dated())
+
+    def _dated(self) -> dict[str, list[tuple[date, Any]]]:
+        """The leaves with projected values added, worked out on first use."""
+        if self._projected is None:
+            self._projected = _project(self.leaves, self.uprating)
+        return self._projected
+
+    def module(self) -> ast.Module:
+        """A module declaring each parameter, uprated values included, to
+        compile alongside the rules."""
+        variables = []
+        for path, dated in self._dated().items():
+            ends = [start - timedelta(days=1) for start, _ in dated[1:]] + [None]
+            values = [
+                ast.TemporalValue(start=start, end=end, expr=ast.Literal(value=value))
+                for (start, value), end in zip(dated, ends)
+            ]
+            variables.append(ast.VariableDecl(path=path, values=values))
+        return ast.Module(path="<parameters>", variables=variables)
+
+
+def _project(
+    leaves: dict[str, list[tuple[date, Any]]], rules: Mapping[str, UpratingRule]
+) -> dict[str, list[tuple[date, Any]]]:
+    """``leaves`` with each uprated parameter's values projected by its index."""
+    _check_rules(leaves, rules)
+    projected = dict(leaves)
+    for path, rule in rules.items():
+        index = leaves[rule.index]
+        last_start, last_value = leaves[path][-1]
+        base = [level for start, level in index if start <= last_start]
+        projected[path] = leaves[path] + [
+            (start, _rounded(last_value * level / base[-1], rule))
+            for start, level in index
+            if start > last_start
+        ]
+    return projected
+
+
+def _check_rules(
+    leaves: Mapping[str, list[tuple[date, Any]]], rules: Mapping[str, UpratingRule]
+) -> None:
+    problems = [
+        problem for path, rule in rules.items() for problem in _problems(leaves, rules, path, rule)
+    ]
+    if problems:
+        raise ParameterError("; ".join(problems))
+
+
+def _problems(
+    leaves: Mapping[str, list[tuple[date, Any]]],
+    rules: Mapping[str, UpratingRule],
+    path: str,
+    rule: UpratingRule,
+) -> list[str]:
+    problems = []
+    if path not in leaves:
+        problems.append(f"uprating rule for unknown parameter: {path}")
+    elif not all(_number(value) for _, value in leaves[path]):
+        problems.append(f"{path}: only numeric parameters can be uprated")
+    if rule.index not in leaves:
+        problems.append(f"{path}: unknown index {rule.index}")
+    elif rule.index in rules:
+        problems.append(f"{path}: index {rule.index} is itself uprated")
+    elif not all(_number(level) and level != 0 for _, level in leaves[rule.index]):
+        problems.append(f"{path}: index {rule.index} must hold non-zero numbers")
+    elif path in leaves and leaves[rule.index][0][0] > leaves[path][-1][0]:
+        last_start = leaves[path][-1][0]
+        problems.append(f"{path}: index {rule.index} has no value in force on {last_start}")
+    if rule.rounding not in ROUNDINGS:
+        problems.append(f"{path}: rounding must be one of {', '.join(ROUNDINGS)}")
+    if not rule.multiple > 0:
+        problems.append(f"{path}: rounding multiple must be positive")
+    return problems
+
+
+def _number(value: Any) -> bool:
+    return isinstance(value, int | float) and not isinstance(value, bool)
+
+
+def _rounded(value: float, rule: UpratingRule) -> Any:
+    """``value`` rounded per ``rule``; whole when rounded to a whole multiple."""
+    if rule.rounding == "none":
+        return value
+    rounded = round_to(value, rule.multiple, rule.rounding)
+    return int(rounded) if float(rule.multiple).is_integer() else rounded
+
+
+def _start(key: Any) -> date | None:
+    if isinstance(key, date):
+        return key
+    try:
+        return date.fromisoformat(key) if isinstance(key, str) else None
+    except ValueError:
+        return None
+
+
+def _leaves(
+    node: Mapping,
+    prefix: str,
+    out: dict[str, list[tuple[date, Any]]],
+    rules: dict[str, UpratingRule],
+) -> None:
+    dates = {key: value for key, value in node.items() if key != "uprating"}
+    starts = [_start(key) for key in dates]
+    if dates and all(starts):
+        dated = sorted(zip(starts, dates.values()), key=lambda pair: pair[0])
+        for start, value in dated:
+            if not isinstance(value, int | float | str):  # bools are ints
+                raise ParameterError(
+                    f"{prefix} on {start}: value must be a number, string or boolean"
+                )
+        out[prefix] = dated
+        if "uprating" in node:
+            rule = node["uprating"]
+            if not isinstance(rule, Mapping):
+                raise ParameterError(f"{prefix}: uprating must be a mapping")
+            try:
+                rules[prefix] = UpratingRule(**rule)
+            except (TypeError, ValidationError) as e:
+                raise ParameterError(f"{prefix}: bad uprating rule: {e}") from None
+        return
+    for key, child in node.items():
+        if not isinstance(key, str) or not SEGMENT.fullmatch(key):
+            where = f" under {prefix}" if prefix else ""
+            raise ParameterError(f"invalid parameter name {key!r}{where}")
+        path = f"{prefix}/{key}" if prefix else key
+        if not isinstance(child, Mapping) or not child:
+            raise ParameterError(f"{path}: expected dated values or nested parameters")
+        _leaves(child, path, out, rules)
+
+
+def load_parameter_tree(source: Mapping[str, Any] | str | Path) -> ParameterTree:
+    """Read a parameter tree from a nested mapping or a JSON or YAML file.
+
+    Nested keys join into the parameter path, so ``{"gov": {"allowance":
+    {"2023-01-01": 12570}}}`` defines gov/allowance from 2023-01-01. A
+    leaf is a mapping whose keys are all ISO dates; each value stays in
+    force until the next date. A leaf may also hold an ``uprating`` key,
+    ``{"index": "idx/cpi", "rounding": "nearest", "multiple": 10}``, to
+    project it forward by that index (see UpratingRule).
+    """
+    if not isinstance(source, Mapping):
+        path = Path(source)
+        text = path.read_text()
+        try:
+            if path.suffix.lower() in (".yaml", ".yml"):
+                source = yaml.safe_load(text)
+            else:
+                source = json.loads(text)
+        except (json.JSONDecodeError, yaml.YAMLError) as e:
+            raise ParameterError(f"{path}: unreadable parameter tree: {e}") from None
+        if not isinstance(source, Mapping):
+            raise ParameterError(f"{path}: expected a mapping at the top level")
+    leaves: dict[str, list[tuple[date, Any]]] = {}
+    rules: dict[str, UpratingRule] = {}
+    _leaves(source, "", leaves, rules)
+    if "" in leaves:
+        raise ParameterError("the top level must name parameters, not dates")
+    _check_rules(leaves, rules)
+    return ParameterTree(leaves=leaves, uprating=rules)
+
+
+class ParameterRow(BaseModel):
+    """One resolved parameter."""
+
+    path: str
+    value: Any
+    source: str  # "literal", "formula" (derived from other parameters) or "override"
+    effective: date | None = None  # start of the value in force; None for overrides
+
+
+def parameter_table(ir: IR, overrides: dict[str, Any] | None = None) -> list[ParameterRow]:
+    """Resolved scalar parameters of ``ir``, sorted by path."""
+    overrides = dict(overrides or {})
+    values = resolve_scalars(ir, overrides)
+    rows = []
+    for path in sorted(values):
+        var = ir.variables[path]
+        if path in overrides:
+            source, effective = "override", None
+        else:
+            source = "literal" if isinstance(var.expr, ast.Literal) else "formula"
+            effective = var.effective
+        rows.append(ParameterRow(path=path, value=values[path], source=source, effective=effective))
+    return rows
+
+
+def _format(path: Path, fmt: str | None) -> str:
+    fmt = fmt or path.suffix.lstrip(".").lower()
+    if fmt not in PARAMETER_FORMATS:
+        raise ParameterError(f"format must be one of {PARAMETER_FORMATS}, got {fmt!r}")
+    return fmt
+
+
+def export_parameters(
+    model: IR | list[ast.Module],
+    period: date | None,
+    overrides: dict[str, Any] | None,
+    path: str | Path,
+    format: str | None = None,
+) -> list[ParameterRow]:
+    """Write the parameter table to ``path`` and return its rows.
+
+    ``model`` is either compiled IR (``period`` must then be None) or the
+    rule modules, compiled as of ``period``. The format defaults to the
+    file extension. Values are JSON-encoded in CSV cells so numbers,
+    booleans and strings read back with their types.
+    """
+    if isinstance(model, IR):
+        if period is not None:
+            raise ParameterError("period applies to modules; the IR is already compiled")
+        ir = model
+    else:
+        if period is None:
+            raise ParameterError("period is required when exporting from modules")
+        ir = Compiler(model).compile(period)
+    path = Path(path)
+    fmt = _format(path, format)
+    rows = parameter_table(ir, overrides)
+    records = [
+        {
+            "path": row.path,
+            "value": row.value,
+            "source": row.source,
+            "effective": row.effective.isoformat() if row.effective else None,
+        }
+        for row in rows
+    ]
+
+    if fmt == "json":
+        path.write_text(json.dumps({"parameters": records}, indent=2) + "\n")
+    else:
+        with path.open("w", newline="") as f:
+            writer = csv.DictWriter(f, fieldnames=COLUMNS, lineterminator="\n")
+            writer.writeheader()
+            for record in records:
+                writer.writerow(record | {"value": json.dumps(record["value"])})
+    return rows
+
+
+def import_parameter_overrides(
+    path: str | Path,
+    format: str | None = None,
+) -> dict[str, Any]:
+    """Read an exported parameter table as ``{path: value}`` overrides.
+
+    The format defaults to the file extension.
+    """
+    path = Path(path)
+    if _format(path, format) == "json":
+        records = json.loads(path.read_text())["parameters"]
+        return {record["path"]: record["value"] for record in records}
+    with path.open(newline="") as f:
+        reader = csv.DictReader(f)
+        if reader.fieldnames is None or not {"path", "value"} <= set(reader.fieldnames):
+            raise ParameterError(f"{path}: expected columns {', '.join(COLUMNS)}")
+        try:
+            return {row["path"]: json.loads(row["value"]) for row in reader}
+        except json.JSONDecodeError as e:
+            raise ParameterError(f"{path}: unreadable value: {e}") from None
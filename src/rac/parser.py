@@ -3,30 +3,40 @@
 Grammar (simplified):
     module      = (entity | variable | amend)*
     entity      = "entity" NAME ":" field*
-    field       = NAME ":" type
+    field       = NAME ":" type ["?"]
     variable    = "variable" PATH ":" [metadata*] ["entity:" NAME] temporal+
+    metadata    = NAME ":" STRING | "default:" ["-"] literal
     temporal    = "from" DATE ["to" DATE] ":" expr
     amend       = "amend" PATH ":" temporal+
-    expr        = match | cond | or_expr
+    expr        = match | cond | scale | lookup | or_expr
     match       = "match" expr ":" case+
     case        = pattern "=>" expr
+    scale       = "scale" ["marginal" | "average"] expr ":" (add_expr "=>" expr)+
+    lookup      = "lookup" ["clamp"] expr ":" (entry "=>" entry)+ ["_" "=>" entry]
+    entry       = ["-"] NUMBER | STRING | "true" | "false"
     cond        = "if" expr ":" expr "else:" expr
-    or_expr     = and_expr ("or" and_expr)*
+    or_expr     = xor_expr ("or" xor_expr)*
+    xor_expr    = and_expr ("xor" and_expr)*
     and_expr    = cmp_expr ("and" cmp_expr)*
-    cmp_expr    = add_expr (("<" | ">" | "<=" | ">=" | "==" | "!=") add_expr)?
+    cmp_expr    = add_expr (("<" | ">" | "<=" | ">=") add_expr
+                  | ("==" | "!=") add_expr ["within" NUMBER])?
     add_expr    = mul_expr (("+" | "-") mul_expr)*
-    mul_expr    = unary (("*" | "/") unary)*
-    unary       = "-" unary | "not" unary | call
+    mul_expr    = unary (("*" | "/" | "//" | "%") unary)*
+    unary       = "-" unary | "not" unary | power
+    power       = call ["**" unary]
     call        = primary ("(" args ")")? ("." NAME)*
-    primary     = NUMBER | STRING | "true" | "false" | NAME | PATH | "(" expr ")"
+    primary     = NUMBER | STRING | DATE | "true" | "false" | NAME | PATH | list | "(" expr ")"
+    list        = "[" [["-"] literal ("," ["-"] literal)*] "]"
 """
 
 import re
 from dataclasses import dataclass
 from datetime import date
 from pathlib import Path
+from typing import Any
 
 from . import ast
+from .config import resolve_config
 
 
 @dataclass
@@ -58,6 +68,7 @@ class Lexer:
         "else",
         "and",
         "or",
+        "xor",
         "not",
         "true",
         "false",
@@ -82,8 +93,11 @@ class Lexer:
         (re.compile(r":"), "COLON"),
         (re.compile(r"\+"), "PLUS"),
         (re.compile(r"-"), "MINUS"),
+        (re.compile(r"\*\*"), "POW"),
         (re.compile(r"\*"), "STAR"),
+        (re.compile(r"//"), "DSLASH"),
         (re.compile(r"/"), "SLASH"),
+        (re.compile(r"%"), "PERCENT"),
         (re.compile(r"<"), "LT"),
         (re.compile(r">"), "GT"),
         (re.compile(r"\("), "LPAREN"),
@@ -92,6 +106,7 @@ class Lexer:
         (re.compile(r"\]"), "RBRACKET"),
         (re.compile(r","), "COMMA"),
         (re.compile(r"\."), "DOT"),
+        (re.compile(r"\?"), "QUESTION"),
     ]
 
     def __init__(self, source: str):
@@ -136,11 +151,29 @@ class Parser:
     """Recursive descent parser for .rac files."""
 
     # Metadata field names allowed in variable declarations
-    METADATA_FIELDS = {"source", "label", "description", "unit"}
+    METADATA_FIELDS = {
+        "source",
+        "label",
+        "description",
+        "unit",
+        "requires_feature",
+        "arithmetic",
+        "missing",
+        "rounding",
+    }
+
+    ARITHMETIC_MODES = {"float", "exact"}
+    MISSING_POLICIES = set(ast.MISSING_POLICIES)
+    ROUND_DIRECTIONS = set(ast.ROUND_DIRECTIONS)
 
-    def __init__(self, tokens: list[Token]):
+    # Parenthesised, argument and branch nesting; each level costs several stack frames
+    MAX_NESTING = 64
+
+    def __init__(self, tokens: list[Token], strict: bool = True):
         self.tokens = tokens
+        self.strict = strict  # reject built-in calls with the wrong argument count
         self.pos = 0
+        self.nesting = 0
 
     def peek(self, offset: int = 0) -> Token:
         idx = self.pos + offset
@@ -191,6 +224,7 @@ class Parser:
         fields = []
         foreign_keys = []
         reverse_relations = []
+        nullable = []
 
         while self.at("IDENT"):
             field_name = self.consume("IDENT").value
@@ -208,10 +242,13 @@ class Parser:
             else:
                 dtype = self.consume("IDENT").value
                 fields.append((field_name, dtype))
+                if self.match("QUESTION"):
+                    nullable.append(field_name)
 
         return ast.EntityDecl(
             name=name,
             fields=fields,
+            nullable=nullable,
             foreign_keys=foreign_keys,
             reverse_relations=reverse_relations,
         )
@@ -246,13 +283,63 @@ class Parser:
                         tok.col,
                     )
                 value = self.consume("STRING").value[1:-1]  # strip quotes
+                if field_name == "arithmetic" and value not in self.ARITHMETIC_MODES:
+                    raise ParseError(
+                        f"arithmetic must be one of {sorted(self.ARITHMETIC_MODES)}, got '{value}'",
+                        tok.line,
+                        tok.col,
+                    )
+                if field_name == "missing" and value not in self.MISSING_POLICIES:
+                    raise ParseError(
+                        f"missing must be one of {sorted(self.MISSING_POLICIES)}, got '{value}'",
+                        tok.line,
+                        tok.col,
+                    )
+                if field_name == "rounding" and value not in self.ROUND_DIRECTIONS:
+                    raise ParseError(
+                        f"rounding must be one of {sorted(self.ROUND_DIRECTIONS)}, got '{value}'",
+                        tok.line,
+                        tok.col,
+                    )
                 metadata[field_name] = value
+            elif (
+                self.at("IDENT")
+                and self.peek().value == "default"
+                and self.peek(1).type == "COLON"
+            ):
+                self.consume("IDENT")
+                self.consume("COLON")
+                metadata["default"] = self._parse_default()
+            elif (
+                self.at("IDENT")
+                and self.peek().value == "round_to"
+                and self.peek(1).type == "COLON"
+            ):
+                self.consume("IDENT")
+                self.consume("COLON")
+                tok = self.peek()
+                step = self._parse_default()
+                if isinstance(step, bool) or not isinstance(step, int | float) or not step > 0:
+                    raise ParseError("round_to must be a positive number", tok.line, tok.col)
+                metadata["round_to"] = step
             else:
                 break
 
         values = self._parse_temporal_values()
         return ast.VariableDecl(path=path, entity=entity, values=values, **metadata)
 
+    def _parse_default(self) -> Any:
+        """A declared default: a number (optionally negated), string or boolean."""
+        tok = self.peek()
+        expr = self.parse_unary()
+        if isinstance(expr, ast.UnaryOp) and isinstance(expr.operand, ast.Literal):
+            value = expr.operand.value
+            if expr.op == "-" and isinstance(value, int | float) and not isinstance(value, bool):
+                return -value
+        elif isinstance(expr, ast.Literal):
+            return expr.value
+        raise ParseError("default must be a literal value", tok.line, tok.col)
+
     def parse_amend(self) -> ast.AmendDecl:
         """Parse amendment declaration."""
         self.consume("AMEND")
@@ -286,15 +373,34 @@ class Parser:
 
     def _parse_date(self) -> date:
         tok = self.consume("DATE")
-        return date.fromisoformat(tok.value)
+        try:
+            return date.fromisoformat(tok.value)
+        except ValueError as e:
+            raise ParseError(f"invalid date {tok.value}: {e}", tok.line, tok.col) from None
+
+    def _nest(self) -> None:
+        if self.nesting >= self.MAX_NESTING:
+            tok = self.peek()
+            raise ParseError(
+                f"expression nested more than {self.MAX_NESTING} levels deep", tok.line, tok.col
+            )
+        self.nesting += 1
 
     def parse_expr(self) -> ast.Expr:
         """Parse expression."""
-        if self.at("MATCH"):
-            return self.parse_match()
-        if self.at("IF"):
-            return self.parse_cond()
-        return self.parse_or()
+        self._nest()
+        try:
+            if self.at("MATCH"):
+                return self.parse_match()
+            if self.at("IF"):
+                return self.parse_cond()
+            if self.at_contextual("scale"):
+                return self.parse_scale()
+            if self.at_contextual("lookup"):
+                return self.parse_lookup()
+            return self.parse_or()
+        finally:
+            self.nesting -= 1
 
     def parse_match(self) -> ast.Match:
         """Parse match expression."""
@@ -312,6 +418,85 @@ class Parser:
 
         return ast.Match(subject=subject, cases=cases, default=None)
 
+    def at_contextual(self, word: str) -> bool:
+        """Whether a ``word`` (scale or lookup) expression starts here.
+
+        "scale" and "lookup" are contextual, so they stay usable as names:
+        they only start an expression when an operand follows on the same line.
+        """
+        tok, nxt = self.peek(), self.peek(1)
+        return (
+            tok.type == "IDENT"
+            and tok.value == word
+            and nxt.line == tok.line
+            and nxt.type in ("IDENT", "PATH", "INT", "FLOAT", "MINUS", "LPAREN")
+        )
+
+    def parse_scale(self) -> ast.Scale:
+        """Parse scale expression: ``scale [marginal|average] base:`` then
+        ``threshold => rate`` brackets with ascending thresholds."""
+        start = self.consume("IDENT")
+        mode = "marginal"
+        tok = self.peek()
+        if tok.type == "IDENT" and tok.value in ast.SCALE_MODES and self.peek(1).type != "COLON":
+            mode = self.consume("IDENT").value
+        base = self.parse_or()
+        self.consume("COLON")
+
+        brackets = []
+        while self.at("INT", "FLOAT", "MINUS", "IDENT", "PATH", "LPAREN"):
+            threshold = self.parse_add()
+            self.consume("ARROW")
+            rate = self.parse_expr()
+            brackets.append((threshold, rate))
+
+        if not brackets:
+            raise ParseError("scale needs at least one bracket", start.line, start.col)
+        return ast.Scale(base=base, brackets=brackets, mode=mode)
+
+    def parse_lookup(self) -> ast.Lookup:
+        """Parse lookup expression: ``lookup [clamp] key:`` then literal
+        ``key => value`` entries, and ``_ => value`` for any other key."""
+        start = self.consume("IDENT")
+        mode = "error"
+        tok = self.peek()
+        if tok.type == "IDENT" and tok.value == "clamp" and self.peek(1).type != "COLON":
+            mode = self.consume("IDENT").value
+        key = self.parse_or()
+        self.consume("COLON")
+
+        table: list[tuple[Any, Any]] = []
+        default = None
+        while self.at("INT", "FLOAT", "MINUS", "STRING", "TRUE", "FALSE", "IDENT"):
+            tok = self.peek()
+            if tok.type == "IDENT":
+                if tok.value != "_" or self.peek(1).type != "ARROW":
+                    break
+                if mode == "clamp":
+                    raise ParseError("a clamped lookup has no default", tok.line, tok.col)
+                self.pos += 1
+                self.consume("ARROW")
+                mode, default = "default", self.parse_entry()
+                break
+            entry = self.parse_entry()
+            self.consume("ARROW")
+            table.append((entry, self.parse_entry()))
+
+        node = ast.Lookup(key=key, table=table, out_of_range=mode, default=default)
+        if problems := ast.lookup_problems(node):
+            raise ParseError(problems[0], start.line, start.col)
+        return node
+
+    def parse_entry(self) -> Any:
+        """A lookup key or value: a number, string or boolean literal."""
+        negative = self.match("MINUS") is not None
+        tok = self.peek()
+        allowed = ("INT", "FLOAT") if negative else ("INT", "FLOAT", "STRING", "TRUE", "FALSE")
+        if tok.type not in allowed:
+            raise ParseError(f"lookup entries must be literals, got {tok.type}", tok.line, tok.col)
+        value = self.parse_primary().value
+        return -value if negative else value
+
     def parse_cond(self) -> ast.Cond:
         """Parse conditional expression."""
         self.consume("IF")
@@ -324,12 +509,19 @@ class Parser:
         return ast.Cond(condition=condition, then_expr=then_expr, else_expr=else_expr)
 
     def parse_or(self) -> ast.Expr:
-        left = self.parse_and()
+        left = self.parse_xor()
         while self.match("OR"):
-            right = self.parse_and()
+            right = self.parse_xor()
             left = ast.BinOp(op="or", left=left, right=right)
         return left
 
+    def parse_xor(self) -> ast.Expr:
+        left = self.parse_and()
+        while self.match("XOR"):
+            right = self.parse_and()
+            left = ast.BinOp(op="xor", left=left, right=right)
+        return left
+
     def parse_and(self) -> ast.Expr:
         left = self.parse_cmp()
         while self.match("AND"):
@@ -339,6 +531,13 @@ class Parser:
 
     def parse_cmp(self) -> ast.Expr:
         left = self.parse_add()
+        # "in" is contextual like "within": only "in [" starts a membership test
+        negated = self.at("NOT")
+        word, bracket = self.peek(1 if negated else 0), self.peek(2 if negated else 1)
+        if word.type == "IDENT" and word.value == "in" and bracket.type == "LBRACKET":
+            self.pos += 2 if negated else 1
+            op = "not in" if negated else "in"
+            return ast.BinOp(op=op, left=left, right=self.parse_categories())
         op_map = {
             "LT": "<",
             "GT": ">",
@@ -349,9 +548,40 @@ class Parser:
         }
         if tok := self.match("LT", "GT", "LE", "GE", "EQ", "NE"):
             right = self.parse_add()
-            return ast.BinOp(op=op_map[tok.type], left=left, right=right)
+            tolerance = None
+            # "within" is contextual, so it stays usable as a name elsewhere
+            nxt = self.peek()
+            if tok.type in ("EQ", "NE") and nxt.type == "IDENT" and nxt.value == "within":
+                self.pos += 1
+                num = self.match("INT", "FLOAT")
+                if num is None:
+                    bad = self.peek()
+                    raise ParseError("expected a number after 'within'", bad.line, bad.col)
+                tolerance = float(num.value)
+            return ast.BinOp(op=op_map[tok.type], left=left, right=right, tolerance=tolerance)
         return left
 
+    def parse_categories(self) -> ast.Literal:
+        """Parse a bracketed list of literal values: the categories after
+        ``in``, or the knots of ``interp``."""
+        self.consume("LBRACKET")
+        values = []
+        while not self.at("RBRACKET"):
+            tok = self.peek()
+            item = self.parse_unary()
+            if isinstance(item, ast.UnaryOp) and item.op == "-":
+                if isinstance(item.operand, ast.Literal) and isinstance(
+                    item.operand.value, int | float
+                ):
+                    item = ast.Literal(value=-item.operand.value)
+            if not isinstance(item, ast.Literal):
+                raise ParseError("list items must be literal values", tok.line, tok.col)
+            values.append(item.value)
+            if not self.match("COMMA"):
+                break
+        self.consume("RBRACKET")
+        return ast.Literal(value=values)
+
     def parse_add(self) -> ast.Expr:
         left = self.parse_mul()
         op_map = {"PLUS": "+", "MINUS": "-"}
@@ -362,18 +592,32 @@ class Parser:
 
     def parse_mul(self) -> ast.Expr:
         left = self.parse_unary()
-        op_map = {"STAR": "*", "SLASH": "/"}
-        while tok := self.match("STAR", "SLASH"):
+        op_map = {"STAR": "*", "SLASH": "/", "DSLASH": "//", "PERCENT": "%"}
+        while tok := self.match("STAR", "SLASH", "DSLASH", "PERCENT"):
             right = self.parse_unary()
             left = ast.BinOp(op=op_map[tok.type], left=left, right=right)
         return left
 
     def parse_unary(self) -> ast.Expr:
-        if self.match("MINUS"):
-            return ast.UnaryOp(op="-", operand=self.parse_unary())
-        if self.match("NOT"):
-            return ast.UnaryOp(op="not", operand=self.parse_unary())
-        return self.parse_postfix()
+        ops = []
+        while tok := self.match("MINUS", "NOT"):
+            ops.append("-" if tok.type == "MINUS" else "not")
+        expr = self.parse_power()
+        for op in reversed(ops):
+            expr = ast.UnaryOp(op=op, operand=expr)
+        return expr
+
+    def parse_power(self) -> ast.Expr:
+        """``**`` is right-associative and binds tighter than a unary minus
+        on its left: ``-x ** 2`` is ``-(x ** 2)``, ``2 ** -1`` is 0.5."""
+        base = self.parse_postfix()
+        if not self.match("POW"):
+            return base
+        self._nest()
+        try:
+            return ast.BinOp(op="**", left=base, right=self.parse_unary())
+        finally:
+            self.nesting -= 1
 
     def parse_postfix(self) -> ast.Expr:
         """Parse postfix operations (function calls, field access)."""
@@ -384,14 +628,18 @@ class Parser:
                 if not isinstance(expr, ast.Var):
                     tok = self.peek()
                     raise ParseError("can only call named functions", tok.line, tok.col)
-                self.consume("LPAREN")
+                paren = self.consume("LPAREN")
                 args = []
                 if not self.at("RPAREN"):
-                    args.append(self.parse_expr())
+                    args.append(self.parse_argument(expr.path, 0))
                     while self.at("COMMA"):
                         self.consume("COMMA")
-                        args.append(self.parse_expr())
+                        args.append(self.parse_argument(expr.path, len(args)))
                 self.consume("RPAREN")
+                if self.strict and (error := ast.arity_error(expr.path, len(args))):
+                    raise ParseError(error, paren.line, paren.col)
+                if expr.path == "interp" and (error := ast.knots_error(args)):
+                    raise ParseError(error, paren.line, paren.col)
                 expr = ast.Call(func=expr.path, args=args)
             elif self.at("DOT"):
                 self.consume("DOT")
@@ -402,20 +650,33 @@ class Parser:
 
         return expr
 
+    def parse_argument(self, func: str, index: int) -> ast.Expr:
+        """Parse a call argument; only interp's knots may be a bracketed list."""
+        if func == "interp" and index > 0 and self.at("LBRACKET"):
+            return self.parse_categories()
+        return self.parse_expr()
+
     def parse_primary(self) -> ast.Expr:
         """Parse primary expression."""
-        if self.at("INT"):
-            return ast.Literal(value=int(self.consume("INT").value))
+        if tok := self.match("INT"):
+            try:
+                return ast.Literal(value=int(tok.value))
+            except ValueError as e:  # beyond the interpreter's integer string limit
+                raise ParseError(str(e), tok.line, tok.col) from None
         if self.at("FLOAT"):
             return ast.Literal(value=float(self.consume("FLOAT").value))
         if self.at("STRING"):
             return ast.Literal(value=self.consume("STRING").value[1:-1])
+        if self.at("DATE"):
+            return ast.Literal(value=self._parse_date())
         if self.match("TRUE"):
             return ast.Literal(value=True)
         if self.match("FALSE"):
             return ast.Literal(value=False)
         if tok := self.match("PATH", "IDENT"):
             return ast.Var(path=tok.value)
+        if tok := self.match("LBRACKET"):
+            raise ParseError(ast.LIST_ERROR, tok.line, tok.col)
         if self.match("LPAREN"):
             expr = self.parse_expr()
             self.consume("RPAREN")
@@ -427,15 +688,19 @@ class Parser:
         )
 
 
-def parse(source: str, path: str = "") -> ast.Module:
-    """Parse .rac source code into an AST."""
+def parse(source: str, path: str = "", strict: bool | None = None) -> ast.Module:
+    """Parse .rac source code into an AST.
+
+    In strict mode (the engine default) built-in calls with the wrong number
+    of arguments are a ParseError.
+    """
     lexer = Lexer(source)
-    parser = Parser(lexer.tokens)
+    parser = Parser(lexer.tokens, strict=resolve_config(strict=strict).strict)
     return parser.parse_module(path)
 
 
-def parse_file(filepath: str | Path) -> ast.Module:
+def parse_file(filepath: str | Path, strict: bool | None = None) -> ast.Module:
     """Parse a .rac file."""
     filepath = Path(filepath)
     source = filepath.read_text()
-    return parse(source, str(filepath))
+    return parse(source, str(filepath), strict)
@@ -0,0 +1,123 @@
+"""Run plans: everything a run decides before evaluating an expression.
+
+A real run and a dry run build the same Plan through the same checks, so a
+dry run (``run(..., dry_run=True)``) finds every IR, data schema and
+linkage problem a real run would hit before evaluation starts, in about
+the time it takes to index the data.
+"""
+
+from pydantic import BaseModel
+
+from .compiler import IR, CompileError, check_expressions, required_inputs, structure_problems
+from .schema import Data
+
+# One float64 per computed value, as the native backend stores results; the
+# Python executor's boxed values take several times this.
+BYTES_PER_VALUE = 8
+
+
+class PlanError(Exception):
+    """Problems that stop a run before evaluation; all are listed at once."""
+
+    def __init__(self, problems: list[str]):
+        self.problems = problems
+        super().__init__("; ".join(problems))
+
+
+class PlanStep(BaseModel):
+    """One variable to evaluate."""
+
+    path: str
+    entity: str | None = None
+    rows: int = 1  # evaluations: the entity's row count, or 1 for a scalar
+
+
+class Plan(BaseModel):
+    """The evaluation order, output schema and memory estimate of a run."""
+
+    steps: list[PlanStep]
+    scalars: list[str]  # scalar outputs, in evaluation order
+    entities: dict[str, list[str]]  # entity -> output columns, in evaluation order
+    estimated_bytes: int
+
+
+class DryRunReport(BaseModel):
+    """What a dry run found: the plan, or the problems preventing one."""
+
+    plan: Plan | None = None
+    problems: list[str] = []
+
+    @property
+    def ok(self) -> bool:
+        return not self.problems
+
+
+def ir_problems(ir: IR) -> list[str]:
+    """Expression limits first, then structure, which relies on them."""
+    try:
+        check_expressions(ir.variables)
+    except CompileError as e:
+        return [str(e)]
+    return structure_problems(ir, declared_entities=False)
+
+
+def entity_inputs(ir: IR) -> dict[str, list[str]]:
+    """Each entity's required_inputs, for the entities with variables."""
+    entities = {var.entity for var in ir.variables.values() if var.entity is not None}
+    return {entity: required_inputs(ir, entity) for entity in sorted(entities)}
+
+
+def data_problems(
+    ir: IR, data: Data, inputs: dict[str, list[str]] | None = None
+) -> list[str]:
+    """Input fields read by an entity's variables but absent from all its rows.
+
+    ``inputs`` is entity_inputs(ir), where the caller already has it.
+    """
+    problems = []
+    for entity, required in (entity_inputs(ir) if inputs is None else inputs).items():
+        rows = data.get_rows(entity)
+        if not rows:
+            continue
+        decl = ir.schema_.entities.get(entity)
+        relations = set(decl.reverse_relations) if decl else set()
+        present = set().union(*rows)
+        absent = [name for name in required if name not in present and name not in relations]
+        if absent:
+            problems.append(f"{entity} rows have no {', '.join(absent)}")
+    return problems
+
+
+def build_plan(ir: IR, data: Data, inputs: dict[str, list[str]] | None = None) -> Plan:
+    """Check ``ir`` against ``data`` and lay out the run; raises PlanError.
+
+    Passing ``inputs``, entity_inputs(ir), says the IR already passed
+    ir_problems: only the data is checked.
+    """
+    problems = ir_problems(ir) if inputs is None else []
+    if problems:
+        raise PlanError(problems)
+    problems = data_problems(ir, data, inputs)
+    if problems:
+        raise PlanError(problems)
+
+    steps = []
+    scalars: list[str] = []
+    entities: dict[str, list[str]] = {}
+    values = 0
+    for path in ir.order:
+        var = ir.variables[path]
+        if var.entity is None:
+            step = PlanStep(path=path)
+            scalars.append(path)
+        else:
+            step = PlanStep(path=path, entity=var.entity, rows=len(data.get_rows(var.entity)))
+            entities.setdefault(var.entity, []).append(path)
+        steps.append(step)
+        values += step.rows
+    return Plan(
+        steps=steps,
+        scalars=scalars,
+        entities=entities,
+        estimated_bytes=values * BYTES_PER_VALUE,
+    )
@@ -0,0 +1,132 @@
+"""Preflight: one go/no-go report before a production run.
+
+preflight() gathers the checks a run would otherwise trip over one at a
+time, each reported as pass, warn or fail with details:
+
+- ``ir``: expression limits and the consistency of variables, deps and order
+- ``references``: variables read but with nothing in force and no default
+- ``functions``: calls to functions that are neither built in nor registered
+- ``inputs``: required input fields against a sample of each entity's rows
+- ``types``: sampled input values against their fields' declared dtypes
+- ``unused``: variables no requested output depends on
+- ``critical_path``: the longest dependency chain, against an optional limit
+"""
+
+from typing import Literal
+
+from pydantic import BaseModel
+
+from . import ast
+from .compiler import IR, CompileError, critical_path, dependency_closure
+from .executor import BUILTINS
+from .functions import get_function
+from .plan import data_problems, ir_problems
+from .schema import Data
+from .typecheck import column_problems
+
+Status = Literal["pass", "warn", "fail"]
+
+SAMPLE_ROWS = 1_000
+
+
+class PreflightCheck(BaseModel):
+    name: str
+    status: Status
+    details: list[str] = []
+
+
+class PreflightReport(BaseModel):
+    checks: list[PreflightCheck]
+
+    @property
+    def status(self) -> Status:
+        """The worst status of any check."""
+        statuses = {check.status for check in self.checks}
+        return "fail" if "fail" in statuses else "warn" if "warn" in statuses else "pass"
+
+    @property
+    def ok(self) -> bool:
+        return self.status != "fail"
+
+    def __getitem__(self, name: str) -> PreflightCheck:
+        return next(check for check in self.checks if check.name == name)
+
+
+def _check(name: str, problems: list[str], status: Status = "fail") -> PreflightCheck:
+    return PreflightCheck(name=name, status=status if problems else "pass", details=problems)
+
+
+def _unresolved(ir: IR) -> list[str]:
+    return [
+        f"{path} reads {dep}, which has nothing in force and no default"
+        for path, var in ir.variables.items()
+        for dep in sorted(var.deps)
+        if dep not in ir.variables
+    ]
+
+
+def _unknown_functions(ir: IR) -> list[str]:
+    problems = []
+    for path, var in ir.variables.items():
+        for node in ast.walk(var.expr):
+            if (
+                isinstance(node, ast.Call)
+                and node.func not in BUILTINS
+                and get_function(node.func) is None
+            ):
+                problems.append(f"{path} calls unknown function {node.func}")
+    return problems
+
+
+def _unused(ir: IR, outputs: list[str] | None) -> list[str]:
+    if outputs is None:
+        outputs = [path for path in ir.order if ir.variables[path].entity is not None]
+    closure = dependency_closure(ir, outputs)
+    return [path for path in ir.order if path not in closure]
+
+
+def preflight(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    outputs: list[str] | None = None,
+    sample_rows: int = SAMPLE_ROWS,
+    max_critical_path: int | None = None,
+) -> PreflightReport:
+    """Check ``ir`` and a sample of ``data`` before a run.
+
+    ``outputs`` are the variables the run is for, by default every entity
+    variable; anything they don't depend on is reported as unused. Only the
+    first ``sample_rows`` rows of each entity are checked for inputs.
+    """
+    problems = ir_problems(ir)
+    checks = [_check("ir", problems)]
+    if problems:
+        # The other checks assume a well-formed IR
+        return PreflightReport(checks=checks)
+
+    tables = data.tables if isinstance(data, Data) else data
+    sample = Data(tables={entity: rows[:sample_rows] for entity, rows in tables.items()})
+    checks.append(_check("references", _unresolved(ir), "warn"))
+    checks.append(_check("functions", _unknown_functions(ir)))
+    checks.append(_check("inputs", data_problems(ir, sample)))
+    checks.append(_check("types", column_problems(ir.schema_, sample.tables)))
+    try:
+        checks.append(_check("unused", _unused(ir, outputs), "warn"))
+    except CompileError as e:
+        checks.append(_check("unused", [str(e)]))
+
+    try:
+        length, chain = critical_path(ir.variables, ir.fixed_point)
+    except CompileError as e:
+        checks.append(_check("critical_path", [str(e)], "warn"))
+    else:
+        detail = [f"{length} steps: {' -> '.join(chain)}"]
+        too_long = max_critical_path is not None and length > max_critical_path
+        checks.append(
+            PreflightCheck(
+                name="critical_path",
+                status="warn" if too_long else "pass",
+                details=detail + ([f"longer than {max_critical_path}"] if too_long else []),
+            )
+        )
+    return PreflightReport(checks=checks)
@@ -0,0 +1,103 @@
+"""Expression-level profiling.
+
+Attributes evaluation time to individual expression nodes, identified by
+(variable path, pre-order node id), aggregated across rows. Profiling every
+k-th row keeps the overhead manageable on large datasets; counts and times
+are for the sampled rows only.
+"""
+
+from collections import defaultdict
+from collections.abc import Callable
+from time import perf_counter
+from typing import Any
+
+from pydantic import BaseModel
+
+from . import ast
+from .compiler import IR
+
+
+class NodeStats(BaseModel):
+    """Aggregated timings for one expression node."""
+
+    variable: str
+    node_id: int
+    snippet: str
+    count: int
+    total_time: float  # seconds, including subexpressions
+    self_time: float  # seconds, excluding subexpressions
+
+
+class Profiler:
+    """Collects per-node timings when passed to the executor."""
+
+    def __init__(self, ir: IR, sample_every: int = 1):
+        if sample_every < 1:
+            raise ValueError("sample_every must be >= 1")
+        self.sample_every = sample_every
+        self.active = True
+        self._nodes: dict[int, tuple[str, int, ast.Expr]] = {}
+        for path, var in ir.variables.items():
+            for node_id, node in enumerate(ast.walk(var.expr)):
+                self._nodes[id(node)] = (path, node_id, node)
+        self._count: dict[int, int] = defaultdict(int)
+        self._total: dict[int, float] = defaultdict(float)
+        self._self: dict[int, float] = defaultdict(float)
+        self._child_time: list[float] = []
+        self.memo_hits: dict[str, int] = defaultdict(int)
+        self.memo_misses: dict[str, int] = defaultdict(int)
+
+    def start_row(self, row: int | None) -> None:
+        """Called by the executor before each row (None for scalars)."""
+        self.active = row is None or row % self.sample_every == 0
+
+    def time(self, expr: ast.Expr, ctx: Any, fn: Callable[[ast.Expr, Any], Any]) -> Any:
+        self._child_time.append(0.0)
+        start = perf_counter()
+        try:
+            return fn(expr, ctx)
+        finally:
+            elapsed = perf_counter() - start
+            children = self._child_time.pop()
+            if self._child_time:
+                self._child_time[-1] += elapsed
+            key = id(expr)
+            if key in self._nodes:
+                self._count[key] += 1
+                self._total[key] += elapsed
+                self._self[key] += elapsed - children
+
+    def record_memo(self, function: str, hit: bool) -> None:
+        """Called by the executor for each memoizable function call."""
+        if hit:
+            self.memo_hits[function] += 1
+        else:
+            self.memo_misses[function] += 1
+
+    def memo_hit_rate(self, function: str) -> float:
+        total = self.memo_hits[function] + self.memo_misses[function]
+        return self.memo_hits[function] / total if total else 0.0
+
+    def stats(self) -> list[NodeStats]:
+        return [
+            NodeStats(
+                variable=self._nodes[key][0],
+                node_id=self._nodes[key][1],
+                snippet=ast.to_source(self._nodes[key][2]),
+                count=count,
+                total_time=self._total[key],
+                self_time=self._self[key],
+            )
+            for key, count in self._count.items()
+        ]
+
+    def variable_times(self) -> dict[str, float]:
+        """Self time summed per variable, in seconds."""
+        times: dict[str, float] = defaultdict(float)
+        for s in self.stats():
+            times[s.variable] += s.self_time
+        return dict(times)
+
+    def hottest(self, n: int = 10) -> list[NodeStats]:
+        """The n nodes with the most self time."""
+        return sorted(self.stats(), key=lambda s: s.self_time, reverse=True)[:n]
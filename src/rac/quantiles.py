@@ -0,0 +1,249 @@
+"""Weighted quantiles, exact and approximate.
+
+The approximate path uses a log-bucketed sketch (as in DDSketch): a value x
+falls in bucket ``ceil(log_gamma(|x|))`` with ``gamma = (1 + a) / (1 - a)``,
+and each bucket reports the value ``2 * gamma**k / (gamma + 1)``. Bucket
+order matches value order, so the bucket containing the exact quantile is
+found exactly and the estimate is within relative error ``a`` of it:
+``|estimate - exact| <= a * |exact|``. Memory grows with the log of the
+value range (about 1,000 buckets for values spanning 1 to 1e9 at a = 1%),
+not with the number of rows.
+
+Bucket weights are accumulated exactly (Shewchuk partial sums), so a sketch
+does not depend on how rows were chunked or in which order per-chunk
+sketches were merged.
+"""
+
+import math
+from collections.abc import Iterable
+
+from .config import EngineConfig, resolve_config
+from .stats import Columns, StatsError, WeightPolicy, _missing, _observed, _weights, raw_column
+
+DEFAULT_RELATIVE_ACCURACY = 0.01
+
+
+def _add_exact(partials: list[float], x: float) -> None:
+    """Add x to a list of non-overlapping partials, keeping the sum exact."""
+    i = 0
+    for y in partials:
+        if abs(x) < abs(y):
+            x, y = y, x
+        hi = x + y
+        lo = y - (hi - x)
+        if lo:
+            partials[i] = lo
+            i += 1
+        x = hi
+    partials[i:] = [x]
+
+
+class QuantileSketch:
+    """Mergeable weighted quantile sketch with relative value error bound."""
+
+    def __init__(self, relative_accuracy: float = DEFAULT_RELATIVE_ACCURACY):
+        if not 0 < relative_accuracy < 1:
+            raise StatsError(f"relative_accuracy must be in (0, 1), got {relative_accuracy}")
+        self.relative_accuracy = relative_accuracy
+        self.gamma = (1 + relative_accuracy) / (1 - relative_accuracy)
+        self._log_gamma = math.log(self.gamma)
+        # Keys are (sign, bucket); zero is (0, 0)
+        self._partials: dict[tuple[int, int], list[float]] = {}
+
+    def _key(self, value: float) -> tuple[int, int]:
+        if value == 0:
+            return (0, 0)
+        sign = 1 if value > 0 else -1
+        return (sign, math.ceil(math.log(abs(value)) / self._log_gamma))
+
+    def _value(self, key: tuple[int, int]) -> float:
+        sign, k = key
+        return sign * 2 * self.gamma**k / (self.gamma + 1)
+
+    def add(self, value: float, weight: float = 1.0) -> None:
+        if weight < 0:
+            raise StatsError("quantiles undefined for negative weights")
+        if math.isnan(value):
+            raise StatsError("cannot add NaN to a quantile sketch")
+        if weight > 0:
+            _add_exact(self._partials.setdefault(self._key(value), []), weight)
+
+    def update(self, values: Iterable[float], weights: Iterable[float] | None = None) -> None:
+        """Add a chunk of values (and optional weights)."""
+        if weights is None:
+            for v in values:
+                self.add(v)
+        else:
+            for v, w in zip(values, weights, strict=True):
+                self.add(v, w)
+
+    def merge(self, other: "QuantileSketch") -> None:
+        """Fold another sketch in; the result is independent of merge order."""
+        if other.gamma != self.gamma:
+            raise StatsError("cannot merge sketches with different relative accuracy")
+        for key, partials in other._partials.items():
+            target = self._partials.setdefault(key, [])
+            for p in partials:
+                _add_exact(target, p)
+
+    @property
+    def buckets(self) -> dict[tuple[int, int], float]:
+        """Total weight per bucket, in value order."""
+        return {key: math.fsum(self._partials[key]) for key in sorted(self._partials, key=_order)}
+
+    @property
+    def total_weight(self) -> float:
+        return math.fsum(p for partials in self._partials.values() for p in partials)
+
+    def quantile(self, q: float) -> float:
+        return self.quantiles([q])[0]
+
+    def quantiles(self, qs: list[float]) -> list[float]:
+        """Estimated weighted quantiles, each within the relative error bound."""
+        _check_qs(qs)
+        buckets = list(self.buckets.items())
+        if not buckets:
+            raise StatsError("quantile of an empty sketch")
+        total = self.total_weight
+        out = []
+        for q in qs:
+            target = q * total
+            cum = 0.0
+            for key, weight in buckets:
+                cum += weight
+                if cum >= target:
+                    break
+            out.append(self._value(key))
+        return out
+
+
+def _order(key: tuple[int, int]) -> tuple[int, int]:
+    sign, k = key
+    return (sign, sign * k)
+
+
+def _check_qs(qs: list[float]) -> None:
+    for q in qs:
+        if not 0 <= q <= 1:
+            raise StatsError(f"quantile must be in [0, 1], got {q}")
+
+
+def _pairs(
+    data: Columns, value_path: str, weight_path: str | None, policy: WeightPolicy | None
+) -> list[tuple[float, float]]:
+    values = raw_column(data, value_path)
+    weights = _weights(data, weight_path, len(values), policy, "quantiles")
+    return _observed(values, weights)
+
+
+def weighted_quantiles(
+    data: Columns,
+    value_path: str,
+    qs: list[float],
+    weight_path: str | None = None,
+    approx: bool | None = None,
+    relative_accuracy: float | None = None,
+    weight_policy: WeightPolicy | None = None,
+    config: EngineConfig | None = None,
+) -> list[float]:
+    """Weighted quantiles: the smallest value whose cumulative weight share
+    reaches q. Rows with a missing value (None or NaN) are left out.
+
+    ``approx=True`` uses a QuantileSketch instead of a full sort; each result
+    is then within ``relative_accuracy`` (relative) of the exact one. Both
+    default to the engine configuration (approx_quantiles, quantile_accuracy).
+    """
+    _check_qs(qs)
+    cfg = resolve_config(config, approx_quantiles=approx, quantile_accuracy=relative_accuracy)
+    pairs = _pairs(data, value_path, weight_path, weight_policy)
+    if not pairs:
+        raise StatsError(f"quantile of empty or zero-weight column: {value_path}")
+    if cfg.approx_quantiles:
+        sketch = QuantileSketch(cfg.quantile_accuracy)
+        for v, w in pairs:
+            sketch.add(v, w)
+        return sketch.quantiles(qs)
+
+    pairs.sort()
+    total = math.fsum(w for _, w in pairs)
+    out = []
+    for q in qs:
+        target = q * total
+        cum = 0.0
+        for v, w in pairs:
+            cum += w
+            if cum >= target:
+                break
+        out.append(v)
+    return out
+
+
+def weighted_quantile(
+    data: Columns,
+    value_path: str,
+    q: float,
+    weight_path: str | None = None,
+    approx: bool | None = None,
+    relative_accuracy: float | None = None,
+    weight_policy: WeightPolicy | None = None,
+    config: EngineConfig | None = None,
+) -> float:
+    """A single weighted quantile (see weighted_quantiles)."""
+    return weighted_quantiles(
+        data, value_path, [q], weight_path, approx, relative_accuracy, weight_policy, config
+    )[0]
+
+
+def quantile_groups(
+    data: Columns,
+    value_path: str,
+    n: int = 10,
+    weight_path: str | None = None,
+    approx: bool | None = None,
+    relative_accuracy: float | None = None,
+    weight_policy: WeightPolicy | None = None,
+    config: EngineConfig | None = None,
+) -> list[int | None]:
+    """Assign each row to a weighted quantile group 1..n (deciles by default).
+
+    Rows at or below the i-th cut point go to group i; a row with a missing
+    value (None or NaN) has no group (None). With ``approx`` the cut points
+    come from a sketch, so rows near a boundary may land in a neighbouring
+    group.
+    """
+    if n < 1:
+        raise StatsError(f"number of groups must be positive, got {n}")
+    cuts = weighted_quantiles(
+        data,
+        value_path,
+        [i / n for i in range(1, n)],
+        weight_path,
+        approx,
+        relative_accuracy,
+        weight_policy,
+        config,
+    )
+    groups: list[int | None] = []
+    for v in raw_column(data, value_path):
+        if _missing(v):
+            groups.append(None)
+            continue
+        group = 1
+        while group < n and v > cuts[group - 1]:
+            group += 1
+        groups.append(group)
+    return groups
+
+
+def sketch_column(
+    data: Columns,
+    value_path: str,
+    weight_path: str | None = None,
+    relative_accuracy: float = DEFAULT_RELATIVE_ACCURACY,
+    weight_policy: WeightPolicy | None = None,
+) -> QuantileSketch:
+    """Sketch of one column, e.g. a chunk of a streamed result."""
+    sketch = QuantileSketch(relative_accuracy)
+    for v, w in _pairs(data, value_path, weight_path, weight_policy):
+        sketch.add(v, w)
+    return sketch
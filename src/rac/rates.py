@@ -0,0 +1,212 @@
+"""Effective tax rates computed from execution results.
+
+The effective average tax rate (EATR) is total tax divided by gross income.
+Division by zero gross follows ``on_zero``: "zero" (the default, matching the
+executor's ``/``), "nan", or "error".
+
+marginal_rates raises one input of every row by a small delta and reports
+the change in each output per unit, evaluating again only the variables
+downstream of that input.
+
+check_monotonic sweeps income for one household-like row and reports where
+a schedule moves against its declared direction, e.g. a benefit that rises
+with income.
+"""
+
+import math
+from numbers import Real
+from typing import Any
+
+from pydantic import BaseModel
+
+from . import ast
+from .compiler import IR, dependents, prune
+from .config import EngineConfig
+from .executor import Executor, run
+from .schema import Data
+
+
+class RateError(Exception):
+    pass
+
+
+ZERO_POLICIES = ("zero", "nan", "error")
+DIRECTIONS = ("decreasing", "increasing")
+
+
+def _ratio(tax: float, gross: float, on_zero: str) -> float:
+    if gross != 0:
+        return tax / gross
+    if on_zero == "zero":
+        return 0.0
+    if on_zero == "nan":
+        return float("nan")
+    raise RateError("average rate undefined for zero gross income")
+
+
+def _values(
+    ir: IR, tables: dict[str, list[dict]], entity: str, path: str
+) -> list[float]:
+    """A computed entity variable, or an input field of that entity."""
+    if path in ir.variables:
+        if ir.variables[path].entity != entity:
+            raise RateError(f"{path} is not a variable of entity {entity}")
+        return [float(v) for v in run(ir, tables).entities[entity][path]]
+    try:
+        return [float(row[path]) for row in tables[entity]]
+    except KeyError:
+        raise RateError(f"unknown variable or field: {path}") from None
+
+
+def eatr_column(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    entity: str,
+    gross_path: str,
+    tax_path: str,
+    on_zero: str = "zero",
+) -> list[float]:
+    """Effective average tax rate for every row of an entity."""
+    if on_zero not in ZERO_POLICIES:
+        raise RateError(f"unknown on_zero policy: {on_zero}")
+    tables = data.tables if isinstance(data, Data) else data
+    if entity not in tables:
+        raise RateError(f"no data for entity: {entity}")
+    gross = _values(ir, tables, entity, gross_path)
+    tax = _values(ir, tables, entity, tax_path)
+    return [_ratio(t, g, on_zero) for t, g in zip(tax, gross)]
+
+
+def eatr(
+    ir: IR,
+    entity: str,
+    row: dict[str, Any],
+    gross_path: str,
+    tax_path: str,
+    on_zero: str = "zero",
+) -> float:
+    """Effective average tax rate for a single row of an entity."""
+    return eatr_column(ir, {entity: [row]}, entity, gross_path, tax_path, on_zero)[0]
+
+
+def _readers(ir: IR, entity: str, field: str) -> list[str]:
+    """Variables of ``entity`` that read the input ``field`` directly."""
+    return [
+        path
+        for path in ir.order
+        if ir.variables[path].entity == entity
+        and any(
+            isinstance(node, ast.Var) and node.path == field
+            for node in ast.walk(ir.variables[path].expr)
+        )
+    ]
+
+
+def marginal_rates(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    entity: str,
+    with_respect_to: str,
+    delta: float = 1.0,
+    targets: list[str] | None = None,
+    config: EngineConfig | None = None,
+) -> dict[str, list[float | None]]:
+    """Per-row change in each target per unit of the input ``with_respect_to``.
+
+    Every row of ``entity`` has the input raised by ``delta`` at once, and
+    only variables downstream of it are evaluated again; the rate is the
+    forward difference divided by ``delta``. ``targets`` defaults to the
+    entity's variables that depend on the input. Where a target reads other
+    entities, the rate reflects all rows moving together, e.g. every member
+    of a household.
+    """
+    if not isinstance(delta, Real) or delta == 0 or not math.isfinite(delta):
+        raise RateError(f"delta must be a finite non-zero number, got {delta!r}")
+    tables = dict(data.tables if isinstance(data, Data) else data)
+    if entity not in tables:
+        raise RateError(f"no data for entity: {entity}")
+    rows = tables[entity]
+    for i, row in enumerate(rows):
+        value = row.get(with_respect_to)
+        if isinstance(value, bool) or not isinstance(value, Real):
+            raise RateError(f"{entity} row {i}: {with_respect_to} is not a number")
+    changed = dependents(ir, _readers(ir, entity, with_respect_to))
+    if targets is None:
+        targets = [p for p in ir.order if p in changed and ir.variables[p].entity == entity]
+    for path in targets:
+        if path not in ir.variables or ir.variables[path].entity != entity:
+            raise RateError(f"{path} is not a variable of entity {entity}")
+    if not targets:
+        return {}
+
+    schema = data.schema_ if isinstance(data, Data) else ir.schema_
+    executor = Executor(prune(ir, targets), config=config)
+    base = executor.execute(Data(tables=tables, schema_=schema))
+    moved = [{**row, with_respect_to: row[with_respect_to] + delta} for row in rows]
+    perturbed = Data(tables={**tables, entity: moved}, schema_=schema)
+    after = executor.execute(perturbed, reuse=base, recompute=changed)
+
+    rates = {}
+    for path in targets:
+        pairs = zip(base.entities[entity][path], after.entities[entity][path])
+        rates[path] = [
+            None if b is None or a is None else (float(a) - float(b)) / delta for b, a in pairs
+        ]
+    return rates
+
+
+class Violation(BaseModel):
+    """An income interval over which the output moves the wrong way."""
+
+    income_from: float
+    income_to: float
+    value_from: float
+    value_to: float
+
+
+def check_monotonic(
+    ir: IR,
+    entity: str,
+    base_row: dict[str, Any],
+    income_path: str,
+    output_path: str,
+    income_min: float,
+    income_max: float,
+    steps: int = 100,
+    direction: str = "decreasing",
+) -> list[Violation]:
+    """Intervals of an income sweep where ``output_path`` breaks ``direction``.
+
+    ``base_row`` is evaluated at ``steps + 1`` evenly spaced incomes from
+    ``income_min`` to ``income_max``. Monotonicity is weak: a flat stretch
+    is fine. Adjacent offending steps are reported as one interval.
+    """
+    if direction not in DIRECTIONS:
+        raise RateError(f"direction must be one of {DIRECTIONS}, got {direction!r}")
+    if steps < 1:
+        raise RateError(f"steps must be at least 1, got {steps}")
+    if income_max <= income_min:
+        raise RateError("income_max must be greater than income_min")
+    width = (income_max - income_min) / steps
+    incomes = [income_min + i * width for i in range(steps)] + [income_max]
+    rows = [{**base_row, "id": i, income_path: x} for i, x in enumerate(incomes)]
+    values = _values(ir, {entity: rows}, entity, output_path)
+
+    sign = -1 if direction == "decreasing" else 1
+    violations: list[Violation] = []
+    start = None
+    for i in range(steps + 1):
+        wrong = i < steps and (values[i + 1] - values[i]) * sign < 0
+        if wrong and start is None:
+            start = i
+        elif not wrong and start is not None:
+            violations.append(
+                Violation(
+                    income_from=incomes[start],
+                    income_to=incomes[i],
+                    value_from=values[start],
+                    value_to=values[i],
+                )
+            )
+            start = None
+    return violations
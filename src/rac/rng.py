@@ -0,0 +1,118 @@
+"""Counter-based random streams and reproducibility manifests.
+
+Every random draw is a pure function of (master seed, feature, key, counter),
+so results do not depend on row order or on how work is split across
+threads. A RandomManifest optionally records each stream a run touched and
+how many draws it consumed, so two runs can be checked stream-for-stream.
+"""
+
+import hashlib
+import struct
+import threading
+from typing import Any
+
+from pydantic import BaseModel, PrivateAttr
+
+from .config import EngineConfig
+from .stats import ColumnStats
+
+
+def stream_id(seed: int, feature: str, key: Any) -> str:
+    """Stable identifier of the stream for (seed, feature, key)."""
+    return hashlib.blake2b(f"{seed}:{feature}:{key!r}".encode(), digest_size=8).hexdigest()
+
+
+def uniform_at(stream: str, counter: int) -> float:
+    """The counter-th uniform [0, 1) draw of a stream."""
+    digest = hashlib.blake2b(f"{stream}:{counter}".encode(), digest_size=8).digest()
+    return struct.unpack("<Q", digest)[0] / 2**64
+
+
+def replication_seeds(seed: int, n: int) -> list[int]:
+    """Master seeds of ``n`` Monte Carlo replications, drawn from ``seed``:
+    replication r gets the same seed however many replications run."""
+    return [int(stream_id(seed, "monte_carlo", r), 16) for r in range(n)]
+
+
+class StreamRecord(BaseModel):
+    feature: str
+    key: str
+    draws: int = 0
+
+
+class RandomManifest(BaseModel):
+    """Streams used by a run, keyed by stream id."""
+
+    seed: int | None = None
+    streams: dict[str, StreamRecord] = {}
+    config: EngineConfig | None = None  # effective engine configuration, if recorded
+    column_stats: dict[str, ColumnStats] = {}  # monitored variables, if collected
+    _lock: threading.Lock = PrivateAttr(default_factory=threading.Lock)
+
+    def record(self, seed: int, feature: str, key: Any, stream: str, draws: int) -> None:
+        with self._lock:
+            if self.seed is None:
+                self.seed = seed
+            entry = self.streams.get(stream)
+            if entry is None:
+                self.streams[stream] = StreamRecord(feature=feature, key=repr(key), draws=draws)
+            else:
+                entry.draws += draws
+
+    def summary(self) -> dict[str, dict[str, int]]:
+        """Per-feature stream and draw counts."""
+        out: dict[str, dict[str, int]] = {}
+        for entry in self.streams.values():
+            counts = out.setdefault(entry.feature, {"streams": 0, "draws": 0})
+            counts["streams"] += 1
+            counts["draws"] += entry.draws
+        return dict(sorted(out.items()))
+
+
+class Stream:
+    """A counter-based random stream for one (feature, key)."""
+
+    def __init__(
+        self, seed: int, feature: str, key: Any, manifest: RandomManifest | None = None
+    ):
+        self.seed = seed
+        self.feature = feature
+        self.key = key
+        self.id = stream_id(seed, feature, key)
+        self.manifest = manifest
+        self.counter = 0
+
+    def uniform(self) -> float:
+        value = uniform_at(self.id, self.counter)
+        self.counter += 1
+        if self.manifest is not None:
+            self.manifest.record(self.seed, self.feature, self.key, self.id, 1)
+        return value
+
+
+def verify_reproducibility(a: RandomManifest, b: RandomManifest) -> list[str]:
+    """Differences between two manifests; empty when they match exactly."""
+    problems = []
+    if a.seed != b.seed:
+        problems.append(f"master seed differs: {a.seed} != {b.seed}")
+    if a.config is not None and b.config is not None and a.config != b.config:
+        changed = [
+            name
+            for name in type(a.config).model_fields
+            if getattr(a.config, name) != getattr(b.config, name)
+        ]
+        problems.append(f"engine config differs: {', '.join(changed)}")
+    for path in sorted(a.column_stats.keys() & b.column_stats.keys()):
+        if a.column_stats[path] != b.column_stats[path]:
+            problems.append(f"column statistics of {path} differ")
+    for sid in sorted(a.streams.keys() - b.streams.keys()):
+        problems.append(f"stream {sid} ({a.streams[sid].feature}) only in first run")
+    for sid in sorted(b.streams.keys() - a.streams.keys()):
+        problems.append(f"stream {sid} ({b.streams[sid].feature}) only in second run")
+    for sid in sorted(a.streams.keys() & b.streams.keys()):
+        if a.streams[sid] != b.streams[sid]:
+            problems.append(
+                f"stream {sid} ({a.streams[sid].feature}) draws differ: "
+                f"{a.streams[sid].draws} != {b.streams[sid].draws}"
+            )
+    return problems
@@ -0,0 +1,148 @@
+"""Reproducible subsampling of input data.
+
+Row sampling draws each row independently (Bernoulli with probability
+``fraction``) and scales its weight by ``1 / fraction`` so weighted totals
+stay unbiased. Entity sampling applies the same draw to a top-level entity
+and carries every linked descendant row along, so households are never
+split from their members. Rows whose weight is invalid under the weight
+policy raise, are kept with zero weight ("clamp"), or are dropped before
+the draw ("skip").
+"""
+
+from .rng import RandomManifest, Stream
+from .schema import Data, Schema
+from .stats import DEFAULT_WEIGHT_POLICY, StatsError, WeightPolicy
+
+
+class SamplingError(Exception):
+    pass
+
+
+def _check_fraction(fraction: float) -> None:
+    if not 0 < fraction <= 1:
+        raise SamplingError(f"fraction must be in (0, 1], got {fraction}")
+
+
+def _reweight(row: dict, weight_column: str | None, fraction: float) -> dict:
+    row = dict(row)
+    if weight_column is not None:
+        if weight_column not in row:
+            raise SamplingError(f"missing weight column: {weight_column}")
+        row[weight_column] = row[weight_column] / fraction
+    return row
+
+
+def sample_rows(
+    rows: list[dict],
+    fraction: float,
+    seed: int = 0,
+    weight_column: str | None = "weight",
+    key: str = "id",
+    manifest: RandomManifest | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> list[dict]:
+    """Sample rows independently, scaling the weight column by 1 / fraction.
+
+    Each row's draw comes from its own stream keyed on the row's ``key``
+    value, so the sample does not depend on row order.
+    """
+    return _sample(
+        rows, fraction, seed, weight_column, key, "sample_rows", manifest, weight_policy
+    )
+
+
+def _sample(
+    rows: list[dict],
+    fraction: float,
+    seed: int,
+    weight_column: str | None,
+    key: str,
+    feature: str,
+    manifest: RandomManifest | None,
+    weight_policy: WeightPolicy | None,
+) -> list[dict]:
+    _check_fraction(fraction)
+    rows = _apply_policy(rows, weight_column, weight_policy)
+    return [
+        _reweight(row, weight_column, fraction)
+        for i, row in enumerate(rows)
+        if Stream(seed, feature, row.get(key, i), manifest).uniform() < fraction
+    ]
+
+
+def _apply_policy(
+    rows: list[dict], weight_column: str | None, policy: WeightPolicy | None
+) -> list[dict]:
+    if weight_column is None:
+        return rows
+    if any(weight_column not in row for row in rows):
+        raise SamplingError(f"missing weight column: {weight_column}")
+    try:
+        weights = (policy or DEFAULT_WEIGHT_POLICY).apply([row[weight_column] for row in rows])
+    except StatsError as e:
+        raise SamplingError(str(e)) from None
+    return [
+        row if w == row[weight_column] else {**row, weight_column: w}
+        for row, w in zip(rows, weights)
+        if w is not None
+    ]
+
+
+def descendants(schema: Schema, entity: str) -> list[tuple[str, str, str]]:
+    """Entities linked below ``entity`` via foreign keys, parents first.
+
+    Returns ``(child, fk_field, parent)`` edges in breadth-first order.
+    """
+    edges = []
+    frontier = [entity]
+    seen = {entity}
+    while frontier:
+        parent = frontier.pop(0)
+        for name, child in schema.entities.items():
+            for fk in child.foreign_keys.values():
+                if fk.target == parent and name not in seen:
+                    edges.append((name, fk.name, parent))
+                    seen.add(name)
+                    frontier.append(name)
+    return edges
+
+
+def sample_entities(
+    data: Data | dict[str, list[dict]],
+    schema: Schema,
+    entity: str,
+    fraction: float,
+    seed: int = 0,
+    weight_column: str | None = "weight",
+    manifest: RandomManifest | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> dict[str, list[dict]]:
+    """Cluster-sample ``entity`` and keep every descendant row of kept groups.
+
+    Only the sampled entity's weight column is adjusted; descendant rows are
+    copied unchanged. Tables not linked to ``entity`` are passed through.
+    """
+    if isinstance(data, Data):
+        data = data.tables
+    if entity not in data:
+        raise SamplingError(f"no data for entity: {entity}")
+
+    result = {name: list(rows) for name, rows in data.items()}
+    result[entity] = _sample(
+        data[entity],
+        fraction,
+        seed,
+        weight_column,
+        "id",
+        "sample_entities",
+        manifest,
+        weight_policy,
+    )
+    kept: dict[str, set] = {entity: {row.get("id") for row in result[entity]}}
+
+    for child, fk_field, parent in descendants(schema, entity):
+        rows = data.get(child, [])
+        result[child] = [row for row in rows if row.get(fk_field) in kept[parent]]
+        kept[child] = {row.get("id") for row in result[child]}
+
+    return result
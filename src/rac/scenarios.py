@@ -0,0 +1,481 @@
+"""Scenario analysis on top of the executor.
+
+Parameter overrides replace a variable's resolved expression with a fixed
+value, so a reform can be expressed without re-parsing or recompiling.
+"""
+
+from collections.abc import Iterator, Sequence
+from concurrent.futures import ThreadPoolExecutor
+from itertools import combinations, islice, product
+from math import factorial
+from typing import Any
+
+from pydantic import BaseModel
+
+from . import ast
+from .compiler import IR, dependents
+from .executor import Executor, Result, run
+from .schema import Data
+from .stats import DEFAULT_WEIGHT_POLICY, WeightPolicy, _missing, compensated_sum
+
+
+class ScenarioError(Exception):
+    pass
+
+
+def apply_overrides(ir: IR, overrides: dict[str, Any]) -> IR:
+    """Return a copy of ir with the given variables fixed to values."""
+    variables = dict(ir.variables)
+    for path, value in overrides.items():
+        if path not in variables:
+            raise ScenarioError(f"unknown variable: {path}")
+        variables[path] = variables[path].model_copy(
+            update={"expr": ast.Literal(value=value), "deps": set()}
+        )
+    return ir.model_copy(update={"variables": variables})
+
+
+def total(
+    result: Result,
+    data: Data | dict[str, list[dict]],
+    output: str,
+    weight_path: str | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> float:
+    """A scalar output's value, or an entity output's (weighted) sum.
+
+    Rows whose output is missing (None) are left out of the sum; a missing
+    scalar raises ScenarioError.
+    """
+    if output in result.scalars:
+        if result.scalars[output] is None:
+            raise ScenarioError(f"{output} is missing")
+        return float(result.scalars[output])
+    for entity, columns in result.entities.items():
+        if output in columns:
+            values = columns[output]
+            if weight_path is None:
+                return float(compensated_sum(v for v in values if v is not None))
+            tables = data.tables if isinstance(data, Data) else data
+            weights = (weight_policy or DEFAULT_WEIGHT_POLICY).apply(
+                [row[weight_path] for row in tables[entity]]
+            )
+            return float(
+                compensated_sum(
+                    v * w for v, w in zip(values, weights) if v is not None and w is not None
+                )
+            )
+    raise ScenarioError(f"unknown output: {output}")
+
+
+class Decomposition(BaseModel):
+    """Attribution of an output change to individual parameter overrides."""
+
+    baseline: float
+    reform: float
+    contributions: dict[str, float]  # parameter path -> contribution
+
+    @property
+    def change(self) -> float:
+        return self.reform - self.baseline
+
+
+def decompose(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    steps: list[tuple[str, Any]],
+    output: str,
+    weight_path: str | None = None,
+    method: str = "sequential",
+    weight_policy: WeightPolicy | None = None,
+) -> Decomposition:
+    """Attribute the change in ``output`` to each (parameter, value) override.
+
+    ``sequential`` applies the overrides cumulatively in the given order and
+    credits each with the change it causes, so results depend on the order.
+    ``shapley`` averages the marginal contribution over all orderings
+    (2^k runs for k parameters). Both sum exactly to the total change.
+    """
+    paths = [path for path, _ in steps]
+    if len(set(paths)) != len(paths):
+        raise ScenarioError("each parameter may appear in only one step")
+    values = dict(steps)
+    cache: dict[frozenset[str], float] = {}
+
+    def outcome(subset: frozenset[str]) -> float:
+        if subset not in cache:
+            reform = apply_overrides(ir, {p: values[p] for p in subset})
+            result = run(reform, data)
+            cache[subset] = total(result, data, output, weight_path, weight_policy)
+        return cache[subset]
+
+    contributions: dict[str, float] = {}
+    if method == "sequential":
+        applied: frozenset[str] = frozenset()
+        for path in paths:
+            after = applied | {path}
+            contributions[path] = outcome(after) - outcome(applied)
+            applied = after
+    elif method == "shapley":
+        k = len(paths)
+        for path in paths:
+            others = [p for p in paths if p != path]
+            share = 0.0
+            for size in range(k):
+                weight = factorial(size) * factorial(k - size - 1) / factorial(k)
+                for subset in combinations(others, size):
+                    s = frozenset(subset)
+                    share += weight * (outcome(s | {path}) - outcome(s))
+            contributions[path] = share
+    else:
+        raise ScenarioError(f"unknown method: {method}")
+
+    return Decomposition(
+        baseline=outcome(frozenset()),
+        reform=outcome(frozenset(paths)),
+        contributions=contributions,
+    )
+
+
+class Outcome(BaseModel):
+    """Weighted count of rows in one category and the sum of their changes."""
+
+    count: float = 0.0
+    total: float = 0.0
+
+
+class WinnersLosers(BaseModel):
+    """Rows better off, worse off and unchanged between two scenarios."""
+
+    gain: Outcome
+    loss: Outcome
+    no_change: Outcome
+
+
+def classify_changes(
+    changes: list[float | None],
+    weights: list[float | None] | None = None,
+    tolerance: float = 1.0,
+) -> WinnersLosers:
+    """Split per-row changes into gain, loss and no change.
+
+    A change within ``tolerance`` of zero counts as no change. Counts and
+    totals are weighted; rows with a None weight (see WeightPolicy) or a
+    missing change (None or NaN) are left out of every category.
+    """
+    if tolerance < 0:
+        raise ScenarioError(f"tolerance must be non-negative, got {tolerance}")
+    if weights is None:
+        weights = [1.0] * len(changes)
+    elif len(weights) != len(changes):
+        raise ScenarioError(f"{len(weights)} weights for {len(changes)} rows")
+    rows: dict[str, list[tuple[float, float]]] = {"gain": [], "loss": [], "no_change": []}
+    for change, weight in zip(changes, weights):
+        if weight is None or _missing(change):
+            continue
+        key = "gain" if change > tolerance else "loss" if change < -tolerance else "no_change"
+        rows[key].append((change, weight))
+    return WinnersLosers(
+        **{
+            key: Outcome(
+                count=float(compensated_sum(w for _, w in members)),
+                total=float(compensated_sum(c * w for c, w in members)),
+            )
+            for key, members in rows.items()
+        }
+    )
+
+
+def winners_losers(
+    baseline: Result,
+    reform: Result,
+    data: Data | dict[str, list[dict]],
+    output: str,
+    weight_path: str | None = None,
+    tolerance: float = 1.0,
+    weight_policy: WeightPolicy | None = None,
+) -> WinnersLosers:
+    """Classify each row's change in an entity output between two runs;
+    a row missing the output in either run has no change to classify."""
+    for entity, columns in baseline.entities.items():
+        if output in columns:
+            break
+    else:
+        raise ScenarioError(f"unknown entity output: {output}")
+    changes = [
+        None if b is None or r is None else r - b
+        for b, r in zip(columns[output], reform.entities[entity][output])
+    ]
+    weights = None
+    if weight_path is not None:
+        tables = data.tables if isinstance(data, Data) else data
+        weights = (weight_policy or DEFAULT_WEIGHT_POLICY).apply(
+            [row[weight_path] for row in tables[entity]]
+        )
+    return classify_changes(changes, weights, tolerance)
+
+
+def sweep(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    param_path: str,
+    param_values: list[Any],
+    output: str,
+    weight_path: str | None = None,
+    weight_policy: WeightPolicy | None = None,
+    max_workers: int | None = None,
+) -> list[float]:
+    """Total of ``output`` with ``param_path`` set to each of ``param_values``.
+
+    The input tables are materialized once and shared by every run. Like
+    ``run``, tables given as a dict are indexed by the model's schema, so
+    aggregates such as ``sum_over`` find each member's parent row. Runs
+    for different values go to a pool of ``max_workers`` threads; evaluation
+    is pure Python and holds the GIL, so the threads overlap only I/O and
+    waits in registered functions, not computation. Results are in
+    ``param_values`` order.
+    """
+    if param_path not in ir.variables:
+        raise ScenarioError(f"unknown variable: {param_path}")
+    if isinstance(data, dict):
+        data = Data(tables=data, schema_=ir.schema_)
+
+    def outcome(value: Any) -> float:
+        reform = apply_overrides(ir, {param_path: value})
+        return total(run(reform, data), data, output, weight_path, weight_policy)
+
+    with ThreadPoolExecutor(max_workers=max_workers) as executor:
+        return list(executor.map(outcome, param_values))
+
+
+def cross_join(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    scenarios: dict[str, dict[str, Any]] | list[dict[str, Any]],
+    entity: str,
+    layout: str = "flat",
+    max_workers: int | None = None,
+) -> list[dict[str, Any]] | dict[str, dict[Any, dict[str, Any]]]:
+    """Evaluate every (``entity`` row, scenario) combination.
+
+    ``scenarios`` maps a label to a set of overrides (a list is labelled
+    "0", "1", ...). With ``layout="flat"`` the result is one row per
+    combination, ``{"scenario": label, "id": row_id, output: value, ...}``,
+    scenario-major; with ``layout="nested"`` it is
+    ``{label: {row_id: {output: value}}}``. Scenarios share inputs
+    materialized once, indexed by the model's schema as in ``run`` so that
+    aggregates over linked entities resolve, and go to a pool of
+    ``max_workers`` threads, which overlap only I/O and waits in registered
+    functions: evaluation holds the GIL.
+    """
+    if layout not in ("flat", "nested"):
+        raise ScenarioError(f"unknown layout: {layout}")
+    if isinstance(scenarios, list):
+        scenarios = {str(i): overrides for i, overrides in enumerate(scenarios)}
+    if isinstance(data, dict):
+        data = Data(tables=data, schema_=ir.schema_)
+    ids = [row.get("id", i) for i, row in enumerate(data.get_rows(entity))]
+    reforms = {label: apply_overrides(ir, overrides) for label, overrides in scenarios.items()}
+
+    def outcome(reform: IR) -> dict[Any, dict[str, Any]]:
+        columns = run(reform, data).entities.get(entity, {})
+        return {
+            row_id: {name: values[i] for name, values in columns.items()}
+            for i, row_id in enumerate(ids)
+        }
+
+    with ThreadPoolExecutor(max_workers=max_workers) as executor:
+        results = dict(zip(reforms, executor.map(outcome, reforms.values())))
+
+    if layout == "nested":
+        return results
+    return [
+        {"scenario": label, "id": row_id, **values}
+        for label, by_id in results.items()
+        for row_id, values in by_id.items()
+    ]
+
+
+class Axis(BaseModel):
+    """Values to substitute for one input field in expand_axes()."""
+
+    field: str
+    values: list[Any]
+
+
+AXIS_MODES = ("cross", "zip")
+
+
+def expand_axes(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    entity: str,
+    axes: list[Axis],
+    mode: str = "cross",
+    chunk_size: int = 10_000,
+) -> Iterator[dict[str, Any]]:
+    """Evaluate every ``entity`` row at each point of a grid over input fields.
+
+    ``mode="cross"`` takes the product of the axes (a 3 x 4 grid gives 12
+    points per row); ``mode="zip"`` pairs them up, and needs equal lengths.
+    Yields one dict per (row, point), grid-major within each row:
+    ``{"id": row_id, field: value, f"{field}_index": i, ..., output: value}``.
+    Points are evaluated ``chunk_size`` virtual rows at a time, so memory is
+    bounded however large the grid. The other tables go along, so formulas
+    can read the rows' parents, except those of entities linking to
+    ``entity``: their links would point past the virtual rows.
+    """
+    if mode not in AXIS_MODES:
+        raise ScenarioError(f"unknown axis mode: {mode}")
+    if not axes:
+        raise ScenarioError("expand_axes needs at least one axis")
+    if mode == "zip" and len({len(axis.values) for axis in axes}) > 1:
+        raise ScenarioError("zipped axes must have the same number of values")
+    if isinstance(data, Data):
+        data = data.tables
+    entities = ir.schema_.entities
+    others = {
+        name: rows
+        for name, rows in data.items()
+        if name != entity
+        and not any(
+            fk.target == entity
+            for fk in (entities[name].foreign_keys.values() if name in entities else ())
+        )
+    }
+    indices = [range(len(axis.values)) for axis in axes]
+    points = list(product(*indices) if mode == "cross" else zip(*indices))
+
+    def virtual_rows() -> Iterator[tuple[Any, tuple[int, ...], dict]]:
+        for i, row in enumerate(data.get(entity, [])):
+            for point in points:
+                values = {axis.field: axis.values[j] for axis, j in zip(axes, point)}
+                yield row.get("id", i), point, {**row, **values}
+
+    rows = virtual_rows()
+    while chunk := list(islice(rows, chunk_size)):
+        table = [{**row, "id": n} for n, (_, _, row) in enumerate(chunk)]
+        columns = run(ir, {**others, entity: table}).entities.get(entity, {})
+        for n, (row_id, point, row) in enumerate(chunk):
+            tagged: dict[str, Any] = {"id": row_id}
+            for axis, j in zip(axes, point):
+                tagged[axis.field] = row[axis.field]
+                tagged[f"{axis.field}_index"] = j
+            yield tagged | {path: values[n] for path, values in columns.items()}
+
+
+AGGREGATES = ("sum", "mean", "min", "max", "count")
+
+
+class ScenarioComparison(BaseModel):
+    """Long-format results of several scenarios, with deltas to a baseline."""
+
+    baseline: str
+    records: list[dict[str, Any]]  # {"scenario", "variable", "aggregate", "value"}
+    deltas: list[dict[str, Any]]  # {"scenario", "variable", "aggregate", "delta"}
+    evaluated: dict[str, list[str]]  # variables each scenario evaluated (the rest were shared)
+
+    def to_arrow(self) -> Any:
+        """The records as a pyarrow Table."""
+        from .arrow import _pyarrow
+
+        return _pyarrow().Table.from_pylist(self.records)
+
+
+def _aggregate(values: list[Any], weights: list[float | None], how: str) -> float:
+    pairs = [(v, w) for v, w in zip(values, weights) if w is not None]
+    if how == "count":
+        return float(compensated_sum(w for _, w in pairs))
+    if how == "sum":
+        return float(compensated_sum(v * w for v, w in pairs))
+    if how == "mean":
+        weight = compensated_sum(w for _, w in pairs)
+        return float(compensated_sum(v * w for v, w in pairs) / weight) if weight else float("nan")
+    present = [v for v, w in pairs if w > 0]
+    if not present:
+        return float("nan")
+    return float(min(present) if how == "min" else max(present))
+
+
+def compare_scenarios(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    weight_col: str | None,
+    scenarios: dict[str, dict[str, Any]],
+    outputs: list[str],
+    aggregates: Sequence[str] = ("sum",),
+    baseline: str | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> ScenarioComparison:
+    """Aggregate ``outputs`` under each named set of overrides.
+
+    The IR is compiled once, and each scenario re-evaluates only the
+    variables its overrides reach; everything else is shared with an
+    unmodified run. Entity outputs are aggregated with ``weight_col`` (sum,
+    mean, min, max or weighted count); scalar outputs report their value
+    under the aggregate "value". Deltas are against ``baseline`` (by default
+    the first scenario). Scenario names are kept verbatim.
+    """
+    if not scenarios:
+        raise ScenarioError("compare_scenarios needs at least one scenario")
+    for how in aggregates:
+        if how not in AGGREGATES:
+            raise ScenarioError(f"unknown aggregate: {how}")
+    baseline = next(iter(scenarios)) if baseline is None else baseline
+    if baseline not in scenarios:
+        raise ScenarioError(f"unknown baseline scenario: {baseline}")
+    if isinstance(data, dict):
+        data = Data(tables=data, schema_=ir.schema_)
+
+    shared = Executor(ir).execute(data)
+    results: dict[str, Result] = {}
+    evaluated: dict[str, list[str]] = {}
+    for name, overrides in scenarios.items():
+        recompute = dependents(ir, list(overrides))
+        result = Executor(apply_overrides(ir, overrides)).execute(data, shared, recompute)
+        results[name] = result
+        evaluated[name] = [path for path in result.order if path not in result.reused]
+
+    policy = weight_policy or DEFAULT_WEIGHT_POLICY
+    weights: dict[str, list[float | None]] = {}
+    records = []
+    for name, result in results.items():
+        for output in outputs:
+            if output in result.scalars:
+                value = result.scalars[output]
+                records.append(
+                    {"scenario": name, "variable": output, "aggregate": "value", "value": value}
+                )
+                continue
+            entity = next((e for e, cols in result.entities.items() if output in cols), None)
+            if entity is None:
+                raise ScenarioError(f"unknown output: {output}")
+            if entity not in weights:
+                rows = data.get_rows(entity)
+                weights[entity] = (
+                    policy.apply([row[weight_col] for row in rows])
+                    if weight_col is not None
+                    else [1.0] * len(rows)
+                )
+            for how in aggregates:
+                value = _aggregate(result.entities[entity][output], weights[entity], how)
+                records.append(
+                    {"scenario": name, "variable": output, "aggregate": how, "value": value}
+                )
+
+    base = {
+        (r["variable"], r["aggregate"]): r["value"] for r in records if r["scenario"] == baseline
+    }
+    deltas = [
+        {
+            "scenario": r["scenario"],
+            "variable": r["variable"],
+            "aggregate": r["aggregate"],
+            "delta": r["value"] - base[(r["variable"], r["aggregate"])],
+        }
+        for r in records
+        if r["scenario"] != baseline
+    ]
+    return ScenarioComparison(
+        baseline=baseline, records=records, deltas=deltas, evaluated=evaluated
+    )
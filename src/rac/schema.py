@@ -12,7 +12,7 @@ class Field(BaseModel):
     """A field on an entity."""
 
     name: str
-    dtype: str  # int, float, str, bool, date
+    dtype: str  # int, float, str, category, bool, date
     nullable: bool = False
     default: Any = None
 
@@ -51,6 +51,11 @@ class Schema(BaseModel):
     def add_entity(self, entity: Entity) -> None:
         self.entities[entity.name] = entity
 
+    def links(self, source: str, target: str) -> list[ForeignKey]:
+        """Foreign keys by which ``source`` rows point at ``target`` rows."""
+        entity = self.entities.get(source)
+        return [fk for fk in entity.foreign_keys.values() if fk.target == target] if entity else []
+
     def infer_reverse_relations(self) -> None:
         """Auto-generate reverse relations from foreign keys."""
         for entity_name, entity in self.entities.items():
@@ -66,24 +71,127 @@ class Schema(BaseModel):
                         )
 
 
+class LinkageError(Exception):
+    pass
+
+
+DEDUPE_POLICIES = ("error", "first")
+
+MAX_EXAMPLES = 3
+
+
+def _examples(items: list[Any]) -> str:
+    shown = ", ".join(repr(item) for item in items[:MAX_EXAMPLES])
+    more = len(items) - MAX_EXAMPLES
+    return shown + (f" (and {more} more)" if more > 0 else "")
+
+
 class Data(BaseModel):
-    """Input data: entity tables with rows."""
+    """Input data: entity tables with rows.
+
+    Given a ``schema``, the index pass also validates linkage: a primary key
+    may appear only once per entity (``dedupe="first"`` drops later rows that
+    exactly repeat an earlier one), and every non-null foreign key must match
+    exactly one parent row. Violations raise LinkageError with examples.
+    """
 
     tables: dict[str, list[dict[str, Any]]]
+    schema_: Schema | None = None
+    dedupe: str = "error"
     _index: dict[str, dict[Any, dict]] = {}
+    _groups: dict[tuple[str, str], dict[Any, list[dict]]] = {}
 
     @model_validator(mode="after")
     def build_index(self) -> "Data":
-        """Build primary key index for fast lookups."""
+        """Build primary key index (and, with a schema, FK group index)."""
+        if self.dedupe not in DEDUPE_POLICIES:
+            raise ValueError(f"dedupe must be one of {DEDUPE_POLICIES}, got {self.dedupe!r}")
         object.__setattr__(self, "_index", {})
+        object.__setattr__(self, "_groups", {})
+        if self.schema_ is None:
+            for entity_name, rows in self.tables.items():
+                self._index[entity_name] = {}
+                for row in rows:
+                    pk = row.get("id")
+                    if pk is not None:
+                        self._index[entity_name][pk] = row
+            return self
+
         for entity_name, rows in self.tables.items():
-            self._index[entity_name] = {}
-            for row in rows:
-                pk = row.get("id")
-                if pk is not None:
-                    self._index[entity_name][pk] = row
+            self.tables[entity_name] = self._index_entity(entity_name, rows)
+        self._check_parents()
         return self
 
+    def _index_entity(self, entity_name: str, rows: list[dict]) -> list[dict]:
+        entity = self.schema_.entities.get(entity_name)
+        key = entity.primary_key if entity else "id"
+        fks = list(entity.foreign_keys) if entity else []
+        index: dict[Any, dict] = {}
+        groups: dict[str, dict[Any, list[dict]]] = {fk: {} for fk in fks}
+        kept = []
+        conflicts = []
+        relinked = []
+        for row in rows:
+            pk = row.get(key)
+            if pk is not None and pk in index:
+                first = index[pk]
+                if row == first and self.dedupe == "first":
+                    continue
+                if any(row.get(fk) != first.get(fk) for fk in fks):
+                    relinked.append(pk)
+                else:
+                    conflicts.append(pk)
+                continue
+            if pk is not None:
+                index[pk] = row
+            for fk in fks:
+                value = row.get(fk)
+                if value is not None:
+                    groups[fk].setdefault(value, []).append(row)
+            kept.append(row)
+
+        if relinked:
+            raise LinkageError(
+                f"{entity_name} rows linked to multiple parents: "
+                f"{_examples(list(dict.fromkeys(relinked)))}"
+            )
+        if conflicts:
+            raise LinkageError(
+                f"duplicate {key} in {entity_name}: {_examples(list(dict.fromkeys(conflicts)))}"
+            )
+        self._index[entity_name] = index
+        for fk, by_value in groups.items():
+            self._groups[(entity_name, fk)] = by_value
+        return kept
+
+    def _check_parents(self) -> None:
+        """Every link matches exactly one parent row. Links to an entity with
+        no table here are left unchecked, so one entity's rows can run alone."""
+        for entity_name, entity in self.schema_.entities.items():
+            for fk in entity.foreign_keys.values():
+                if fk.target not in self.tables:
+                    continue
+                by_value = self._groups.get((entity_name, fk.name), {})
+                target = self.schema_.entities.get(fk.target)
+                if target is not None and fk.target_field == target.primary_key:
+                    counts = dict.fromkeys(self._index.get(fk.target, {}), 1)
+                else:
+                    counts = {}
+                    for row in self.tables.get(fk.target, []):
+                        value = row.get(fk.target_field)
+                        counts[value] = counts.get(value, 0) + 1
+                orphans = [value for value in by_value if value not in counts]
+                if orphans:
+                    raise LinkageError(
+                        f"{entity_name}.{fk.name} matches no {fk.target}: {_examples(orphans)}"
+                    )
+                ambiguous = [value for value in by_value if counts[value] > 1]
+                if ambiguous:
+                    raise LinkageError(
+                        f"{entity_name}.{fk.name} matches multiple {fk.target} rows: "
+                        f"{_examples(ambiguous)}"
+                    )
+
     def get_row(self, entity: str, pk: Any) -> dict | None:
         return self._index.get(entity, {}).get(pk)
 
@@ -91,4 +199,7 @@ class Data(BaseModel):
         return self.tables.get(entity, [])
 
     def get_related(self, entity: str, fk_field: str, fk_value: Any) -> list[dict]:
+        groups = self._groups.get((entity, fk_field))
+        if groups is not None:
+            return groups.get(fk_value, [])
         return [row for row in self.tables.get(entity, []) if row.get(fk_field) == fk_value]
@@ -0,0 +1,466 @@
+"""Weighted statistics over execution results.
+
+Aggregates take either columnar data (``{path: [values]}``, e.g. an entry of
+``Result.entities``) or row data (``[{path: value}]``, e.g. an input table).
+
+Totals are compensated sums (see compensated_sum) added in row order, so
+summing millions of small amounts doesn't drift from the exact total and
+the same rows always give the same result.
+
+Weighted routines take a WeightPolicy deciding what to do with negative and
+zero weights. Totals and frequency tables are well-defined under negative
+weights (replicate weights rely on this); Gini and quantiles are not, and
+raise if a negative weight survives the policy.
+"""
+
+import hashlib
+import math
+from collections.abc import Iterable, Iterator, Mapping, Sequence
+from numbers import Real
+from typing import Any, Literal
+
+from pydantic import BaseModel
+
+
+class StatsError(Exception):
+    pass
+
+
+class WeightPolicy(BaseModel):
+    """Which weights are valid, and what to do with the rest.
+
+    ``on_invalid`` is "error" (raise), "clamp" (treat as zero weight) or
+    "skip" (drop the row). Zero weights never contribute to a statistic, but
+    a clamped row still counts as observed, e.g. as a crosstab category.
+    """
+
+    allow_negative: bool = False
+    allow_zero: bool = True
+    on_invalid: Literal["error", "clamp", "skip"] = "error"
+
+    def is_valid(self, weight: float) -> bool:
+        if weight < 0:
+            return self.allow_negative
+        if weight == 0:
+            return self.allow_zero
+        return True
+
+    def apply(self, weights: Sequence[float]) -> list[float | None]:
+        """Weights under the policy; None marks a skipped row."""
+        out: list[float | None] = []
+        for i, w in enumerate(weights):
+            if self.is_valid(w):
+                out.append(w)
+            elif self.on_invalid == "error":
+                kind = "negative" if w < 0 else "zero"
+                raise StatsError(f"{kind} weights not allowed by weight policy (row {i}: {w})")
+            elif self.on_invalid == "clamp":
+                out.append(0.0)
+            else:
+                out.append(None)
+        return out
+
+
+DEFAULT_WEIGHT_POLICY = WeightPolicy()
+
+
+def compensated_sum(values: Iterable[Any]) -> Any:
+    """The sum of ``values``, added one at a time in the order given.
+
+    Float additions carry Neumaier's compensation (Kahan's, also right when
+    an addend outweighs the running total), so rounding error stays near
+    one ulp of the total rather than growing with the count. Ints,
+    Fractions and fixed-point values add exactly and come back as sum()
+    gives them; NaN and infinities propagate as in a plain sum.
+
+    Neumaier rather than ``math.fsum`` or pairwise summation: it is one
+    pass in a fixed order, so the generated Python, JavaScript and Rust
+    (``rac_sum``) repeat the same loop and return the same bits, where
+    fsum's exact partials have no cheap equivalent there and pairwise
+    results depend on how the values are split.
+    """
+    total: Any = 0
+    compensation = 0.0
+    for value in values:
+        step = total + value
+        if isinstance(step, float) and math.isfinite(step):
+            if abs(total) >= abs(value):
+                compensation += (total - step) + value
+            else:
+                compensation += (value - step) + total
+        total = step
+    return total + compensation if compensation else total
+
+
+def _running_sums(values: Iterable[float]) -> Iterator[float]:
+    """compensated_sum of each prefix of ``values``, in one pass."""
+    total = 0.0
+    compensation = 0.0
+    for value in values:
+        step = total + value
+        if math.isfinite(step):
+            if abs(total) >= abs(value):
+                compensation += (total - step) + value
+            else:
+                compensation += (value - step) + total
+        total = step
+        yield total + compensation
+
+
+Columns = Mapping[str, Sequence[Any]] | Sequence[Mapping[str, Any]]
+
+
+def raw_column(data: Columns, path: str) -> list[Any]:
+    """Extract one column unconverted from columnar or row data."""
+    if isinstance(data, Mapping):
+        if path not in data:
+            raise StatsError(f"unknown column: {path}")
+        return list(data[path])
+    try:
+        return [row[path] for row in data]
+    except KeyError:
+        raise StatsError(f"unknown column: {path}") from None
+
+
+def column(data: Columns, path: str) -> list[float]:
+    """Extract one column as floats from columnar or row data."""
+    return [float(v) for v in raw_column(data, path)]
+
+
+# A group key column, or several forming a composite key
+GroupPath = str | Sequence[str]
+
+
+def group_keys(data: Columns, group_path: GroupPath) -> list[Any]:
+    """Each row's group: the key column's value, or for several key columns
+    the tuple of their values, so rows group together only when all match."""
+    if isinstance(group_path, str):
+        return raw_column(data, group_path)
+    if not group_path:
+        raise StatsError("a group key needs at least one column")
+    return list(zip(*(raw_column(data, path) for path in group_path)))
+
+
+def _weights(
+    data: Columns,
+    weight_path: str | None,
+    n: int,
+    policy: WeightPolicy | None = None,
+    undefined_for_negative: str | None = None,
+) -> list[float | None]:
+    """Row weights under ``policy``; None marks a skipped row.
+
+    ``undefined_for_negative`` names a statistic that cannot take negative
+    weights, which then raise even when the policy allows them.
+    """
+    if weight_path is None:
+        return [1.0] * n
+    weights = column(data, weight_path)
+    if len(weights) != n:
+        raise StatsError(f"weight column {weight_path} has {len(weights)} rows, expected {n}")
+    applied = (policy or DEFAULT_WEIGHT_POLICY).apply(weights)
+    if undefined_for_negative and any(w is not None and w < 0 for w in applied):
+        raise StatsError(f"{undefined_for_negative} undefined for negative weights")
+    return applied
+
+
+def _missing(value: Any) -> bool:
+    """None or NaN: no value to measure."""
+    return value is None or (isinstance(value, float) and math.isnan(value))
+
+
+def _observed(values: list[Any], weights: list[float | None]) -> list[tuple[float, float]]:
+    """(value, weight) of each row with a value and a positive weight;
+    missing (None or NaN) values are left out, as skipped rows are."""
+    return [
+        (float(v), w)
+        for v, w in zip(values, weights)
+        if not _missing(v) and w is not None and w > 0
+    ]
+
+
+def gini(
+    data: Columns,
+    value_path: str,
+    weight_path: str | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> float:
+    """Weighted Gini coefficient in [0, 1].
+
+    Uses the sorted-cumulative (Lorenz area) formula. Negative values and
+    negative weights are rejected (whatever the weight policy); rows with a
+    missing value (None or NaN) are left out. An empty population, zero total weight or
+    zero total value yield 0.0.
+    """
+    values = raw_column(data, value_path)
+    if any(v is not None and v < 0 for v in values):
+        raise StatsError(f"gini undefined for negative values in {value_path}")
+    weights = _weights(data, weight_path, len(values), weight_policy, "gini")
+    pairs = sorted(_observed(values, weights))
+    total_weight = compensated_sum(w for _, w in pairs)
+    total_value = compensated_sum(v * w for v, w in pairs)
+    if total_weight == 0 or total_value == 0:
+        return 0.0
+
+    # Area under the Lorenz curve via trapezoids over cumulative value share
+    cum_values = list(_running_sums(v * w for v, w in pairs))
+    area = compensated_sum(
+        w * (prev + cum) for (_, w), prev, cum in zip(pairs, [0.0] + cum_values, cum_values)
+    )
+    g = 1.0 - area / (total_weight * total_value)
+    return min(1.0, max(0.0, g))
+
+
+def _tie_rank(member: Any, seed: int | None) -> tuple:
+    """Order among tied members: lowest id, or a seeded hash of the id."""
+    if seed is None:
+        return (member,)
+    digest = hashlib.blake2b(f"{seed}:{member!r}".encode(), digest_size=8).digest()
+    return (digest, member)
+
+
+def _group_arg(
+    data: Columns,
+    group_path: GroupPath,
+    value_path: str,
+    id_path: str,
+    seed: int | None,
+    sign: float,
+) -> dict[Any, Any]:
+    groups = group_keys(data, group_path)
+    values = column(data, value_path)
+    ids = raw_column(data, id_path)
+    best: dict[Any, tuple[float, tuple, Any]] = {}
+    for group, value, member in zip(groups, values, ids):
+        key = (-sign * value, _tie_rank(member, seed))
+        if group not in best or key < best[group][:2]:
+            best[group] = (*key, member)
+    return {group: entry[2] for group, entry in best.items()}
+
+
+def group_argmax(
+    data: Columns,
+    group_path: GroupPath,
+    value_path: str,
+    id_path: str = "id",
+    seed: int | None = None,
+) -> dict[Any, Any]:
+    """Member id with the largest value in each group.
+
+    With several ``group_path`` columns the groups are keyed by tuples.
+    Ties go to the lowest id, or with ``seed`` to an arbitrary but
+    reproducible member chosen by a seeded hash of the member ids.
+    """
+    return _group_arg(data, group_path, value_path, id_path, seed, 1.0)
+
+
+def group_argmin(
+    data: Columns,
+    group_path: GroupPath,
+    value_path: str,
+    id_path: str = "id",
+    seed: int | None = None,
+) -> dict[Any, Any]:
+    """Member id with the smallest value in each group (ties as group_argmax)."""
+    return _group_arg(data, group_path, value_path, id_path, seed, -1.0)
+
+
+def group_sum(
+    data: Columns,
+    group_path: GroupPath,
+    value_path: str,
+    where_path: str | None = None,
+) -> list[float]:
+    """Each member's group total of ``value_path``, broadcast back to the members.
+
+    With ``where_path`` only members where that column is true contribute,
+    though every member still receives its group's total.
+    """
+    groups = group_keys(data, group_path)
+    values = column(data, value_path)
+    where = raw_column(data, where_path) if where_path else [True] * len(values)
+    members: dict[Any, list[float]] = {}
+    for group, value, keep in zip(groups, values, where):
+        members.setdefault(group, []).append(value if keep else 0.0)
+    totals = {group: float(compensated_sum(values)) for group, values in members.items()}
+    return [totals[group] for group in groups]
+
+
+def _categories(values: list[Any], declared: Sequence[Any] | None) -> list[Any]:
+    """Declared categories first, then unseen ones in encounter order."""
+    order = list(declared or [])
+    seen = set(order)
+    for v in values:
+        if v not in seen:
+            seen.add(v)
+            order.append(v)
+    return order
+
+
+def crosstab(
+    data: Columns,
+    rows_var: str,
+    cols_var: str | None = None,
+    weight_path: str | None = None,
+    normalize: str | None = None,
+    categories: Mapping[str, Sequence[Any]] | None = None,
+    weight_policy: WeightPolicy | None = None,
+) -> dict[Any, Any]:
+    """Weighted one- or two-way frequency table.
+
+    One-way tables map category -> weight; two-way tables map row category ->
+    {column category -> weight}. Missing values (None) form their own
+    category. Categories are ordered by ``categories`` (declared order) then
+    by first appearance, and declared or observed categories with zero
+    weight are kept. ``normalize`` is None, "row", "column" or "total";
+    shares of an all-zero row/column/table are 0.0. Rows skipped by the
+    weight policy are left out entirely.
+    """
+    categories = categories or {}
+    row_vals = raw_column(data, rows_var)
+    weights = _weights(data, weight_path, len(row_vals), weight_policy)
+    kept = [i for i, w in enumerate(weights) if w is not None]
+    row_vals = [row_vals[i] for i in kept]
+    weights = [weights[i] for i in kept]
+    row_cats = _categories(row_vals, categories.get(rows_var))
+
+    if cols_var is None:
+        if normalize not in (None, "total"):
+            raise StatsError(f"invalid normalize for one-way table: {normalize}")
+        cells: dict[Any, list[float]] = {r: [] for r in row_cats}
+        for r, w in zip(row_vals, weights):
+            cells[r].append(w)
+        one_way = {r: float(compensated_sum(ws)) for r, ws in cells.items()}
+        if normalize == "total":
+            grand = compensated_sum(one_way.values())
+            one_way = {k: v / grand if grand else 0.0 for k, v in one_way.items()}
+        return one_way
+
+    all_col_vals = raw_column(data, cols_var)
+    col_vals = [all_col_vals[i] for i in kept]
+    col_cats = _categories(col_vals, categories.get(cols_var))
+    weights_in: dict[Any, dict[Any, list[float]]] = {r: {c: [] for c in col_cats} for r in row_cats}
+    for r, c, w in zip(row_vals, col_vals, weights):
+        weights_in[r][c].append(w)
+    table = {
+        r: {c: float(compensated_sum(ws)) for c, ws in cells.items()}
+        for r, cells in weights_in.items()
+    }
+
+    if normalize is None:
+        return table
+    if normalize == "row":
+        for cells in table.values():
+            row_total = compensated_sum(cells.values())
+            for c in cells:
+                cells[c] = cells[c] / row_total if row_total else 0.0
+    elif normalize == "column":
+        col_totals = {c: compensated_sum(table[r][c] for r in row_cats) for c in col_cats}
+        for cells in table.values():
+            for c in cells:
+                cells[c] = cells[c] / col_totals[c] if col_totals[c] else 0.0
+    elif normalize == "total":
+        grand = compensated_sum(w for cells in table.values() for w in cells.values())
+        for cells in table.values():
+            for c in cells:
+                cells[c] = cells[c] / grand if grand else 0.0
+    else:
+        raise StatsError(f"invalid normalize: {normalize}")
+    return table
+
+
+class Allocation(BaseModel):
+    """Result of a capped per-group allocation."""
+
+    granted: list[float]  # per member, in input row order
+    residual: dict[Any, float]  # unused cap per group
+
+
+def capped_allocate(
+    data: Columns, group_path: GroupPath, demand_path: str, cap_path: str
+) -> Allocation:
+    """Grant each group up to its cap, split among members by demand.
+
+    A group whose total demand fits under the cap is granted in full; above
+    the cap every member is scaled down by the same factor. The residual is
+    the unused part of each group's cap. All rows of a group must carry the
+    same cap; negative demands or caps are rejected. Several ``group_path``
+    columns form a composite key.
+    """
+    groups = group_keys(data, group_path)
+    demands = column(data, demand_path)
+    caps = column(data, cap_path)
+
+    group_cap: dict[Any, float] = {}
+    group_demand: dict[Any, float] = {}
+    for group, demand, cap in zip(groups, demands, caps):
+        if demand < 0 or cap < 0:
+            raise StatsError(f"negative demand or cap in group {group!r}")
+        if group_cap.setdefault(group, cap) != cap:
+            raise StatsError(f"inconsistent cap in group {group!r}")
+        group_demand[group] = group_demand.get(group, 0.0) + demand
+
+    scale = {
+        group: min(1.0, group_cap[group] / total) if total > 0 else 0.0
+        for group, total in group_demand.items()
+    }
+    granted = [demand * scale[group] for group, demand in zip(groups, demands)]
+    residual = {group: max(0.0, group_cap[group] - group_demand[group]) for group in group_cap}
+    return Allocation(granted=granted, residual=residual)
+
+
+class ColumnStats(BaseModel):
+    """Streaming summary of one variable's values, for drift monitoring.
+
+    Accumulates with Welford's algorithm, so a single pass over the values
+    suffices. Accumulators over separate chunks combine with merge(); merging
+    in a fixed chunk order gives the same result however the work was split.
+    None and NaN count as nulls and are otherwise ignored.
+    """
+
+    count: int = 0  # non-null values
+    nulls: int = 0
+    min: float | None = None
+    max: float | None = None
+    mean: float = 0.0
+    m2: float = 0.0  # sum of squared deviations from the mean
+
+    @property
+    def variance(self) -> float | None:
+        """Population variance (NumPy's default ddof=0); None with no values."""
+        return self.m2 / self.count if self.count else None
+
+    @property
+    def stddev(self) -> float | None:
+        variance = self.variance
+        return None if variance is None else math.sqrt(variance)
+
+    def add(self, value: Any) -> None:
+        if _missing(value):
+            self.nulls += 1
+            return
+        if isinstance(value, str) or not isinstance(value, Real):
+            raise StatsError(f"column statistics need numbers, got {value!r}")
+        x = float(value)
+        self.count += 1
+        delta = x - self.mean
+        self.mean += delta / self.count
+        self.m2 += delta * (x - self.mean)
+        self.min = x if self.min is None else min(self.min, x)
+        self.max = x if self.max is None else max(self.max, x)
+
+    def merge(self, other: "ColumnStats") -> "ColumnStats":
+        """Statistics over both sets of values (Chan et al.'s pairwise update)."""
+        count = self.count + other.count
+        if not other.count or not self.count:
+            chosen = self if self.count else other
+            return chosen.model_copy(update={"nulls": self.nulls + other.nulls})
+        delta = other.mean - self.mean
+        return ColumnStats(
+            count=count,
+            nulls=self.nulls + other.nulls,
+            min=min(self.min, other.min),
+            max=max(self.max, other.max),
+            mean=self.mean + delta * other.count / count,
+            m2=self.m2 + other.m2 + delta * delta * self.count * other.count / count,
+        )
@@ -0,0 +1,119 @@
+"""Trace: why one row's output has the value it has.
+
+trace() evaluates a variable for a single row and returns its evaluation
+tree: each variable with its value and formula, the input fields it read,
+and beneath it every variable it depends on, down to the parameters.
+"""
+
+from typing import Any
+
+from pydantic import BaseModel
+
+from . import ast
+from .compiler import IR, cross_entity_variables, prune
+from .config import EngineConfig
+from .executor import Executor
+from .schema import Data
+
+
+class TraceError(Exception):
+    pass
+
+
+class TraceNode(BaseModel):
+    """One variable's value for the traced row, and how it was reached."""
+
+    path: str
+    value: Any
+    expr: str  # the formula in force, as .rac source
+    entity: str | None = None  # None for parameters (scalars)
+    inputs: dict[str, Any] = {}  # row fields the formula reads directly
+    deps: list["TraceNode"] = []  # in evaluation order
+
+    def find(self, path: str) -> "TraceNode | None":
+        """The first node for ``path`` in this tree, depth first."""
+        if self.path == path:
+            return self
+        for dep in self.deps:
+            if (found := dep.find(path)) is not None:
+                return found
+        return None
+
+    def parameters(self) -> dict[str, Any]:
+        """Every scalar the tree reads, by path."""
+        found = {} if self.entity is not None else {self.path: self.value}
+        for dep in self.deps:
+            found |= dep.parameters()
+        return found
+
+    def render(self, indent: str = "  ") -> str:
+        """The tree as indented text, one variable per line."""
+        lines: list[str] = []
+        self._render(lines, 0, indent)
+        return "\n".join(lines)
+
+    def _render(self, lines: list[str], depth: int, indent: str) -> None:
+        inputs = "".join(f", {field}={value!r}" for field, value in self.inputs.items())
+        lines.append(f"{indent * depth}{self.path} = {self.value!r}  [{self.expr}{inputs}]")
+        for dep in self.deps:
+            dep._render(lines, depth + 1, indent)
+
+
+def _fields(expr: ast.Expr) -> list[str]:
+    names = [node.path for node in ast.walk(expr) if isinstance(node, ast.Var)]
+    return list(dict.fromkeys(name for name in names if "/" not in name))
+
+
+def trace(
+    ir: IR,
+    data: Data | dict[str, list[dict]],
+    entity: str,
+    row: int,
+    target: str,
+    config: EngineConfig | None = None,
+) -> TraceNode:
+    """The evaluation tree of ``target`` for row ``row`` of ``entity``.
+
+    Only that row is evaluated. A variable read by several others appears
+    under each of them. Variables reading other entities can't be traced
+    one row at a time.
+    """
+    if target not in ir.variables:
+        raise TraceError(f"unknown variable: {target}")
+    var = ir.variables[target]
+    if var.entity is not None and var.entity != entity:
+        raise TraceError(f"{target} is not a variable of entity {entity}")
+    pruned = prune(ir, [target])
+    if cross := cross_entity_variables(pruned):
+        raise TraceError(f"{cross[0]} reads another entity, which trace doesn't support")
+    tables = data.tables if isinstance(data, Data) else data
+    rows = tables.get(entity, [])
+    if not 0 <= row < len(rows):
+        raise TraceError(f"{entity} has no row {row}")
+    record = rows[row]
+    # The row alone, without the schema: its links point at rows left out
+    result = Executor(pruned, config=config).execute(Data(tables={entity: [record]}))
+    values = {**result.scalars, **result.entities.get(entity, {})}
+
+    def node(path: str, ancestors: tuple[str, ...]) -> TraceNode:
+        var = pruned.variables[path]
+        value = values[path][0] if var.entity is not None else values[path]
+        deps = [
+            dep
+            for dep in pruned.order
+            if dep in var.deps and dep not in ancestors  # fixed-point groups are cyclic
+        ]
+        return TraceNode(
+            path=path,
+            value=value,
+            expr=ast.to_source(var.expr),
+            entity=var.entity,
+            inputs={
+                field: record.get(field)
+                for field in _fields(var.expr)
+                if var.entity is not None and field in record
+            },
+            deps=[node(dep, (*ancestors, path)) for dep in deps],
+        )
+
+    return node(target, ())
@@ -0,0 +1,272 @@
+"""Value types of expressions, inferred and checked at compile time.
+
+Input fields carry their declared dtype (bool, int, float, str, date) and
+each variable's type follows from its formula, so a flag stays a bool and a
+count stays an int rather than becoming a float. Expressions that mix types
+in a way that can only be a mistake, like arithmetic on a string or
+ordering a number against a date, are reported as problems. Where a type
+can't be known (registered functions, field access on members, branches of
+different types) it is "any" and nothing is checked against it.
+"""
+
+from collections.abc import Iterable, Mapping
+from datetime import date
+from typing import TYPE_CHECKING, Any, Literal
+
+from . import ast
+from .schema import Schema
+
+if TYPE_CHECKING:
+    from .compiler import ResolvedVar
+
+ValueType = Literal["bool", "int", "float", "str", "date", "any"]
+
+DTYPES: dict[str, ValueType] = {
+    "bool": "bool",
+    "int": "int",
+    "float": "float",
+    "str": "str",
+    "category": "str",
+    "date": "date",
+}
+
+NUMERIC = ("bool", "int", "float")
+
+# Built-ins whose result type doesn't depend on their arguments
+RESULT_TYPES: dict[str, ValueType] = {
+    "len": "int",
+    "count_over": "int",
+    "round": "int",
+    "any": "bool",
+    "all": "bool",
+    "any_over": "bool",
+    "all_over": "bool",
+    "apportion": "float",
+    "equivalence_scale": "float",
+    "equivalized_income": "float",
+    "age_at": "int",
+    "year_of": "int",
+    "month_of": "int",
+    "months_between": "int",
+    "days_between": "int",
+    "is_missing": "bool",
+    "floor": "int",
+    "ceil": "int",
+    "trunc": "int",
+    "sign": "int",
+    "interp": "float",
+    "uniform": "float",
+    "normal": "float",
+    "bernoulli": "bool",
+}
+
+# Built-ins whose arguments are all dates (ISO-date strings are read as dates)
+DATE_ARGS = ("age_at", "year_of", "month_of", "months_between", "days_between")
+
+# Built-ins whose result joins the types of their arguments
+JOINED = ("min", "max", "abs", "clip", "coalesce")
+
+
+def value_type(value: Any) -> ValueType:
+    """The type of a runtime value."""
+    if isinstance(value, bool):
+        return "bool"
+    if isinstance(value, int):
+        return "int"
+    if isinstance(value, float):
+        return "float"
+    if isinstance(value, str):
+        return "str"
+    if isinstance(value, date):
+        return "date"
+    return "any"
+
+
+def join(types: Iterable[ValueType]) -> ValueType:
+    """The type of a value that may come from any of ``types``.
+
+    Numbers widen (bool to int to float); anything else mixed is "any".
+    """
+    found = set(types)
+    if len(found) == 1:
+        return found.pop()
+    if found and found <= set(NUMERIC):
+        return "float" if "float" in found else "int"
+    return "any"
+
+
+class _Checker:
+    def __init__(self, schema: Schema, known: Mapping[str, ValueType]):
+        self.schema = schema
+        self.known = known
+        self.problems: list[str] = []
+
+    def infer(self, expr: ast.Expr, entity: str | None) -> ValueType:
+        match expr:
+            case ast.Literal(value=v):
+                return value_type(v)
+            case ast.Var(path=path):
+                if "/" in path:
+                    return self.known.get(path, "any")
+                fld = self.schema.entities.get(entity or "")
+                if fld is not None and path in fld.fields:
+                    return DTYPES.get(fld.fields[path].dtype, "any")
+                return "any"
+            case ast.BinOp(op="in" | "not in", left=left, right=ast.Literal(value=list(v))):
+                member = self.infer(left, entity)
+                for category in {value_type(item) for item in v}:
+                    self._binary("==", member, category)
+                return "bool"
+            case ast.BinOp(op=op, left=left, right=right):
+                return self._binary(op, self.infer(left, entity), self.infer(right, entity))
+            case ast.UnaryOp(op=op, operand=operand):
+                inner = self.infer(operand, entity)
+                if op == "not":
+                    return "bool"
+                self._numeric(f"unary {op}", inner)
+                return "int" if inner == "bool" else inner
+            case ast.Call(func=func, args=args):
+                types = [self.infer(a, entity) for a in args]
+                if func in DATE_ARGS and ast.arity_error(func, len(args)) is None:
+                    for t in types:
+                        if t in NUMERIC:
+                            self.problems.append(f"{func} needs dates, got {t}")
+                if func in ast.RANDOM:
+                    self._numeric(f"{func}()", *types)
+                if func in RESULT_TYPES:
+                    return RESULT_TYPES[func]
+                if func in JOINED:
+                    return join(types) if types else "any"
+                if func == "where" and len(types) == 3:
+                    return join(types[1:])
+                return "any"
+            case ast.Match(subject=subject, cases=cases, default=default):
+                self.infer(subject, entity)
+                results = [self.infer(r, entity) for _, r in cases]
+                for pattern, _ in cases:
+                    self.infer(pattern, entity)
+                if default is not None:
+                    results.append(self.infer(default, entity))
+                return join(results)
+            case ast.Cond(condition=cond, then_expr=then_e, else_expr=else_e):
+                self.infer(cond, entity)
+                return join([self.infer(then_e, entity), self.infer(else_e, entity)])
+            case ast.Scale(base=base, brackets=brackets):
+                self._numeric("scale", self.infer(base, entity))
+                for threshold, rate in brackets:
+                    self._numeric("scale", self.infer(threshold, entity))
+                    self._numeric("scale", self.infer(rate, entity))
+                return "float"
+            case ast.Lookup(key=key, table=table, out_of_range=mode, default=default):
+                subject = self.infer(key, entity)
+                for category in {value_type(k) for k, _ in table}:
+                    self._binary("==", subject, category)
+                values = [value_type(v) for _, v in table]
+                return join([*values, value_type(default)] if mode == "default" else values)
+        for child in ast.children(expr):
+            self.infer(child, entity)
+        return "any"
+
+    def _numeric(self, what: str, *types: ValueType) -> None:
+        for t in types:
+            if t in ("str", "date"):
+                self.problems.append(f"{what} needs numbers, got {t}")
+
+    def _binary(self, op: str, left: ValueType, right: ValueType) -> ValueType:
+        if op in ("and", "or", "xor"):
+            return "bool"
+        if op in ("==", "!=", "<", ">", "<=", ">="):
+            dates = {left, right} == {"str", "date"}  # ISO-date strings compare as dates
+            if "any" not in (left, right) and join([left, right]) == "any" and not dates:
+                self.problems.append(f"{left} {op} {right} compares different types")
+            return "bool"
+        self._numeric(f"{op}", left, right)
+        if op in ("/", "**"):
+            return "float"
+        if "any" in (left, right):
+            return "any"
+        return "int" if join([left, right]) == "bool" else join([left, right])
+
+
+def expression_type(
+    expr: ast.Expr,
+    entity: str | None,
+    schema: Schema,
+    known: Mapping[str, ValueType] | None = None,
+) -> ValueType:
+    """The type of ``expr``, given the ``known`` types of the variables it
+    reads; others count as any."""
+    return _Checker(schema, known or {}).infer(expr, entity)
+
+
+def variable_types(
+    variables: Mapping[str, "ResolvedVar"], schema: Schema, order: list[str]
+) -> tuple[dict[str, ValueType], list[str]]:
+    """Each variable's inferred type, with the type problems found on the way.
+
+    ``order`` puts each variable after its deps, so theirs are known first.
+    """
+    known: dict[str, ValueType] = {}
+    problems: list[str] = []
+    for path in order:
+        var = variables[path]
+        checker = _Checker(schema, known)
+        known[path] = checker.infer(var.expr, var.entity)
+        if var.round_to is not None:
+            checker._numeric("round_to", known[path])
+        problems.extend(f"{path}: {problem}" for problem in checker.problems)
+    return known, problems
+
+
+def column_problems(schema: Schema, tables: Mapping[str, list[dict]]) -> list[str]:
+    """Input values that don't fit their field's declared dtype.
+
+    An int field accepts whole floats, and a float field any number; a
+    bool field accepts only booleans. A missing value (None, or NaN in a
+    float field) is reported only where the field isn't declared nullable.
+    """
+    problems = []
+    for entity_name, rows in tables.items():
+        entity = schema.entities.get(entity_name)
+        if entity is None:
+            continue
+        for name, fld in entity.fields.items():
+            expected = DTYPES.get(fld.dtype)
+            if expected is None:
+                continue
+            for i, row in enumerate(rows):
+                value = row.get(name)
+                if value is None or (isinstance(value, float) and value != value):
+                    if fld.nullable:
+                        continue
+                    problems.append(
+                        f"{entity_name} row {i}: {name} is missing but not declared nullable"
+                    )
+                    break
+                if _fits(value, expected):
+                    continue
+                problems.append(
+                    f"{entity_name} row {i}: {name} should be {expected}, "
+                    f"got {value_type(value)} {value!r}"
+                )
+                break  # one report per column
+    return problems
+
+
+def _fits(value: Any, expected: ValueType) -> bool:
+    actual = value_type(value)
+    if expected == "float":
+        return actual in NUMERIC
+    if expected == "int":
+        return actual in ("bool", "int") or (actual == "float" and float(value).is_integer())
+    if expected == "date":
+        return actual == "date" or (actual == "str" and _is_iso_date(value))
+    return actual == expected
+
+
+def _is_iso_date(text: str) -> bool:
+    try:
+        date.fromisoformat(text)
+    except ValueError:
+        return False
+    return True
@@ -0,0 +1,59 @@
+"""Per-row memoization of registered functions on a credit-heavy model.
+
+Five credits each apply the same federal bracket schedule, a registered
+function, to a person's income. With ``memoize=True`` the schedule is
+evaluated once per row instead of five times; this times both and checks
+they give the same credits.
+
+Usage:
+    python tests/benchmarks/bench_memo.py [rows]
+"""
+
+import statistics
+import sys
+import time
+from datetime import date
+
+from rac import compile, parse, register_function, run
+
+CREDITS = 5
+# 200 brackets of 1000 each, the rate rising by a tenth of a point per bracket
+BRACKETS = [(1000.0 * i, 0.001 * i) for i in range(200)]
+
+SOURCE = "entity person:\n    income: float\n" + "".join(
+    f"variable person/credit_{i}:\n"
+    f"    entity: person\n"
+    f"    from 2024-01-01: federal_tax(income) * 0.0{i}\n"
+    for i in range(1, CREDITS + 1)
+)
+
+
+def federal_tax(income: float) -> float:
+    tax = 0.0
+    for (lower, rate), (upper, _) in zip(BRACKETS, BRACKETS[1:] + [(float("inf"), 0.0)]):
+        if income > lower:
+            tax += (min(income, upper) - lower) * rate
+    return tax
+
+
+def timed(memoize: bool) -> tuple[float, dict]:
+    register_function("federal_tax", federal_tax, memoize=memoize)
+    seconds = []
+    for _ in range(5):
+        start = time.perf_counter()
+        result = run(ir, data)
+        seconds.append(time.perf_counter() - start)
+    return statistics.median(seconds), result.entities["person"]
+
+
+rows = int(sys.argv[1]) if len(sys.argv) > 1 else 5000
+ir = compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+data = {"person": [{"id": i, "income": 37.5 * i} for i in range(rows)]}
+
+plain, expected = timed(memoize=False)
+memoized, credits = timed(memoize=True)
+if credits != expected:
+    sys.exit("memoized credits differ from the plain run")
+print(f"{rows} people, {CREDITS} credits sharing one bracket schedule:")
+print(f"  memoize=False: {plain * 1000:.1f}ms")
+print(f"  memoize=True:  {memoized * 1000:.1f}ms ({plain / memoized:.1f}x faster)")
@@ -0,0 +1,51 @@
+"""Single-household latency of Model.run, end to end from Python.
+
+One household runs in-process (below ``sequential_threshold``); this times
+that path against the 200µs budget and checks every household gives
+exactly what the native binary gives.
+
+Usage:
+    python tests/benchmarks/bench_single_household.py [iterations]
+"""
+
+import statistics
+import sys
+import time
+from datetime import date
+from pathlib import Path
+
+from rac import Model
+
+BUDGET_US = 200.0
+SOURCE = Path(__file__).parents[2] / "examples" / "uk_tax_benefit.rac"
+HOUSEHOLDS = [
+    {"id": i, "income": 1250.5 * i, "age": 18 + i % 60, "has_children": i % 3 == 0}
+    for i in range(100)
+]
+
+iterations = int(sys.argv[1]) if len(sys.argv) > 1 else 2000
+model = Model.from_file(SOURCE, as_of=date(2025, 6, 1))
+
+# Every household alone in-process matches the batch through the binary
+native = model.run({"person": HOUSEHOLDS}, sequential_threshold=0).arrays["person"]
+for i, household in enumerate(HOUSEHOLDS):
+    alone = model.run({"person": [household]}).arrays["person"]
+    if alone[0].tobytes() != native[i].tobytes():
+        sys.exit(f"household {i}: in-process {alone[0].tolist()} != native {native[i].tolist()}")
+
+data = {"person": [HOUSEHOLDS[42]]}
+for _ in range(100):
+    model.run(data)
+timings = []
+for _ in range(iterations):
+    start = time.perf_counter()
+    model.run(data)
+    timings.append((time.perf_counter() - start) * 1e6)
+timings.sort()
+
+median = statistics.median(timings)
+p99 = timings[int(len(timings) * 0.99)]
+print(f"Single household, {iterations} runs: median {median:.1f}µs, p99 {p99:.1f}µs")
+print(f"Matches native for {len(HOUSEHOLDS)} households")
+if median > BUDGET_US:
+    sys.exit(f"median {median:.1f}µs is over the {BUDGET_US:.0f}µs budget")
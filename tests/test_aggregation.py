@@ -0,0 +1,130 @@
+"""Aggregating member variables up to a group entity and broadcasting down."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    ExecutionError,
+    compile,
+    execute_columns,
+    generate_python,
+    parse,
+    run,
+)
+
+SOURCE = """
+    entity household:
+        rent: float
+    entity person:
+        household: -> household
+        income: float
+        age: int
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * 0.2
+    variable person/child:
+        entity: person
+        from 2024-01-01: age < 18
+    variable household/tax:
+        entity: household
+        from 2024-01-01: sum_over(person, person/tax)
+    variable household/size:
+        entity: household
+        from 2024-01-01: count_over(person)
+    variable household/has_child:
+        entity: household
+        from 2024-01-01: any_over(person, person/child)
+    variable household/all_children:
+        entity: household
+        from 2024-01-01: all_over(person, person/child)
+    variable household/top_tax:
+        entity: household
+        from 2024-01-01: max_over(person, person/tax)
+    variable household/rent_share:
+        entity: household
+        from 2024-01-01: rent / max(1, household/size)
+    variable person/rent_share:
+        entity: person
+        from 2024-01-01: household/rent_share
+"""
+
+DATA = {
+    "household": [{"id": 1, "rent": 1200.0}, {"id": 2, "rent": 800.0}, {"id": 3, "rent": 500.0}],
+    "person": [
+        {"id": 10, "household": 1, "income": 30000.0, "age": 40},
+        {"id": 11, "household": 2, "income": 10000.0, "age": 70},
+        {"id": 12, "household": 1, "income": 5000.0, "age": 12},
+        {"id": 13, "household": None, "income": 1000.0, "age": 30},
+    ],
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestAggregation:
+    def test_reductions(self, ir):
+        households = run(ir, DATA).entities["household"]
+        assert households["household/tax"] == [7000.0, 2000.0, 0]
+        assert households["household/size"] == [2, 1, 0]
+        assert households["household/has_child"] == [True, False, False]
+        assert households["household/all_children"] == [False, False, True]
+        assert households["household/top_tax"] == [6000.0, 2000.0, 0]
+
+    def test_broadcast_down(self, ir):
+        people = run(ir, DATA).entities["person"]
+        assert people["person/rent_share"] == [600.0, 800.0, 600.0, None]
+
+    def test_row_path_agrees(self, ir):
+        rows = run(ir, DATA, config=EngineConfig(columnar=False))
+        assert rows.entities == run(ir, DATA).entities
+
+    def test_member_inputs_are_not_group_inputs(self, ir):
+        report = run(ir, DATA, dry_run=True)
+        assert report.ok, report.problems
+
+    def test_nan_propagates_through_max(self, ir):
+        people = [dict(row) for row in DATA["person"]]
+        people[2]["income"] = float("nan")
+        top = run(ir, {**DATA, "person": people}).entities["household"]["household/top_tax"]
+        assert top[0] != top[0] and top[1] == 2000.0
+
+    def test_column_inputs_cannot_aggregate(self, ir):
+        with pytest.raises(ExecutionError, match="needs run"):
+            execute_columns(ir, "household", {"rent": [1.0]}, outputs=["household/tax"])
+
+    def test_backends_reject(self, ir):
+        with pytest.raises(CompileError, match="only the executor supports"):
+            generate_python(ir)
+
+
+class TestLinks:
+    def _compile(self, source):
+        return compile([parse(source)], as_of=date(2024, 1, 1))
+
+    def test_no_link(self):
+        source = SOURCE.replace("        household: -> household\n", "")
+        with pytest.raises(CompileError, match="person has no link to household"):
+            self._compile(source)
+
+    def test_several_links(self):
+        source = SOURCE.replace(
+            "household: -> household\n", "household: -> household\n        former: -> household\n"
+        )
+        with pytest.raises(CompileError, match="person has several links to household"):
+            self._compile(source)
+
+    def test_value_must_belong_to_the_members(self):
+        source = SOURCE.replace("sum_over(person, person/tax)", "sum_over(person, household/size)")
+        with pytest.raises(CompileError, match="sum_over takes a variable of person"):
+            self._compile(source)
+
+    def test_first_argument_is_an_entity(self):
+        source = SOURCE.replace("count_over(person)", "count_over(person/tax)")
+        with pytest.raises(CompileError, match="takes an entity name first"):
+            self._compile(source)
@@ -0,0 +1,105 @@
+"""apportion(): scale an amount by the share of a period a span covers."""
+
+from datetime import date
+
+import pytest
+
+from rac import compile, execute, generate_javascript, generate_python, generate_rust, parse
+from rac.executor import BUILTINS
+
+apportion = BUILTINS["apportion"]
+
+# (formula over start/end, with the period days 1..30, expected)
+CASES = [
+    ("apportion(300, 1, 30, 1, 30)", 300),  # full period
+    ("apportion(300, -10, 45, 1, 30)", 300),  # clamped to the period
+    ("apportion(300, 31, 40, 1, 30)", 0),  # after the period
+    ("apportion(300, -5, 0, 1, 30)", 0),  # before the period
+    ("apportion(300, 16, 30, 1, 30)", 150),  # second half
+    ("apportion(300, 1, 1, 1, 30)", 10),  # a single day, end inclusive
+    ("apportion(300, 1, 1, 1, 30, false)", 0),  # empty span, end exclusive
+    ("apportion(300, 1, 16, 1, 31, false)", 150),  # exclusive ends on both spans
+    ("apportion(300, 5, 3, 1, 30)", 0),  # end before start
+]
+
+
+def build(formula):
+    module = parse(f"""
+        variable gov/out:
+            from 2024-01-01: {formula}
+    """)
+    return compile([module], as_of=date(2024, 1, 1))
+
+
+def run_executor(ir):
+    return execute(ir, {}).scalars["gov/out"]
+
+
+def run_generated_python(ir):
+    namespace: dict = {}
+    exec(generate_python(ir), namespace)
+    return namespace["compute_scalars"]()["gov_out"]
+
+
+ENGINES = {"executor": run_executor, "python": run_generated_python}
+
+
+@pytest.mark.parametrize("engine", list(ENGINES))
+@pytest.mark.parametrize("formula, expected", CASES)
+def test_matrix(engine, formula, expected):
+    actual = ENGINES[engine](build(formula))
+    assert actual == pytest.approx(expected), f"{engine}: {formula} gave {actual!r}"
+
+
+class TestApportion:
+    def test_partial_overlap_factor(self):
+        # days 11-20 of a 30-day period
+        assert apportion(90, 11, 20, 1, 30) == pytest.approx(30)
+
+    def test_dates(self):
+        # 1 Feb-29 Feb 2024 against the year: 29 of 366 days
+        value = apportion(
+            366, date(2024, 2, 1), date(2024, 2, 29), date(2024, 1, 1), date(2024, 12, 31)
+        )
+        assert value == pytest.approx(29)
+
+    def test_exclusive_end_day(self):
+        # [1 Jan, 1 Feb) against [1 Jan 2024, 1 Jan 2025): 31 of 366 days
+        value = apportion(
+            366, date(2024, 1, 1), date(2024, 2, 1), date(2024, 1, 1), date(2025, 1, 1), False
+        )
+        assert value == pytest.approx(31)
+
+    def test_empty_period(self):
+        assert apportion(100, 1, 10, 5, 5, False) == 0
+
+    def test_missing_input_propagates(self):
+        assert apportion(100, float("nan"), 10, 1, 30) != apportion(100, float("nan"), 10, 1, 30)
+
+    def test_entity_spans(self):
+        module = parse("""
+            entity person:
+                start: int
+                end: int
+            variable person/rent:
+                entity: person
+                from 2024-01-01: apportion(1200, start, end, 1, 12)
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        result = execute(
+            ir,
+            {"person": [{"id": 1, "start": 1, "end": 12}, {"id": 2, "start": 4, "end": 6}]},
+        )
+        assert result.entities["person"]["person/rent"] == pytest.approx([1200, 300])
+
+
+class TestCompiledBackends:
+    def test_rust(self):
+        code = generate_rust(build("apportion(300, 1, 15, 1, 30)"))
+        assert "fn rac_apportion(" in code
+        assert "rac_apportion(300_f64, 1_f64, 15_f64, 1_f64, 30_f64, true)" in code
+
+    def test_javascript(self):
+        code = generate_javascript(build("apportion(300, 1, 15, 1, 30, false)"))
+        assert "function racApportion(" in code
+        assert "racApportion(300, 1, 15, 1, 30, false)" in code
@@ -0,0 +1,137 @@
+"""Power, modulo and floor division: precedence, negative operands, zero
+divisors, and agreement between the executor paths and generated Python."""
+
+import math
+from datetime import date
+from fractions import Fraction
+
+import pytest
+
+from rac import (
+    EngineConfig,
+    ParseError,
+    compile,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    parse,
+    run,
+)
+from rac.ast import to_source
+
+NAN = float("nan")
+INF = float("inf")
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+# (formula, x, y, expected)
+CASES = [
+    ("x ** y", 2, 3, 8),
+    ("x ** y", 2.0, -1, 0.5),
+    ("x ** y", 9.0, 0.5, 3.0),
+    ("x ** y", -8.0, 3, -512.0),
+    ("x ** y", -8.0, 0.5, NAN),
+    ("x ** y", 0, -1, 0),
+    ("x ** y", 10.0, 400, INF),
+    ("x ** y", -10.0, 401, -INF),
+    ("x % y", 7, 52, 7),
+    ("x % y", -7, 52, 45),
+    ("x % y", 7, -52, -45),
+    ("x % y", 7.5, 2, 1.5),
+    ("x % y", 7, 0, 0),
+    ("x // y", 7, 2, 3),
+    ("x // y", -7, 2, -4),
+    ("x // y", 7, -2, -4),
+    ("x // y", 7.5, 2, 3.0),
+    ("x // y", 7, 0, 0),
+    ("x // y", NAN, 2, NAN),
+]
+
+
+def build(formula):
+    module = parse(f"""
+        entity person:
+            x: float
+            y: float
+        variable person/out:
+            entity: person
+            from 2024-01-01: {formula}
+    """)
+    return compile([module], as_of=date(2024, 1, 1))
+
+
+def same(actual, expected):
+    if isinstance(expected, float) and math.isnan(expected):
+        return isinstance(actual, float) and math.isnan(actual)
+    return actual == expected and type(actual) is type(expected)
+
+
+def expr_of(formula):
+    module = parse(f"variable gov/a:\n    from 2024-01-01: {formula}\n")
+    return module.variables[0].values[0].expr
+
+
+@pytest.mark.parametrize("formula, x, y, expected", CASES)
+def test_executor(formula, x, y, expected):
+    ir = build(formula)
+    for config in CONFIGS:
+        result = run(ir, {"person": [{"id": 1, "x": x, "y": y}]}, config=config)
+        actual = result.entities["person"]["person/out"][0]
+        assert same(actual, expected), f"{formula} with {x}, {y} under {config}: {actual!r}"
+
+
+@pytest.mark.parametrize("formula, x, y, expected", CASES)
+def test_generated_python(formula, x, y, expected):
+    namespace: dict = {}
+    exec(generate_python(build(formula)), namespace)
+    row = namespace["PersonInput"](x=x, y=y)
+    actual = namespace["compute_person"](row, namespace["compute_scalars"]())["person_out"]
+    assert same(actual, expected), f"{formula} with {x}, {y}: {actual!r}"
+
+
+class TestParsing:
+    @pytest.mark.parametrize(
+        "formula, source",
+        [
+            ("-2 ** 2", "-(2 ** 2)"),
+            ("2 ** 3 ** 2", "(2 ** (3 ** 2))"),
+            ("2 ** -1", "(2 ** -1)"),
+            ("2 * 3 ** 2", "(2 * (3 ** 2))"),
+            ("7 // 2 * 3 % 4", "(((7 // 2) * 3) % 4)"),
+            ("1 + 7 % 3", "(1 + (7 % 3))"),
+        ],
+    )
+    def test_precedence(self, formula, source):
+        assert to_source(expr_of(formula)) == source
+
+    def test_power_chain_nesting_is_bounded(self):
+        with pytest.raises(ParseError, match="nested more than"):
+            expr_of(" ** ".join(["2"] * 500))
+
+
+class TestExact:
+    def test_exact_operands_stay_exact(self):
+        # (1/3) ** 2 as a float would round before the sum; exactly it's 1/9
+        module = parse("""
+            variable gov/a:
+                arithmetic: "exact"
+                from 2024-01-01: (1 / 3) ** 2 + 7 % 3 + 7 // 2
+        """)
+        result = run(compile([module], as_of=date(2024, 1, 1)), {})
+        assert result.scalars["gov/a"] == float(Fraction(1, 9) + 1 + 3)
+
+    def test_large_exponents_go_through_floats(self):
+        module = parse("variable gov/a:\n    from 2024-01-01: 3 ** 1000\n")
+        result = run(compile([module], as_of=date(2024, 1, 1)), {})
+        assert result.scalars["gov/a"] == INF
+
+
+def test_codegen_uses_helpers():
+    ir = build("x ** 2 + x % 52 + x // 7")
+    python, js, rust = generate_python(ir), generate_javascript(ir), generate_rust(ir)
+    for helpers, source in (
+        (("_pow(", "_mod(", "_floordiv("), python),
+        (("racPow(", "racMod(", "racFloorDiv("), js),
+        (("rac_pow(", "rac_mod(", "rac_floor_div("), rust),
+    ):
+        assert all(helper in source for helper in helpers)
@@ -0,0 +1,77 @@
+"""Tests for built-in function arity checking."""
+
+from datetime import date
+
+import pytest
+
+from rac import EngineConfig, ExecutionError, ParseError, compile, execute, parse
+from rac.ast import BUILTIN_ARITY, arity_error
+
+
+def calls(kind):
+    """(func, n_args) pairs that violate each built-in's arity."""
+    out = []
+    for func, (lo, hi) in BUILTIN_ARITY.items():
+        if kind == "too_few":
+            if lo > 0:
+                out.append((func, lo - 1))
+        elif hi is not None:
+            out.append((func, hi + 1))
+    return out
+
+
+def source(func, n_args):
+    args = ", ".join(["1"] * n_args)
+    return f"""
+        variable gov/out:
+            from 2024-01-01: {func}({args})
+    """
+
+
+BAD = calls("too_few") + calls("too_many")
+
+
+def test_table_covers_every_builtin():
+    from rac.executor import BUILTINS
+
+    assert set(BUILTIN_ARITY) == set(BUILTINS)
+
+
+@pytest.mark.parametrize("func, n_args", BAD)
+def test_parse_rejects(func, n_args):
+    with pytest.raises(ParseError, match=rf"{func}\(\) takes .* got {n_args}"):
+        parse(source(func, n_args))
+
+
+@pytest.mark.parametrize("func, n_args", BAD)
+def test_execute_rejects(func, n_args):
+    ir = compile([parse(source(func, n_args), strict=False)], as_of=date(2024, 1, 1))
+    with pytest.raises(ExecutionError, match=rf"{func}\(\) takes .* got {n_args}"):
+        execute(ir, {})
+
+
+def test_messages():
+    assert arity_error("clip", 2) == "clip() takes 3 arguments, got 2"
+    assert arity_error("abs", 2) == "abs() takes 1 argument, got 2"
+    assert arity_error("max", 0) == "max() takes at least 1 argument, got 0"
+    assert arity_error("clip", 3) is None
+    assert arity_error("user_defined", 7) is None
+
+
+def test_lenient_mode_skips_check():
+    from rac.executor import run
+
+    module = parse(source("max", 0), strict=False)
+    ir = compile([module], as_of=date(2024, 1, 1))
+    # Lenient: the call reaches the Python builtin, which fails on its own terms
+    with pytest.raises(TypeError):
+        run(ir, {}, config=EngineConfig(strict=False))
+
+
+def test_valid_calls_still_parse():
+    module = parse("""
+        variable gov/out:
+            from 2024-01-01: clip(max(1, 2, 3), 0, round(abs(5)))
+    """)
+    ir = compile([module], as_of=date(2024, 1, 1))
+    assert execute(ir, {}).scalars["gov/out"] == 3
@@ -0,0 +1,265 @@
+"""Tests for Apache Arrow input/output (requires pyarrow)."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompiledModel,
+    ReadStats,
+    compile,
+    execute,
+    execute_arrow,
+    execute_from_arrow_file,
+    execute_from_parquet_file,
+    execute_polars,
+    parse,
+)
+
+pa = pytest.importorskip("pyarrow")
+import pyarrow.ipc  # noqa: E402
+
+SOURCE = """
+    entity person:
+        income: float
+        age: int
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+
+    variable person/senior:
+        entity: person
+        from 2024-01-01: age >= 65
+"""
+
+ROWS = [{"id": i, "income": 1000.0 * i, "age": 20 + 10 * i} for i in range(7)]
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _write(path, rows, fmt, batch_size=3):
+    table = pa.Table.from_pylist(rows)
+    with pa.OSFile(str(path), "wb") as sink:
+        opener = pa.ipc.new_file if fmt == "file" else pa.ipc.new_stream
+        with opener(sink, table.schema) as writer:
+            for batch in table.to_batches(max_chunksize=batch_size):
+                writer.write_batch(batch)
+
+
+class TestExecuteFromArrowFile:
+    @pytest.mark.parametrize("fmt", ["file", "stream"])
+    def test_round_trip(self, ir, tmp_path, fmt):
+        src, dst = tmp_path / "in.arrow", tmp_path / "out.arrow"
+        _write(src, ROWS, fmt)
+        assert execute_from_arrow_file(ir, "person", src, dst) == len(ROWS)
+
+        with pa.OSFile(str(dst), "rb") as f:
+            reader = pa.ipc.open_file(f)
+            assert reader.num_record_batches == 3
+            table = reader.read_all()
+
+        assert table.schema.names == ["person/tax", "person/senior"]
+        assert all(t == pa.float64() for t in table.schema.types)
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        assert table.column("person/tax").to_pylist() == expected["person/tax"]
+        assert table.column("person/senior").to_pylist() == [
+            float(v) for v in expected["person/senior"]
+        ]
+
+    def test_empty_input(self, ir, tmp_path):
+        src, dst = tmp_path / "in.arrow", tmp_path / "out.arrow"
+        schema = pa.schema([("id", pa.int64()), ("income", pa.float64()), ("age", pa.int64())])
+        with pa.OSFile(str(src), "wb") as sink, pa.ipc.new_file(sink, schema):
+            pass
+        assert execute_from_arrow_file(ir, "person", src, dst) == 0
+        with pa.OSFile(str(dst), "rb") as f:
+            assert pa.ipc.open_file(f).read_all().num_rows == 0
+
+
+class TestColumnPruning:
+    @pytest.fixture
+    def wide(self, tmp_path):
+        """400-column input of which the model reads only income and age."""
+        rows = [
+            {**row, **{f"unused_{j}": float(j) for j in range(397)}} for row in ROWS
+        ]
+        path = tmp_path / "wide.arrow"
+        _write(path, rows, "file")
+        return path
+
+    def _read(self, path):
+        with pa.OSFile(str(path), "rb") as f:
+            return pa.ipc.open_file(f).read_all()
+
+    @pytest.mark.parametrize("fmt", ["file", "stream"])
+    def test_pruned_matches_full(self, ir, tmp_path, fmt):
+        src = tmp_path / "in.arrow"
+        _write(src, [{**row, "extra": 1.0} for row in ROWS], fmt)
+        stats = ReadStats()
+        execute_from_arrow_file(ir, "person", src, tmp_path / "out.arrow", stats=stats)
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        table = self._read(tmp_path / "out.arrow")
+        assert table.column("person/tax").to_pylist() == expected["person/tax"]
+        assert (stats.columns_read, stats.columns_available) == (2, 4)
+
+    def test_wide_file_reads_closure_only(self, ir, wide, tmp_path):
+        stats = ReadStats()
+        n = execute_from_arrow_file(
+            ir, "person", wide, tmp_path / "out.arrow", outputs=["person/tax"], stats=stats
+        )
+        assert n == stats.rows == len(ROWS)
+        assert stats.columns_available == 400
+        assert stats.columns_read == 1  # income
+
+        table = self._read(tmp_path / "out.arrow")
+        assert table.schema.names == ["person/tax"]
+        expected = execute(ir, {"person": ROWS}).entities["person"]["person/tax"]
+        assert table.column("person/tax").to_pylist() == expected
+
+    def test_passthrough_columns(self, ir, wide, tmp_path):
+        stats = ReadStats()
+        execute_from_arrow_file(
+            ir,
+            "person",
+            wide,
+            tmp_path / "out.arrow",
+            outputs=["person/senior"],
+            passthrough=["id"],
+            stats=stats,
+        )
+        assert stats.columns_read == 2  # id, age
+        table = self._read(tmp_path / "out.arrow")
+        assert table.schema.names == ["id", "person/senior"]
+        assert table.column("id").to_pylist() == [row["id"] for row in ROWS]
+
+    def test_unknown_passthrough(self, ir, wide, tmp_path):
+        with pytest.raises(ValueError, match="missing_col"):
+            execute_from_arrow_file(
+                ir, "person", wide, tmp_path / "out.arrow", passthrough=["missing_col"]
+            )
+
+
+class TestParquet:
+    @pytest.fixture
+    def pq(self):
+        return pytest.importorskip("pyarrow.parquet")
+
+    def test_round_trip(self, ir, tmp_path, pq):
+        src, dst = tmp_path / "in.parquet", tmp_path / "out.parquet"
+        pq.write_table(pa.Table.from_pylist([{**row, "extra": 1.0} for row in ROWS]), src)
+        stats = ReadStats()
+        n = execute_from_parquet_file(ir, "person", src, dst, stats=stats, batch_size=3)
+        assert n == stats.rows == len(ROWS)
+        assert (stats.columns_read, stats.columns_available) == (2, 4)
+
+        table = pq.read_table(dst)
+        assert table.schema.names == ["person/tax", "person/senior"]
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        assert table.column("person/tax").to_pylist() == expected["person/tax"]
+
+    def test_compiled_model(self, ir, tmp_path, pq):
+        src, dst = tmp_path / "in.parquet", tmp_path / "out.parquet"
+        pq.write_table(pa.Table.from_pylist(ROWS), src)
+        model = CompiledModel.compile(ir)
+        n = model.run_parquet(src, dst, "person", columns=["person/tax"], passthrough=["id"])
+        assert n == len(ROWS)
+        table = pq.read_table(dst)
+        assert table.schema.names == ["id", "person/tax"]
+        assert table.column("id").to_pylist() == [row["id"] for row in ROWS]
+
+    def test_compiled_model_runs_each_batch_through_run(self, ir, tmp_path, pq, monkeypatch):
+        src, dst = tmp_path / "in.parquet", tmp_path / "out.parquet"
+        pq.write_table(pa.Table.from_pylist(ROWS), src)
+        model = CompiledModel.compile(ir)
+        calls = []
+        run = model.run
+        monkeypatch.setattr(model, "run", lambda *a: calls.append(1) or run(*a))
+        model.run_parquet(src, dst, "person")
+        assert calls == [1]
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        assert pq.read_table(dst).column("person/tax").to_pylist() == expected["person/tax"]
+
+    def test_string_and_missing_outputs(self, tmp_path, pq):
+        ir = compile(
+            [
+                parse("""
+                    entity person:
+                        income: float?
+                    variable person/band:
+                        entity: person
+                        from 2024-01-01: where(income > 1000, "high", "low")
+                    variable person/half:
+                        entity: person
+                        from 2024-01-01: income / 2
+                """)
+            ],
+            as_of=date(2024, 1, 1),
+        )
+        src, dst = tmp_path / "in.parquet", tmp_path / "out.parquet"
+        pq.write_table(pa.Table.from_pylist([{"income": 3000.0}, {"income": None}]), src)
+        execute_from_parquet_file(ir, "person", src, dst)
+        table = pq.read_table(dst)
+        assert table.schema.field("person/band").type == pa.string()
+        assert table.column("person/band").to_pylist() == ["high", None]
+        assert table.column("person/half").to_pylist() == [1500.0, None]
+
+    def test_empty_input(self, ir, tmp_path, pq):
+        src, dst = tmp_path / "in.parquet", tmp_path / "out.parquet"
+        schema = pa.schema([("income", pa.float64()), ("age", pa.int64())])
+        pq.write_table(schema.empty_table(), src)
+        assert execute_from_parquet_file(ir, "person", src, dst) == 0
+        assert pq.read_table(dst).num_rows == 0
+
+
+class TestExecuteArrow:
+    def test_record_batch(self, ir):
+        batch = pa.RecordBatch.from_pylist(ROWS)
+        out = execute_arrow(ir, "person", batch, passthrough=["id"])
+        assert isinstance(out, pa.RecordBatch)
+        assert out.schema.names == ["id", "person/tax", "person/senior"]
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        assert out.column("person/tax").to_pylist() == expected["person/tax"]
+
+    def test_table_keeps_its_batches(self, ir):
+        table = pa.Table.from_batches(pa.Table.from_pylist(ROWS).to_batches(max_chunksize=3))
+        out = execute_arrow(ir, "person", table, outputs=["person/tax"])
+        assert isinstance(out, pa.Table)
+        assert len(out.to_batches()) == 3
+        expected = execute(ir, {"person": ROWS}).entities["person"]["person/tax"]
+        assert out.column("person/tax").to_pylist() == expected
+
+    def test_empty_table(self, ir):
+        schema = pa.schema([("id", pa.int64()), ("income", pa.float64()), ("age", pa.int64())])
+        out = execute_arrow(ir, "person", schema.empty_table(), passthrough=["id"])
+        assert out.num_rows == 0
+        assert out.schema.names == ["id", "person/tax", "person/senior"]
+
+    def test_rejects_rows(self, ir):
+        with pytest.raises(TypeError, match="RecordBatch or Table"):
+            execute_arrow(ir, "person", ROWS)
+
+
+class TestPolars:
+    @pytest.fixture
+    def pl(self):
+        return pytest.importorskip("polars")
+
+    def test_round_trip(self, ir, pl):
+        frame = pl.DataFrame(ROWS)
+        out = CompiledModel.compile(ir).run_polars(frame, "person", passthrough=["id"])
+        assert isinstance(out, pl.DataFrame)
+        assert out.columns == ["id", "person/tax", "person/senior"]
+        expected = execute(ir, {"person": ROWS}).entities["person"]
+        assert out["person/tax"].to_list() == expected["person/tax"]
+        assert out["id"].to_list() == [row["id"] for row in ROWS]
+
+    def test_rejects_rows(self, ir, pl):
+        with pytest.raises(TypeError, match="polars DataFrame"):
+            execute_polars(ir, "person", ROWS)
@@ -0,0 +1,137 @@
+"""Tests for forward-mode automatic differentiation."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import Context, Data, Dual, compile, derivatives, eval_dual, parse
+
+
+def _ir(source: str):
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+class TestDual:
+    def test_product_rule(self):
+        x = Dual(3.0, 1.0)
+        y = x * x * x
+        assert y.value == 27.0
+        assert y.deriv == 27.0  # 3x^2
+
+    def test_quotient_rule(self):
+        x = Dual(2.0, 1.0)
+        y = 1 / x
+        assert y.value == 0.5
+        assert y.deriv == -0.25  # -1/x^2
+
+    def test_constants_have_zero_derivative(self):
+        y = Dual(5.0) + 2 * 3
+        assert y.deriv == 0.0
+
+    def test_comparisons_use_value(self):
+        assert Dual(1.0, 9.0) < 2
+        assert Dual(2.0, 1.0) == 2.0
+        assert max(Dual(1.0, 5.0), 0).deriv == 5.0
+        assert max(Dual(-1.0, 5.0), 0) == 0
+
+    def test_abs_subgradient(self):
+        assert abs(Dual(-2.0, 1.0)).deriv == -1.0
+        assert abs(Dual(0.0, 1.0)).deriv == 0.0
+
+    def test_round_is_flat(self):
+        assert round(Dual(2.4, 1.0)).deriv == 0.0
+
+    def test_power_rule(self):
+        x = Dual(3.0, 1.0)
+        assert (x**2).value == 9.0 and (x**2).deriv == 6.0
+        assert (2**x).deriv == pytest.approx(8 * math.log(2))
+        assert (x**x).deriv == pytest.approx(27 * (math.log(3) + 1))
+        assert (Dual(0.0, 1.0) ** 0.5).deriv == math.inf
+        assert math.isnan((Dual(-8.0, 1.0) ** (1 / 3)).value)
+
+    def test_floordiv_and_mod_are_piecewise(self):
+        x = Dual(7.0, 1.0)
+        assert (x // 2).value == 3.0 and (x // 2).deriv == 0.0
+        assert (x % 2).value == 1.0 and (x % 2).deriv == 1.0
+        assert (20 % x).value == 6.0 and (20 % x).deriv == -2.0
+
+    def test_eval_dual_lifts_constants(self):
+        from rac import Literal
+
+        ctx = Context(data=Data(tables={}))
+        assert eval_dual(Literal(value=4), ctx).deriv == 0.0
+
+
+class TestDerivatives:
+    def test_smooth_formula_matches_analytic(self):
+        ir = _ir("""
+            entity person:
+                income: float
+                hours: float
+            variable person/y:
+                entity: person
+                from 2024-01-01: 0.001 * income * income + 3 * income / hours - 7
+        """)
+        rows = [{"id": i, "income": x, "hours": 40.0} for i, x in enumerate([0.0, 10.0, 2500.0])]
+        d = derivatives(ir, {"person": rows}, "person", "income")
+        expected = [0.002 * r["income"] + 3 / 40 for r in rows]
+        assert d["person/y"] == pytest.approx(expected)
+
+    def test_chain_through_intermediate_variables(self):
+        ir = _ir("""
+            entity person:
+                income: float
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable person/taxable:
+                entity: person
+                from 2024-01-01: income * income
+            variable person/tax:
+                entity: person
+                from 2024-01-01: person/taxable * gov/rate
+        """)
+        d = derivatives(ir, {"person": [{"id": 1, "income": 50.0}]}, "person", "income")
+        assert d["person/taxable"] == [100.0]
+        assert d["person/tax"] == pytest.approx([20.0])
+
+    def test_piecewise_uses_active_branch(self):
+        ir = _ir("""
+            entity person:
+                income: float
+            variable person/benefit:
+                entity: person
+                from 2024-01-01: max(0, 1000 - 0.5 * income)
+            variable person/credit:
+                entity: person
+                from 2024-01-01:
+                    if income < 100: income * 0.1
+                    else: 10
+        """)
+        rows = [{"id": 1, "income": 50.0}, {"id": 2, "income": 5000.0}]
+        d = derivatives(ir, {"person": rows}, "person", "income")
+        assert d["person/benefit"] == [-0.5, 0.0]
+        assert d["person/credit"] == pytest.approx([0.1, 0.0])
+
+    def test_power_and_remainder(self):
+        ir = _ir("""
+            entity person:
+                income: float
+            variable person/y:
+                entity: person
+                from 2024-01-01: income ** 2 + income % 30 + income // 30
+        """)
+        d = derivatives(ir, {"person": [{"id": 1, "income": 50.0}]}, "person", "income")
+        assert d["person/y"] == [101.0]
+
+    def test_input_data_not_mutated(self):
+        ir = _ir("""
+            entity person:
+                income: float
+            variable person/y:
+                entity: person
+                from 2024-01-01: income * 2
+        """)
+        rows = [{"id": 1, "income": 5.0}]
+        derivatives(ir, {"person": rows}, "person", "income")
+        assert rows == [{"id": 1, "income": 5.0}]
@@ -0,0 +1,84 @@
+"""Tests for the input-row memoization cache."""
+
+from datetime import date
+
+import pytest
+
+from rac import RowCache, blake2_hasher, compile, execute, parse
+
+SOURCE = """
+    entity person:
+        income: float
+        age: int
+        name: str
+
+    variable gov/allowance:
+        from 2024-01-01: 10000
+
+    variable person/taxable:
+        entity: person
+        from 2024-01-01: max(0, income - gov/allowance)
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: person/taxable * 0.2
+
+    variable person/pension:
+        entity: person
+        from 2024-01-01: age * 100
+"""
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestRowCache:
+    def test_key_uses_only_transitive_inputs(self, ir):
+        cache = RowCache(ir, "person", ["person/tax"])
+        assert cache.fields == ["income"]
+        alice = {"id": 1, "income": 30000.0, "age": 40, "name": "Alice"}
+        bob = {"id": 2, "income": 30000.0, "age": 70, "name": "Bob"}
+        assert cache.key(alice) == cache.key(bob)
+        assert cache.key(alice) != cache.key(alice | {"income": 30000.000000000004})
+
+        columns = cache.run({"person": [alice, bob]})
+        assert columns == {"person/tax": [4000.0, 4000.0]}
+        assert (cache.hits, cache.misses) == (1, 1)
+
+    def test_all_outputs_match_executor(self, ir):
+        rows = [
+            {"id": i, "income": float(income), "age": age, "name": str(i)}
+            for i, (income, age) in enumerate([(5000, 30), (20000, 30), (5000, 30), (20000, 65)])
+        ]
+        cache = RowCache(ir, "person")
+        assert cache.fields == ["income", "age"]
+        columns = cache.run({"person": rows})
+        expected = execute(ir, {"person": rows}).entities["person"]
+        assert columns == expected
+        assert (cache.hits, cache.misses) == (1, 3)
+
+    def test_entries_persist_across_batches(self, ir):
+        cache = RowCache(ir, "person", ["person/pension"])
+        cache.run({"person": [{"id": 1, "age": 66}]})
+        assert cache.run({"person": [{"id": 9, "age": 66, "income": 1.0}]}) == {
+            "person/pension": [6600]
+        }
+        assert (cache.hits, cache.misses) == (1, 1)
+
+    def test_pluggable_hasher(self, ir):
+        seen = []
+
+        def hasher(values):
+            seen.append(values)
+            return values
+
+        cache = RowCache(ir, "person", ["person/tax"], hasher=hasher)
+        cache.run({"person": [{"id": 1, "income": 12000.0, "name": "x"}]})
+        assert seen == [(12000.0,)]
+
+    def test_default_hasher_is_exact(self):
+        assert blake2_hasher((0.0,)) != blake2_hasher((-0.0,))
+        assert blake2_hasher((1,)) != blake2_hasher((1.0,))
+        assert len(blake2_hasher((1.5, "a", None))) == 16
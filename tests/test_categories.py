@@ -0,0 +1,111 @@
+"""Categorical values: string categories, in/not in, dictionary-encoded columns."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    Categorical,
+    CompileError,
+    compile,
+    execute_columns,
+    generate_javascript,
+    generate_python,
+    parse,
+    run,
+)
+from rac.ast import to_source
+from rac.parser import ParseError
+
+SOURCE = """
+    entity person:
+        region: category
+        filing_status: str
+    variable person/scottish:
+        entity: person
+        from 2024-01-01: region == "SCOTLAND"
+    variable person/devolved:
+        entity: person
+        from 2024-01-01: region in ["SCOTLAND", "WALES", "NORTHERN_IRELAND"]
+    variable person/joint:
+        entity: person
+        from 2024-01-01: filing_status not in ["single", "head_of_household"]
+"""
+
+REGIONS = ["ENGLAND", "SCOTLAND", "WALES", "ENGLAND"]
+STATUSES = ["single", "married_joint", "head_of_household", "married_joint"]
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestMembership:
+    def test_run(self, ir):
+        rows = [
+            {"id": i, "region": r, "filing_status": s}
+            for i, (r, s) in enumerate(zip(REGIONS, STATUSES))
+        ]
+        columns = run(ir, {"person": rows}).entities["person"]
+        assert columns["person/scottish"] == [False, True, False, False]
+        assert columns["person/devolved"] == [False, True, True, False]
+        assert columns["person/joint"] == [False, True, False, True]
+
+    def test_round_trips_through_source(self, ir):
+        expr = ir.variables["person/joint"].expr
+        assert to_source(expr) == '(filing_status not in ["single", "head_of_household"])'
+
+    def test_in_is_still_a_name(self):
+        module = parse("variable gov/x:\n    from 2024-01-01: in + 1\n")
+        assert to_source(module.variables[0].values[0].expr) == "(in + 1)"
+
+    def test_categories_must_be_literals(self):
+        with pytest.raises(ParseError, match="must be literal"):
+            parse("variable gov/x:\n    from 2024-01-01: 1 in [gov/y]\n")
+
+    def test_type_mismatch_rejected(self):
+        source = SOURCE + """
+    variable person/bad:
+        entity: person
+        from 2024-01-01: region in [1, 2]
+"""
+        with pytest.raises(CompileError, match="compares different types"):
+            compile([parse(source)], as_of=date(2024, 1, 1))
+
+    def test_generated_code(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        person = namespace["PersonInput"](region="WALES", filing_status="single")
+        out = namespace["compute_person"](person, namespace["compute_scalars"]())
+        assert (out["person_devolved"], out["person_joint"]) == (True, False)
+        assert '["single", "head_of_household"].includes(' in generate_javascript(ir)
+
+
+class TestCategorical:
+    def test_encode(self):
+        column = Categorical.encode(["b", "a", None, "b"])
+        assert (column.codes, column.categories) == ([0, 1, None, 0], ["b", "a"])
+        assert list(column) == ["b", "a", None, "b"]
+        assert column[1:3] == ["a", None]
+
+    def test_columns_match_plain_strings(self, ir):
+        plain = {"region": REGIONS, "filing_status": STATUSES}
+        encoded = {name: Categorical.encode(values) for name, values in plain.items()}
+        assert execute_columns(ir, "person", encoded) == execute_columns(ir, "person", plain)
+
+    def test_compared_once_per_category(self, ir):
+        class Counting(str):
+            compared = 0
+
+            def __eq__(self, other):
+                Counting.compared += 1
+                return str.__eq__(self, other)
+
+            __hash__ = str.__hash__
+
+        categories = [Counting("ENGLAND"), Counting("SCOTLAND")]
+        column = Categorical([0, 1, 0, 0, 1, 0], categories)
+        out = execute_columns(ir, "person", {"region": column}, outputs=["person/scottish"])
+        assert out["person/scottish"] == [False, True, False, False, True, False]
+        assert Counting.compared == 2
@@ -5,6 +5,10 @@ Tests the full pipeline: parse .rac source -> compile -> generate JS/Python.
 
 from datetime import date
 
+import pytest
+
+from rac import CompileError
+
 # Shared RAC source used across tests
 TAX_MODEL_SOURCE = """
     entity person:
@@ -98,6 +102,19 @@ class TestJavaScriptGenerator:
         assert "Math.min" in js_code
         assert "Math.max" in js_code
 
+    def test_generate_js_coalesce(self):
+        from rac import compile, generate_javascript, parse
+
+        module = parse("""
+            entity person:
+                hours: float
+            variable person/hours_filled:
+                entity: person
+                from 2024-01-01: coalesce(hours, 35)
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        assert "?? 35" in generate_javascript(ir)
+
     def test_generate_js_min_max(self):
         from rac import compile, generate_javascript, parse
 
@@ -126,6 +143,16 @@ class TestJavaScriptGenerator:
         assert "Math.abs" in js_code
         assert "Math.round" in js_code
 
+    def test_generate_js_where_and_rounding(self):
+        from rac import compile, generate_javascript, parse
+
+        module = parse("""
+            variable gov/a:
+                from 2024-01-01: where(1 > 0, floor(2.5), sign(-2))
+        """)
+        js_code = generate_javascript(compile([module], as_of=date(2024, 6, 1)))
+        assert "((1 > 0) ? Math.floor(2.5) : Math.sign((-2)))" in js_code
+
     def test_generate_js_unary_neg(self):
         from rac import compile, generate_javascript, parse
 
@@ -258,6 +285,7 @@ class TestJavaScriptGenerator:
         )
         result = gen._gen_expr(expr)
         assert "===" in result
+        assert result.endswith(": racNoMatch(status))")
 
     def test_generate_js_unknown_builtin(self):
         from rac.codegen.javascript import JavaScriptGenerator
@@ -266,11 +294,11 @@ class TestJavaScriptGenerator:
 
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = JavaScriptGenerator(ir, "test")
-        result = gen._gen_builtin_call("unknown_func", ["1", "2"])
-        assert "unknown" in result
+        with pytest.raises(CompileError, match="unknown_func has no JavaScript translation"):
+            gen._gen_builtin_call("unknown_func", ["1", "2"])
 
     def test_generate_js_unknown_expr(self):
-        """Unknown AST node type falls to default."""
+        """An unknown AST node type is refused, not computed as 0."""
         from rac.codegen.javascript import JavaScriptGenerator
         from rac.compiler import IR
         from rac.schema import Schema
@@ -278,8 +306,8 @@ class TestJavaScriptGenerator:
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = JavaScriptGenerator(ir, "test")
         # Pass a non-Expr object to trigger default branch
-        result = gen._gen_expr("not_an_expr")
-        assert result == "0"
+        with pytest.raises(CompileError, match="can't generate JavaScript for str"):
+            gen._gen_expr("not_an_expr")
 
     def test_generate_js_reserved_word_ident(self):
         from rac.codegen.javascript import JavaScriptGenerator
@@ -299,8 +327,10 @@ class TestJavaScriptGenerator:
 
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = JavaScriptGenerator(ir, "test")
-        assert "reduce" in gen._gen_builtin_call("sum", ["arr"])
+        assert gen._gen_builtin_call("sum", ["arr"]) == "racSum(arr)"
         assert "length" in gen._gen_builtin_call("len", ["arr"])
+        assert gen._gen_builtin_call("any", ["arr"]) == "arr.some(Boolean)"
+        assert gen._gen_builtin_call("all", ["arr"]) == "arr.every(Boolean)"
 
     def test_generate_js_or_operator(self):
         from rac import compile, generate_javascript, parse
@@ -428,6 +458,18 @@ class TestPythonGenerator:
         assert "min(" in py_code
         assert "max(" in py_code
 
+    def test_generate_python_coalesce(self):
+        from rac import compile, generate_python, parse
+
+        module = parse("""
+            variable gov/val:
+                from 2024-01-01: coalesce(1, 2)
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        py_code = generate_python(ir)
+        assert "if v is not None" in py_code
+        assert "unknown" not in py_code
+
     def test_generate_python_min_max(self):
         from rac import compile, generate_python, parse
 
@@ -589,19 +631,19 @@ class TestPythonGenerator:
 
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = PythonGenerator(ir, "test")
-        result = gen._gen_builtin_call("unknown_func", ["1", "2"])
-        assert "unknown" in result
+        with pytest.raises(CompileError, match="unknown_func has no Python translation"):
+            gen._gen_builtin_call("unknown_func", ["1", "2"])
 
     def test_generate_python_unknown_expr(self):
-        """Unknown AST node type falls to default."""
+        """An unknown AST node type is refused, not computed as 0."""
         from rac.codegen.python import PythonGenerator
         from rac.compiler import IR
         from rac.schema import Schema
 
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = PythonGenerator(ir, "test")
-        result = gen._gen_expr("not_an_expr")
-        assert result == "0"
+        with pytest.raises(CompileError, match="can't generate Python for str"):
+            gen._gen_expr("not_an_expr")
 
     def test_generate_python_reserved_word_ident(self):
         from rac.codegen.python import PythonGenerator
@@ -621,8 +663,10 @@ class TestPythonGenerator:
 
         ir = IR(schema_=Schema(), variables={}, order=[])
         gen = PythonGenerator(ir, "test")
-        assert gen._gen_builtin_call("sum", ["arr"]) == "sum(arr)"
+        assert gen._gen_builtin_call("sum", ["arr"]) == "_sum(arr)"
         assert gen._gen_builtin_call("len", ["arr"]) == "len(arr)"
+        assert gen._gen_builtin_call("any", ["arr"]) == "any(arr)"
+        assert gen._gen_builtin_call("all", ["arr"]) == "all(arr)"
 
     def test_generate_python_field_types(self):
         from rac.codegen.python import PythonGenerator
@@ -696,6 +740,25 @@ class TestPythonGenerator:
         result = namespace["compute_person"](person, scalars)
         assert result["person_tax"] == 10000.0
 
+    def test_generate_python_exec_unmatched(self):
+        """A value no case matches raises, as in the executor, rather than giving 0."""
+        from rac import compile, generate_python, parse
+
+        module = parse("""
+            entity person:
+                band: int
+            variable person/rate:
+                entity: person
+                from 2024-01-01: match band: 1 => 0.1 2 => 0.2
+        """)
+        namespace: dict = {}
+        exec(generate_python(compile([module], as_of=date(2024, 6, 1))), namespace)
+        scalars = namespace["compute_scalars"]()
+        person = namespace["PersonInput"]
+        assert namespace["compute_person"](person(band=2), scalars)["person_rate"] == 0.2
+        with pytest.raises(ValueError, match="no match for: 3"):
+            namespace["compute_person"](person(band=3), scalars)
+
     def test_generate_python_exec_conditional(self):
         """Verify conditional logic executes correctly."""
         from rac import compile, generate_python, parse
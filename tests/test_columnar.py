@@ -0,0 +1,149 @@
+"""Column-at-a-time evaluation agrees with the row-by-row path."""
+
+from datetime import date
+
+import numpy as np
+import pytest
+
+from rac import (
+    EngineConfig,
+    ExecutionError,
+    compile,
+    execute_columns,
+    execute_numpy,
+    parse,
+    register_function,
+    run,
+    unregister_function,
+)
+
+SOURCE = """
+    entity person:
+        income: float
+        age: int
+        status: str
+    variable gov/threshold:
+        from 2024-01-01: 20000
+    variable person/taxable:
+        entity: person
+        from 2024-01-01: max(0, income - gov/threshold)
+    variable person/band:
+        entity: person
+        from 2024-01-01:
+            match status:
+                "single" => 1
+                "joint" => 2
+                "widowed" => 1
+    variable person/credit:
+        entity: person
+        from 2024-01-01: if age >= 65 and not (status == "joint"): probe(income) else: 0
+    variable person/net:
+        entity: person
+        from 2024-01-01: income - person/taxable * 0.2 + person/credit / person/band
+"""
+
+DATA = {
+    "person": [
+        {"id": 1, "income": 10000.0, "age": 70, "status": "single"},
+        {"id": 2, "income": 50000.0, "age": 40, "status": "joint"},
+        {"id": 3, "income": 30000.0, "age": 66, "status": "widowed"},
+        {"id": 4, "income": 0.0, "age": 80, "status": "single"},
+    ]
+}
+
+ROWS = EngineConfig(columnar=False)
+
+
+@pytest.fixture
+def calls():
+    seen: list = []
+    register_function("probe", lambda x: seen.append(x) or x / 10, memoize=False)
+    yield seen
+    unregister_function("probe")
+
+
+@pytest.fixture
+def ir(calls):
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestColumnar:
+    def test_matches_row_path(self, ir):
+        columnar = run(ir, DATA)
+        assert columnar.entities == run(ir, DATA, config=ROWS).entities
+        assert columnar.entities["person"]["person/credit"] == [1000.0, 0, 3000.0, 0.0]
+
+    def test_branch_sees_only_its_rows(self, ir, calls):
+        run(ir, DATA)
+        assert calls == [10000.0, 30000.0, 0.0]
+
+    def test_match_without_default_names_the_value(self):
+        source = SOURCE.replace('"widowed" => 1', "")
+        ir = compile([parse(source)], as_of=date(2024, 1, 1))
+        for config in (None, ROWS):
+            with pytest.raises(ExecutionError, match="no match for: widowed"):
+                run(ir, DATA, config=config, outputs=["person/band"])
+
+    def test_missing_field(self, ir):
+        data = {"person": [{**row, "age": 30} for row in DATA["person"]]}
+        del data["person"][2]["status"]
+        with pytest.raises(ExecutionError, match="undefined: status"):
+            run(ir, data, outputs=["person/band"])
+
+    def test_empty_table(self, ir):
+        assert run(ir, {"person": []}).entities["person"]["person/net"] == []
+
+
+NUMERIC = """
+    entity person:
+        income: float
+        age: int
+    variable gov/threshold:
+        from 2024-01-01: 20000
+    variable person/taxable:
+        entity: person
+        from 2024-01-01: max(0, income - gov/threshold)
+    variable person/senior:
+        entity: person
+        from 2024-01-01: age >= 65
+"""
+
+
+@pytest.fixture
+def numeric():
+    return compile([parse(NUMERIC)], as_of=date(2024, 1, 1))
+
+
+class TestExecuteColumns:
+    def test_matches_run(self, numeric):
+        rows = [{"id": 1, "income": 10000.0, "age": 70}, {"id": 2, "income": 50000.0, "age": 40}]
+        fields = {"income": [10000.0, 50000.0], "age": [70, 40]}
+        expected = run(numeric, {"person": rows}).entities["person"]
+        assert execute_columns(numeric, "person", fields) == expected
+
+    def test_outputs_prune(self, numeric):
+        fields = {"income": [10000.0, 50000.0]}
+        assert execute_columns(numeric, "person", fields, outputs=["person/taxable"]) == {
+            "person/taxable": [0, 30000.0]
+        }
+
+    def test_ragged_columns(self, numeric):
+        with pytest.raises(ExecutionError, match="differ in length"):
+            execute_columns(numeric, "person", {"income": [1.0, 2.0], "age": [1]})
+
+    def test_missing_column(self, numeric):
+        with pytest.raises(ExecutionError, match="undefined: age"):
+            execute_columns(numeric, "person", {"income": [1.0]})
+
+
+class TestExecuteNumpy:
+    def test_float64_outputs(self, numeric):
+        columns = {"income": np.array([10000.0, 50000.0]), "age": np.array([70, 40])}
+        out = execute_numpy(numeric, "person", columns)
+        assert list(out) == ["person/taxable", "person/senior"]
+        assert list(out["person/taxable"]) == [0.0, 30000.0]
+        assert list(out["person/senior"]) == [1.0, 0.0]
+
+    def test_rejects_two_dimensional_input(self, numeric):
+        with pytest.raises(ExecutionError, match="one-dimensional"):
+            execute_numpy(numeric, "person", {"income": np.array([[1.0, 2.0]])})
@@ -0,0 +1,236 @@
+"""CompiledModel: an IR checked and prepared once, run many times."""
+
+import shutil
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompiledModel,
+    CompileError,
+    EngineConfig,
+    compile,
+    dump_ir,
+    parse,
+    register_function,
+    run,
+    unregister_function,
+)
+
+SOURCE = """
+    entity person:
+        income: float
+    variable gov/rate:
+        from 2024-01-01: probe(0.2)
+    variable gov/allowance:
+        from 2024-01-01: 10000
+    variable person/taxable:
+        entity: person
+        from 2024-01-01: max(0, income - gov/allowance)
+    variable person/tax:
+        entity: person
+        from 2024-01-01: person/taxable * gov/rate
+"""
+
+
+def _data(*incomes):
+    return {"person": [{"id": i, "income": v} for i, v in enumerate(incomes)]}
+
+
+@pytest.fixture
+def calls():
+    seen: list = []
+    register_function("probe", lambda x: seen.append(x) or x, memoize=False)
+    yield seen
+    unregister_function("probe")
+
+
+@pytest.fixture
+def ir(calls):
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestCompiledModel:
+    def test_matches_run(self, ir):
+        model = CompiledModel.compile(ir)
+        for data in (_data(5000.0, 30000.0), _data(12000.0)):
+            assert model.run(data).entities == run(ir, data).entities
+
+    def test_scalars_resolved_once(self, ir, calls):
+        model = CompiledModel.compile(ir)
+        for income in range(5):
+            model.run(_data(float(income)))
+        assert calls == [0.2]
+        assert model.scalars == {"gov/rate": 0.2, "gov/allowance": 10000}
+
+    def test_outputs(self, ir):
+        model = CompiledModel.compile(ir)
+        result = model.run(_data(30000.0), outputs=["person/taxable"])
+        assert result.entities == {"person": {"person/taxable": [20000.0]}}
+
+    def test_from_serialized_ir(self, ir):
+        model = CompiledModel.compile(dump_ir(ir))
+        assert model.run(_data(30000.0)).entities["person"]["person/tax"] == [4000.0]
+
+    def test_rejects_inconsistent_ir(self, ir):
+        broken = ir.model_copy(update={"order": list(reversed(ir.order))})
+        with pytest.raises(CompileError, match="before its dependency"):
+            CompiledModel.compile(broken)
+
+
+class TestBatches:
+    def test_matches_one_run(self, ir):
+        model = CompiledModel.compile(ir)
+        incomes = [5000.0, 30000.0, 12000.0, 80000.0, 0.0]
+        results = model.run_batches(_data(*incomes[i : i + 2]) for i in range(0, 5, 2))
+        taxes = [tax for r in results for tax in r.entities["person"]["person/tax"]]
+        assert taxes == run(ir, _data(*incomes)).entities["person"]["person/tax"]
+
+    def test_lazy(self, ir):
+        model = CompiledModel.compile(ir)
+        read = []
+
+        def batches():
+            for income in (1.0, 2.0, 3.0):
+                read.append(income)
+                yield _data(income)
+
+        results = model.run_batches(batches(), outputs=["person/taxable"])
+        assert read == []
+        next(results)
+        assert read == [1.0]
+        assert len(list(results)) == 2
+
+
+class TestCsv:
+    def test_run_csv(self, ir, tmp_path):
+        path = tmp_path / "people.csv"
+        path.write_text("id;income\n1;5000\n2;30000\n")
+        model = CompiledModel.compile(ir)
+        columns = model.run_csv(path, "person", delimiter=";")
+        assert columns == run(ir, _data(5000.0, 30000.0)).entities["person"]
+
+    def test_number_format_from_config(self, ir, tmp_path):
+        path = tmp_path / "people.csv"
+        path.write_text('id,income\n1,"30.000,00"\n')
+        model = CompiledModel.compile(ir, EngineConfig(number_format="eu"))
+        columns = model.run_csv(path, "person", outputs=["person/tax"])
+        assert columns["person/tax"] == run(ir, _data(30000.0)).entities["person"]["person/tax"]
+
+
+class TestSaveLoad:
+    def test_round_trip(self, ir, tmp_path):
+        path = tmp_path / "model.rac"
+        model = CompiledModel.compile(ir, EngineConfig(number_format="eu"), backend="bytecode")
+        model.save(path)
+        loaded = CompiledModel.load(path)
+        assert loaded.ir == ir
+        assert loaded.backend == "bytecode"
+        assert loaded.config == model.config
+        data = _data(5000.0, 30000.0)
+        assert loaded.run(data).entities == model.run(data).entities
+
+    def test_default_config(self, ir, tmp_path):
+        path = tmp_path / "model.rac"
+        CompiledModel.compile(ir).save(path)
+        assert CompiledModel.load(path).config is None
+
+    def test_rejects_bad_files(self, ir, tmp_path):
+        path = tmp_path / "model.rac"
+        path.write_bytes(dump_ir(ir).encode())
+        with pytest.raises(CompileError, match="not a saved model"):
+            CompiledModel.load(path)
+
+        CompiledModel.compile(ir).save(path)
+        blob = path.read_bytes()
+        path.write_bytes(blob[:-1] + bytes([blob[-1] ^ 1]))
+        with pytest.raises(CompileError, match="checksum"):
+            CompiledModel.load(path)
+
+        path.write_bytes(blob[:8] + b"\xff\xff" + blob[10:])
+        with pytest.raises(CompileError, match="newer than supported"):
+            CompiledModel.load(path)
+
+
+class TestBackend:
+    def test_bytecode_matches_tree(self, ir):
+        model = CompiledModel.compile(ir, backend="bytecode")
+        data = _data(5000.0, 30000.0, 12000.0)
+        assert model.run(data).entities == CompiledModel.compile(ir).run(data).entities
+
+    def test_programs_compiled_once(self, ir):
+        model = CompiledModel.compile(ir, backend="bytecode")
+        model.run(_data(1.0))
+        programs = dict(model._executor(None).programs)
+        model.run(_data(2.0))
+        assert set(programs) == {"person/taxable", "person/tax"}
+        assert all(model._executor(None).programs[p] is programs[p] for p in programs)
+
+    @pytest.mark.skipif(not shutil.which("cargo"), reason="Rust toolchain not available")
+    def test_native_matches_tree(self):
+        source = SOURCE.replace("probe(0.2)", "0.2") + """
+    variable person/pays:
+        entity: person
+        from 2024-01-01: person/tax > 0
+"""
+        ir = compile([parse(source)], as_of=date(2024, 1, 1))
+        model = CompiledModel.compile(ir, backend="native")
+        data = _data(5000.0, 30000.0, 12000.0)
+        assert model.run(data).entities == CompiledModel.compile(ir).run(data).entities
+        assert model.run(data, outputs=["person/taxable"]).entities == {
+            "person": {"person/taxable": [0.0, 20000.0, 2000.0]}
+        }
+        assert model.scalars == {"gov/rate": 0.2, "gov/allowance": 10000}
+
+    def test_native_has_no_fixed_point(self, ir):
+        config = EngineConfig(decimal_places=2)
+        with pytest.raises(CompileError, match="no fixed-point arithmetic"):
+            CompiledModel.compile(ir, config, backend="native")
+
+    def test_unknown_backend(self, ir):
+        with pytest.raises(CompileError, match="unknown backend 'jit'"):
+            CompiledModel.compile(ir, backend="jit")
+
+
+def _expr(source):
+    """The formula of a one-line scalar variable."""
+    module = parse(f"variable gov/x:\n    from 2024-01-01: {source}\n")
+    return module.variables[0].values[0].expr
+
+
+class TestReform:
+    def test_parameter_value(self, ir):
+        model = CompiledModel.compile(ir)
+        result = model.run_with_reform(_data(30000.0), {"gov/allowance": 12000})
+        assert result.entities["person"]["person/tax"] == [pytest.approx(3600.0)]
+
+    def test_expression(self, ir):
+        model = CompiledModel.compile(ir)
+        overrides = {"person/taxable": _expr("max(0, income - 2 * gov/allowance)")}
+        result = model.run_with_reform(_data(30000.0), overrides)
+        assert result.entities["person"]["person/tax"] == [pytest.approx(2000.0)]
+
+    def test_side_by_side(self, ir):
+        model = CompiledModel.compile(ir)
+        comparison = model.run_with_reform(_data(30000.0), {"gov/rate": 0.25}, compare=True)
+        assert comparison.baseline.entities["person"]["person/tax"] == [4000.0]
+        assert comparison.reform.entities["person"]["person/tax"] == [5000.0]
+        assert comparison.changed == ["gov/rate", "person/tax"]
+        # Only what the reform touches is evaluated again
+        assert "person/taxable" in comparison.reform.reused
+
+    def test_baseline_model_unchanged(self, ir, calls):
+        model = CompiledModel.compile(ir)
+        model.run_with_reform(_data(30000.0), {"gov/allowance": 0})
+        assert model.run(_data(30000.0)).entities["person"]["person/tax"] == [4000.0]
+        assert calls == [0.2]
+
+    def test_unknown_variable(self, ir):
+        model = CompiledModel.compile(ir)
+        with pytest.raises(CompileError, match="unknown variable"):
+            model.run_with_reform(_data(1.0), {"gov/missing": 1})
+
+    def test_cycle_rejected(self, ir):
+        model = CompiledModel.compile(ir)
+        with pytest.raises(CompileError, match="circular dependency"):
+            model.run_with_reform(_data(1.0), {"gov/allowance": _expr("person/tax")})
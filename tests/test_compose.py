@@ -0,0 +1,182 @@
+"""Tests for composing compiled IR bundles."""
+
+from datetime import date
+
+import pytest
+
+from rac import CompileError, compile, compose, execute, parse
+from rac.ast import BinOp, Literal, Match, Var
+
+PERSON = """
+    entity person:
+        income: float
+"""
+
+FEDERAL = PERSON + """
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+"""
+
+STATE = PERSON + """
+    variable gov/rate:
+        from 2024-01-01: 0.05
+
+    variable person/state_tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+"""
+
+CREDITS = PERSON + """
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/credit:
+        entity: person
+        from 2024-01-01: person/tax * 0.5
+"""
+
+DATA = {"person": [{"id": 1, "income": 1000.0}]}
+
+
+def build(source):
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+def outputs(ir):
+    return {k: v[0] for k, v in execute(ir, DATA).entities["person"].items()}
+
+
+class TestCompose:
+    def test_clean_merge(self):
+        extra = build(PERSON + """
+    variable person/double:
+        entity: person
+        from 2024-01-01: income * 2
+""")
+        merged = compose([build(FEDERAL), extra])
+        assert merged.conflicts == []
+        assert outputs(merged.ir) == {"person/tax": 200.0, "person/double": 2000.0}
+
+    def test_identical_duplicate_merges(self):
+        merged = compose([build(FEDERAL), build(CREDITS)])
+        assert [(c.path, c.identical) for c in merged.conflicts] == [("gov/rate", True)]
+        # Cross-bundle dependency resolved and ordered in the union
+        order = merged.ir.order
+        assert order.index("person/tax") < order.index("person/credit")
+        assert outputs(merged.ir)["person/credit"] == 100.0
+
+    def test_conflict_error(self):
+        with pytest.raises(CompileError, match="gov/rate"):
+            compose([build(FEDERAL), build(STATE)])
+
+    def test_conflict_last_wins(self):
+        merged = compose([build(FEDERAL), build(STATE)], on_conflict="last_wins")
+        assert merged.conflicts[0].sources == ["0", "1"]
+        assert not merged.conflicts[0].identical
+        assert outputs(merged.ir) == {"person/tax": 50.0, "person/state_tax": 50.0}
+
+    def test_conflict_namespace(self):
+        merged = compose(
+            {"federal": build(FEDERAL), "state": build(STATE)}, on_conflict="namespace"
+        )
+        assert "gov/rate" not in merged.ir.variables
+        result = execute(merged.ir, DATA)
+        assert result.scalars["federal/gov/rate"] == 0.2
+        assert result.scalars["state/gov/rate"] == 0.05
+        assert outputs(merged.ir) == {"person/tax": 200.0, "person/state_tax": 50.0}
+
+    def test_namespace_renames_match_patterns(self):
+        federal = build(FEDERAL)
+        # A flat 100 when income is 5000 times the bundle's own rate
+        pattern = BinOp(op="*", left=Var(path="gov/rate"), right=Literal(value=5000))
+        federal.variables["person/tax"].expr = Match(
+            subject=Var(path="income"),
+            cases=[(pattern, Literal(value=100))],
+            default=Literal(value=0),
+        )
+        merged = compose({"federal": federal, "state": build(STATE)}, on_conflict="namespace")
+        tax = merged.ir.variables["person/tax"]
+        assert tax.expr.cases[0][0].left.path == "federal/gov/rate"
+        assert "federal/gov/rate" in tax.deps
+        assert outputs(merged.ir) == {"person/tax": 100, "person/state_tax": 50.0}
+
+    def test_namespace_ambiguous_reference(self):
+        user = build(PERSON + """
+    variable person/net:
+        entity: person
+        from 2024-01-01: income * (1 - gov/rate)
+""")
+        user.variables.pop("gov/rate", None)
+        with pytest.raises(CompileError, match="ambiguous|defined differently"):
+            compose([build(FEDERAL), build(STATE), user], on_conflict="namespace")
+
+    def test_schema_field_conflict(self):
+        other = build("""
+    entity person:
+        income: int
+""")
+        with pytest.raises(CompileError, match="income"):
+            compose([build(PERSON), other])
+
+    def test_unknown_policy(self):
+        with pytest.raises(CompileError, match="policy"):
+            compose([build(FEDERAL)], on_conflict="merge")
+
+
+TAX = PERSON + """
+    variable person/income:
+        entity: person
+        from 2024-01-01: income * 0.9
+
+    variable person/liability:
+        entity: person
+        from 2024-01-01: person/income * 0.2
+"""
+
+BENEFIT = """
+    variable person/income:
+        entity: person
+        from 2024-01-01: income - tax/person/liability
+
+    variable person/award:
+        entity: person
+        from 2024-01-01: max(0, 5000 - person/income * 0.1)
+
+    amend person/award:
+        from 2025-01-01: 0
+"""
+
+
+class TestNamespaces:
+    def modules(self):
+        tax, benefit = parse(TAX), parse(BENEFIT)
+        tax.namespace, benefit.namespace = "tax", "benefit"
+        return [tax, benefit]
+
+    def test_same_path_does_not_collide(self):
+        ir = compile(self.modules(), as_of=date(2024, 1, 1))
+        assert {"tax/person/income", "benefit/person/income"} <= set(ir.variables)
+        assert "person/income" not in ir.variables
+
+    def test_local_and_cross_references(self):
+        ir = compile(self.modules(), as_of=date(2024, 1, 1))
+        assert ir.variables["tax/person/liability"].deps == {"tax/person/income"}
+        assert ir.variables["benefit/person/award"].deps == {"benefit/person/income"}
+
+        person = execute(ir, {"person": [{"id": 1, "income": 10000.0}]}).entities["person"]
+        assert person["tax/person/income"] == [9000.0]
+        assert person["tax/person/liability"] == [1800.0]
+        assert person["benefit/person/income"] == [8200.0]
+        assert person["benefit/person/award"] == [pytest.approx(4180.0)]
+
+    def test_amendment_follows_namespace(self):
+        ir = compile(self.modules(), as_of=date(2025, 6, 1))
+        assert ir.variables["benefit/person/award"].deps == set()
+
+    def test_without_namespace_paths_collide(self):
+        with pytest.raises(CompileError, match="duplicate variable: person/income"):
+            compile([parse(TAX), parse(BENEFIT)], as_of=date(2024, 1, 1))
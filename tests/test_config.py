@@ -0,0 +1,174 @@
+"""Tests for layered engine configuration."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    ConfigError,
+    EngineConfig,
+    ExecutionError,
+    RandomManifest,
+    compile,
+    eval_variable,
+    parse,
+    resolve_config,
+    set_default_config,
+    verify_reproducibility,
+    weighted_quantile,
+)
+from rac.config import BUILTIN_CONFIG
+from rac.ingest import EU, US
+
+# Median 101 exactly; the 1% sketch reports a value slightly off it
+DATA = {"x": [float(i) for i in range(1, 202)]}
+
+
+@pytest.fixture(autouse=True)
+def clean(monkeypatch):
+    for name in EngineConfig.model_fields:
+        monkeypatch.delenv(f"RAC_{name.upper()}", raising=False)
+    set_default_config(None)
+    yield
+    set_default_config(None)
+
+
+@pytest.fixture
+def tax_var():
+    module = parse("""
+        entity person:
+            income: float
+        variable person/tax:
+            entity: person
+            from 2024-01-01: income * gov/rate
+    """)
+    return compile([module], as_of=date(2024, 1, 1)).variables["person/tax"]
+
+
+def is_approx(**kwargs):
+    return weighted_quantile(DATA, "x", 0.5, **kwargs) != 101.0
+
+
+class TestConstruction:
+    def test_from_dict(self):
+        cfg = EngineConfig.from_dict({"strict": False, "number_format": "eu"})
+        assert cfg.strict is False
+        assert cfg.number_format == EU
+
+    def test_thread_options_validated(self):
+        with pytest.raises(ConfigError, match="num_threads"):
+            EngineConfig.from_dict({"num_threads": -1})
+        with pytest.raises(ConfigError, match="chunk_size"):
+            EngineConfig.from_dict({"chunk_size": 0})
+
+    def test_from_dict_rejects_unknown(self):
+        with pytest.raises(ConfigError, match="threads"):
+            EngineConfig.from_dict({"threads": 8})
+
+    def test_from_env(self):
+        cfg = EngineConfig.from_env(
+            {"RAC_STRICT": "0", "RAC_APPROX_QUANTILES": "yes", "RAC_SEQUENTIAL_THRESHOLD": "50"}
+        )
+        assert (cfg.strict, cfg.approx_quantiles, cfg.sequential_threshold) == (False, True, 50)
+        assert cfg.quantile_accuracy is None
+
+    def test_from_env_invalid(self):
+        with pytest.raises(ConfigError, match="sequential_threshold"):
+            EngineConfig.from_env({"RAC_SEQUENTIAL_THRESHOLD": "lots"})
+
+    def test_builtin(self):
+        assert resolve_config() == BUILTIN_CONFIG
+
+
+class TestPrecedence:
+    """call > instance > process default > env > built-in."""
+
+    def test_sequential_threshold(self, monkeypatch):
+        assert resolve_config().sequential_threshold == 2_000
+        monkeypatch.setenv("RAC_SEQUENTIAL_THRESHOLD", "10")
+        assert resolve_config().sequential_threshold == 10
+        set_default_config({"sequential_threshold": 20})
+        assert resolve_config().sequential_threshold == 20
+        instance = EngineConfig(sequential_threshold=30)
+        assert resolve_config(instance).sequential_threshold == 30
+        assert resolve_config(instance, sequential_threshold=40).sequential_threshold == 40
+        # An instance layer that leaves the option unset falls through
+        assert resolve_config(EngineConfig(strict=False)).sequential_threshold == 20
+
+    def test_strict(self, monkeypatch, tax_var):
+        inputs = {"income": 100.0}
+        with pytest.raises(ExecutionError):
+            eval_variable(tax_var, inputs)
+        monkeypatch.setenv("RAC_STRICT", "false")
+        assert eval_variable(tax_var, inputs) == 0
+        set_default_config(EngineConfig(strict=True))
+        with pytest.raises(ExecutionError):
+            eval_variable(tax_var, inputs)
+        assert eval_variable(tax_var, inputs, config=EngineConfig(strict=False)) == 0
+        with pytest.raises(ExecutionError):
+            eval_variable(tax_var, inputs, strict=True, config=EngineConfig(strict=False))
+
+    def test_approx_quantiles(self, monkeypatch):
+        assert not is_approx()
+        monkeypatch.setenv("RAC_APPROX_QUANTILES", "1")
+        assert is_approx()
+        set_default_config(EngineConfig(approx_quantiles=False))
+        assert not is_approx()
+        assert is_approx(config=EngineConfig(approx_quantiles=True))
+        assert not is_approx(approx=False, config=EngineConfig(approx_quantiles=True))
+
+    def test_number_format(self, monkeypatch):
+        assert resolve_config().number_format is None
+        monkeypatch.setenv("RAC_NUMBER_FORMAT", "EU")
+        assert resolve_config().number_format == EU
+        assert resolve_config(number_format=US).number_format == US
+
+
+    def test_thread_options_reach_the_binary(self, monkeypatch):
+        from rac import Model, compile
+
+        class Binary:
+            entity_outputs = {"person": ["person/tax"]}
+            calls: list = []
+
+            def run(self, data, number_format, **options):
+                self.calls.append(options)
+                return {}
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * 0.2
+        """)
+        binary = Binary()
+        ir = compile([module], as_of=date(2024, 1, 1))
+        model = Model(ir, binary, EngineConfig(num_threads=4))
+        data = {"person": [{"income": 1.0}]}
+        model.run(data, sequential_threshold=0)
+        model.run(data, sequential_threshold=0, parallel=False, chunk_size=256)
+        assert binary.calls == [
+            {
+                "num_threads": 4,
+                "chunk_size": 1,
+                "parallel": True,
+                "deterministic": False,
+                "seed": 0,
+            },
+            {
+                "num_threads": 4,
+                "chunk_size": 256,
+                "parallel": False,
+                "deterministic": False,
+                "seed": 0,
+            },
+        ]
+
+
+class TestRecording:
+    def test_manifest_reports_config_difference(self):
+        a = RandomManifest(seed=1, config=resolve_config())
+        b = RandomManifest(seed=1, config=resolve_config(approx_quantiles=True))
+        assert verify_reproducibility(a, a.model_copy()) == []
+        assert verify_reproducibility(a, b) == ["engine config differs: approx_quantiles"]
@@ -0,0 +1,156 @@
+"""Dates: date literals, ISO-date inputs and the date built-ins."""
+
+import math
+import shutil
+from datetime import date
+
+import pytest
+
+from rac import (
+    IR,
+    CompileError,
+    EngineConfig,
+    Model,
+    compile,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    parse,
+    run,
+)
+from rac.ast import to_source
+from rac.executor import EvalError
+
+SOURCE = """
+    entity person:
+        birthdate: date
+        claim_date: date
+    variable gov/period_start:
+        from 2024-04-06: 2024-04-06
+    variable person/age:
+        entity: person
+        from 2024-04-06: age_at(gov/period_start, birthdate)
+    variable person/born_before_cutoff:
+        entity: person
+        from 2024-04-06: birthdate < 1957-04-06
+    variable person/birth_year:
+        entity: person
+        from 2024-04-06: year_of(birthdate)
+    variable person/months_claimed:
+        entity: person
+        from 2024-04-06: months_between(claim_date, gov/period_start)
+    variable person/days_claimed:
+        entity: person
+        from 2024-04-06: days_between(claim_date, gov/period_start)
+"""
+
+PEOPLE = [
+    {"birthdate": date(1957, 4, 5), "claim_date": date(2024, 1, 6)},
+    {"birthdate": date(1957, 4, 6), "claim_date": date(2024, 1, 7)},
+    {"birthdate": date(1990, 4, 7), "claim_date": date(2023, 4, 6)},
+]
+
+EXPECTED = {
+    "person/age": [67, 67, 33],
+    "person/born_before_cutoff": [True, False, False],
+    "person/birth_year": [1957, 1957, 1990],
+    "person/months_claimed": [3, 2, 12],
+    "person/days_claimed": [91, 90, 366],
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 4, 6))
+
+
+def _people(rows):
+    return {"person": [{"id": i, **row} for i, row in enumerate(rows)]}
+
+
+class TestExecute:
+    def test_dates(self, ir):
+        for config in (None, EngineConfig(columnar=False)):
+            out = run(ir, _people(PEOPLE), config=config).entities["person"]
+            assert {path: out[path] for path in EXPECTED} == EXPECTED
+
+    def test_iso_strings_read_as_dates(self, ir):
+        iso = [{k: v.isoformat() for k, v in row.items()} for row in PEOPLE]
+        out = run(ir, _people(iso)).entities["person"]
+        assert {path: out[path] for path in EXPECTED} == EXPECTED
+
+    def test_missing_date_is_missing(self, ir):
+        rows = [{"birthdate": None, "claim_date": date(2024, 1, 6)}]
+        out = run(ir, _people(rows), outputs=["person/age"]).entities["person"]
+        assert out["person/age"] == [None]
+
+    def test_invalid_date(self, ir):
+        rows = [{"birthdate": "06/04/1957", "claim_date": date(2024, 1, 6)}]
+        with pytest.raises(EvalError, match="expected a date") as info:
+            run(ir, _people(rows))
+        assert info.value.kind == "invalid_date"
+
+
+class TestCompile:
+    def test_literal_round_trips_through_source(self, ir):
+        expr = ir.variables["person/born_before_cutoff"].expr
+        assert to_source(expr) == "(birthdate < 1957-04-06)"
+
+    def test_ir_json_round_trip(self, ir):
+        loaded = IR.model_validate_json(ir.model_dump_json(by_alias=True))
+        out = run(loaded, _people(PEOPLE)).entities["person"]
+        assert out["person/born_before_cutoff"] == EXPECTED["person/born_before_cutoff"]
+
+    def test_number_is_not_a_date(self):
+        source = SOURCE + """
+    variable person/bad:
+        entity: person
+        from 2024-04-06: year_of(2024)
+"""
+        with pytest.raises(CompileError, match="year_of needs dates"):
+            compile([parse(source)], as_of=date(2024, 4, 6))
+
+    def test_date_is_not_a_number(self):
+        source = SOURCE + """
+    variable person/bad:
+        entity: person
+        from 2024-04-06: birthdate + 1
+"""
+        with pytest.raises(CompileError, match="needs numbers, got date"):
+            compile([parse(source)], as_of=date(2024, 4, 6))
+
+
+class TestCodegen:
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        for i, row in enumerate(PEOPLE):
+            person = namespace["PersonInput"](**{k: v.isoformat() for k, v in row.items()})
+            out = namespace["compute_person"](person, scalars)
+            for path, values in EXPECTED.items():
+                assert out[path.replace("/", "_")] == values[i]
+
+    def test_javascript(self, ir):
+        code = generate_javascript(ir)
+        assert "function racAgeAt(" in code
+        assert '"1957-04-06"' in code
+
+    def test_rust(self, ir):
+        code = generate_rust(ir)
+        assert "fn rac_ymd(days: f64)" in code
+        assert "(input.birthdate < (-4653_f64))" in code
+
+
+@pytest.mark.skipif(not shutil.which("cargo"), reason="Rust toolchain not available")
+class TestNative:
+    def test_binary_matches_run(self):
+        model = Model.from_source(SOURCE, as_of=date(2024, 4, 6))
+        rows = PEOPLE + [{"birthdate": None, "claim_date": "2024-01-06"}]
+        result = model.run({"person": rows}, sequential_threshold=0).arrays["person"]
+        outputs = model.outputs("person")
+        for j, path in enumerate(outputs):
+            assert result[:3, j].tolist() == [float(v) for v in EXPECTED[path]]
+        missing = dict(zip(outputs, result[3].tolist()))
+        assert math.isnan(missing["person/age"]) and math.isnan(missing["person/birth_year"])
+        assert (missing["person/months_claimed"], missing["person/days_claimed"]) == (3, 91)
@@ -0,0 +1,72 @@
+"""Declared variable defaults: the value used when nothing is in force."""
+
+from datetime import date
+
+import pytest
+
+from rac import EngineConfig, ExecutionError, ParseError, compile, dump_ir, load_ir, parse, run
+
+SOURCE = """
+    entity person:
+        income: float
+    variable gov/niche_factor:
+        default: 1.0
+        from 2025-01-01: 1.5
+    variable gov/unset_rate:
+        from 2025-01-01: 0.1
+    variable person/scaled:
+        entity: person
+        from 2024-01-01: income * gov/niche_factor
+    variable person/levy:
+        entity: person
+        from 2024-01-01: income * gov/unset_rate
+"""
+
+DATA = {"person": [{"id": 1, "income": 1000.0}]}
+
+
+def build(source, as_of=date(2024, 6, 1)):
+    return compile([parse(source)], as_of=as_of)
+
+
+class TestParse:
+    @pytest.mark.parametrize(
+        "literal, value",
+        [("1.0", 1.0), ("-2", -2), ('"none"', "none"), ("true", True)],
+    )
+    def test_literal(self, literal, value):
+        module = parse(f"variable gov/a:\n    default: {literal}\n")
+        assert module.variables[0].default == value
+        assert module.variables[0].values == []
+
+    def test_must_be_literal(self):
+        with pytest.raises(ParseError, match="default must be a literal"):
+            parse("variable gov/a:\n    default: gov/b\n")
+
+
+class TestResolve:
+    def test_default_when_nothing_in_force(self):
+        var = build(SOURCE).variables["gov/niche_factor"]
+        assert var.expr.value == 1.0 and var.effective is None
+
+    def test_value_in_force_wins(self):
+        var = build(SOURCE, as_of=date(2025, 6, 1)).variables["gov/niche_factor"]
+        assert var.expr.value == 1.5 and var.effective == date(2025, 1, 1)
+
+    def test_round_trip(self):
+        ir = build(SOURCE)
+        assert load_ir(dump_ir(ir)) == ir
+
+
+class TestRun:
+    def test_dependent_uses_default_not_zero(self):
+        result = run(build(SOURCE), DATA, outputs=["person/scaled"])
+        assert result.entities["person"]["person/scaled"] == [1000.0]
+
+    def test_strict_errors_without_default(self):
+        with pytest.raises(ExecutionError, match="undefined: gov/unset_rate"):
+            run(build(SOURCE), DATA)
+
+    def test_lenient_reads_zero_without_default(self):
+        result = run(build(SOURCE), DATA, config=EngineConfig(strict=False))
+        assert result.entities["person"] == {"person/scaled": [1000.0], "person/levy": [0]}
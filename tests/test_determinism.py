@@ -0,0 +1,128 @@
+"""Parallel runs give bit-for-bit the same results for 1, 4 and 16 threads."""
+
+import shutil
+from datetime import date
+
+import pytest
+
+from rac import EngineConfig, compile, cross_join, parse, resolve_config, sweep
+from rac.rng import stream_id, uniform_at
+
+THREADS = (1, 4, 16)
+
+SOURCE = """
+    entity household:
+        rent: float
+
+    entity person:
+        household: -> household
+        income: float
+        age: float
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: max(0, income - 12570) * gov/rate + where(age > 65, income * 0.01, 0)
+
+    variable household/tax:
+        entity: household
+        from 2024-01-01: sum_over(person, person/tax)
+
+    variable household/net_rent:
+        entity: household
+        from 2024-01-01: rent - household/tax / 52
+"""
+
+
+def _draw(name: str, i: int) -> float:
+    return uniform_at(stream_id(2024, name, i), 0)
+
+
+# A reference dataset: 500 households of 1 to 4 people with incomes over
+# six orders of magnitude, so summation order would show in the last bits
+HOUSEHOLDS = [{"id": h, "rent": 300 + 900 * _draw("rent", h)} for h in range(500)]
+PEOPLE = [
+    {
+        "id": 10 * h + k,
+        "household": h,
+        "income": 10 ** (1 + 5 * _draw("income", 10 * h + k)) + 0.01,
+        "age": 18 + 80 * _draw("age", 10 * h + k),
+    }
+    for h in range(500)
+    for k in range(1 + int(4 * _draw("size", h)))
+]
+DATA = {"household": HOUSEHOLDS, "person": PEOPLE}
+
+
+def _bits(values) -> list[str]:
+    return [float(v).hex() for v in values]
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestScenarios:
+    @pytest.mark.parametrize("threads", THREADS[1:])
+    def test_sweep(self, ir, threads):
+        rates = [i / 100 for i in range(24)]
+        one = sweep(ir, DATA, "gov/rate", rates, "household/tax", max_workers=1)
+        many = sweep(ir, DATA, "gov/rate", rates, "household/tax", max_workers=threads)
+        assert _bits(many) == _bits(one)
+
+    @pytest.mark.parametrize("threads", THREADS[1:])
+    def test_cross_join(self, ir, threads):
+        scenarios = [{"gov/rate": i / 10} for i in range(8)]
+        one = cross_join(ir, DATA, scenarios, "household", max_workers=1)
+        many = cross_join(ir, DATA, scenarios, "household", max_workers=threads)
+        assert [list(row) for row in many] == [list(row) for row in one]
+        for a, b in zip(one, many):
+            assert _bits(a[k] for k in a if k.startswith("household/")) == _bits(
+                b[k] for k in b if k.startswith("household/")
+            )
+
+
+class TestNative:
+    def test_generated_main_splits_into_fixed_blocks(self, ir):
+        from rac.native import _generate_main
+
+        main = _generate_main(ir, {"person": ["income", "age"]}, {"person": ["person/tax"]})
+        assert 'let deterministic = args.get(6).is_some_and(|a| a == "1");' in main
+        assert ".par_chunks(n_input_fields * chunk_size)" in main
+        assert ".par_chunks_mut(n_output_fields * chunk_size)" in main
+
+    def test_option_reaches_the_binary(self, ir, monkeypatch):
+        from rac import Model
+
+        class Binary:
+            entity_outputs = {"person": ["person/tax"]}
+            calls: list = []
+
+            def run(self, data, number_format, **options):
+                self.calls.append(options["deterministic"])
+                return {}
+
+        binary = Binary()
+        model = Model(ir, binary, EngineConfig(deterministic=True))
+        model.run({"person": PEOPLE[:1]}, sequential_threshold=0)
+        model.run({"person": PEOPLE[:1]}, sequential_threshold=0, deterministic=False)
+        assert binary.calls == [True, False]
+        assert resolve_config().deterministic is False
+        monkeypatch.setenv("RAC_DETERMINISTIC", "1")
+        assert resolve_config().deterministic is True
+
+    @pytest.mark.skipif(not shutil.which("cargo"), reason="Rust toolchain not available")
+    @pytest.mark.parametrize("chunk_size", [1, 7, 4096])
+    def test_binary_across_thread_counts(self, chunk_size):
+        from rac import Model
+
+        source = SOURCE.split("    variable household/tax")[0]
+        model = Model.from_source(source, as_of=date(2024, 1, 1))
+        people = [{k: row[k] for k in ("income", "age")} for row in PEOPLE]
+        config = EngineConfig(deterministic=True, chunk_size=chunk_size, sequential_threshold=0)
+        runs = [model.run({"person": people}, config=config, num_threads=n) for n in THREADS]
+        reference = runs[0].arrays["person"].tobytes()
+        assert all(run.arrays["person"].tobytes() == reference for run in runs[1:])
@@ -0,0 +1,103 @@
+"""Equivalence scales and equivalized household income."""
+
+from datetime import date
+
+import pytest
+
+from rac import compile, execute, generate_javascript, generate_python, generate_rust, parse
+from rac.executor import BUILTINS
+
+equivalence_scale = BUILTINS["equivalence_scale"]
+equivalized_income = BUILTINS["equivalized_income"]
+
+SOURCE = """
+    entity household:
+        income: float
+        adults: int
+        children: int
+    variable gov/first:
+        from 2024-01-01: 1.0
+    variable gov/other:
+        from 2024-01-01: 0.7
+    variable gov/child:
+        from 2024-01-01: 0.5
+    variable household/scale:
+        entity: household
+        from 2024-01-01: equivalence_scale(adults, children)
+    variable household/equivalized:
+        entity: household
+        from 2024-01-01: equivalized_income(income, adults, children)
+    variable household/old_oecd_scale:
+        entity: household
+        from 2024-01-01: equivalence_scale(adults, children, gov/first, gov/other, gov/child)
+"""
+
+DATA = {
+    "household": [
+        {"id": 1, "income": 42000.0, "adults": 2, "children": 2},
+        {"id": 2, "income": 15000.0, "adults": 1, "children": 0},
+        {"id": 3, "income": 13000.0, "adults": 1, "children": 1},
+    ]
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestScale:
+    def test_oecd_modified(self):
+        # Two adults and two children: 1 + 0.5 + 2 * 0.3
+        assert equivalence_scale(2, 2) == pytest.approx(2.1)
+        assert equivalence_scale(1, 0) == 1
+
+    def test_custom_weights(self):
+        # The original OECD scale: 1 + 0.7 + 2 * 0.5
+        assert equivalence_scale(2, 2, 1.0, 0.7, 0.5) == pytest.approx(2.7)
+
+    def test_children_only(self):
+        assert equivalence_scale(0, 3) == pytest.approx(1.6)
+
+    def test_empty_household(self):
+        assert equivalence_scale(0, 0) == 0
+        assert equivalized_income(0, 0, 0) == 0
+
+    def test_group_income_is_summed(self):
+        assert equivalized_income([30000, 12000, 0, 0], 2, 2) == pytest.approx(20000)
+
+    def test_missing_input_propagates(self):
+        value = equivalence_scale(float("nan"), 2)
+        assert value != value
+
+
+class TestExecute:
+    def test_columns(self, ir):
+        result = execute(ir, DATA).entities["household"]
+        assert result["household/scale"] == pytest.approx([2.1, 1.0, 1.3])
+        assert result["household/equivalized"] == pytest.approx([20000, 15000, 10000])
+        assert result["household/old_oecd_scale"] == pytest.approx([2.7, 1.0, 1.5])
+
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        expected = execute(ir, DATA).entities["household"]
+        for i, row in enumerate(DATA["household"]):
+            inputs = {k: v for k, v in row.items() if k != "id"}
+            out = namespace["compute_household"](namespace["HouseholdInput"](**inputs), scalars)
+            for name in ("equivalized", "old_oecd_scale"):
+                column = expected[f"household/{name}"]
+                assert out[f"household_{name}"] == pytest.approx(column[i])
+
+
+class TestCompiledBackends:
+    def test_rust_fills_default_weights(self, ir):
+        code = generate_rust(ir)
+        assert "fn rac_equivalence_scale(" in code
+        assert "0.5_f64, 0.3_f64)" in code
+
+    def test_javascript(self, ir):
+        code = generate_javascript(ir)
+        assert "function racEquivalizedIncome(" in code
+        assert "racEquivalenceScale(" in code
@@ -0,0 +1,90 @@
+"""EvalError: evaluation failures located by variable, row and expression."""
+
+from datetime import date
+
+import pytest
+
+from rac import EngineConfig, EvalError, ExecutionError, compile, parse, run
+from rac.ast import BinOp, Literal, Var
+from rac.compiler import IR, ResolvedVar
+from rac.executor import Executor
+from rac.schema import Data, Schema
+
+SOURCE = """
+    entity person:
+        age: int
+        status: str
+    variable person/band:
+        entity: person
+        from 2024-01-01:
+            match status:
+                "single" => 1
+                "married" => 2
+    variable person/bonus:
+        entity: person
+        from 2024-01-01: pension_top_up(age)
+"""
+
+ROWS = EngineConfig(columnar=False)
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _data(*statuses):
+    return {"person": [{"id": i, "age": 70, "status": s} for i, s in enumerate(statuses)]}
+
+
+class TestLocation:
+    def test_no_match(self, ir):
+        for config in (None, ROWS):
+            with pytest.raises(EvalError) as info:
+                run(ir, _data("single", "widowed"), outputs=["person/band"], config=config)
+            error = info.value
+            assert error.kind == "no_match"
+            assert (error.variable, error.row) == ("person/band", 1)
+            assert error.expr.startswith("match status:")
+            assert "in person/band row 1" in str(error)
+
+    def test_unknown_function(self, ir):
+        for config in (None, ROWS):
+            with pytest.raises(EvalError) as info:
+                run(ir, _data("single"), outputs=["person/bonus"], config=config)
+            assert info.value.kind == "unknown_function"
+            assert info.value.variable == "person/bonus"
+            assert info.value.expr == "pension_top_up(age)"
+
+    def test_undefined_input(self, ir):
+        data = _data("single", "married")
+        del data["person"][1]["status"]
+        for config in (None, ROWS):
+            with pytest.raises(EvalError, match="undefined: status") as info:
+                run(ir, data, outputs=["person/band"], config=config)
+            error = info.value
+            assert (error.kind, error.row, error.expr) == ("undefined", 1, "status")
+
+
+class TestScalars:
+    def _ir(self, expr, deps=()):
+        variables = {"gov/x": ResolvedVar(path="gov/x", expr=expr, deps=set(deps))}
+        return IR(schema_=Schema(), variables=variables, order=["gov/x"])
+
+    def test_unknown_operator(self):
+        ir = self._ir(BinOp(op="<<", left=Literal(value=2), right=Literal(value=3)))
+        with pytest.raises(EvalError) as info:
+            Executor(ir).execute(Data(tables={}))
+        assert info.value.kind == "unknown_operator"
+        assert (info.value.variable, info.value.row) == ("gov/x", None)
+        assert str(info.value) == "unknown op: << (in gov/x: (2 << 3))"
+
+    def test_undefined_variable_in_strict_mode(self):
+        ir = self._ir(Var(path="gov/unset"), deps=["gov/unset"])
+        with pytest.raises(EvalError, match="undefined: gov/unset"):
+            Executor(ir).execute(Data(tables={}))
+        lenient = Executor(ir, config=EngineConfig(strict=False)).execute(Data(tables={}))
+        assert lenient.scalars["gov/x"] == 0
+
+    def test_is_an_execution_error(self):
+        assert issubclass(EvalError, ExecutionError)
@@ -0,0 +1,189 @@
+"""Fixed-point decimal arithmetic, selected with the decimal_places option."""
+
+import math
+from datetime import date
+from fractions import Fraction
+
+import pytest
+
+from rac import (
+    CompiledModel,
+    ConfigError,
+    EngineConfig,
+    EvalError,
+    Fixed,
+    FixedOverflow,
+    compile,
+    eval_variable,
+    execute_columns,
+    parse,
+    run,
+    to_fixed,
+)
+
+SOURCE = """
+    entity person:
+        income: float
+        weeks: int
+
+    variable gov/rate:
+        from 2024-01-01: 0.1 + 0.2
+
+    variable person/weekly:
+        entity: person
+        from 2024-01-01: income / weeks
+
+    variable person/award:
+        entity: person
+        from 2024-01-01: person/weekly * gov/rate
+
+    variable person/exactly_point_three:
+        entity: person
+        from 2024-01-01: income * 0 + 0.1 + 0.2 == 0.3
+
+    variable person/band:
+        entity: person
+        from 2024-01-01:
+            lookup clamp person/award:
+                0 => "low"
+                0.3 => "mid"
+                100 => "high"
+"""
+
+MILLI = EngineConfig(decimal_places=3)
+CONFIGS = (
+    MILLI,
+    EngineConfig(decimal_places=3, columnar=False),
+    EngineConfig(decimal_places=3, bytecode=True, columnar=False),
+)
+DATA = {"person": [{"id": 1, "income": 100.0, "weeks": 3}, {"id": 2, "income": 52.0, "weeks": 52}]}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestFixed:
+    def test_sums_are_exact(self):
+        assert to_fixed(0.1, 3) + to_fixed(0.2, 3) == 0.3
+        assert str(to_fixed(0.1, 3) + 0.2) == "0.300"
+
+    @pytest.mark.parametrize(
+        "value, text",
+        [
+            (0.125, "0.12"),
+            (0.135, "0.14"),
+            (-0.125, "-0.12"),
+            (2, "2.00"),
+            (Fraction(2, 3), "0.67"),
+        ],
+    )
+    def test_reading_rounds_halves_to_even(self, value, text):
+        assert str(to_fixed(value, 2)) == text
+
+    def test_products_and_quotients_round_to_a_unit(self):
+        assert str(to_fixed(1, 3) / 3) == "0.333"
+        assert str(to_fixed(0.005, 3) * to_fixed(0.5, 3)) == "0.002"  # 0.0025, halves to even
+        assert str(to_fixed(-7, 3) // 2) == "-4.000"
+        assert str(to_fixed(-7, 3) % 52) == "45.000"
+        assert str(to_fixed(1.5, 3) ** 2) == "2.250"
+
+    def test_what_stays_a_float(self):
+        assert to_fixed(math.inf, 3) == math.inf
+        assert math.isnan(to_fixed(1, 3) + math.nan)
+        assert isinstance(to_fixed(2, 3) ** 0.5, float)
+        assert to_fixed(True, 3) is True
+        assert to_fixed("0.1", 3) == "0.1"
+
+    def test_equal_values_hash_alike(self):
+        assert {0.3: "x", 2: "y"}[to_fixed(0.3, 3)] == "x"
+        assert {0.3: "x", 2: "y"}[to_fixed(2, 3)] == "y"
+        assert math.floor(to_fixed(-1.5, 3)) == -2 and round(to_fixed(2.5, 3)) == 2
+
+    def test_overflow(self):
+        with pytest.raises(FixedOverflow, match="out of fixed-point range with 3 decimal places"):
+            to_fixed(2.0**63, 3)
+        assert Fixed(2**63 - 1, 0).units == 2**63 - 1
+
+
+class TestExecute:
+    def test_decimal_results(self, ir):
+        for config in CONFIGS:
+            result = run(ir, DATA, config=config)
+            assert result.scalars["gov/rate"] == 0.3
+            person = result.entities["person"]
+            assert person["person/weekly"] == [33.333, 1.0]
+            assert person["person/award"] == [10.0, 0.3]  # 33.333 * 0.3 = 9.9999 -> 10.000
+            assert person["person/exactly_point_three"] == [True, True]
+            assert person["person/band"] == ["mid", "mid"]
+
+    def test_floats_by_default(self, ir):
+        result = run(ir, DATA)
+        assert result.scalars["gov/rate"] == 0.1 + 0.2
+        assert result.entities["person"]["person/exactly_point_three"] == [False, False]
+
+    def test_overflow_is_an_eval_error(self):
+        module = parse("""
+            entity person:
+                income: float
+            variable person/scaled:
+                entity: person
+                from 2024-01-01: income * 1000000000000000
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        for config in CONFIGS:
+            with pytest.raises(EvalError, match="out of fixed-point range") as info:
+                run(ir, {"person": [{"id": 1, "income": 10000.0}]}, config=config)
+            assert info.value.kind == "overflow"
+            assert info.value.variable == "person/scaled"
+
+    def test_exact_variables_stay_exact(self):
+        module = parse("""
+            variable gov/third:
+                arithmetic: "exact"
+                from 2024-01-01: 1 / 3 * 3
+            variable gov/fixed_third:
+                from 2024-01-01: 1 / 3 * 3
+        """)
+        scalars = run(compile([module], as_of=date(2024, 1, 1)), {}, config=MILLI).scalars
+        assert scalars == {"gov/third": 1.0, "gov/fixed_third": 0.999}
+
+    def test_rounding_rules(self):
+        module = parse("""
+            variable gov/a:
+                round_to: 0.05
+                rounding: "up"
+                from 2024-01-01: 1 / 3
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        assert run(ir, {}, config=MILLI).scalars["gov/a"] == 0.35
+
+    def test_execute_columns_and_eval_variable(self, ir):
+        fields = {"income": [100.0], "weeks": [3]}
+        columns = execute_columns(ir, "person", fields, outputs=["person/award"], config=MILLI)
+        assert columns["person/award"] == [10.0]
+        var = ir.variables["person/weekly"]
+        inputs = {"income": 100.0, "weeks": 3}
+        assert eval_variable(var, inputs, config=MILLI) == 33.333
+
+
+class TestCompiledModel:
+    def test_selected_per_model(self, ir, tmp_path):
+        fixed, floating = CompiledModel(ir, MILLI), CompiledModel(ir)
+        assert fixed.scalars["gov/rate"] == 0.3
+        assert floating.scalars["gov/rate"] == 0.1 + 0.2
+        assert fixed.run(DATA).entities["person"]["person/award"] == [10.0, 0.3]
+        path = tmp_path / "model.racm"
+        fixed.save(path)
+        loaded = CompiledModel.load(path)
+        assert loaded.run(DATA).entities == fixed.run(DATA).entities
+
+    def test_bytecode_backend_falls_back_to_the_tree(self, ir):
+        model = CompiledModel(ir, MILLI, backend="bytecode")
+        assert model.run(DATA).entities["person"]["person/weekly"] == [33.333, 1.0]
+
+    @pytest.mark.parametrize("places", [-1, 19])
+    def test_places_checked(self, places):
+        with pytest.raises(ConfigError, match="decimal_places must be from 0 to 18"):
+            EngineConfig.from_dict({"decimal_places": places})
@@ -0,0 +1,153 @@
+"""Circular policies solved by fixed-point iteration."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    compile,
+    compose,
+    critical_path,
+    dump_ir,
+    generate_python,
+    load_ir,
+    parse,
+    run,
+)
+
+# The tax counts the benefit as income; the benefit tapers with the tax
+CIRCULAR = """
+    entity household:
+        income: float
+    variable household/tax:
+        entity: household
+        from 2024-01-01: 0.2 * (income + household/benefit)
+    variable household/benefit:
+        entity: household
+        from 2024-01-01: max(0, 1000 - 0.5 * household/tax)
+    variable household/net:
+        entity: household
+        from 2024-01-01: income + household/benefit - household/tax
+"""
+
+# x = 2x + 1 has a fixed point (-1) that iteration runs away from
+DIVERGENT = """
+    entity household:
+        income: float
+    variable household/x:
+        entity: household
+        from 2024-01-01: 2 * household/y + 1
+    variable household/y:
+        entity: household
+        from 2024-01-01: household/x
+"""
+
+GROUP = {"household/tax", "household/benefit"}
+DATA = {"household": [{"id": 1, "income": 5000.0}, {"id": 2, "income": 20000.0}]}
+
+
+def build(source, fixed_point=GROUP):
+    return compile([parse(source)], as_of=date(2024, 1, 1), fixed_point=fixed_point)
+
+
+class TestCompile:
+    def test_cycle_needs_designation(self):
+        with pytest.raises(CompileError, match="circular dependency"):
+            build(CIRCULAR, fixed_point=None)
+
+    def test_group_kept_together_before_dependents(self):
+        ir = build(CIRCULAR)
+        assert ir.fixed_point == [["household/tax", "household/benefit"]]
+        assert ir.order == ["household/tax", "household/benefit", "household/net"]
+
+    def test_partial_designation_is_still_circular(self):
+        with pytest.raises(CompileError, match="circular dependency"):
+            build(CIRCULAR, fixed_point={"household/tax"})
+
+    def test_unknown_variable(self):
+        with pytest.raises(CompileError, match="unknown fixed-point variable: household/nope"):
+            build(CIRCULAR, fixed_point={"household/nope"})
+
+    def test_group_within_one_entity(self):
+        source = """
+            entity person:
+                income: float
+            variable gov/a:
+                from 2024-01-01: sum(person/b)
+            variable person/b:
+                entity: person
+                from 2024-01-01: gov/a
+        """
+        with pytest.raises(CompileError, match="spans several entities"):
+            build(source, fixed_point={"gov/a", "person/b"})
+
+    def test_round_trip(self):
+        ir = build(CIRCULAR)
+        assert load_ir(dump_ir(ir)) == ir
+
+    def test_critical_path_skips_reads_within_a_group(self):
+        ir = build(CIRCULAR)
+        assert critical_path(ir.variables, ir.fixed_point) == (
+            2,
+            ["household/benefit", "household/net"],
+        )
+        with pytest.raises(CompileError, match="circular dependency"):
+            critical_path(ir.variables)
+
+    def test_compose_carries_groups(self):
+        flat = build("""
+            entity household:
+                income: float
+            variable household/benefit:
+                entity: household
+                from 2024-01-01: 500
+        """, fixed_point=None)
+        merged = compose({"circular": build(CIRCULAR), "flat": flat}, on_conflict="namespace").ir
+        assert merged.fixed_point == [["household/tax", "circular/household/benefit"]]
+        assert merged.order.index("household/net") > merged.order.index("household/tax")
+        expected = run(build(CIRCULAR), DATA).entities["household"]["household/tax"]
+        assert run(merged, DATA).entities["household"]["household/tax"] == expected
+
+    def test_codegen_refuses_groups(self):
+        with pytest.raises(CompileError, match="only supported by the executor"):
+            generate_python(build(CIRCULAR))
+
+
+class TestSolve:
+    def test_converges_per_row(self):
+        result = run(build(CIRCULAR), DATA)
+        columns = result.entities["household"]
+        # tax = 0.2 * (5000 + 1000 - 0.5 * tax), so tax = 1200 / 1.1
+        assert columns["household/tax"][0] == pytest.approx(1200 / 1.1)
+        assert columns["household/benefit"][0] == pytest.approx(1000 - 600 / 1.1)
+        # At 20000 the benefit is fully withdrawn
+        assert columns["household/tax"][1] == pytest.approx(4000)
+        assert columns["household/benefit"][1] == 0
+        assert columns["household/net"][1] == pytest.approx(16000)
+        assert result.warnings == []
+
+    def test_non_convergence_is_reported(self):
+        result = run(build(DIVERGENT, fixed_point={"household/x", "household/y"}), DATA)
+        assert result.warnings == [
+            "household/x, household/y did not converge within 100 iterations "
+            "for household rows 1, 2"
+        ]
+
+    def test_iteration_limit_is_configurable(self):
+        config = EngineConfig(max_iterations=2)
+        result = run(build(CIRCULAR), DATA, config=config)
+        assert "within 2 iterations for household rows 1" in result.warnings[0]
+        loose = EngineConfig(max_iterations=2, iteration_tolerance=1000.0)
+        assert run(build(CIRCULAR), DATA, config=loose).warnings == []
+
+    def test_scalar_group(self):
+        source = """
+            variable gov/a:
+                from 2024-01-01: 0.5 * gov/b + 1
+            variable gov/b:
+                from 2024-01-01: gov/a
+        """
+        result = run(build(source, fixed_point={"gov/a", "gov/b"}), {})
+        assert result.scalars["gov/a"] == pytest.approx(2)
@@ -0,0 +1,93 @@
+"""Tests for locale-aware currency formatting."""
+
+import pytest
+
+from rac import RunStats, format_currency, format_currency_column, run_summary
+
+
+class TestFormatCurrency:
+    @pytest.mark.parametrize(
+        "value, expected",
+        [
+            (1234.56, "£1,234.56"),
+            (0.0, "£0.00"),
+            (1234567.891, "£1,234,567.89"),
+            (-1234.56, "-£1,234.56"),
+            (-0.001, "£0.00"),
+        ],
+    )
+    def test_gbp(self, value, expected):
+        assert format_currency(value, "en_GB", "GBP") == expected
+
+    @pytest.mark.parametrize(
+        "value, expected",
+        [
+            (1234.56, "1.234,56 €"),
+            (999.5, "999,50 €"),
+            (1234567.0, "1.234.567,00 €"),
+            (-1234.56, "-1.234,56 €"),
+        ],
+    )
+    def test_eur_de(self, value, expected):
+        assert format_currency(value, "de_DE", "EUR") == expected
+
+    def test_eur_fr(self):
+        # French groups thousands with a narrow no-break space
+        assert format_currency(-1234.5, "fr_FR", "EUR") == "-1\u202f234,50 €"
+
+    def test_usd(self):
+        assert format_currency(1234.5, "en_US", "USD") == "$1,234.50"
+
+    def test_unknown_currency_uses_code(self):
+        assert format_currency(10, "en_GB", "CHF") == "CHF10.00"
+
+    def test_decimals(self):
+        assert format_currency(1234.567, "en_GB", "GBP", decimals=0) == "£1,235"
+
+    def test_unknown_locale(self):
+        with pytest.raises(ValueError, match="unknown locale"):
+            format_currency(1.0, "xx_XX", "GBP")
+
+    def test_column(self):
+        assert format_currency_column([1.5, -2000.0], "de_DE", "EUR") == [
+            "1,50 €",
+            "-2.000,00 €",
+        ]
+
+
+STATS = RunStats(
+    rows=1_234_567,
+    wall_time=2.5,
+    variables=42,
+    warnings=3,
+    variable_times={"person/tax": 1.2, "person/credit": 0.8, "hh/rent": 0.05, "hh/benefit": 0.1},
+)
+
+
+class TestRunSummary:
+    def test_plain_snapshot(self):
+        summary = run_summary(STATS, {"person/tax": 12345678.9, "people": 1000000})
+        assert summary == (
+            "rows processed       1,234,567\n"
+            "wall time            2.50 s\n"
+            "throughput           493,827 rows/s\n"
+            "variables evaluated  42\n"
+            "warnings             3\n"
+            "slowest variables    "
+            "person/tax (1.20 s), person/credit (0.80 s), hh/benefit (0.10 s)\n"
+            "person/tax           12,345,678.90\n"
+            "people               1,000,000"
+        )
+
+    def test_markdown_snapshot(self):
+        stats = RunStats(rows=10, wall_time=0.0, variables=2)
+        assert run_summary(stats, {"total": -1500.5}, markdown=True) == (
+            "| metric | value |\n"
+            "| --- | --- |\n"
+            "| rows processed | 10 |\n"
+            "| wall time | 0.00 s |\n"
+            "| throughput | n/a |\n"
+            "| variables evaluated | 2 |\n"
+            "| warnings | 0 |\n"
+            "| total | -1,500.50 |"
+        )
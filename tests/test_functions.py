@@ -0,0 +1,208 @@
+"""Tests for registered Python functions and their failure policies."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    FunctionError,
+    Profiler,
+    compile,
+    execute,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    parse,
+    register_function,
+    run,
+    unregister_function,
+)
+from rac.functions import MEMO_ROW_SIZE, CallLog, call_function, get_function
+
+SOURCE = """
+    entity household:
+        postcode: float
+
+    variable household/quoted_rent:
+        entity: household
+        from 2024-01-01: lookup_rent(postcode)
+
+    variable household/rent:
+        entity: household
+        from 2024-01-01: coalesce(household/quoted_rent, 0) * 12
+"""
+
+DATA = {"household": [{"id": i, "postcode": float(i)} for i in range(4)]}
+
+
+class Flaky:
+    """Raises TimeoutError on the first ``failures`` calls."""
+
+    def __init__(self, failures: int):
+        self.failures = failures
+        self.calls = 0
+
+    def __call__(self, postcode):
+        self.calls += 1
+        if self.calls <= self.failures:
+            raise TimeoutError("lookup service timed out")
+        return 100.0 + postcode
+
+
+@pytest.fixture
+def ir():
+    yield compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+    unregister_function("lookup_rent")
+
+
+class TestRegisteredFunctions:
+    def test_plain_call(self, ir):
+        register_function("lookup_rent", lambda postcode: 500.0)
+        result = execute(ir, DATA)
+        assert result.entities["household"]["household/rent"] == [6000.0] * 4
+        assert result.function_stats["lookup_rent"].calls == 4
+        assert result.warnings == []
+
+    def test_fails_fast_by_default(self, ir):
+        register_function("lookup_rent", Flaky(1))
+        with pytest.raises(TimeoutError):
+            execute(ir, DATA)
+
+    def test_retry_succeeds(self, ir):
+        flaky = Flaky(2)
+        register_function("lookup_rent", flaky, retries=2, retry_backoff_ms=1)
+        result = execute(ir, DATA)
+        assert result.entities["household"]["household/rent"] == [1200.0, 1212.0, 1224.0, 1236.0]
+        stats = result.function_stats["lookup_rent"]
+        assert (stats.calls, stats.retries, stats.failures) == (4, 2, 0)
+        assert flaky.calls == 6
+        assert result.warnings == ["lookup_rent() retried 2 times over 4 calls"]
+
+    def test_exhausted_retries_raise(self, ir):
+        register_function("lookup_rent", Flaky(3), retries=2)
+        with pytest.raises(TimeoutError):
+            execute(ir, DATA)
+
+    def test_fallback_to_missing(self, ir):
+        register_function("lookup_rent", Flaky(3), on_failure="missing")
+        result = execute(ir, {"household": DATA["household"] + [{"id": 4, "postcode": 4.0}]})
+        household = result.entities["household"]
+        assert household["household/quoted_rent"] == [None, None, None, 103.0, 104.0]
+        assert household["household/rent"] == [0, 0, 0, 1236.0, 1248.0]
+        assert result.function_stats["lookup_rent"].failures == 3
+        assert result.warnings == [
+            "lookup_rent() failed for household/quoted_rent rows 0-2 (3 calls): "
+            "TimeoutError: lookup service timed out"
+        ]
+
+    def test_fallback_to_default(self, ir):
+        register_function("lookup_rent", Flaky(1), on_failure="default", default=0.0)
+        result = execute(ir, DATA)
+        assert result.entities["household"]["household/rent"] == [0.0, 1212.0, 1224.0, 1236.0]
+        assert result.warnings == [
+            "lookup_rent() failed for household/quoted_rent row 0 (1 call): "
+            "TimeoutError: lookup service timed out"
+        ]
+
+    def test_stats_are_per_run(self, ir):
+        register_function("lookup_rent", lambda postcode: 1.0)
+        execute(ir, DATA)
+        assert execute(ir, DATA).function_stats["lookup_rent"].calls == 4
+
+    @pytest.mark.parametrize(
+        "generate, language",
+        [(generate_python, "Python"), (generate_javascript, "JavaScript"), (generate_rust, "Rust")],
+    )
+    def test_generated_code_refuses(self, ir, generate, language):
+        with pytest.raises(CompileError, match=f"lookup_rent has no {language} translation"):
+            generate(ir)
+
+    def test_invalid_registration(self):
+        with pytest.raises(FunctionError, match="built-in"):
+            register_function("max", max)
+        with pytest.raises(FunctionError, match="on_failure"):
+            register_function("lookup_rent", abs, on_failure="skip")
+
+
+CREDITS = """
+    entity person:
+        income: float
+""" + "".join(
+    f"""
+    variable person/credit_{i}:
+        entity: person
+        from 2024-01-01: bracket(income) * {i}
+"""
+    for i in range(1, 6)
+)
+
+
+class Counter:
+    def __init__(self):
+        self.args = []
+
+    def __call__(self, income):
+        self.args.append(income)
+        return income * 0.1
+
+
+class TestMemo:
+    @pytest.fixture
+    def credits_ir(self):
+        yield compile([parse(CREDITS)], as_of=date(2024, 1, 1))
+        unregister_function("bracket")
+
+    @pytest.mark.parametrize("config", [None, EngineConfig(columnar=False)])
+    def test_repeated_calls_evaluated_once_per_row(self, credits_ir, config):
+        bracket = Counter()
+        register_function("bracket", bracket, memoize=True)
+        incomes = [100.0, 200.0, 100.0]
+        data = {"person": [{"id": i, "income": v} for i, v in enumerate(incomes)]}
+        result = run(credits_ir, data, config=config)
+        # Rows 0 and 2 share an income but not a memo
+        assert bracket.args == [100.0, 200.0, 100.0]
+        stats = result.function_stats["bracket"]
+        assert (stats.calls, stats.memo_hits) == (3, 12)
+        assert result.entities["person"]["person/credit_5"] == [50.0, 100.0, 50.0]
+
+    def test_exact_bits_keying(self, credits_ir):
+        register_function("bracket", Counter(), memoize=True)
+        log = CallLog()
+        spec = get_function("bracket")
+        for income in [0.3, 0.1 + 0.2, 0.0, -0.0, 1, 1.0, 0.3]:
+            call_function("bracket", spec, [income], log, "person/credit_1", 0, entity="person")
+        assert (log.stats["bracket"].calls, log.stats["bracket"].memo_hits) == (6, 1)
+
+    def test_row_memo_is_bounded(self, credits_ir):
+        register_function("bracket", Counter(), memoize=True)
+        log = CallLog()
+        spec = get_function("bracket")
+        for income in range(MEMO_ROW_SIZE + 10):
+            call_function("bracket", spec, [income], log, "person/credit_1", 0, entity="person")
+        assert len(log.memo[("person", 0)]) == MEMO_ROW_SIZE
+
+    def test_memoize_off_by_default(self, credits_ir):
+        bracket = Counter()
+        register_function("bracket", bracket)
+        result = execute(credits_ir, {"person": [{"id": 1, "income": 1.0}]})
+        assert len(bracket.args) == 5
+        assert result.function_stats["bracket"].memo_hits == 0
+
+    def test_profiler_hit_stats(self, credits_ir):
+        register_function("bracket", Counter(), memoize=True)
+        profiler = Profiler(credits_ir)
+        run(credits_ir, {"person": [{"id": 1, "income": 1.0}]}, profiler=profiler)
+        assert (profiler.memo_hits["bracket"], profiler.memo_misses["bracket"]) == (4, 1)
+        assert profiler.memo_hit_rate("bracket") == 0.8
+
+    def test_failed_calls_not_memoized(self, ir):
+        register_function("lookup_rent", Flaky(1), on_failure="missing", memoize=True)
+        log = CallLog()
+        spec = get_function("lookup_rent")
+        values = [
+            call_function("lookup_rent", spec, [7.0], log, "household/quoted_rent", 0)
+            for _ in range(3)
+        ]
+        assert values == [None, 107.0, 107.0]
@@ -0,0 +1,387 @@
+"""Structural fuzzing of the untrusted input paths: .rac source and serialized IR.
+
+Every input must either load or fail with ParseError/CompileError; anything
+else (KeyError, RecursionError, a hang) is a bug. Runs are seeded, so a
+failure reproduces from the printed seed and case number. For an extended
+session raise the case count, e.g. ``RAC_FUZZ_CASES=200000 pytest tests/test_fuzz.py``.
+Issues the fuzzer found are pinned as regression tests at the bottom.
+"""
+
+import copy
+import json
+import os
+import random
+from datetime import date
+from pathlib import Path
+
+import pytest
+
+from rac import (
+    CompileError,
+    ExecutionError,
+    IRSchemaError,
+    ParseError,
+    compile,
+    compiler,
+    dump_ir,
+    execute,
+    ir_json_schema,
+    register_function,
+    unregister_function,
+    load_ir,
+    load_ir_json,
+    parse,
+)
+from rac.ast import MAX_EXPR_DEPTH
+from rac.parser import Parser
+
+CASES = int(os.environ.get("RAC_FUZZ_CASES", "300"))
+SEED = int(os.environ.get("RAC_FUZZ_SEED", "0"))
+
+FIXTURES = sorted((Path(__file__).parent / "fixtures" / "ir").glob("*.json"))
+
+SOURCE = """
+entity person:
+    income: float
+    age: int
+variable gov/rate:
+    from 2024-01-01: 0.2
+variable person/tax:
+    entity: person
+    from 2024-01-01: if age >= 18: max(0, income * gov/rate) else: 0
+variable person/band:
+    entity: person
+    from 2024-01-01 to 2030-12-31: match age: 1 => "a" 2 => "b"
+"""
+
+TOKENS = [
+    "entity", "variable", "amend", "from", "to", "match", "if", "else", "and", "or", "not",
+    "true", "false", "person", "gov/rate", "income", "2024-01-01", "2024-13-45", "0", "1.5",
+    "9" * 5000, '"s"', "=>", "<=", ">=", "==", "!=", "->", ":", "+", "-", "*", "/", "<", ">",
+    "(", ")", "[", "]", ",", ".", "\n", "    ", " ", "#", "max", "clip", "apportion",
+]  # fmt: skip
+
+JUNK = [None, True, -1, 0, 2**70, 1e308, "", "x", "literal", [], {}, [1, [2]], {"type": "var"}]
+
+
+def source_case(rng):
+    """A valid module with a run of random tokens spliced in."""
+    lines = SOURCE.splitlines()
+    at = rng.randrange(len(lines))
+    noise = "".join(rng.choice(TOKENS) + rng.choice(["", " "]) for _ in range(rng.randint(1, 30)))
+    if rng.random() < 0.1:
+        noise = rng.choice(["(", "-", "not ", "f("]) * rng.randint(50, 3000)
+    cut = rng.randrange(len(lines[at]) + 1)
+    lines[at] = lines[at][:cut] + noise + lines[at][cut:]
+    return "\n".join(lines)
+
+
+def _nodes(root):
+    stack = [((), root)]
+    while stack:
+        path, value = stack.pop()
+        yield path, value
+        if isinstance(value, dict):
+            stack.extend(((*path, k), v) for k, v in value.items())
+        elif isinstance(value, list):
+            stack.extend(((*path, i), v) for i, v in enumerate(value))
+
+
+def _set(root, path, value):
+    for key in path[:-1]:
+        root = root[key]
+    root[path[-1]] = value
+
+
+def mutate(raw, rng):
+    """Apply a few structural mutations to a serialized IR.
+
+    Burying a node under a deep chain ends the run of mutations, since
+    copying a deep tree would overflow the harness's own stack.
+    """
+    raw = copy.deepcopy(raw)
+    for _ in range(rng.randint(1, 4)):
+        nodes = list(_nodes(raw))
+        path, value = rng.choice(nodes)
+        kind = rng.randrange(5)
+        if not path:
+            if kind == 0:
+                return rng.choice(JUNK)
+            continue
+        if kind == 0:
+            _set(raw, path, copy.deepcopy(rng.choice(JUNK)))
+        elif kind == 1:  # delete
+            parent = raw
+            for key in path[:-1]:
+                parent = parent[key]
+            del parent[path[-1]]
+        elif kind == 2:  # graft another subtree here
+            _set(raw, path, copy.deepcopy(rng.choice(nodes)[1]))
+        elif kind == 3:  # bury under a unary chain, possibly past the depth limit
+            for _ in range(rng.choice([3, MAX_EXPR_DEPTH, 5000])):
+                value = {"type": "unaryop", "op": "-", "operand": value}
+            _set(raw, path, value)
+            return raw
+        elif isinstance(value, list):
+            value.extend(copy.deepcopy(value))
+    return raw
+
+
+def check_source(source):
+    try:
+        module = parse(source)
+    except ParseError:
+        return
+    try:
+        compile([module], as_of=date(2024, 1, 1))
+    except CompileError:
+        pass
+
+
+def check_ir(raw, as_text):
+    if as_text:
+        try:
+            raw = json.dumps(raw)
+        except RecursionError:  # too deep for the encoder; load the dict instead
+            pass
+    try:
+        ir = load_ir(raw)
+    except CompileError:
+        return
+    # A loaded IR is well formed: round-trips and reaches the executor
+    assert load_ir(dump_ir(ir)) == ir
+    if isinstance(raw, str):
+        try:
+            assert load_ir_json(raw) == ir  # stricter, but never different
+        except CompileError:
+            pass
+
+
+def test_source_fuzz():
+    rng = random.Random(SEED)
+    for case in range(CASES):
+        source = source_case(rng)
+        try:
+            check_source(source)
+        except Exception as e:
+            pytest.fail(f"seed {SEED} case {case}: {type(e).__name__}: {e}\n{source}")
+
+
+def test_ir_fuzz():
+    rng = random.Random(SEED)
+    seeds = [json.loads(path.read_text())["ir"] for path in FIXTURES]
+    seeds.append(json.loads(dump_ir(compile([parse(SOURCE)], as_of=date(2024, 1, 1)))))
+    for case in range(CASES):
+        raw = mutate(rng.choice(seeds), rng)
+        try:
+            check_ir(raw, as_text=rng.random() < 0.5)
+        except Exception as e:
+            pytest.fail(f"seed {SEED} case {case}: {type(e).__name__}: {e}")
+
+
+def _chain(depth):
+    expr = {"type": "literal", "value": 1}
+    for _ in range(depth - 1):
+        expr = {"type": "unaryop", "op": "-", "operand": expr}
+    return expr
+
+
+def _ir(variables, order=None, schema=None):
+    return {
+        "version": 1,
+        "schema_": schema or {},
+        "variables": {path: {"path": path, **var} for path, var in variables.items()},
+        "order": list(variables) if order is None else order,
+    }
+
+
+class TestLoadIrJson:
+    def test_matches_load_ir(self):
+        text = dump_ir(compile([parse(SOURCE)], as_of=date(2024, 1, 1)))
+        assert load_ir_json(text) == load_ir(text)
+
+    @pytest.mark.parametrize(
+        "expr, path",
+        [
+            ({"type": "nope"}, '$.variables["gov/a"].expr.type'),
+            (
+                {"type": "binop", "op": "+", "left": {"type": "literal", "value": 1}},
+                '$.variables["gov/a"].expr.right',
+            ),
+            (
+                {"type": "call", "func": "max", "args": [{"type": "var"}]},
+                '$.variables["gov/a"].expr.args[0].path',
+            ),
+        ],
+    )
+    def test_error_path(self, expr, path):
+        with pytest.raises(IRSchemaError) as info:
+            load_ir_json(json.dumps(_ir({"gov/a": {"expr": expr}})))
+        assert info.value.path == path
+        assert str(info.value).startswith(f"invalid IR at {path}: ")
+
+    def test_unknown_node_type_named(self):
+        with pytest.raises(CompileError, match="unknown node type 'nope'"):
+            load_ir_json(json.dumps(_ir({"gov/a": {"expr": {"type": "nope"}}})))
+
+    @pytest.mark.parametrize(
+        "text, match",
+        [
+            ('{"version": 1, "version": 2}', "duplicate key 'version'"),
+            ('{"version": NaN}', "NaN is not a JSON number"),
+            ("[]", r"invalid IR at \$: expected an object"),
+        ],
+    )
+    def test_strict_json(self, text, match):
+        with pytest.raises(CompileError, match=match):
+            load_ir_json(text)
+
+    def test_schema(self):
+        schema = ir_json_schema()
+        assert set(schema["required"]) >= {"schema_", "variables", "order"}
+        assert "BinOp" in schema["$defs"]
+
+
+ONE = {"type": "literal", "value": 1}
+
+
+class TestStrictLoad:
+    @pytest.mark.parametrize(
+        "expr, message",
+        [
+            ({"type": "binop", "op": "<<", "left": ONE, "right": ONE}, "unknown operator '<<'"),
+            ({"type": "unaryop", "op": "~", "operand": ONE}, "unknown unary operator '~'"),
+            ({"type": "call", "func": "nosuch", "args": [ONE]}, r"unknown function nosuch\(\)"),
+            ({"type": "call", "func": "abs", "args": [ONE, ONE]}, "abs.. takes 1 argument, got 2"),
+        ],
+    )
+    def test_rejected_at_load(self, expr, message):
+        raw = _ir({"gov/a": {"expr": expr}})
+        for load in (load_ir, load_ir_json):
+            with pytest.raises(CompileError, match=f"gov/a: {message}"):
+                load(json.dumps(raw))
+        ir = load_ir(raw, strict=False)  # left for the executor to reject
+        with pytest.raises(ExecutionError, match="unknown|takes"):
+            execute(ir, {})
+
+    def test_registered_function_accepted(self):
+        raw = _ir({"gov/a": {"expr": {"type": "call", "func": "double", "args": [ONE]}}})
+        register_function("double", lambda x: 2 * x)
+        try:
+            assert execute(load_ir(raw), {}).scalars["gov/a"] == 2
+        finally:
+            unregister_function("double")
+
+
+class TestRegressions:
+    """Inputs the fuzzer found that escaped as something other than a clean error."""
+
+    @pytest.mark.parametrize("source", [[], "x", None, 3])
+    def test_ir_not_an_object(self, source):
+        with pytest.raises(CompileError, match="must be a JSON object"):
+            load_ir(json.dumps(source))
+
+    def test_invalid_json(self):
+        with pytest.raises(CompileError, match="not valid JSON"):
+            load_ir("{")
+
+    def test_deeply_nested_json(self):
+        with pytest.raises(CompileError, match="nested too deeply"):
+            load_ir("[" * 100_000 + "]" * 100_000)
+
+    def test_oversized_text(self, monkeypatch):
+        monkeypatch.setattr(compiler, "MAX_IR_BYTES", 10)
+        with pytest.raises(CompileError, match="bytes"):
+            load_ir(json.dumps(_ir({})))
+
+    def test_too_many_nodes(self, monkeypatch):
+        monkeypatch.setattr(compiler, "MAX_IR_NODES", 5)
+        with pytest.raises(CompileError, match="expression nodes"):
+            load_ir(_ir({"gov/a": {"expr": _chain(3)}, "gov/b": {"expr": _chain(3)}}))
+
+    def test_expression_too_deep(self):
+        load_ir(_ir({"gov/a": {"expr": _chain(MAX_EXPR_DEPTH)}}))
+        with pytest.raises(CompileError, match="nested 201 levels deep"):
+            load_ir(_ir({"gov/a": {"expr": _chain(MAX_EXPR_DEPTH + 1)}}))
+        with pytest.raises(CompileError, match="nested more than 250 levels"):
+            load_ir({"variables": {"gov/a": {"expr": _chain(5000)}}})  # legacy layout
+
+    def test_deepest_accepted_expression_evaluates(self):
+        ir = load_ir(_ir({"gov/a": {"expr": _chain(MAX_EXPR_DEPTH)}}))
+        assert execute(ir, {}).scalars["gov/a"] == (-1) ** (MAX_EXPR_DEPTH - 1)
+
+    def test_deep_value_outside_expressions(self):
+        schema = {"entities": {"person": {"name": "person", "fields": {"x": {
+            "name": "x", "dtype": "float", "default": _chain(400)
+        }}}}}  # fmt: skip
+        with pytest.raises(CompileError, match="nested more than"):
+            load_ir(_ir({}, schema=schema))
+
+    def test_literal_must_be_scalar(self):
+        expr = {"type": "literal", "value": {"type": "var", "path": "gov/b"}}
+        with pytest.raises(CompileError, match="gov/a: literal must be"):
+            load_ir(_ir({"gov/a": {"expr": expr}}))
+
+    def test_validation_error_is_summarised(self):
+        with pytest.raises(CompileError, match=r"invalid IR at variables\.gov/a\.expr"):
+            load_ir(_ir({"gov/a": {"expr": {"type": "nope"}}}))
+
+    @pytest.mark.parametrize("version", ["9", None, -1, True])
+    def test_version_type(self, version):
+        with pytest.raises(CompileError, match="non-negative integer"):
+            load_ir({"version": version})
+
+    @pytest.mark.parametrize("variables", [[], {"gov/a": 1}])
+    def test_legacy_variables_shape(self, variables):
+        with pytest.raises(CompileError, match="must be an object"):
+            load_ir({"variables": variables})
+
+    def test_order_unknown_variable(self):
+        with pytest.raises(CompileError, match="unknown variable: gov/zz"):
+            load_ir(_ir({}, order=["gov/zz"]))
+
+    def test_order_duplicate_and_missing(self):
+        lit = {"expr": {"type": "literal", "value": 1}}
+        with pytest.raises(CompileError, match="more than once"):
+            load_ir(_ir({"gov/a": lit}, order=["gov/a", "gov/a"]))
+        with pytest.raises(CompileError, match="omits variables: gov/a"):
+            load_ir(_ir({"gov/a": lit}, order=[]))
+
+    def test_order_against_dependencies(self):
+        variables = {
+            "gov/a": {"expr": {"type": "var", "path": "gov/b"}, "deps": ["gov/b"]},
+            "gov/b": {"expr": {"type": "literal", "value": 1}},
+        }
+        with pytest.raises(CompileError, match="before its dependency gov/b"):
+            load_ir(_ir(variables, order=["gov/a", "gov/b"]))
+
+    def test_unknown_entity(self):
+        var = {"entity": "person", "expr": {"type": "literal", "value": 1}}
+        with pytest.raises(CompileError, match="unknown entity person"):
+            load_ir(_ir({"person/a": var}))
+
+    def test_invalid_date(self):
+        with pytest.raises(ParseError, match="invalid date 2024-13-45"):
+            parse("variable gov/a:\n    from 2024-13-45: 1\n")
+
+    def test_oversized_integer(self):
+        with pytest.raises(ParseError, match="line 2"):
+            parse(f"variable gov/a:\n    from 2024-01-01: {'9' * 5000}\n")
+
+    @pytest.mark.parametrize("prefix", ["(", "max(", "if true: "])
+    def test_source_nesting(self, prefix):
+        source = f"variable gov/a:\n    from 2024-01-01: {prefix * 1000}1\n"
+        with pytest.raises(ParseError, match=f"more than {Parser.MAX_NESTING} levels"):
+            parse(source)
+
+    def test_long_unary_chain_parses_then_fails_to_compile(self):
+        module = parse(f"variable gov/a:\n    from 2024-01-01: {'-' * 5000}1\n")
+        with pytest.raises(CompileError, match="nested 5001 levels deep"):
+            compile([module], as_of=date(2024, 1, 1))
+
+    def test_long_dependency_chain_compiles(self):
+        source = "variable gov/v0:\n    from 2024-01-01: 1\n" + "".join(
+            f"variable gov/v{i}:\n    from 2024-01-01: gov/v{i - 1}\n" for i in range(1, 3000)
+        )
+        ir = compile([parse(source)], as_of=date(2024, 1, 1))
+        assert ir.order[0] == "gov/v0" and ir.order[-1] == "gov/v2999"
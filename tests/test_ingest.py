@@ -0,0 +1,206 @@
+"""Tests for input ingestion: CSV reading and locale-tolerant numbers."""
+
+import math
+from datetime import date
+from pathlib import Path
+
+import pytest
+
+from rac import NumberFormat, compile, execute, format_number, parse, parse_number, read_csv
+from rac.executor import run
+from rac.ingest import EU, US, apply_missing, coerce_number, is_sentinel
+
+FIXTURES = Path(__file__).parent / "fixtures"
+
+
+class TestParseNumber:
+    @pytest.mark.parametrize(
+        "text, expected",
+        [
+            ("1234.56", 1234.56),
+            ("1,234.56", 1234.56),
+            ("$1,234,567.89", 1234567.89),
+            ("(500)", -500.0),
+            ("-75.25", -75.25),
+            ("  42 ", 42.0),
+            ("1e3", 1000.0),
+        ],
+    )
+    def test_us(self, text, expected):
+        assert parse_number(text, US) == pytest.approx(expected)
+
+    @pytest.mark.parametrize(
+        "text, expected",
+        [
+            ("1234,56", 1234.56),
+            ("1.234,56", 1234.56),
+            ("1.234.567,89 €", 1234567.89),
+            ("(1.000,00)", -1000.0),
+            ("-75,25", -75.25),
+        ],
+    )
+    def test_eu(self, text, expected):
+        assert parse_number(text, EU) == pytest.approx(expected)
+
+    @pytest.mark.parametrize("text", ["1.234,56", "1,23", "abc", "", "--5", "(-)"])
+    def test_us_rejects(self, text):
+        with pytest.raises(ValueError, match="invalid number"):
+            parse_number(text, US)
+
+    def test_eu_rejects_us_style(self):
+        with pytest.raises(ValueError):
+            parse_number("1,234.56", EU)
+
+    def test_parens_negative_disabled(self):
+        with pytest.raises(ValueError):
+            parse_number("(500)", NumberFormat(parens_negative=False))
+
+    def test_custom_currency_symbols(self):
+        fmt = NumberFormat(currency_symbols=("CHF",), thousands="'")
+        assert parse_number("CHF 1'250.50", fmt) == pytest.approx(1250.5)
+
+    def test_coerce_number(self):
+        assert coerce_number(3) == 3.0
+        assert coerce_number("2.5") == 2.5
+        assert coerce_number("2,5", EU) == 2.5
+
+
+class TestFormatNumber:
+    @pytest.mark.parametrize("fmt", [US, EU])
+    @pytest.mark.parametrize("value", [0.0, 12.5, -1000.0, 1234567.89])
+    def test_round_trip(self, fmt, value):
+        assert parse_number(format_number(value, fmt), fmt) == pytest.approx(value)
+
+    def test_formats(self):
+        assert format_number(1234.5, US) == "1,234.50"
+        assert format_number(1234.5, EU) == "1.234,50"
+        assert format_number(-3.0, US) == "(3.00)"
+
+
+class TestReadCsv:
+    def _check(self, rows, report):
+        assert rows[0]["income"] == pytest.approx(52000.5)
+        assert rows[1]["income"] == pytest.approx(1234567.89)
+        assert rows[2]["income"] is None
+        assert rows[3]["income"] == "n/a"
+        assert [r["rent"] for r in rows] == pytest.approx([850.0, 1200.0, 1050.0, 900.0])
+        assert [r["balance"] for r in rows] == pytest.approx([-120.0, 0.0, -75.25, -1000.0])
+        assert rows[0]["name"] == "Alice"
+        assert [(e.row, e.column, e.value) for e in report.errors] == [(3, "income", "n/a")]
+        assert not report.ok
+
+    def test_us_fixture(self):
+        rows, report = read_csv(
+            FIXTURES / "messy_us.csv", numeric=["id", "income", "rent", "balance"]
+        )
+        self._check(rows, report)
+
+    def test_eu_fixture(self):
+        rows, report = read_csv(
+            FIXTURES / "messy_eu.csv",
+            numeric=["id", "income", "rent", "balance"],
+            number_format=EU,
+            delimiter=";",
+        )
+        self._check(rows, report)
+
+    def test_us_and_eu_agree(self):
+        cols = ["id", "income", "rent", "balance"]
+        us, _ = read_csv(FIXTURES / "messy_us.csv", numeric=cols)
+        eu, _ = read_csv(FIXTURES / "messy_eu.csv", numeric=cols, number_format=EU, delimiter=";")
+        assert us == eu
+
+    def test_per_column_override(self, tmp_path):
+        path = tmp_path / "mixed.csv"
+        path.write_text('id,us_amount,eu_amount\n1,"1,234.50","1.234,50"\n')
+        rows, report = read_csv(path, column_formats={"eu_amount": EU})
+        assert rows[0]["us_amount"] == pytest.approx(1234.5)
+        assert rows[0]["eu_amount"] == pytest.approx(1234.5)
+        assert report.ok
+
+    def test_inferred_numeric_columns(self):
+        rows, report = read_csv(FIXTURES / "messy_us.csv")
+        assert rows[0]["rent"] == 850.0
+        # income has an unparseable cell, so it is not inferred as numeric
+        assert rows[0]["income"] == "$52,000.50"
+        assert report.ok
+
+    def test_round_trip_through_csv(self, tmp_path):
+        values = [1234.5, -99.99, 0.0, 1e6]
+        for fmt, delimiter in [(US, ","), (EU, ";")]:
+            path = tmp_path / "out.csv"
+            lines = ["id" + delimiter + "amount"]
+            lines += [f'{i}{delimiter}"{format_number(v, fmt)}"' for i, v in enumerate(values)]
+            path.write_text("\n".join(lines) + "\n")
+            rows, report = read_csv(path, number_format=fmt, delimiter=delimiter)
+            assert [r["amount"] for r in rows] == pytest.approx(values)
+            assert report.ok
+
+    def test_inferred_boolean_columns(self, tmp_path):
+        path = tmp_path / "flags.csv"
+        path.write_text("id,disabled,note\n1,TRUE,yes\n2,false,\n3,,no\n")
+        rows, report = read_csv(path)
+        assert [r["disabled"] for r in rows] == [True, False, None]
+        assert [r["note"] for r in rows] == ["yes", None, "no"]
+        assert report.ok
+        rows, _ = read_csv(path, numeric=["id"])
+        assert rows[0]["disabled"] == "TRUE"  # declared columns turn inference off
+
+
+NAN = float("nan")
+
+
+class TestMissingSentinels:
+    def test_is_sentinel(self):
+        assert is_sentinel(-999.0, [-999])
+        assert is_sentinel(NAN, [NAN])
+        assert not is_sentinel(NAN, [-999])
+        assert not is_sentinel(0.0, [NAN])
+
+    def test_apply_missing(self):
+        rows = [{"a": -999, "b": NAN}, {"a": 1.0}]
+        assert apply_missing(rows, {"a": [-999], "b": [NAN]}) == [
+            {"a": None, "b": None},
+            {"a": 1.0, "b": None},
+        ]
+        assert rows[0]["a"] == -999  # input untouched
+
+    def test_read_csv_sentinels(self, tmp_path):
+        path = tmp_path / "survey.csv"
+        path.write_text("id,income,hours\n1,-999,NA\n2,500,40\n3,,-999\n")
+        rows, report = read_csv(path, missing={"income": [-999], "hours": ["NA"]})
+        assert [r["income"] for r in rows] == [None, 500.0, None]
+        # -999 is only a sentinel where declared; "NA" does not block inference
+        assert [r["hours"] for r in rows] == [None, 40.0, -999.0]
+        assert report.ok
+
+    def test_coalesce_supplies_defaults(self):
+        module = parse("""
+            entity person:
+                income: float
+                hours: float
+            variable person/income_filled:
+                entity: person
+                from 2024-01-01: coalesce(income, 0)
+            variable person/hours_filled:
+                entity: person
+                from 2024-01-01: coalesce(hours, 35)
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        data = {
+            "person": [
+                {"id": 1, "income": -999.0, "hours": NAN},
+                {"id": 2, "income": 1200.0, "hours": 20.0},
+            ]
+        }
+        result = run(ir, data, missing={"person": {"income": [-999], "hours": [NAN]}})
+        person = result.entities["person"]
+        assert person["person/income_filled"] == [0, 1200.0]
+        assert person["person/hours_filled"] == [35, 20.0]
+
+        # Undeclared, the sentinel flows through as a value; NaN is still
+        # skipped by coalesce, matching the native backend
+        plain = execute(ir, data).entities["person"]
+        assert plain["person/income_filled"] == [-999.0, 1200.0]
+        assert plain["person/hours_filled"] == [35, 20.0]
+        assert math.isnan(data["person"][0]["hours"])
@@ -0,0 +1,104 @@
+"""interp(x, xs, ys): piecewise-linear interpolation over literal knots."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    ParseError,
+    compile,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    load_ir,
+    parse,
+    run,
+)
+
+SOURCE = """
+    entity person:
+        age: int?
+    variable person/factor:
+        entity: person
+        from 2024-01-01: interp(age, [60, 65, 70], [0.7, 1.0, 1.3])
+"""
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+AGES = [50, 60, 62, 65, 69, 70, 90, None]
+FACTORS = [0.7, 0.7, 0.82, 1.0, 1.24, 1.3, 1.3, None]
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _data(ages):
+    return {"person": [{"id": i, "age": age} for i, age in enumerate(ages)]}
+
+
+class TestExecute:
+    def test_interpolates_and_is_flat_outside_the_knots(self, ir):
+        for config in CONFIGS:
+            factors = run(ir, _data(AGES), config=config).entities["person"]["person/factor"]
+            assert factors[:-1] == pytest.approx(FACTORS[:-1])
+            assert factors[-1] is None
+
+    def test_nan_propagates(self, ir):
+        for config in CONFIGS:
+            factors = run(ir, _data([math.nan]), config=config).entities["person"]
+            assert math.isnan(factors["person/factor"][0])
+
+    def test_single_knot_is_constant(self):
+        module = parse("variable gov/a:\n    from 2024-01-01: interp(3, [1], [5])\n")
+        assert run(compile([module], as_of=date(2024, 1, 1)), {}).scalars["gov/a"] == 5.0
+
+
+class TestValidation:
+    @pytest.mark.parametrize(
+        "call, message",
+        [
+            ("interp(1, [0, 2, 1], [0, 1, 2])", "xs must strictly ascend, got 2 then 1"),
+            ("interp(1, [0, 0], [0, 1])", "xs must strictly ascend, got 0 then 0"),
+            ("interp(1, [0, 1], [0, 1, 2])", "as many ys as xs, and at least one; got 2 and 3"),
+            ("interp(1, [], [])", "at least one; got 0 and 0"),
+            ('interp(1, [0, 1], ["a", "b"])', "knots must be numbers"),
+            ("interp(1, gov/xs, [0, 1])", "knots must be literal lists"),
+            ("interp([0, 1], [0, 1], [0, 1])", "list can only follow in or not in"),
+            ("[1, 2] + 1", "list can only follow in or not in"),
+            ("max([1, 2])", "list can only follow in or not in"),
+        ],
+    )
+    def test_rejected_at_parse(self, call, message):
+        with pytest.raises(ParseError, match=message):
+            parse(f"variable gov/a:\n    from 2024-01-01: {call}\n")
+
+    def test_loaded_ir_is_checked(self, ir):
+        data = ir.model_dump(by_alias=True)
+        data["variables"]["person/factor"]["expr"]["args"][1]["value"] = [70, 65, 60]
+        with pytest.raises(CompileError, match="person/factor: interp.. xs must strictly ascend"):
+            load_ir(data)
+
+    def test_loaded_ir_lists_only_as_knots(self, ir):
+        data = ir.model_dump(by_alias=True)
+        data["variables"]["person/factor"]["expr"]["args"][0] = {"type": "literal", "value": [1]}
+        with pytest.raises(CompileError, match="person/factor: a bracketed list can only follow"):
+            load_ir(data)
+
+
+class TestCodegen:
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        for age, factor in zip(AGES, FACTORS):
+            out = namespace["compute_person"](namespace["PersonInput"](age=age), scalars)
+            assert out["person_factor"] == (None if factor is None else pytest.approx(factor))
+
+    def test_javascript_and_rust(self, ir):
+        assert "racInterp(input.age, [60, 65, 70], [0.7, 1.0, 1.3])" in generate_javascript(ir)
+        assert "rac_interp((input.age) as f64, &[60_f64, 65_f64, 70_f64], " in generate_rust(ir)
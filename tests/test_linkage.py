@@ -0,0 +1,121 @@
+"""Tests for entity linkage validation in the multi-entity data path."""
+
+from datetime import date
+
+import pytest
+
+from rac import Data, LinkageError, compile, execute, parse
+from rac.executor import run
+
+SOURCE = """
+    entity household:
+        rent: float
+
+    entity person:
+        household: -> household
+        income: float
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * 0.1
+"""
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def tables(households, people):
+    return {"household": households, "person": people}
+
+
+class TestDuplicateKeys:
+    def test_conflicting_parent_rows(self, ir):
+        data = tables(
+            [{"id": 1, "rent": 500.0}, {"id": 1, "rent": 900.0}, {"id": 2, "rent": 0.0}],
+            [{"id": 10, "household": 2, "income": 1.0}],
+        )
+        with pytest.raises(LinkageError, match="duplicate id in household: 1"):
+            execute(ir, data)
+
+    def test_examples_are_capped(self, ir):
+        households = [{"id": i, "rent": r} for i in range(5) for r in (1.0, 2.0)]
+        with pytest.raises(LinkageError, match=r"0, 1, 2 \(and 2 more\)"):
+            execute(ir, tables(households, []))
+
+    def test_exact_duplicate_errors_by_default(self, ir):
+        data = tables([{"id": 1, "rent": 500.0}, {"id": 1, "rent": 500.0}], [])
+        with pytest.raises(LinkageError, match="duplicate id in household"):
+            execute(ir, data)
+
+    def test_exact_duplicate_dropped_with_first(self, ir):
+        data = tables(
+            [{"id": 1, "rent": 500.0}, {"id": 1, "rent": 500.0}],
+            [{"id": 10, "household": 1, "income": 100.0}],
+        )
+        result = run(ir, data, dedupe="first")
+        assert result.entities["person"]["person/tax"] == [10.0]
+
+    def test_dedupe_first_still_rejects_conflicts(self, ir):
+        data = tables([{"id": 1, "rent": 500.0}, {"id": 1, "rent": 600.0}], [])
+        with pytest.raises(LinkageError, match="duplicate id"):
+            run(ir, data, dedupe="first")
+
+    def test_unknown_policy(self, ir):
+        with pytest.raises(ValueError, match="dedupe must be one of"):
+            Data(tables={}, schema_=ir.schema_, dedupe="last")
+
+
+class TestForeignKeys:
+    def test_orphan_member(self, ir):
+        data = tables(
+            [{"id": 1, "rent": 0.0}],
+            [{"id": 10, "household": 1, "income": 1.0}, {"id": 11, "household": 7, "income": 1.0}],
+        )
+        with pytest.raises(LinkageError, match="person.household matches no household: 7"):
+            execute(ir, data)
+
+    def test_member_linked_to_two_parents(self, ir):
+        data = tables(
+            [{"id": 1, "rent": 0.0}, {"id": 2, "rent": 0.0}],
+            [{"id": 10, "household": 1, "income": 1.0}, {"id": 10, "household": 2, "income": 1.0}],
+        )
+        with pytest.raises(LinkageError, match="person rows linked to multiple parents: 10"):
+            execute(ir, data)
+
+    def test_parent_table_absent_is_unchecked(self, ir):
+        people = [{"id": 10, "household": 7, "income": 50.0}]
+        assert run(ir, {"person": people}).entities["person"]["person/tax"] == [5.0]
+        with pytest.raises(LinkageError, match="person.household matches no household: 7"):
+            run(ir, tables([], people))
+
+    def test_single_entity_helpers(self, ir):
+        from rac import RowCache, eatr
+
+        person = {"id": 10, "household": 7, "income": 50.0}
+        assert RowCache(ir, "person").run({"person": [person]})["person/tax"] == [5.0]
+        assert eatr(ir, "person", person, "income", "person/tax") == 0.1
+
+    def test_null_link_allowed(self, ir):
+        data = tables([], [{"id": 10, "household": None, "income": 50.0}])
+        assert execute(ir, data).entities["person"]["person/tax"] == [5.0]
+
+    def test_group_index(self, ir):
+        data = Data(
+            tables=tables(
+                [{"id": 1, "rent": 0.0}, {"id": 2, "rent": 0.0}],
+                [
+                    {"id": 10, "household": 1},
+                    {"id": 11, "household": 2},
+                    {"id": 12, "household": 1},
+                ],
+            ),
+            schema_=ir.schema_,
+        )
+        assert [row["id"] for row in data.get_related("person", "household", 1)] == [10, 12]
+        assert data.get_related("person", "household", 3) == []
+
+    def test_without_schema_is_unchecked(self):
+        data = Data(tables={"person": [{"id": 1, "household": 9}, {"id": 1}]})
+        assert data.get_row("person", 1) == {"id": 1}
@@ -0,0 +1,110 @@
+"""Logical operators: and/or short-circuit and give booleans, xor, and
+three-valued logic with missing operands."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    EngineConfig,
+    compile,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    parse,
+    register_function,
+    run,
+    unregister_function,
+)
+from rac.ast import BinOp, to_source
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+
+def build(formula):
+    module = parse(f"""
+        entity person:
+            x: float?
+            y: float?
+        variable person/out:
+            entity: person
+            from 2024-01-01: {formula}
+    """)
+    return compile([module], as_of=date(2024, 1, 1))
+
+
+def outputs(formula, rows, config=None):
+    data = {"person": [{"id": i, **row} for i, row in enumerate(rows)]}
+    return run(build(formula), data, config=config).entities["person"]["person/out"]
+
+
+@pytest.fixture
+def probe():
+    seen: list = []
+    register_function("probe", lambda v: seen.append(v) or v, memoize=False)
+    yield seen
+    unregister_function("probe")
+
+
+class TestShortCircuit:
+    def test_right_side_skipped_once_decided(self, probe):
+        rows = [{"x": 0.0, "y": 1.0}, {"x": 1.0, "y": 2.0}, {"x": None, "y": 3.0}]
+        for config in CONFIGS:
+            probe.clear()
+            assert outputs("x > 0 and probe(y) > 1", rows, config) == [False, True, None]
+            assert probe == [2.0, 3.0]
+            probe.clear()
+            assert outputs("x > 0 or probe(y) > 1", rows, config) == [False, True, True]
+            assert probe == [1.0, 3.0]
+
+    def test_results_are_booleans(self):
+        rows = [{"x": 2.0, "y": 3.0}, {"x": 0.0, "y": 3.0}]
+        for config in CONFIGS:
+            assert outputs("x and y", rows, config) == [True, False]
+            assert outputs("x or y", rows, config) == [True, True]
+
+
+class TestXor:
+    @pytest.mark.parametrize(
+        "x, y, expected",
+        [
+            (1.0, 1.0, False),
+            (1.0, 0.0, True),
+            (0.0, 1.0, True),
+            (0.0, 0.0, False),
+            (None, 1.0, None),
+            (1.0, None, None),
+        ],
+    )
+    def test_truth_table(self, x, y, expected):
+        for config in CONFIGS:
+            assert outputs("x > 0 xor y > 0", [{"x": x, "y": y}], config) == [expected]
+
+    def test_precedence(self):
+        expr = parse("""
+            variable gov/a:
+                from 2024-01-01: true or false xor true and false
+        """).variables[0].values[0].expr
+        # and binds tighter than xor, which binds tighter than or
+        assert to_source(expr) == "(true or (false xor (true and false)))"
+        assert isinstance(expr, BinOp) and expr.op == "or"
+
+    def test_codegen(self):
+        ir = build("x > 0 xor y > 0")
+        assert "(bool((input_data.x > 0)) != bool((input_data.y > 0)))" in generate_python(ir)
+        assert "Boolean(" in generate_javascript(ir)
+        assert " ^ " in generate_rust(ir)
+
+    def test_generated_python_agrees(self):
+        namespace: dict = {}
+        exec(generate_python(build("x > 0 xor y > 0")), namespace)
+        row = namespace["PersonInput"](x=1.0, y=0.0)
+        result = namespace["compute_person"](row, namespace["compute_scalars"]())
+        assert result["person_out"] is True
+
+
+class TestNotEqual:
+    def test_missing_and_nan(self):
+        rows = [{"x": 1.0, "y": 1.0}, {"x": 1.0, "y": None}, {"x": float("nan"), "y": 1.0}]
+        for config in CONFIGS:
+            assert outputs("x != y", rows, config) == [False, None, True]
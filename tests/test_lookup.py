@@ -0,0 +1,203 @@
+"""Lookup expressions: a key mapped through a literal table."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    EvalError,
+    Lookup,
+    ParseError,
+    compile,
+    dump_ir,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    load_ir,
+    parse,
+    run,
+)
+from rac.ast import to_source
+
+SOURCE = """
+    entity person:
+        children: int?
+        band: str
+    variable person/child_allowance:
+        entity: person
+        from 2024-01-01:
+            lookup clamp children:
+                0 => 0
+                1 => 2000
+                2 => 3500
+                4 => 5000
+    variable person/band_ratio:
+        entity: person
+        from 2024-01-01:
+            lookup band:
+                "A" => 0.6
+                "B" => 0.7
+                "C" => 0.8
+                _ => 1.0
+"""
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+ROWS = [(-1, "A"), (0, "B"), (1, "A"), (3, "B"), (4, "A"), (9, "B"), (None, "A")]
+
+
+def _data(rows):
+    return {"person": [{"id": i, "children": c, "band": b} for i, (c, b) in enumerate(rows)]}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _compile(formula):
+    source = SOURCE + f"""
+    variable person/other:
+        entity: person
+        from 2024-01-01:
+            {formula}
+"""
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+class TestParse:
+    def test_modes(self, ir):
+        allowance = ir.variables["person/child_allowance"].expr
+        assert isinstance(allowance, Lookup)
+        assert allowance.out_of_range == "clamp"
+        assert allowance.table == [(0, 0), (1, 2000), (2, 3500), (4, 5000)]
+        assert ir.variables["person/band_ratio"].expr.out_of_range == "default"
+        assert ir.variables["person/band_ratio"].expr.default == 1.0
+        other = _compile('lookup band: "A" => 0.6').variables["person/other"].expr
+        assert other.out_of_range == "error"
+
+    def test_to_source(self, ir):
+        source = to_source(ir.variables["person/band_ratio"].expr)
+        assert source == 'lookup band: "A" => 0.6; "B" => 0.7; "C" => 0.8; _ => 1.0'
+
+    def test_negative_entries(self):
+        expr = _compile("lookup children: -1 => -0.5  1 => 2").variables["person/other"].expr
+        assert expr.table == [(-1, -0.5), (1, 2)]
+
+    def test_lookup_is_still_a_name(self):
+        module = parse("variable gov/a:\n    from 2024-01-01: lookup * 2\n")
+        assert to_source(module.variables[0].values[0].expr) == "(lookup * 2)"
+
+    @pytest.mark.parametrize(
+        "formula, message",
+        [
+            ("lookup children: 1 => 2  1 => 3", "key 1 appears more than once"),
+            ('lookup clamp band: "A" => 1', "clamped lookup needs numeric keys"),
+            ("lookup clamp children: 1 => 2  _ => 0", "clamped lookup has no default"),
+            ("lookup children: 1 => children", "must be literals, got IDENT"),
+            ("lookup children: _ => 1", "needs at least one entry"),
+        ],
+    )
+    def test_rejected(self, formula, message):
+        with pytest.raises(ParseError, match=message):
+            _compile(formula)
+
+    def test_key_type_checked(self):
+        with pytest.raises(CompileError, match="compares different types"):
+            _compile('lookup children: "A" => 1')
+
+
+class TestExecute:
+    def test_clamp_takes_the_key_at_or_below(self, ir):
+        for config in CONFIGS:
+            values = run(ir, _data(ROWS), config=config).entities["person"]
+            assert values["person/child_allowance"] == [0, 0, 2000, 3500, 5000, 5000, None]
+
+    def test_default(self, ir):
+        rows = [(0, "A"), (0, "C"), (0, "D")]
+        for config in CONFIGS:
+            values = run(ir, _data(rows), config=config).entities["person"]
+            assert values["person/band_ratio"] == [0.6, 0.8, 1.0]
+
+    def test_error_names_the_row(self):
+        ir = _compile('lookup band: "A" => 0.6  "B" => 0.7')
+        rows = [(0, "A"), (0, "B"), (0, "D")]
+        for config in CONFIGS:
+            with pytest.raises(EvalError, match="no lookup entry for: 'D'") as info:
+                run(ir, _data(rows), config=config)
+            assert info.value.kind == "out_of_range"
+            assert info.value.row == 2
+
+    def test_nan_key_propagates(self, ir):
+        for config in CONFIGS:
+            values = run(ir, _data([(math.nan, "A")]), config=config).entities["person"]
+            assert math.isnan(values["person/child_allowance"][0])
+
+    def test_exact(self):
+        module = parse("""
+            variable gov/a:
+                arithmetic: "exact"
+                from 2024-01-01: (lookup 2: 1 => 0.1  2 => 0.2) * 3
+        """)
+        assert run(compile([module], as_of=date(2024, 1, 1)), {}).scalars["gov/a"] == 0.6
+
+
+class TestLoadIR:
+    def test_round_trips(self, ir):
+        assert load_ir(dump_ir(ir)).variables == ir.variables
+
+    @pytest.mark.parametrize(
+        "update, message",
+        [
+            ({"out_of_range": "nearest"}, "unknown lookup mode nearest"),
+            ({"table": []}, "needs at least one entry"),
+            ({"table": [["A", [1]]]}, "must be numbers, strings or booleans"),
+            ({"out_of_range": "default", "default": None}, "needs a default"),
+        ],
+    )
+    def test_rejected(self, ir, update, message):
+        data = ir.model_dump(by_alias=True)
+        data["variables"]["person/band_ratio"]["expr"].update(update)
+        with pytest.raises(CompileError, match=f"person/band_ratio: .*{message}"):
+            load_ir(data)
+
+
+class TestCodegen:
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        expected = run(ir, _data(ROWS)).entities["person"]
+        for i, (children, band) in enumerate(ROWS):
+            row = namespace["PersonInput"](children=children, band=band)
+            out = namespace["compute_person"](row, scalars)
+            assert out["person_child_allowance"] == expected["person/child_allowance"][i]
+            assert out["person_band_ratio"] == expected["person/band_ratio"][i]
+
+    def test_javascript(self, ir):
+        code = generate_javascript(ir)
+        assert "function racLookup(" in code
+        table = "[[0, 0], [1, 2000], [2, 3500], [4, 5000]]"
+        assert f'racLookup(input.children, {table}, "clamp", null)' in code
+
+    def test_rust(self):
+        module = parse("""
+            entity person:
+                children: int
+            variable person/allowance:
+                entity: person
+                from 2024-01-01:
+                    lookup clamp children:
+                        2 => 3500
+                        0 => 0
+        """)
+        code = generate_rust(compile([module], as_of=date(2024, 1, 1)))
+        table = "&[(0.0_f64, 0.0_f64), (2.0_f64, 3500.0_f64)]"
+        assert f"rac_lookup((input.children) as f64, {table}, true, None)" in code
+
+    def test_rust_rejects_strings(self, ir):
+        with pytest.raises(CompileError, match="is a string"):
+            generate_rust(ir)
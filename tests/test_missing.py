@@ -0,0 +1,160 @@
+"""Missing values: None propagates, is_missing and coalesce test for it,
+and each variable's missing policy says what a missing result becomes."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    compile,
+    execute_columns,
+    generate_python,
+    generate_rust,
+    parse,
+    run,
+)
+from rac.executor import EvalError
+from rac.parser import ParseError
+
+# (formula, x, expected); y is always 2.0
+CASES = [
+    ("x + y", None, None),
+    ("-x", None, None),
+    ("max(0, x)", None, None),
+    ("round(x)", None, None),
+    ("abs(x)", None, None),
+    ("x > y", None, None),
+    ("x == y", None, None),
+    ("x > 0 and y > 5", None, False),
+    ("y > 0 or x > 0", None, True),
+    ("x > 0 and y > 0", None, None),
+    ("not (x > 0)", None, None),
+    ("if x > 0: 1 else: 2", None, None),
+    ("is_missing(x)", None, True),
+    ("is_missing(x)", float("nan"), True),
+    ("is_missing(x)", 0.0, False),
+    ("coalesce(x, y)", None, 2.0),
+    ("coalesce(x, y)", 1.0, 1.0),
+]
+
+
+def build(formula, policy=""):
+    module = parse(f"""
+        entity person:
+            x: float?
+            y: float
+        variable person/out:
+            entity: person
+            {policy}
+            from 2024-01-01: {formula}
+    """)
+    return compile([module], as_of=date(2024, 1, 1))
+
+
+def _people(*xs):
+    return {"person": [{"id": i, "x": x, "y": 2.0} for i, x in enumerate(xs)]}
+
+
+@pytest.mark.parametrize("formula, x, expected", CASES)
+def test_propagation(formula, x, expected):
+    ir = build(formula)
+    for config in (None, EngineConfig(columnar=False)):
+        out = run(ir, _people(x), config=config).entities["person"]["person/out"]
+        assert out == [expected]
+
+
+class TestPolicy:
+    def test_propagate_by_default(self):
+        out = run(build("x * 2"), _people(1.0, None)).entities["person"]
+        assert out["person/out"] == [2.0, None]
+
+    def test_default(self):
+        ir = build("x * 2", 'missing: "default"\n            default: 0')
+        for config in (None, EngineConfig(columnar=False)):
+            out = run(ir, _people(1.0, None, float("nan")), config=config).entities["person"]
+            assert out["person/out"] == [2.0, 0, 0]
+
+    def test_error(self):
+        ir = build("x * 2", 'missing: "error"')
+        for config in (None, EngineConfig(columnar=False)):
+            with pytest.raises(EvalError, match=r"missing value \(in person/out row 1") as info:
+                run(ir, _people(1.0, None), config=config)
+            assert info.value.kind == "missing"
+
+    def test_execute_columns(self):
+        ir = build("x * 2", 'missing: "default"\n            default: -1')
+        out = execute_columns(ir, "person", {"x": [None, 3.0], "y": [2.0, 2.0]})
+        assert out["person/out"] == [-1, 6.0]
+
+    def test_default_needs_a_default(self):
+        with pytest.raises(CompileError, match="needs a declared default"):
+            build("x", 'missing: "default"')
+
+    def test_unknown_policy(self):
+        with pytest.raises(ParseError, match="missing must be one of"):
+            build("x", 'missing: "zero"')
+
+
+class TestNullable:
+    def test_declared_on_the_field(self):
+        fields = build("x").schema_.entities["person"].fields
+        assert (fields["x"].nullable, fields["y"].nullable) == (True, False)
+
+
+AGGREGATES = """
+    entity household:
+        rent: float
+    entity person:
+        household: -> household
+        income: float?
+    variable person/income:
+        entity: person
+        from 2024-01-01: income
+    variable household/total:
+        entity: household
+        from 2024-01-01: sum_over(person, person/income)
+    variable household/top:
+        entity: household
+        from 2024-01-01: max_over(person, person/income)
+    variable household/bottom:
+        entity: household
+        from 2024-01-01: min_over(person, person/income)
+"""
+
+
+def test_aggregates_propagate_missing():
+    ir = compile([parse(AGGREGATES)], as_of=date(2024, 1, 1))
+    data = {
+        "household": [{"id": 1, "rent": 0.0}, {"id": 2, "rent": 0.0}],
+        "person": [
+            {"id": 10, "household": 1, "income": None},
+            {"id": 11, "household": 1, "income": 5.0},
+            {"id": 12, "household": 2, "income": 2.0},
+        ],
+    }
+    households = run(ir, data).entities["household"]
+    for path in ("household/total", "household/top", "household/bottom"):
+        assert households[path] == [None, 2.0]
+
+
+def test_generated_python_is_missing():
+    namespace: dict = {}
+    exec(generate_python(build("is_missing(x)")), namespace)
+    compute = namespace["compute_person"]
+    scalars = namespace["compute_scalars"]()
+    assert compute(namespace["PersonInput"](x=None, y=2.0), scalars)["person_out"] is True
+    assert compute(namespace["PersonInput"](x=1.0, y=2.0), scalars)["person_out"] is False
+
+
+def test_rust_strings_are_never_missing():
+    module = parse("""
+        entity person:
+            name: str
+        variable person/anonymous:
+            entity: person
+            from 2024-01-01: is_missing(name)
+    """)
+    with pytest.raises(CompileError, match="is_missing of a str has no Rust translation"):
+        generate_rust(compile([module], as_of=date(2024, 1, 1)))
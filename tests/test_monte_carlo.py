@@ -0,0 +1,209 @@
+"""Monte Carlo replications: Model.run_monte_carlo and the native binary's random draws."""
+
+import math
+import shutil
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    Model,
+    compile,
+    generate_javascript,
+    generate_rust,
+    parse,
+    replication_seeds,
+    run,
+)
+
+SOURCE = """
+    entity person:
+        id: int
+        income: float
+        eligible: float
+
+    variable gov/shock:
+        from 2024-01-01: normal(1, 0.1)
+
+    variable person/takes_up:
+        entity: person
+        from 2024-01-01: where(eligible > 0 and bernoulli(0.7), 1, 0)
+
+    variable person/reported:
+        entity: person
+        from 2024-01-01: income * gov/shock + normal(0, 100)
+
+    variable person/benefit:
+        entity: person
+        from 2024-01-01: person/takes_up * where(uniform() < 0.5, 10, 20)
+"""
+
+# No declared id: streams are keyed by each row's own id, of any type
+UNDECLARED = """
+    entity person:
+        income: float
+
+    variable person/reported:
+        entity: person
+        from 2024-01-01: income + normal(0, 100)
+"""
+UNDECLARED_ROWS = [{"id": key, "income": 1.0} for key in ("a", 1e17, 1e-05, 7)] + [{"income": 2.0}]
+
+OUTPUTS = ["person/takes_up", "person/reported", "person/benefit"]
+ROWS = [{"id": 100 + i, "income": 100.0 * i, "eligible": float(i % 3 != 0)} for i in range(30)]
+DATA = {"person": ROWS}
+
+
+class InProcess:
+    """The parts of a CompiledBinary the in-process path reads."""
+
+    entity_outputs = {"person": OUTPUTS}
+    entity_schemas = {"person": ["id", "income", "eligible"]}
+
+    def run_monte_carlo(self, *args, **kwargs):
+        raise AssertionError("spawned the binary")
+
+
+@pytest.fixture(scope="module")
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+@pytest.fixture
+def model(ir):
+    return Model(ir, InProcess())
+
+
+def _replications(ir, seed, n):
+    """Each replication's outputs, computed one run at a time."""
+    out = []
+    for s in replication_seeds(seed, n):
+        result = run(ir, DATA, config=EngineConfig(seed=s))
+        out.append((result.entities["person"], result.scalars))
+    return out
+
+
+class TestReplications:
+    def test_seeds(self):
+        seeds = replication_seeds(7, 10)
+        assert seeds[:3] == replication_seeds(7, 3)
+        assert len(set(seeds)) == 10 and seeds != replication_seeds(8, 10)
+
+    def test_means_and_standard_errors(self, ir, model):
+        result = model.run_monte_carlo(DATA, 20, seed=7)
+        draws = _replications(ir, 7, 20)
+        for path in OUTPUTS:
+            for i in (0, 1, 29):
+                values = [person[path][i] for person, _ in draws]
+                mean = sum(values) / 20
+                sd = math.sqrt(sum((v - mean) ** 2 for v in values) / 19)
+                assert result.mean("person", path)[i] == pytest.approx(mean)
+                assert result.standard_error("person", path)[i] == pytest.approx(sd / math.sqrt(20))
+        shocks = [scalars["gov/shock"] for _, scalars in draws]
+        assert result.scalar_means["gov/shock"] == pytest.approx(sum(shocks) / 20)
+        assert result.n_replications == 20 and result.seed == 7
+
+    def test_draws(self, ir, model):
+        result = model.run_monte_carlo(DATA, 3, seed=7, keep_draws=True)
+        assert result.draws["person"].shape == (3, 30, 3)
+        person, _ = _replications(ir, 7, 3)[2]
+        assert result.draws["person"][2][:, 1].tolist() == person["person/reported"]
+        assert model.run_monte_carlo(DATA, 3, seed=7).draws is None
+
+    def test_replication_reruns_alone(self, model):
+        result = model.run_monte_carlo(DATA, 4, seed=7, keep_draws=True)
+        config = EngineConfig(seed=replication_seeds(7, 4)[3])
+        alone = model.run(DATA, config=config).arrays["person"]
+        assert alone.tolist() == result.draws["person"][3].tolist()
+
+    def test_seed_defaults_to_the_config(self, ir):
+        model = Model(ir, InProcess(), EngineConfig(seed=7))
+        reseeded = model.run_monte_carlo(DATA, 5)
+        assert reseeded.seed == 7
+        assert reseeded.means == Model(ir, InProcess()).run_monte_carlo(DATA, 5, seed=7).means
+
+    def test_one_replication_has_no_standard_error(self, model):
+        result = model.run_monte_carlo(DATA, 1, seed=7)
+        assert all(math.isnan(v) for v in result.standard_error("person", "person/reported"))
+        assert math.isnan(result.scalar_standard_errors["gov/shock"])
+
+    def test_deterministic_model(self):
+        source = "entity person:\n    income: float\n"
+        source += "variable person/tax:\n    entity: person\n    from 2024-01-01: income * 0.2"
+        ir = compile([parse(source)], as_of=date(2024, 1, 1))
+
+        class Binary(InProcess):
+            entity_outputs = {"person": ["person/tax"]}
+            entity_schemas = {"person": ["income"]}
+
+        result = Model(ir, Binary()).run_monte_carlo({"person": [{"income": 100.0}]}, 3)
+        assert result.mean("person", "person/tax").tolist() == [20.0]
+        assert result.standard_error("person", "person/tax").tolist() == [0.0]
+
+    def test_undeclared_ids_key_as_run_does(self):
+        ir = compile([parse(UNDECLARED)], as_of=date(2024, 1, 1))
+
+        class Binary(InProcess):
+            entity_outputs = {"person": ["person/reported"]}
+            entity_schemas = {"person": ["income"]}
+
+        data = {"person": UNDECLARED_ROWS}
+        alone = Model(ir, Binary()).run(data, config=EngineConfig(seed=3)).arrays["person"]
+        expected = run(ir, data, config=EngineConfig(seed=3)).entities["person"]
+        assert alone[:, 0].tolist() == expected["person/reported"]
+
+    def test_needs_a_replication(self, model):
+        with pytest.raises(ValueError, match="n_replications must be at least 1, got 0"):
+            model.run_monte_carlo(DATA, 0)
+
+
+class TestCodegen:
+    def test_rust_draws_the_executors_streams(self, ir):
+        rust = generate_rust(ir)
+        assert "fn rac_blake2b_64(data: &[u8]) -> u64 {" in rust
+        assert "pub fn compute(seed: &str) -> Self {" in rust
+        assert "scalars: &Scalars, seed: &str, key: &str) -> Self {" in rust
+        assert 'let stream = rac_stream(seed, "person/benefit", key);' in rust
+        assert "rac_uniform(&stream, 0)" in rust
+        assert 'panic!("bernoulli probability is missing")' in rust
+
+    def test_javascript_refuses(self, ir):
+        with pytest.raises(CompileError, match="draws random numbers"):
+            generate_javascript(ir)
+
+
+@pytest.mark.skipif(not shutil.which("cargo"), reason="Rust toolchain not available")
+class TestNative:
+    @pytest.fixture(scope="class")
+    def native(self):
+        return Model.from_source(SOURCE, as_of=date(2024, 1, 1))
+
+    def test_run_matches_the_executor(self, native):
+        config = EngineConfig(seed=3)
+        binary = native.run(DATA, config=config, sequential_threshold=0).arrays["person"]
+        in_process = native.run(DATA, config=config).arrays["person"]
+        assert binary.tobytes() == in_process.tobytes()
+
+    def test_ids_key_streams_as_run_does(self):
+        native = Model.from_source(UNDECLARED, as_of=date(2024, 1, 1))
+        data = {"person": UNDECLARED_ROWS}
+        config = EngineConfig(seed=3)
+        binary = native.run(data, config=config, sequential_threshold=0).arrays["person"]
+        ir = compile([parse(UNDECLARED)], as_of=date(2024, 1, 1))
+        expected = run(ir, data, config=config).entities["person"]["person/reported"]
+        assert binary[:, 0].tolist() == expected
+
+    @pytest.mark.parametrize("threads", [1, 4, 16])
+    def test_replications_match_in_process(self, native, threads):
+        binary = native.run_monte_carlo(
+            DATA, 70, seed=7, keep_draws=True, sequential_threshold=0, num_threads=threads
+        )
+        reference = native.run_monte_carlo(DATA, 70, seed=7, keep_draws=True)
+        assert binary.means["person"].tobytes() == reference.means["person"].tobytes()
+        assert (
+            binary.standard_errors["person"].tobytes()
+            == reference.standard_errors["person"].tobytes()
+        )
+        assert binary.draws["person"].tobytes() == reference.draws["person"].tobytes()
@@ -0,0 +1,120 @@
+"""NaN semantics: every engine must agree on NaN operands.
+
+min/max/clip propagate NaN, round, abs, floor, ceil, trunc and sign keep it
+(round, floor, ceil and trunc keep infinities too),
+comparisons with NaN are false except ``!=``, and a condition on NaN takes
+the else branch.
+"""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import compile, execute, generate_javascript, generate_python, generate_rust, parse
+
+NAN = float("nan")
+
+# (formula, x, expected); y is always 2.0
+CASES = [
+    ("max(0, x)", NAN, NAN),
+    ("max(x, 0)", NAN, NAN),
+    ("min(x, 5)", NAN, NAN),
+    ("min(5, x)", NAN, NAN),
+    ("max(y, x, 1)", NAN, NAN),
+    ("clip(x, 0, 10)", NAN, NAN),
+    ("abs(x)", NAN, NAN),
+    ("round(x)", NAN, NAN),
+    ("floor(x)", NAN, NAN),
+    ("ceil(x)", NAN, NAN),
+    ("trunc(x)", NAN, NAN),
+    ("round(x)", -math.inf, -math.inf),
+    ("floor(x)", math.inf, math.inf),
+    ("ceil(x)", -math.inf, -math.inf),
+    ("trunc(x)", math.inf, math.inf),
+    ("sign(x)", NAN, NAN),
+    ("where(x > 0, x, y)", NAN, 2.0),
+    ("x + y", NAN, NAN),
+    ("x > 0", NAN, False),
+    ("x < 0", NAN, False),
+    ("x == x", NAN, False),
+    ("x != x", NAN, True),
+    ("if x > 0: 1 else: 2", NAN, 2),
+    ("max(0, x)", -3.0, 0),
+    ("min(x, y)", 1.5, 1.5),
+    ("clip(x, 0, 10)", 12.0, 10),
+    ("round(x)", 2.6, 3),
+    ("floor(x)", -2.5, -3),
+    ("ceil(x)", -2.5, -2),
+    ("trunc(x)", -2.5, -2),
+    ("sign(x)", -2.5, -1),
+    ("sign(x)", 0.0, 0),
+    ("where(x > y, x, y)", 3.5, 3.5),
+]
+
+
+def build(formula):
+    module = parse(f"""
+        entity person:
+            x: float
+            y: float
+        variable person/out:
+            entity: person
+            from 2024-01-01: {formula}
+    """)
+    return compile([module], as_of=date(2024, 1, 1))
+
+
+def same(actual, expected):
+    if isinstance(expected, float) and math.isnan(expected):
+        return isinstance(actual, float) and math.isnan(actual)
+    return actual == expected
+
+
+def run_executor(ir, x):
+    result = execute(ir, {"person": [{"id": 1, "x": x, "y": 2.0}]})
+    return result.entities["person"]["person/out"][0]
+
+
+def run_generated_python(ir, x):
+    namespace: dict = {}
+    exec(generate_python(ir), namespace)
+    row = namespace["PersonInput"](x=x, y=2.0)
+    return namespace["compute_person"](row, namespace["compute_scalars"]())["person_out"]
+
+
+ENGINES = {"executor": run_executor, "python": run_generated_python}
+
+
+@pytest.mark.parametrize("engine", list(ENGINES))
+@pytest.mark.parametrize("formula, x, expected", CASES)
+def test_matrix(engine, formula, x, expected):
+    actual = ENGINES[engine](build(formula), x)
+    assert same(actual, expected), f"{engine}: {formula} with x={x} gave {actual!r}"
+
+
+@pytest.mark.parametrize("formula, x, expected", CASES)
+def test_engines_agree(formula, x, expected):
+    ir = build(formula)
+    results = [engine(ir, x) for engine in ENGINES.values()]
+    assert all(same(r, results[0]) for r in results)
+
+
+class TestCompiledBackends:
+    """Backends that cannot run here must emit NaN-propagating code."""
+
+    def test_rust_min_max_propagate(self):
+        code = generate_rust(build("clip(max(0, x), min(x, y), 10)"))
+        assert "if a.is_nan() || b.is_nan() { f64::NAN }" in code
+        assert "rac_max(0" in code
+        assert "rac_min(rac_max(" in code
+        assert ", f64::min)" not in code and ".max(0" not in code
+
+    def test_rust_sign_of_zero(self):
+        code = generate_rust(build("sign(floor(x))"))
+        assert ".floor()" in code and ".signum()" not in code
+
+    def test_javascript_uses_nan_propagating_math(self):
+        # Math.min/Math.max already return NaN when any argument is NaN
+        code = generate_javascript(build("clip(max(0, x), 0, 10)"))
+        assert "Math.max(0" in code
@@ -0,0 +1,94 @@
+"""Evaluation order derived by the engine, and cycles named in full."""
+
+import json
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    CycleError,
+    compile,
+    dump_ir,
+    execute,
+    expression_deps,
+    load_ir,
+    parse,
+    topological_order,
+)
+
+SOURCE = """
+    entity person:
+        income: float
+    variable gov/rate:
+        from 2024-01-01: 0.2
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+    variable person/net:
+        entity: person
+        from 2024-01-01: income - person/tax
+"""
+
+CIRCULAR = """
+    variable gov/a:
+        from 2024-01-01: gov/c + 1
+    variable gov/b:
+        from 2024-01-01: gov/a * 2
+    variable gov/c:
+        from 2024-01-01: gov/b - 3
+    variable gov/d:
+        from 2024-01-01: gov/a
+"""
+
+
+@pytest.fixture
+def raw():
+    return json.loads(dump_ir(compile([parse(SOURCE)], as_of=date(2024, 1, 1))))
+
+
+class TestCycles:
+    def test_names_every_variable_on_the_cycle(self):
+        with pytest.raises(CycleError) as exc:
+            compile([parse(CIRCULAR)], as_of=date(2024, 1, 1))
+        assert exc.value.cycle == ["gov/a", "gov/c", "gov/b", "gov/a"]
+        assert str(exc.value) == "circular dependency: gov/a -> gov/c -> gov/b -> gov/a"
+        assert isinstance(exc.value, CompileError)
+
+    def test_self_reference(self):
+        with pytest.raises(CycleError) as exc:
+            compile([parse("variable gov/a:\n    from 2024-01-01: gov/a + 1\n")], date(2024, 1, 1))
+        assert exc.value.cycle == ["gov/a", "gov/a"]
+
+    def test_fixed_point_groups_are_not_cycles(self):
+        ir = compile(
+            [parse(CIRCULAR)], as_of=date(2024, 1, 1), fixed_point={"gov/a", "gov/b", "gov/c"}
+        )
+        assert topological_order(ir.variables, ir.fixed_point)[-1] == "gov/d"
+        with pytest.raises(CycleError):
+            topological_order(ir.variables)
+
+
+class TestDerivedOrder:
+    def test_order_omitted_is_derived(self, raw):
+        del raw["order"]
+        ir = load_ir(raw)
+        assert ir.order == ["gov/rate", "person/tax", "person/net"]
+        data = {"person": [{"id": 1, "income": 100.0}]}
+        assert execute(ir, data).entities["person"]["person/net"] == [80.0]
+
+    def test_derived_order_rejects_a_cycle(self, raw):
+        del raw["order"]
+        raw["variables"]["person/tax"]["deps"] = ["gov/rate", "person/net"]
+        raw["variables"]["person/tax"]["expr"] = raw["variables"]["person/net"]["expr"]
+        with pytest.raises(CycleError, match="person/tax -> person/net -> person/tax"):
+            load_ir(raw)
+
+    def test_deps_must_cover_the_expression(self, raw):
+        raw["variables"]["person/net"]["deps"] = []
+        with pytest.raises(CompileError, match="person/net reads person/tax, missing from"):
+            load_ir(raw)
+
+    def test_expression_deps(self, raw):
+        ir = load_ir(raw)
+        assert expression_deps(ir.variables["person/net"].expr) == {"person/tax"}
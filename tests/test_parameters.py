@@ -0,0 +1,303 @@
+"""Tests for parameter trees, uprating, and exporting resolved parameters
+and importing them as overrides."""
+
+import json
+from datetime import date
+
+import pytest
+
+from rac import (
+    ParameterError,
+    UpratingRule,
+    apply_overrides,
+    compile,
+    execute,
+    export_parameters,
+    import_parameter_overrides,
+    load_parameter_tree,
+    parameters,
+    parse,
+)
+
+SOURCE = """
+    entity person:
+        income: float
+
+    variable gov/allowance:
+        from 2023-01-01: 10000
+        from 2024-04-06: 12570
+
+    variable gov/rate:
+        from 2020-01-01: 0.2
+
+    variable gov/higher_threshold:
+        from 2020-01-01: gov/allowance * 4
+
+    variable gov/scheme:
+        from 2020-01-01: "standard"
+
+    variable person/tax:
+        entity: person
+        from 2020-01-01: max(0, min(income, gov/higher_threshold) - gov/allowance) * gov/rate
+"""
+
+DATA = {"person": [{"id": 1, "income": 30000.0}, {"id": 2, "income": 90000.0}]}
+PERIOD = date(2024, 6, 1)
+
+
+@pytest.fixture
+def modules():
+    return [parse(SOURCE)]
+
+
+class TestExport:
+    def test_csv_snapshot(self, modules, tmp_path):
+        out = tmp_path / "params.csv"
+        export_parameters(modules, PERIOD, {"gov/rate": 0.25}, out)
+        assert out.read_text() == (
+            "path,value,source,effective\n"
+            "gov/allowance,12570,literal,2024-04-06\n"
+            "gov/higher_threshold,50280,formula,2020-01-01\n"
+            "gov/rate,0.25,override,\n"
+            'gov/scheme,"""standard""",literal,2020-01-01\n'
+        )
+
+    def test_json_snapshot(self, modules, tmp_path):
+        out = tmp_path / "params.json"
+        export_parameters(modules, date(2023, 6, 1), None, out, format="json")
+        assert out.read_text() == (
+            "{\n"
+            '  "parameters": [\n'
+            "    {\n"
+            '      "path": "gov/allowance",\n'
+            '      "value": 10000,\n'
+            '      "source": "literal",\n'
+            '      "effective": "2023-01-01"\n'
+            "    },\n"
+            "    {\n"
+            '      "path": "gov/higher_threshold",\n'
+            '      "value": 40000,\n'
+            '      "source": "formula",\n'
+            '      "effective": "2020-01-01"\n'
+            "    },\n"
+            "    {\n"
+            '      "path": "gov/rate",\n'
+            '      "value": 0.2,\n'
+            '      "source": "literal",\n'
+            '      "effective": "2020-01-01"\n'
+            "    },\n"
+            "    {\n"
+            '      "path": "gov/scheme",\n'
+            '      "value": "standard",\n'
+            '      "source": "literal",\n'
+            '      "effective": "2020-01-01"\n'
+            "    }\n"
+            "  ]\n"
+            "}\n"
+        )
+
+    def test_from_ir(self, modules, tmp_path):
+        ir = compile(modules, as_of=PERIOD)
+        rows = export_parameters(ir, None, None, tmp_path / "p.csv")
+        assert [row.path for row in rows] == [
+            "gov/allowance",
+            "gov/higher_threshold",
+            "gov/rate",
+            "gov/scheme",
+        ]
+        with pytest.raises(ParameterError, match="already compiled"):
+            export_parameters(ir, PERIOD, None, tmp_path / "p.csv")
+
+    def test_modules_need_period(self, modules, tmp_path):
+        with pytest.raises(ParameterError, match="period is required"):
+            export_parameters(modules, None, None, tmp_path / "p.csv")
+
+    def test_format_from_extension(self, modules, tmp_path):
+        export_parameters(modules, PERIOD, None, tmp_path / "p.json")
+        assert "parameters" in json.loads((tmp_path / "p.json").read_text())
+        export_parameters(modules, PERIOD, None, tmp_path / "p.CSV")
+        assert (tmp_path / "p.CSV").read_text().startswith("path,value,source,effective\n")
+
+    def test_unknown_format(self, modules, tmp_path):
+        with pytest.raises(ParameterError, match="format must be one of"):
+            export_parameters(modules, PERIOD, None, tmp_path / "p.xlsx", format="xlsx")
+
+
+class TestRoundTrip:
+    @pytest.mark.parametrize("fmt", ["csv", "json"])
+    def test_import_reproduces_run(self, modules, tmp_path, fmt):
+        ir = compile(modules, as_of=PERIOD)
+        overrides = {"gov/allowance": 15000}
+        original = execute(apply_overrides(ir, overrides), DATA)
+
+        out = tmp_path / f"params.{fmt}"
+        export_parameters(modules, PERIOD, overrides, out, format=fmt)
+        imported = import_parameter_overrides(out)
+        assert imported["gov/higher_threshold"] == 60000
+        assert imported["gov/scheme"] == "standard"
+
+        reproduced = execute(apply_overrides(ir, imported), DATA)
+        assert reproduced == original
+
+    def test_bad_csv(self, tmp_path):
+        out = tmp_path / "params.csv"
+        out.write_text("name,amount\ngov/rate,0.2\n")
+        with pytest.raises(ParameterError, match="expected columns"):
+            import_parameter_overrides(out)
+
+
+TREE = {
+    "gov": {
+        "allowance": {"2023-01-01": 10000, "2024-04-06": 12570},
+        "rates": {"basic": {"2020-01-01": 0.2}},
+    }
+}
+
+RULES = """
+    entity person:
+        income: float
+
+    variable person/tax:
+        entity: person
+        from 2020-01-01: max(0, income - gov/allowance) * gov/rates/basic
+"""
+
+
+class TestParameterTree:
+    def test_paths_join_nested_keys(self):
+        tree = load_parameter_tree(TREE)
+        assert set(tree.leaves) == {"gov/allowance", "gov/rates/basic"}
+
+    def test_as_of_resolution(self):
+        tree = load_parameter_tree(TREE)
+        assert tree.value("gov/allowance", date(2024, 4, 5)) == 10000
+        assert tree.value("gov/allowance", date(2024, 4, 6)) == 12570
+        assert tree.at(date(2022, 1, 1)) == {"gov/rates/basic": 0.2}
+        with pytest.raises(ParameterError, match="no value in force"):
+            tree.value("gov/allowance", date(2022, 1, 1))
+
+    def test_compiled_with_rules(self):
+        modules = [parse(RULES), load_parameter_tree(TREE).module()]
+        data = {"person": [{"id": 1, "income": 30000.0}]}
+        for as_of, tax in [(date(2024, 1, 1), 4000.0), (date(2024, 6, 1), 3486.0)]:
+            result = execute(compile(modules, as_of=as_of), data)
+            assert result.entities["person"]["person/tax"] == [pytest.approx(tax)]
+
+    def test_yaml_file(self, tmp_path):
+        path = tmp_path / "params.yaml"
+        path.write_text("gov:\n  allowance:\n    2023-01-01: 10000\n    2024-04-06: 12570\n")
+        tree = load_parameter_tree(path)
+        assert tree.value("gov/allowance", date(2024, 6, 1)) == 12570
+
+    def test_boolean_values(self):
+        tree = load_parameter_tree({"gov": {"capped": {"2020-01-01": False, "2024-01-01": True}}})
+        assert tree.at(date(2023, 1, 1)) == {"gov/capped": False}
+        assert tree.value("gov/capped", date(2024, 1, 1)) is True
+
+    def test_json_file(self, tmp_path):
+        path = tmp_path / "params.json"
+        path.write_text(json.dumps(TREE))
+        assert load_parameter_tree(path).leaves == load_parameter_tree(TREE).leaves
+
+    @pytest.mark.parametrize(
+        "tree, message",
+        [
+            ({"gov": {"rate": {"2020-01-01": [0.2]}}}, "must be a number"),
+            ({"gov": {"bad-name": {"2020-01-01": 1}}}, "invalid parameter name"),
+            ({"gov": {"rate": 0.2}}, "expected dated values"),
+            ({"2020-01-01": 1}, "must name parameters"),
+        ],
+    )
+    def test_malformed(self, tree, message):
+        with pytest.raises(ParameterError, match=message):
+            load_parameter_tree(tree)
+
+
+UPRATED = {
+    "gov": {
+        "allowance": {
+            "2023-01-01": 10000,
+            "2024-04-06": 12570,
+            "uprating": {"index": "index/cpi", "rounding": "nearest", "multiple": 10},
+        },
+        "rates": {"basic": {"2020-01-01": 0.2}},
+    },
+    "index": {
+        "cpi": {"2023-04-06": 95.0, "2024-04-06": 100.0, "2025-04-06": 103.1},
+        "earnings": {"2025-01-01": 100.0},
+    },
+}
+
+
+class TestUprating:
+    def test_projects_past_the_last_legislated_value(self):
+        tree = load_parameter_tree(UPRATED)
+        assert tree.uprating["gov/allowance"].index == "index/cpi"
+        assert tree.value("gov/allowance", date(2025, 4, 5)) == 12570
+        # 12570 * 1.031 = 12959.67, to the nearest 10
+        assert tree.value("gov/allowance", date(2025, 4, 6)) == 12960
+        assert tree.uprated().leaves["gov/allowance"][-1] == (date(2025, 4, 6), 12960)
+        assert tree.leaves["gov/allowance"][-1] == (date(2024, 4, 6), 12570)
+
+    def test_projects_once(self, monkeypatch):
+        tree = load_parameter_tree(UPRATED)
+        calls = []
+        project = parameters._project
+        monkeypatch.setattr(parameters, "_project", lambda *a: calls.append(1) or project(*a))
+        assert tree.at(date(2025, 4, 6))["gov/allowance"] == 12960
+        assert tree.value("gov/allowance", date(2025, 4, 6)) == 12960
+        assert len(calls) == 1
+        plain = tree.uprated()
+        assert not plain.uprating and plain.uprated().leaves == plain.leaves
+
+    @pytest.mark.parametrize(
+        "rounding, multiple, expected",
+        [("none", 1, 12959.67), ("down", 10, 12950), ("up", 100, 13000), ("nearest", 0.5, 12959.5)],
+    )
+    def test_rounding(self, rounding, multiple, expected):
+        tree = load_parameter_tree(UPRATED)
+        rule = UpratingRule(index="index/cpi", rounding=rounding, multiple=multiple)
+        tree = tree.model_copy(update={"uprating": {"gov/allowance": rule}})
+        assert tree.value("gov/allowance", date(2026, 1, 1)) == pytest.approx(expected)
+
+    def test_compiled_with_rules(self):
+        modules = [parse(RULES), load_parameter_tree(UPRATED).module()]
+        data = {"person": [{"id": 1, "income": 30000.0}]}
+        result = execute(compile(modules, as_of=date(2025, 6, 1)), data)
+        assert result.entities["person"]["person/tax"] == [pytest.approx((30000 - 12960) * 0.2)]
+
+    def test_yaml_file(self, tmp_path):
+        path = tmp_path / "params.yaml"
+        path.write_text(
+            "gov:\n  allowance:\n    2024-04-06: 12570\n"
+            "    uprating: {index: index/cpi, rounding: up, multiple: 10}\n"
+            "index:\n  cpi:\n    2024-04-06: 100\n    2025-04-06: 102\n"
+        )
+        assert load_parameter_tree(path).value("gov/allowance", date(2025, 6, 1)) == 12830
+
+    def test_uprating_is_still_a_name(self):
+        tree = load_parameter_tree({"gov": {"uprating": {"factor": {"2020-01-01": 1.1}}}})
+        assert set(tree.leaves) == {"gov/uprating/factor"} and not tree.uprating
+
+    @pytest.mark.parametrize(
+        "rule, message",
+        [
+            ({"index": "index/rpi"}, "unknown index index/rpi"),
+            ({"index": "index/earnings"}, "no value in force on 2024-04-06"),
+            ({"index": "index/cpi", "rounding": "banker"}, "rounding must be one of"),
+            ({"index": "index/cpi", "multiple": 0}, "multiple must be positive"),
+            ({"rounding": "up"}, "bad uprating rule"),
+            ("index/cpi", "uprating must be a mapping"),
+        ],
+    )
+    def test_malformed(self, rule, message):
+        tree = json.loads(json.dumps(UPRATED))
+        tree["gov"]["allowance"]["uprating"] = rule
+        with pytest.raises(ParameterError, match=message):
+            load_parameter_tree(tree)
+
+    def test_index_must_not_be_uprated(self):
+        tree = json.loads(json.dumps(UPRATED))
+        tree["index"]["cpi"]["uprating"] = {"index": "index/earnings"}
+        with pytest.raises(ParameterError, match="index index/cpi is itself uprated"):
+            load_parameter_tree(tree)
@@ -0,0 +1,112 @@
+"""run_periods: the same rules over several periods, with the input read once."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    Compiler,
+    Data,
+    EngineConfig,
+    PeriodError,
+    compile,
+    parse,
+    period_start,
+    run,
+    run_periods,
+)
+from rac import periods as periods_module
+
+SOURCE = """
+    entity person:
+        income: float
+
+    variable gov/allowance:
+        from 2023-01-01: 10000
+        from 2024-04-06: 12570
+
+    variable gov/rate:
+        from 2020-01-01: 0.2
+        from 2025-01-01: 0.25
+
+    variable gov/bonus:
+        from 2025-01-01: 100
+
+    variable person/tax:
+        entity: person
+        from 2020-01-01: max(0, income - gov/allowance) * gov/rate
+"""
+
+DATA = {"person": [{"id": 1, "income": 20000.0}, {"id": 2, "income": 50000.0}]}
+
+
+@pytest.fixture
+def modules():
+    return [parse(SOURCE)]
+
+
+class TestRunPeriods:
+    def test_parameters_resolve_per_period(self, modules):
+        results = run_periods(modules, DATA, ["2023", "2024", "2025"])
+        assert list(results) == ["2023", "2024", "2025"]
+        tax = {period: r.entities["person"]["person/tax"] for period, r in results.items()}
+        assert tax["2023"] == pytest.approx([2000, 8000])
+        assert tax["2024"] == pytest.approx([2000, 8000])  # the new allowance starts in April
+        assert tax["2025"] == pytest.approx([1857.5, 9357.5])
+        assert "gov/bonus" not in results["2024"].scalars
+        assert results["2025"].scalars["gov/bonus"] == 100
+
+    def test_matches_a_run_per_period(self, modules):
+        results = run_periods(modules, DATA, ["2023-06", date(2024, 4, 6)])
+        for period, start in (("2023-06", date(2023, 6, 1)), ("2024-04-06", date(2024, 4, 6))):
+            expected = run(compile(modules, as_of=start), DATA)
+            assert results[period].scalars == expected.scalars
+            assert results[period].entities == expected.entities
+
+    def test_input_is_read_once(self, modules, monkeypatch):
+        built = []
+
+        class CountingData(Data):
+            def __init__(self, **kwargs):
+                built.append(kwargs)
+                super().__init__(**kwargs)
+
+        monkeypatch.setattr(periods_module, "Data", CountingData)
+        run_periods(modules, DATA, ["2023", "2024", "2025"])
+        assert len(built) == 1
+
+    def test_outputs_and_config(self, modules):
+        results = run_periods(
+            modules, DATA, ["2025"], outputs=["person/tax"], config=EngineConfig(columnar=False)
+        )
+        assert set(results["2025"].scalars) == {"gov/allowance", "gov/rate"}
+        assert results["2025"].entities["person"]["person/tax"] == pytest.approx([1857.5, 9357.5])
+
+    def test_duplicate_period_rejected(self, modules):
+        with pytest.raises(PeriodError, match="2024 is listed more than once"):
+            run_periods(modules, DATA, ["2024", "2025", "2024"])
+
+
+class TestPeriodStart:
+    @pytest.mark.parametrize(
+        "period, start",
+        [
+            ("2024", date(2024, 1, 1)),
+            ("2024-04", date(2024, 4, 1)),
+            ("2024-04-06", date(2024, 4, 6)),
+            (date(2024, 4, 6), date(2024, 4, 6)),
+        ],
+    )
+    def test_start(self, period, start):
+        assert period_start(period) == start
+
+    @pytest.mark.parametrize("period", ["24", "2024-13", "2024-02-30", "FY2024", "2024-01-01-01"])
+    def test_rejected(self, period):
+        with pytest.raises(PeriodError, match="expected YYYY, YYYY-MM or YYYY-MM-DD"):
+            period_start(period)
+
+
+def test_compiler_compiles_again_for_another_date(modules):
+    compiler = Compiler(modules)
+    assert compiler.compile(date(2023, 1, 1)).variables["gov/allowance"].expr.value == 10000
+    assert compiler.compile(date(2025, 1, 1)).variables["gov/allowance"].expr.value == 12570
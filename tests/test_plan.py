@@ -0,0 +1,148 @@
+"""Run plans and dry runs: every check before the evaluation boundary."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    BYTES_PER_VALUE,
+    DryRunReport,
+    PlanError,
+    build_plan,
+    compile,
+    parse,
+    register_function,
+    run,
+    unregister_function,
+)
+from rac.plan import entity_inputs
+from rac.schema import Data
+
+SOURCE = """
+    entity household:
+        rent: float
+    entity person:
+        household: -> household
+        income: float
+        age: int
+    variable gov/rate:
+        from 2024-01-01: 0.2
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+    variable person/senior:
+        entity: person
+        from 2024-01-01: probe(age) >= 65
+    variable household/rent_share:
+        entity: household
+        from 2024-01-01: rent / 2
+"""
+
+DATA = {
+    "household": [{"id": 1, "rent": 1000.0}],
+    "person": [
+        {"id": 1, "household_id": 1, "income": 30000.0, "age": 70},
+        {"id": 2, "household_id": 1, "income": 10000.0, "age": 40},
+    ],
+}
+
+
+@pytest.fixture
+def calls():
+    seen: list = []
+    register_function("probe", lambda x: seen.append(x) or x, memoize=False)
+    yield seen
+    unregister_function("probe")
+
+
+@pytest.fixture
+def ir(calls):
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _without(entity, field):
+    rows = [{k: v for k, v in row.items() if k != field} for row in DATA[entity]]
+    return {**DATA, entity: rows}
+
+
+def _duplicate_id():
+    return {**DATA, "household": DATA["household"] * 2}
+
+
+def _reordered(ir):
+    return ir.model_copy(update={"order": list(reversed(ir.order))})
+
+
+# (seeded error, IR transform, data, run kwargs)
+SEEDED = {
+    "missing input": (lambda ir: ir, _without("person", "income"), {}),
+    "duplicate id": (lambda ir: ir, _duplicate_id(), {}),
+    "order against deps": (_reordered, DATA, {}),
+    "unknown output": (lambda ir: ir, DATA, {"outputs": ["person/nope"]}),
+    "unknown monitored path": (lambda ir: ir, DATA, {"collect_stats": ["person/nope"]}),
+}
+
+
+class TestPlan:
+    def test_layout(self, ir):
+        plan = build_plan(ir, Data(tables=DATA, schema_=ir.schema_))
+        assert [step.path for step in plan.steps] == ir.order
+        assert plan.scalars == ["gov/rate"]
+        assert plan.entities == {
+            "person": ["person/tax", "person/senior"],
+            "household": ["household/rent_share"],
+        }
+        rows = {step.path: step.rows for step in plan.steps}
+        assert rows == {
+            "gov/rate": 1,
+            "person/tax": 2,
+            "person/senior": 2,
+            "household/rent_share": 1,
+        }
+        assert plan.estimated_bytes == 6 * BYTES_PER_VALUE
+
+    def test_lists_every_missing_input(self, ir):
+        data = Data(tables=_without("person", "age"), schema_=ir.schema_)
+        data = Data(tables={**data.tables, "household": [{"id": 1}]}, schema_=ir.schema_)
+        with pytest.raises(PlanError) as exc:
+            build_plan(ir, data)
+        assert exc.value.problems == ["household rows have no rent", "person rows have no age"]
+
+    def test_empty_entity_needs_no_inputs(self, ir):
+        plan = build_plan(ir, Data(tables={"household": [], "person": []}, schema_=ir.schema_))
+        assert plan.estimated_bytes == BYTES_PER_VALUE
+
+    def test_checked_ir_still_checks_data(self, ir):
+        inputs = entity_inputs(ir)
+        assert inputs == {"household": ["rent"], "person": ["income", "age"]}
+        # The IR's own checks are skipped, so a bad order goes unnoticed
+        build_plan(_reordered(ir), Data(tables=DATA, schema_=ir.schema_), inputs)
+        data = Data(tables=_without("person", "income"), schema_=ir.schema_)
+        with pytest.raises(PlanError, match="person rows have no income"):
+            build_plan(ir, data, inputs)
+
+
+class TestDryRun:
+    def test_evaluates_nothing(self, ir, calls):
+        report = run(ir, DATA, dry_run=True)
+        assert isinstance(report, DryRunReport)
+        assert report.ok and report.plan is not None
+        assert calls == []
+
+    def test_shares_the_real_run_plan(self, ir):
+        dry = run(ir, DATA, dry_run=True)
+        assert dry.plan == run(ir, DATA).plan
+
+    def test_outputs_prune_the_plan(self, ir, calls):
+        report = run(ir, DATA, dry_run=True, outputs=["person/tax"])
+        assert [step.path for step in report.plan.steps] == ["gov/rate", "person/tax"]
+
+    @pytest.mark.parametrize("case", list(SEEDED))
+    def test_catches_what_a_real_run_would(self, ir, case):
+        transform, data, kwargs = SEEDED[case]
+        seeded = transform(ir)
+        with pytest.raises(Exception) as exc:
+            run(seeded, data, **kwargs)
+        report = run(seeded, data, dry_run=True, **kwargs)
+        assert not report.ok and report.plan is None
+        assert "; ".join(report.problems) == str(exc.value)
@@ -0,0 +1,118 @@
+"""Preflight reports: pass/warn/fail per check before a production run."""
+
+from datetime import date
+
+from rac import compile, parse, preflight, register_function, unregister_function
+
+SOURCE = """
+    entity person:
+        income: float
+        age: int
+    variable gov/rate:
+        from 2024-01-01: 0.2
+    variable gov/retired_rate:
+        from 2024-01-01: 0.1
+    variable person/taxable:
+        entity: person
+        from 2024-01-01: max(0, income - 10000)
+    variable person/tax:
+        entity: person
+        from 2024-01-01: person/taxable * gov/rate
+    variable person/senior:
+        entity: person
+        from 2024-01-01: age >= 65
+"""
+
+DATA = {"person": [{"id": i, "income": 20000.0 + i, "age": 30 + i} for i in range(50)]}
+
+CHECKS = ["ir", "references", "functions", "inputs", "types", "unused", "critical_path"]
+
+
+def build(source=SOURCE):
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+def failing(report):
+    return {check.name: check.status for check in report.checks if check.status != "pass"}
+
+
+class TestPreflight:
+    def test_flags_exactly_the_one_issue(self):
+        report = preflight(build(), DATA)
+        assert [check.name for check in report.checks] == CHECKS
+        assert failing(report) == {"unused": "warn"}
+        assert report["unused"].details == ["gov/retired_rate"]
+        assert report.status == "warn" and report.ok
+
+    def test_unknown_function_fails(self):
+        source = SOURCE.replace("age >= 65", "pension_age(age)")
+        report = preflight(build(source), DATA, outputs=["person/tax", "person/senior"])
+        assert failing(report) == {"functions": "fail", "unused": "warn"}
+        assert report["functions"].details == ["person/senior calls unknown function pension_age"]
+        assert not report.ok
+        register_function("pension_age", lambda age: age >= 66)
+        try:
+            assert preflight(build(source), DATA)["functions"].status == "pass"
+        finally:
+            unregister_function("pension_age")
+
+    def test_inputs_checked_on_a_sample(self):
+        rows = DATA["person"][:10] + [{"id": 99, "income": 1.0}] * 5
+        data = {"person": [{"id": i, "income": 1.0} for i in range(3)]}
+        assert preflight(build(), data)["inputs"].details == ["person rows have no age"]
+        # Only the sample is read: rows past it are never looked at
+        assert preflight(build(), {"person": rows}, sample_rows=10)["inputs"].status == "pass"
+
+    def test_unresolved_reference_warns(self):
+        source = SOURCE + """
+    variable gov/surcharge:
+        from 2030-01-01: 5
+    variable person/levy:
+        entity: person
+        from 2024-01-01: income * 0 + gov/surcharge
+"""
+        report = preflight(build(source), DATA)
+        assert report["references"].status == "warn"
+        assert "person/levy reads gov/surcharge" in report["references"].details[0]
+
+    def test_critical_path(self):
+        chain = "2 steps: gov/rate -> person/tax"
+        assert preflight(build(), DATA, max_critical_path=2)["critical_path"].details == [chain]
+        check = preflight(build(), DATA, max_critical_path=1)["critical_path"]
+        assert check.status == "warn"
+        assert check.details == [chain, "longer than 1"]
+
+    def test_critical_path_of_fixed_point_groups(self):
+        source = """
+    entity person:
+        income: float
+    variable person/tax:
+        entity: person
+        from 2024-01-01: 0.2 * (income + person/benefit)
+    variable person/benefit:
+        entity: person
+        from 2024-01-01: max(0, 1000 - 0.5 * person/tax)
+"""
+        group = {"person/tax", "person/benefit"}
+        ir = compile([parse(source)], as_of=date(2024, 1, 1), fixed_point=group)
+        check = preflight(ir, DATA)["critical_path"]
+        assert check.status == "pass"
+        assert check.details == ["1 steps: person/tax"]
+
+    def test_broken_ir_stops_early(self):
+        ir = build().model_copy(update={"order": ["gov/rate"]})
+        report = preflight(ir, DATA)
+        assert [check.name for check in report.checks] == ["ir"]
+        assert report.status == "fail"
+
+
+class TestTypes:
+    def test_mistyped_input(self):
+        data = {"person": [{"id": 1, "income": 100.0, "age": 40.5}]}
+        report = preflight(build(), data)
+        assert report["types"].status == "fail"
+        assert report["types"].details == ["person row 0: age should be int, got float 40.5"]
+
+    def test_whole_float_is_an_int(self):
+        data = {"person": [{"id": 1, "income": 100, "age": 40.0}]}
+        assert preflight(build(), data)["types"].status == "pass"
@@ -0,0 +1,106 @@
+"""Tests for expression-level profiling."""
+
+import time
+from datetime import date
+from unittest.mock import patch
+
+import pytest
+
+from rac import Profiler, compile, execute, parse, run
+
+SOURCE = """
+    entity person:
+        income: float
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/bracket:
+        entity: person
+        from 2024-01-01: slow(income) * gov/rate
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: max(0, person/bracket - 10)
+"""
+
+
+def slow(x):
+    time.sleep(0.001)
+    return x
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _data(n: int) -> dict:
+    return {"person": [{"id": i, "income": 100.0 * i} for i in range(n)]}
+
+
+class TestProfiler:
+    def test_hottest_node_is_slow_call(self, ir):
+        profiler = Profiler(ir)
+        with patch.dict("rac.executor.BUILTINS", {"slow": slow}):
+            run(ir, _data(5), profiler=profiler)
+        top = profiler.hottest(3)
+        assert top[0].variable == "person/bracket"
+        assert top[0].snippet == "slow(income)"
+        assert top[0].node_id == 1
+        assert top[0].count == 5
+
+    def test_inclusive_time_covers_children(self, ir):
+        profiler = Profiler(ir)
+        with patch.dict("rac.executor.BUILTINS", {"slow": slow}):
+            run(ir, _data(3), profiler=profiler)
+        by_node = {(s.variable, s.node_id): s for s in profiler.stats()}
+        root = by_node[("person/bracket", 0)]
+        call = by_node[("person/bracket", 1)]
+        assert root.total_time >= call.total_time
+        assert root.self_time < call.self_time
+
+    def test_sampling_every_kth_row(self, ir):
+        profiler = Profiler(ir, sample_every=3)
+        with patch.dict("rac.executor.BUILTINS", {"slow": slow}):
+            run(ir, _data(10), profiler=profiler)
+        counts = {(s.variable, s.node_id): s.count for s in profiler.stats()}
+        assert counts[("person/tax", 0)] == 4  # rows 0, 3, 6, 9
+        assert counts[("gov/rate", 0)] == 1  # scalars are always profiled
+
+    def test_variable_times(self, ir):
+        profiler = Profiler(ir)
+        with patch.dict("rac.executor.BUILTINS", {"slow": slow}):
+            run(ir, _data(5), profiler=profiler)
+        times = profiler.variable_times()
+        assert set(times) == {"gov/rate", "person/bracket", "person/tax"}
+        assert max(times, key=times.get) == "person/bracket"
+        assert times["person/bracket"] >= 0.005
+
+    def test_results_unchanged(self, ir):
+        with patch.dict("rac.executor.BUILTINS", {"slow": slow}):
+            plain = execute(ir, _data(4))
+            profiled = run(ir, _data(4), profiler=Profiler(ir, sample_every=2))
+        assert plain.entities == profiled.entities
+
+    def test_repeated_subtree_counts(self):
+        ir = compile(
+            [
+                parse("""
+                    entity person:
+                        income: float
+                    variable person/x:
+                        entity: person
+                        from 2024-01-01: max(income, 5) + max(income, 5) + max(income, 5)
+                """)
+            ],
+            as_of=date(2024, 1, 1),
+        )
+        profiler = Profiler(ir)
+        run(ir, _data(2), profiler=profiler)
+        snippets = [s.snippet for s in profiler.stats() if s.count == 2]
+        assert snippets.count("max(income, 5)") == 3
+
+    def test_invalid_sample_every(self, ir):
+        with pytest.raises(ValueError):
+            Profiler(ir, sample_every=0)
@@ -0,0 +1,153 @@
+"""Tests for exact and approximate weighted quantiles."""
+
+import random
+
+import pytest
+
+from rac import (
+    QuantileSketch,
+    StatsError,
+    quantile_groups,
+    sketch_column,
+    weighted_quantile,
+    weighted_quantiles,
+)
+
+QS = [0.01, 0.1, 0.25, 0.5, 0.75, 0.9, 0.99]
+
+
+@pytest.fixture(scope="module")
+def big():
+    """1M log-normal incomes with integer weights, plus some zeros and negatives."""
+    rng = random.Random(0)
+    n = 1_000_000
+    income = [rng.lognormvariate(10, 1) for _ in range(n)]
+    for i in range(0, n, 50):
+        income[i] = 0.0
+    for i in range(7, n, 101):
+        income[i] = -rng.lognormvariate(7, 1)
+    weight = [float(rng.randint(1, 500)) for _ in range(n)]
+    return {"income": income, "weight": weight}
+
+
+class TestExact:
+    def test_unweighted(self):
+        data = {"x": [5.0, 1.0, 3.0, 2.0, 4.0]}
+        assert weighted_quantiles(data, "x", [0, 0.2, 0.5, 1]) == [1.0, 1.0, 3.0, 5.0]
+
+    def test_weighted(self):
+        data = [{"x": 1.0, "w": 1.0}, {"x": 2.0, "w": 3.0}, {"x": 10.0, "w": 1.0}]
+        assert weighted_quantile(data, "x", 0.5, "w") == 2.0
+        assert weighted_quantile(data, "x", 0.9, "w") == 10.0
+
+    def test_zero_weight_rows_ignored(self):
+        data = {"x": [100.0, 1.0, 2.0], "w": [0.0, 1.0, 1.0]}
+        assert weighted_quantile(data, "x", 1.0, "w") == 2.0
+
+    def test_missing_values_left_out(self):
+        data = {"x": [None, 1.0, 2.0, 3.0], "w": [10.0, 1.0, 1.0, 1.0]}
+        assert weighted_quantile(data, "x", 0.5, "w") == 2.0
+        assert weighted_quantile(data, "x", 0.5, "w", approx=True) == pytest.approx(2.0, rel=0.01)
+
+    def test_nan_values_left_out(self):
+        data = {"x": [float("nan"), 1.0, 2.0, 3.0]}
+        assert weighted_quantile(data, "x", 0.5) == 2.0
+        assert weighted_quantile(data, "x", 0.5, approx=True) == pytest.approx(2.0, rel=0.01)
+        expected = sketch_column({"x": [1.0, 2.0, 3.0]}, "x")
+        assert sketch_column(data, "x").buckets == expected.buckets
+
+    def test_errors(self):
+        with pytest.raises(StatsError, match="negative weights"):
+            weighted_quantile({"x": [1.0], "w": [-1.0]}, "x", 0.5, "w")
+        with pytest.raises(StatsError, match="empty"):
+            weighted_quantile({"x": []}, "x", 0.5)
+        with pytest.raises(StatsError, match=r"\[0, 1\]"):
+            weighted_quantile({"x": [1.0]}, "x", 1.5)
+
+
+class TestApprox:
+    def test_error_bound_on_1m_rows(self, big):
+        exact = weighted_quantiles(big, "income", QS, "weight")
+        approx = weighted_quantiles(big, "income", QS, "weight", approx=True)
+        for e, a in zip(exact, approx):
+            assert abs(a - e) <= 0.01 * abs(e)
+
+    def test_tighter_accuracy(self, big):
+        exact = weighted_quantile(big, "income", 0.5, "weight")
+        approx = weighted_quantile(
+            big, "income", 0.5, "weight", approx=True, relative_accuracy=0.001
+        )
+        assert abs(approx - exact) <= 0.001 * abs(exact)
+
+    def test_zero_and_negative_values(self):
+        data = {"x": [-8.0, -2.0, 0.0, 0.0, 3.0]}
+        approx = weighted_quantiles(data, "x", [0.0, 0.3, 0.5, 1.0], approx=True)
+        assert approx[0] == pytest.approx(-8.0, rel=0.01)
+        assert approx[1] == pytest.approx(-2.0, rel=0.01)
+        assert approx[2] == 0.0
+        assert approx[3] == pytest.approx(3.0, rel=0.01)
+
+
+class TestSketch:
+    @staticmethod
+    def chunked(data, size):
+        sketch = QuantileSketch()
+        n = len(data["income"])
+        for start in range(0, n, size):
+            chunk = {k: v[start : start + size] for k, v in data.items()}
+            sketch.merge(sketch_column(chunk, "income", "weight"))
+        return sketch
+
+    def test_independent_of_chunk_size(self, big):
+        whole = sketch_column(big, "income", "weight")
+        assert self.chunked(big, 65_536).buckets == whole.buckets
+        assert self.chunked(big, 99_991).buckets == whole.buckets
+
+    def test_independent_of_merge_order(self):
+        rng = random.Random(1)
+        chunks = [
+            {
+                "income": [rng.uniform(0, 1e5) for _ in range(500)],
+                "weight": [rng.uniform(0.1, 3.0) for _ in range(500)],
+            }
+            for _ in range(8)
+        ]
+        sketches = [sketch_column(c, "income", "weight") for c in chunks]
+        forward, backward = QuantileSketch(), QuantileSketch()
+        for s in sketches:
+            forward.merge(s)
+        for s in reversed(sketches):
+            backward.merge(s)
+        assert forward.buckets == backward.buckets
+
+    def test_merge_mismatched_accuracy(self):
+        with pytest.raises(StatsError, match="relative accuracy"):
+            QuantileSketch(0.01).merge(QuantileSketch(0.02))
+
+    def test_empty(self):
+        with pytest.raises(StatsError, match="empty"):
+            QuantileSketch().quantile(0.5)
+
+
+class TestQuantileGroups:
+    def test_deciles(self):
+        data = {"x": [float(i) for i in range(1, 101)]}
+        groups = quantile_groups(data, "x")
+        assert groups[:10] == [1] * 10
+        assert groups[-10:] == [10] * 10
+        assert [groups.count(g) for g in range(1, 11)] == [10] * 10
+
+    def test_weighted_groups(self):
+        data = {"x": [1.0, 2.0, 3.0, 4.0], "w": [3.0, 1.0, 1.0, 3.0]}
+        assert quantile_groups(data, "x", n=2, weight_path="w") == [1, 1, 2, 2]
+
+    def test_missing_value_has_no_group(self):
+        data = {"x": [1.0, None, 2.0, 3.0, float("nan"), 4.0]}
+        assert quantile_groups(data, "x", n=2) == [1, None, 1, 2, None, 2]
+
+    def test_approx_groups_close_to_exact(self, big):
+        sample = {k: v[:20_000] for k, v in big.items()}
+        exact = quantile_groups(sample, "income", weight_path="weight")
+        approx = quantile_groups(sample, "income", weight_path="weight", approx=True)
+        assert all(abs(a - e) <= 1 for a, e in zip(exact, approx))
+        assert sum(a != e for a, e in zip(exact, approx)) < 0.05 * len(exact)
@@ -243,6 +243,32 @@ class TestAST:
         assert expr.type == "binop"
         assert expr.left.type == "literal"
 
+    def test_walk_pre_order(self):
+        from rac import parse
+        from rac.ast import walk
+
+        module = parse("""
+            variable gov/x:
+                from 2024-01-01: max(0, a * 2)
+        """)
+        nodes = list(walk(module.variables[0].values[0].expr))
+        assert [n.type for n in nodes] == ["call", "literal", "binop", "var", "literal"]
+
+    def test_to_source(self):
+        from rac import parse
+        from rac.ast import to_source
+
+        module = parse("""
+            variable gov/x:
+                from 2024-01-01:
+                    if not flag and age >= 65: max(0, income * 0.2)
+                    else: -gov/y
+        """)
+        expr = module.variables[0].values[0].expr
+        assert to_source(expr) == (
+            "if (not flag and (age >= 65)): max(0, (income * 0.2)) else: -gov/y"
+        )
+
 
 # -- Compiler ----------------------------------------------------------------
 
@@ -370,6 +396,125 @@ class TestCompiler:
         ir = compile([module], as_of=date(2024, 6, 1))
         assert "gov/rate" not in ir.variables
 
+    def test_feature_flags(self):
+        from unittest.mock import patch
+
+        from rac import compile, execute, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/pilot_bonus:
+                entity: person
+                requires_feature: "pilot"
+                from 2024-01-01: bonus(income)
+            variable person/benefit:
+                entity: person
+                from 2024-01-01: 100 + person/pilot_bonus
+        """)
+        data = {"person": [{"id": 1, "income": 1000.0}]}
+        calls = []
+
+        def bonus(x):
+            calls.append(x)
+            return x * 0.05
+
+        off = compile([module], as_of=date(2024, 6, 1))
+        assert "person/pilot_bonus" not in off.variables
+        assert off.variables["person/benefit"].deps == set()
+
+        on = compile([module], as_of=date(2024, 6, 1), features={"pilot"})
+        assert on.variables["person/pilot_bonus"].requires_feature == "pilot"
+
+        with patch.dict("rac.executor.BUILTINS", {"bonus": bonus}):
+            assert execute(off, data).entities["person"]["person/benefit"] == [100]
+            assert calls == []
+            assert execute(on, data).entities["person"]["person/benefit"] == [150.0]
+            assert calls == [1000.0]
+
+    def test_disabled_variable_reads_its_default(self):
+        from rac import compile, execute, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/pilot_bonus:
+                entity: person
+                requires_feature: "pilot"
+                default: 25
+                from 2024-01-01: income * 0.05
+            variable person/pilot_eligible:
+                entity: person
+                requires_feature: "pilot"
+                from 2024-01-01: income > 500
+            variable person/benefit:
+                entity: person
+                from 2024-01-01: 100 + person/pilot_bonus
+            variable person/flagged:
+                entity: person
+                from 2024-01-01: person/pilot_eligible
+        """)
+        off = compile([module], as_of=date(2024, 6, 1))
+        result = execute(off, {"person": [{"id": 1, "income": 1000.0}]}).entities["person"]
+        assert result["person/benefit"] == [125]
+        assert result["person/flagged"] == [False]
+
+    def test_critical_path(self):
+        from rac import compile, critical_path, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable gov/threshold:
+                from 2024-01-01: 1000
+            variable person/taxable:
+                entity: person
+                from 2024-01-01: max(0, income - gov/threshold)
+            variable person/tax:
+                entity: person
+                from 2024-01-01: person/taxable * gov/rate
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax
+            variable person/flag:
+                entity: person
+                from 2024-01-01: income > gov/threshold
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        length, path = critical_path(ir.variables)
+        assert length == 4
+        assert path == ["gov/threshold", "person/taxable", "person/tax", "person/net"]
+        assert critical_path({}) == (0, [])
+
+    def test_required_inputs(self):
+        from rac import CompileError, compile, dependency_closure, parse, required_inputs
+
+        module = parse("""
+            entity person:
+                income: float
+                age: int
+                hours: float
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * gov/rate
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax
+            variable person/senior:
+                entity: person
+                from 2024-01-01: age >= 65
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        assert dependency_closure(ir, ["person/net"]) == {"person/net", "person/tax", "gov/rate"}
+        assert required_inputs(ir, "person", ["person/net"]) == ["income"]
+        assert required_inputs(ir, "person") == ["income", "age"]
+        with pytest.raises(CompileError, match="unknown variable"):
+            required_inputs(ir, "person", ["person/missing"])
+
 
 # -- Executor ----------------------------------------------------------------
 
@@ -516,6 +661,332 @@ class TestExecutor:
         assert result.scalars["gov/b"] == 20
         assert result.scalars["gov/c"] == 30
 
+    def test_shared_dependency_evaluated_once_per_row(self):
+        from unittest.mock import patch
+
+        from rac import compile, execute, parse
+
+        calls = []
+
+        def expensive(x):
+            calls.append(x)
+            return x * 2
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/base:
+                entity: person
+                from 2024-01-01: expensive(income)
+            variable person/a:
+                entity: person
+                from 2024-01-01: person/base + 1
+            variable person/b:
+                entity: person
+                from 2024-01-01: person/base + 2
+            variable person/c:
+                entity: person
+                from 2024-01-01: person/base + person/a + person/b
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        data = {"person": [{"id": 1, "income": 10}, {"id": 2, "income": 20}]}
+        with patch.dict("rac.executor.BUILTINS", {"expensive": expensive}):
+            result = execute(ir, data)
+        assert calls == [10, 20]
+        assert result.entities["person"]["person/c"] == [63, 123]
+
+    def test_resolve_scalars_matches_full_run(self):
+        from rac import ExecutionError, compile, execute, parse, resolve_scalars
+
+        module = parse("""
+            entity person:
+                income: float
+            variable gov/base_allowance:
+                from 2024-01-01: 10000
+            variable gov/allowance:
+                from 2024-01-01: gov/base_allowance * 1.1
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable person/tax:
+                entity: person
+                from 2024-01-01: max(0, income - gov/allowance) * gov/rate
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        full = execute(ir, {"person": [{"id": 1, "income": 50000}]})
+
+        assert resolve_scalars(ir) == full.scalars
+        reform = resolve_scalars(ir, {"gov/base_allowance": 20000})
+        assert reform["gov/allowance"] == pytest.approx(22000)
+        assert reform["gov/rate"] == 0.2
+        assert "person/tax" not in reform
+        with pytest.raises(ExecutionError, match="unknown variable"):
+            resolve_scalars(ir, {"gov/missing": 1})
+
+    def test_eval_variable_in_isolation(self):
+        from rac import ExecutionError, compile, eval_variable, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable gov/allowance:
+                from 2024-01-01: 10000
+            variable person/tax:
+                entity: person
+                from 2024-01-01: max(0, income - gov/allowance) * 0.2
+        """)
+        var = compile([module], as_of=date(2024, 6, 1)).variables["person/tax"]
+
+        assert eval_variable(var, {"income": 30000, "gov/allowance": 5000}) == 5000
+        with pytest.raises(ExecutionError, match="gov/allowance"):
+            eval_variable(var, {"income": 30000})
+        assert eval_variable(var, {"income": 30000}, strict=False) == 6000
+
+    def test_result_ranges(self):
+        from rac import compile, execute, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - 20000
+            variable person/credit:
+                entity: person
+                from 2024-01-01: coalesce(income, 0) * 0
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        incomes = [35000, 5000, float("nan"), 80000, 12000]
+        data = {"person": [{"id": i, "income": v} for i, v in enumerate(incomes)]}
+        result = execute(ir, data)
+
+        ranges = result.ranges()
+        net = [v for v in result.entities["person"]["person/net"] if v == v]
+        assert ranges["person/net"] == (min(net), max(net)) == (-15000, 60000)
+        assert ranges["person/credit"] == (0, 0)
+
+    def test_result_to_matrix(self):
+        from rac import ExecutionError, compile, execute, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * 0.2
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax
+            variable person/taxpayer:
+                entity: person
+                from 2024-01-01: person/tax > 0
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        incomes = [50000, 0, 12000]
+        result = execute(ir, {"person": [{"id": i, "income": v} for i, v in enumerate(incomes)]})
+
+        matrix, names = result.to_matrix("person")
+        assert names == ["person/tax", "person/net", "person/taxpayer"]
+        assert matrix.shape == (3, 3)
+        assert matrix[0, 0] == 10000.0
+        assert matrix[2, 1] == 9600.0
+        assert (matrix[0, 2], matrix[1, 2]) == (1.0, 0.0)
+        with pytest.raises(ExecutionError, match="no outputs"):
+            result.to_matrix("household")
+
+    def test_result_to_long(self):
+        from rac import ExecutionError, compile, execute, parse
+
+        module = parse("""
+            entity household:
+                rent: float
+            entity person:
+                income: float
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * gov/rate
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax
+            variable household/rent_paid:
+                entity: household
+                from 2024-01-01: rent * 12
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        data = {
+            "person": [{"id": 7, "income": 1000}, {"id": 9, "income": 500}],
+            "household": [{"id": "h1", "rent": 100}],
+        }
+        result = execute(ir, data)
+
+        records = result.to_long()
+        # One record per (row, entity variable); scalars are not rows
+        assert len(records) == 2 * 2 + 1
+        assert records[:2] == [
+            {"entity": "person", "entity_id": 7, "variable": "person/tax", "value": 200},
+            {"entity": "person", "entity_id": 7, "variable": "person/net", "value": 800},
+        ]
+        wide = result.entities
+        for r in records:
+            row = result.row_ids[r["entity"]].index(r["entity_id"])
+            assert wide[r["entity"]][r["variable"]][row] == r["value"]
+        [household] = result.to_long("household")
+        assert (household["entity_id"], household["value"]) == ("h1", 1200)
+        with pytest.raises(ExecutionError, match="no outputs"):
+            result.to_long("family")
+
+    def test_exact_arithmetic(self):
+        from rac import compile, eval_variable, execute, parse
+
+        def source(arithmetic: str) -> str:
+            return f"""
+                entity person:
+                    income: float
+                variable gov/residue:
+                    arithmetic: "{arithmetic}"
+                    from 2024-01-01: 0.1 + 0.2 - 0.3
+                variable person/third:
+                    entity: person
+                    arithmetic: "{arithmetic}"
+                    from 2024-01-01: income / 3
+                variable person/residue:
+                    entity: person
+                    arithmetic: "{arithmetic}"
+                    from 2024-01-01: person/third + person/third + person/third - income
+            """
+
+        data = {"person": [{"id": 1, "income": 0.21}]}
+        inexact = execute(compile([parse(source("float"))], as_of=date(2024, 6, 1)), data)
+        assert inexact.scalars["gov/residue"] != 0
+        assert inexact.entities["person"]["person/residue"] != [0]
+
+        ir = compile([parse(source("exact"))], as_of=date(2024, 6, 1))
+        exact = execute(ir, data)
+        assert exact.scalars["gov/residue"] == 0
+        assert exact.entities["person"]["person/residue"] == [0]
+        assert exact.entities["person"]["person/third"] == [0.07]
+        assert type(exact.entities["person"]["person/third"][0]) is float
+        assert eval_variable(ir.variables["person/third"], {"income": 0.21}) == 0.07
+
+    def test_unknown_arithmetic_mode(self):
+        from rac import ParseError, parse
+
+        with pytest.raises(ParseError, match="arithmetic must be one of"):
+            parse("""
+                variable gov/x:
+                    arithmetic: "decimal"
+                    from 2024-01-01: 1
+            """)
+
+    def test_result_order_is_topological(self):
+        from rac import compile, execute, parse
+
+        module = parse("""
+            entity person:
+                income: float
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax + person/credit
+            variable person/credit:
+                entity: person
+                from 2024-01-01: person/tax * gov/share
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * gov/rate
+            variable gov/share:
+                from 2024-01-01: gov/rate / 2
+            variable gov/rate:
+                from 2024-01-01: 0.2
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        result = execute(ir, {"person": [{"id": 1, "income": 100.0}]})
+
+        assert sorted(result.order) == sorted(ir.variables)
+        position = {path: i for i, path in enumerate(result.order)}
+        for path, var in ir.variables.items():
+            assert all(position[dep] < position[path] for dep in var.deps)
+
+    def test_outputs_prune_unrelated_variables(self):
+        from rac import compile, parse, register_function, run, unregister_function
+
+        calls = []
+        register_function("probe", lambda x: calls.append(x) or x, memoize=False)
+        try:
+            module = parse("""
+                entity person:
+                    income: float
+                    age: int
+                variable gov/rate:
+                    from 2024-01-01: 0.2
+                variable person/tax:
+                    entity: person
+                    from 2024-01-01: income * gov/rate
+                variable person/net:
+                    entity: person
+                    from 2024-01-01: income - person/tax
+                variable person/senior:
+                    entity: person
+                    from 2024-01-01: probe(age) >= 65
+            """)
+            ir = compile([module], as_of=date(2024, 6, 1))
+            data = {
+                "person": [
+                    {"id": 1, "income": 100.0, "age": 70},
+                    {"id": 2, "income": 50.0, "age": 30},
+                ]
+            }
+
+            result = run(ir, data, outputs=["person/net"])
+            assert calls == []
+            assert result.order == ["gov/rate", "person/tax", "person/net"]
+            assert result.entities["person"] == {
+                "person/tax": pytest.approx([20.0, 10.0]),
+                "person/net": pytest.approx([80.0, 40.0]),
+            }
+            assert result.scalars == {"gov/rate": 0.2}
+
+            full = run(ir, data)
+            assert calls == [70, 30]
+            assert full.entities["person"]["person/net"] == result.entities["person"]["person/net"]
+        finally:
+            unregister_function("probe")
+
+    def test_outputs_unknown_variable(self):
+        from rac import CompileError, compile, parse, run
+
+        ir = compile([parse(TAX_MODEL_SOURCE)], as_of=date(2024, 6, 1))
+        with pytest.raises(CompileError, match="unknown variable: person/nope"):
+            run(ir, {"person": []}, outputs=["person/nope"])
+
+    def test_work_budget(self):
+        from rac import EngineConfig, WorkBudgetExceeded, compile, execute, parse, run
+
+        module = parse("""
+            entity person:
+                income: float
+            variable gov/rate:
+                from 2024-01-01: 0.2
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * gov/rate
+            variable person/net:
+                entity: person
+                from 2024-01-01: income - person/tax
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        data = {"person": [{"id": i, "income": 1000.0 * i} for i in range(10)]}
+        # gov/rate is 1 node, each person/tax and person/net row 3
+        assert execute(ir, data) == run(ir, data, config=EngineConfig(work_budget=61))
+
+        with pytest.raises(WorkBudgetExceeded) as info:
+            run(ir, data, config=EngineConfig(work_budget=20))
+        err = info.value
+        assert (err.variable, err.row, err.completed, err.total) == ("person/tax", 6, 1, 3)
+        assert str(err) == (
+            "work budget of 20 nodes exceeded at person/tax row 6 (1 of 3 variables completed)"
+        )
+
 
 # -- Rust Codegen ------------------------------------------------------------
 
@@ -585,6 +1056,21 @@ class TestRustCodegen:
         assert ".max(" in rust_code
         assert ".min(" in rust_code
 
+    def test_generate_rust_coalesce(self):
+        from rac import compile, generate_rust, parse
+
+        module = parse("""
+            entity person:
+                hours: float
+            variable person/hours_filled:
+                entity: person
+                from 2024-01-01: coalesce(hours, 35)
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        rust_code = generate_rust(ir)
+        assert ".is_nan()" in rust_code
+        assert "unknown" not in rust_code
+
 
 # -- Native Compilation (requires Rust toolchain) ---------------------------
 
@@ -998,7 +1484,7 @@ class TestRustCodegenCoverage:
         ir = compile([module], as_of=date(2024, 6, 1))
         rust_code = generate_rust(ir)
         assert ".abs()" in rust_code
-        assert ".round()" in rust_code
+        assert ".round_ties_even() + 0.0" in rust_code
 
     def test_generate_rust_and_or(self):
         from rac import compile, generate_rust, parse
@@ -1479,13 +1965,14 @@ class TestRustCodegenCoverage2:
         )
         gen = RustGenerator(ir, "test")
         code = gen.generate()
-        assert ".iter().sum::<f64>()" in code
+        assert "rac_sum(&items)" in code
         assert ".len() as f64" in code
+        assert gen._gen_builtin_call("any", ["items"]) == "items.iter().any(|v| *v != 0.0)"
 
     def test_gen_unknown_builtin(self):
         from rac.ast import Call, Literal
         from rac.codegen.rust import RustGenerator
-        from rac.compiler import IR, ResolvedVar
+        from rac.compiler import IR, CompileError, ResolvedVar
         from rac.schema import Schema
 
         ir = IR(
@@ -1499,8 +1986,8 @@ class TestRustCodegenCoverage2:
             order=["gov/x"],
         )
         gen = RustGenerator(ir, "test")
-        code = gen.generate()
-        assert "/* unknown: bogus */" in code
+        with pytest.raises(CompileError, match="bogus has no Rust translation"):
+            gen.generate()
 
     def test_gen_field_access(self):
         from rac import compile, generate_rust, parse
@@ -1595,8 +2082,9 @@ class TestRustCodegenCoverage2:
         assert "1_f64" in code
 
     def test_gen_expr_catchall(self):
-        """Unknown AST node type in codegen falls to default."""
+        """An unknown AST node type is refused, not computed as 0."""
         from rac.codegen.rust import RustGenerator
+        from rac.compiler import CompileError
 
         gen = RustGenerator.__new__(RustGenerator)
         gen.ir = None
@@ -1604,8 +2092,8 @@ class TestRustCodegenCoverage2:
         class FakeExpr:
             pass
 
-        result = gen._gen_expr(FakeExpr())
-        assert result == "0.0_f64"
+        with pytest.raises(CompileError, match="can't generate Rust for FakeExpr"):
+            gen._gen_expr(FakeExpr())
 
     def test_gen_max_n_args(self):
         """max with more than 2 args uses fold."""
@@ -1756,6 +2244,26 @@ class TestNativeCoverage:
         assert '"person"' in main_code
         assert "as i64" in main_code  # age is int
 
+    def test_generate_main_uses_a_scoped_pool(self):
+        """Threads come from a pool built per run, never rayon's global one."""
+        from rac import compile, parse
+        from rac.native import _generate_main
+
+        module = parse("""
+            entity person:
+                income: float
+
+            variable person/tax:
+                entity: person
+                from 2024-01-01: income * 0.2
+        """)
+        ir = compile([module], as_of=date(2024, 6, 1))
+        main_code = _generate_main(ir, {"person": ["income"]}, {"person": ["person/tax"]})
+        assert "ThreadPoolBuilder::new()" in main_code
+        assert "pool.install(" in main_code
+        assert ".with_min_len(chunk_size)" in main_code
+        assert "if threads == 1" in main_code
+
     def test_generate_main_empty_outputs(self):
         """_generate_main skips entity with no outputs (L216-217)."""
         from rac import compile, parse
@@ -1941,6 +2449,58 @@ class TestModelCoverage:
         assert "person" in result.arrays
         assert abs(result.arrays["person"][0, 0] - 10000.0) < 0.01
 
+    def test_tiny_run_stays_in_process(self, tax_model):
+        from unittest.mock import patch
+
+        data = {"person": [{"id": 1, "income": 50000.0}]}
+        with patch.object(tax_model._binary, "run", side_effect=AssertionError("spawned")):
+            result = tax_model.run(data)
+        assert result.arrays["person"].shape == (1, 1)
+        assert result.arrays["person"][0, 0] == 10000.0
+
+    def test_large_run_uses_binary(self, tax_model):
+        from unittest.mock import patch
+
+        data = {"person": [{"id": 1, "income": 50000.0}]}
+        with patch.object(tax_model._binary, "run", wraps=tax_model._binary.run) as spy:
+            tax_model.run(data, sequential_threshold=0)
+        spy.assert_called_once()
+
+    def test_in_process_matches_native(self, tax_model):
+        import numpy as np
+
+        data = {"person": [{"id": i, "income": 1234.5 * i + 0.1} for i in range(200)]}
+        native = tax_model.run(data, sequential_threshold=0)
+        in_process = tax_model.run(data, sequential_threshold=10**9)
+        assert np.array_equal(native.arrays["person"], in_process.arrays["person"])
+
+    def test_in_process_arithmetic_matches_native(self):
+        from rac import Model
+
+        source = "entity person:\n    x: float\n    y: float\n"
+        for name, formula in [
+            ("rounded", "round(x)"),
+            ("ratio", "x / y"),
+            ("quotient", "x // y"),
+            ("remainder", "x % y"),
+            ("power", "x ** y"),
+        ]:
+            source += f"variable person/{name}:\n    entity: person\n"
+            source += f"    from 2024-01-01: {formula}\n"
+        model = Model.from_source(source, as_of=date(2024, 6, 1))
+        xs = [-2.5, -0.5, 0.5, 1.5, 2.5, 7.0, 0.0]
+        rows = [{"x": x, "y": y} for x in xs for y in [0.0, 2.0, -1.0, 0.5]]
+        native = model.run({"person": rows}, sequential_threshold=0)
+        in_process = model.run({"person": rows}, sequential_threshold=10**9)
+        # repr tells -0.0 from 0.0
+        assert repr(native.arrays["person"].tolist()) == repr(in_process.arrays["person"].tolist())
+
+    def test_in_process_numpy_input(self, tax_model):
+        import numpy as np
+
+        result = tax_model.run({"person": np.array([[50000.0], [0.0]])})
+        assert result.arrays["person"][:, 0].tolist() == [10000.0, 0.0]
+
     def test_model_inputs(self, tax_model):
         inputs = tax_model.inputs("person")
         assert "income" in inputs
@@ -0,0 +1,193 @@
+"""The random built-ins: uniform(), normal(mu, sigma) and bernoulli(p)."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    EvalError,
+    ParseError,
+    RowCache,
+    compile,
+    eval_variable,
+    execute_columns,
+    generate_python,
+    parse,
+    preflight,
+    run,
+)
+from rac.rng import stream_id, uniform_at
+
+SOURCE = """
+    entity person:
+        income: float
+        eligible: bool
+
+    variable gov/draw:
+        from 2024-01-01: uniform()
+
+    variable person/u:
+        entity: person
+        from 2024-01-01: uniform()
+
+    variable person/takes_up:
+        entity: person
+        from 2024-01-01: eligible and bernoulli(0.7)
+
+    variable person/reported:
+        entity: person
+        from 2024-01-01: income + normal(0, 100)
+
+    variable person/pair:
+        entity: person
+        from 2024-01-01: uniform() - uniform()
+
+    variable person/branch:
+        entity: person
+        from 2024-01-01: if income > 500: uniform() else: uniform()
+"""
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+DATA = {
+    "person": [
+        {"id": 100 + i, "income": 100.0 * i, "eligible": i % 3 != 0} for i in range(2000)
+    ]
+}
+
+
+@pytest.fixture(scope="module")
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+@pytest.fixture(scope="module")
+def result(ir):
+    return run(ir, DATA)
+
+
+class TestDraws:
+    def test_keyed_by_seed_variable_and_row_id(self, result):
+        u = result.entities["person"]["person/u"]
+        assert u[0] == uniform_at(stream_id(0, "person/u", 100), 0)
+        assert result.scalars["gov/draw"] == uniform_at(stream_id(0, "gov/draw", None), 0)
+
+    def test_same_on_every_engine_path(self, ir, result):
+        for config in CONFIGS[1:]:
+            other = run(ir, DATA, config=config)
+            assert other.entities == result.entities and other.scalars == result.scalars
+
+    def test_row_order_does_not_matter(self, ir, result):
+        reversed_rows = {"person": DATA["person"][::-1]}
+        u = run(ir, reversed_rows).entities["person"]["person/u"]
+        assert u[::-1] == result.entities["person"]["person/u"]
+
+    def test_seed(self, ir, result):
+        reseeded = run(ir, DATA, config=EngineConfig(seed=1)).entities["person"]["person/u"]
+        assert reseeded != result.entities["person"]["person/u"]
+        again = run(ir, DATA, config=EngineConfig(seed=1)).entities["person"]["person/u"]
+        assert again == reseeded
+
+    def test_each_call_draws_its_own_number(self, result):
+        assert all(v != 0 for v in result.entities["person"]["person/pair"])
+
+    def test_branches_draw_at_fixed_counters(self, result):
+        branch = result.entities["person"]["person/branch"]
+        stream = stream_id(0, "person/branch", 100 + 9)  # income 900 takes the first call
+        assert branch[9] == uniform_at(stream, 0)
+        assert branch[1] == uniform_at(stream_id(0, "person/branch", 101), 2)
+
+    def test_distributions(self, result):
+        person = result.entities["person"]
+        u = person["person/u"]
+        assert all(0 <= v < 1 for v in u) and abs(sum(u) / len(u) - 0.5) < 0.03
+        takes_up = list(zip(person["person/takes_up"], DATA["person"]))
+        eligible = [t for t, row in takes_up if row["eligible"]]
+        assert abs(sum(eligible) / len(eligible) - 0.7) < 0.04
+        assert not any(t for t, row in takes_up if not row["eligible"])
+        noise = [r - row["income"] for r, row in zip(person["person/reported"], DATA["person"])]
+        mean = sum(noise) / len(noise)
+        sd = math.sqrt(sum((x - mean) ** 2 for x in noise) / len(noise))
+        assert abs(mean) < 7 and 90 < sd < 110
+
+    def test_missing_arguments(self):
+        module = parse("""
+            entity person:
+                p: float?
+            variable person/hit:
+                entity: person
+                from 2024-01-01: bernoulli(p)
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        data = {"person": [{"id": 1, "p": None}, {"id": 2, "p": 1.0}]}
+        for config in CONFIGS:
+            assert run(ir, data, config=config).entities["person"]["person/hit"] == [None, True]
+
+    @pytest.mark.parametrize(
+        "formula, message",
+        [
+            ("bernoulli(p)", "bernoulli probability 1.5 is not in"),
+            ("normal(0, 1 - p)", "normal standard deviation -0.5 is negative"),
+        ],
+    )
+    def test_invalid_arguments(self, formula, message):
+        module = parse(f"""
+            entity person:
+                p: float
+            variable person/x:
+                entity: person
+                from 2024-01-01: {formula}
+        """)
+        ir = compile([module], as_of=date(2024, 1, 1))
+        for config in CONFIGS:
+            with pytest.raises(EvalError, match=message) as info:
+                run(ir, {"person": [{"id": 1, "p": 0.5}, {"id": 2, "p": 1.5}]}, config=config)
+            assert info.value.kind == "invalid_argument"
+            assert info.value.row == 1
+
+
+class TestEntryPoints:
+    def test_execute_columns_keys_by_the_id_column(self, ir, result):
+        fields = {"id": [100, 101], "income": [0.0, 100.0], "eligible": [False, True]}
+        columns = execute_columns(ir, "person", fields, outputs=["person/u"])
+        assert columns["person/u"] == result.entities["person"]["person/u"][:2]
+
+    def test_eval_variable(self, ir, result):
+        inputs = {"id": 100, "income": 0.0}
+        value = eval_variable(ir.variables["person/branch"], inputs)
+        assert value == result.entities["person"]["person/branch"][0]
+
+    def test_row_cache_keys_by_id(self, ir, result):
+        cache = RowCache(ir, "person", outputs=["person/u"])
+        rows = [{**row, "income": 0.0} for row in DATA["person"][:50]]
+        assert cache.run({"person": rows})["person/u"] == result.entities["person"]["person/u"][:50]
+        assert cache.misses == 50
+        cache.run({"person": rows})
+        assert cache.hits == 50
+
+
+class TestValidation:
+    def test_arity(self):
+        with pytest.raises(ParseError, match=r"normal\(\) takes 2 arguments, got 1"):
+            parse("variable gov/x:\n    from 2024-01-01: normal(1)")
+
+    def test_types(self):
+        module = parse("""
+            entity person:
+                name: str
+            variable person/x:
+                entity: person
+                from 2024-01-01: bernoulli(name)
+        """)
+        with pytest.raises(CompileError, match=r"bernoulli\(\) needs numbers, got str"):
+            compile([module], as_of=date(2024, 1, 1))
+
+    def test_preflight_knows_them(self, ir):
+        assert preflight(ir, DATA).ok
+
+    def test_codegen_refuses(self, ir):
+        with pytest.raises(CompileError, match="draws random numbers, which only the executor"):
+            generate_python(ir)
@@ -0,0 +1,177 @@
+"""Tests for effective tax rate helpers."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    RateError,
+    check_monotonic,
+    compile,
+    eatr,
+    eatr_column,
+    marginal_rates,
+    parse,
+    register_function,
+    unregister_function,
+)
+
+FLAT = """
+    entity person:
+        income: float
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * 0.25
+"""
+
+PROGRESSIVE = """
+    entity person:
+        income: float
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: max(0, income - 10000) * 0.2 + max(0, income - 50000) * 0.2
+"""
+
+# Phases out above 20000 but, through a bug, jumps back up between 30000 and 35000
+BENEFIT = """
+    entity household:
+        income: float
+        size: int
+
+    variable household/tapered:
+        entity: household
+        from 2024-01-01: max(0, 6000 * size - max(0, income - 20000) * 0.5)
+
+    variable household/benefit:
+        entity: household
+        from 2024-01-01: if income >= 30000 and income < 35000: 4000 * size else: household/tapered
+"""
+
+INCOMES = [5000.0, 20000.0, 60000.0, 200000.0]
+
+
+def build(source):
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+def people(incomes):
+    return {"person": [{"id": i, "income": x} for i, x in enumerate(incomes)]}
+
+
+class TestEatr:
+    def test_flat_rate_constant(self):
+        rates = eatr_column(build(FLAT), people(INCOMES), "person", "income", "person/tax")
+        assert rates == pytest.approx([0.25] * len(INCOMES))
+
+    def test_progressive_rises(self):
+        rates = eatr_column(
+            build(PROGRESSIVE), people(INCOMES), "person", "income", "person/tax"
+        )
+        assert rates[0] == 0.0
+        assert all(a < b for a, b in zip(rates, rates[1:]))
+        assert rates[-1] < 0.4
+
+    def test_single_row(self):
+        ir = build(PROGRESSIVE)
+        rate = eatr(ir, "person", {"id": 1, "income": 60000.0}, "income", "person/tax")
+        assert rate == pytest.approx(12000 / 60000)
+
+    def test_zero_gross_policies(self):
+        ir = build(FLAT)
+        row = {"id": 1, "income": 0.0}
+        assert eatr(ir, "person", row, "income", "person/tax") == 0.0
+        assert math.isnan(eatr(ir, "person", row, "income", "person/tax", on_zero="nan"))
+        with pytest.raises(RateError, match="zero gross"):
+            eatr(ir, "person", row, "income", "person/tax", on_zero="error")
+
+    def test_unknown_path(self):
+        with pytest.raises(RateError, match="unknown"):
+            eatr(build(FLAT), "person", {"id": 1, "income": 1.0}, "wages", "person/tax")
+
+
+class TestCheckMonotonic:
+    def test_flags_bump(self):
+        ir = build(BENEFIT)
+        base = {"size": 1}
+        violations = check_monotonic(
+            ir, "household", base, "income", "household/benefit", 0, 60000, steps=60
+        )
+        assert len(violations) == 1
+        bump = violations[0]
+        assert (bump.income_from, bump.income_to) == (29000, 30000)
+        assert bump.value_to > bump.value_from
+
+    def test_well_behaved_schedule_passes(self):
+        ir = build(PROGRESSIVE)
+        args = ("person", {}, "income", "person/tax", 0, 100000)
+        assert check_monotonic(ir, *args, direction="increasing") == []
+        assert len(check_monotonic(ir, *args)) == 1  # tax rises over the whole range
+
+    def test_invalid_arguments(self):
+        ir = build(FLAT)
+        args = ("person", {}, "income", "person/tax")
+        with pytest.raises(RateError, match="direction"):
+            check_monotonic(ir, *args, 0, 1, direction="up")
+        with pytest.raises(RateError, match="steps"):
+            check_monotonic(ir, *args, 0, 1, steps=0)
+        with pytest.raises(RateError, match="income_max"):
+            check_monotonic(ir, *args, 1, 1)
+
+
+MTR = """
+    entity person:
+        income: float
+        age: int
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: scale income:
+            10000 => 0.2
+            50000 => 0.4
+
+    variable person/benefit:
+        entity: person
+        from 2024-01-01: max(0, 3000 - income * 0.5)
+
+    variable person/pension_age:
+        entity: person
+        from 2024-01-01: probe(age) >= 66
+"""
+
+
+class TestMarginalRates:
+    @pytest.fixture
+    def ir(self):
+        return compile([parse(MTR)], as_of=date(2024, 1, 1))
+
+    @pytest.fixture
+    def calls(self):
+        seen: list = []
+        register_function("probe", lambda x: seen.append(x) or x, memoize=False)
+        yield seen
+        unregister_function("probe")
+
+    def test_rates(self, ir):
+        incomes = (5000.0, 30000.0, 60000.0)
+        data = {"person": [{"id": i, "income": v, "age": 40} for i, v in enumerate(incomes)]}
+        rates = marginal_rates(ir, data, "person", "income", delta=10)
+        assert set(rates) == {"person/tax", "person/benefit"}
+        assert rates["person/tax"] == pytest.approx([0, 0.2, 0.4])
+        assert rates["person/benefit"] == pytest.approx([-0.5, 0, 0])
+
+    def test_only_downstream_recomputed(self, ir, calls):
+        data = {"person": [{"id": 1, "income": 30000.0, "age": 70}]}
+        marginal_rates(ir, data, "person", "income", targets=["person/tax", "person/pension_age"])
+        assert calls == [70]
+
+    def test_errors(self, ir):
+        data = {"person": [{"id": 1, "income": None, "age": 40}]}
+        with pytest.raises(RateError, match="is not a number"):
+            marginal_rates(ir, data, "person", "income")
+        with pytest.raises(RateError, match="delta must be"):
+            marginal_rates(ir, data, "person", "income", delta=0)
+        with pytest.raises(RateError, match="not a variable of entity"):
+            marginal_rates(ir, {"person": []}, "person", "income", targets=["person/missing"])
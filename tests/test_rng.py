@@ -0,0 +1,87 @@
+"""Tests for counter-based random streams and reproducibility manifests."""
+
+from concurrent.futures import ThreadPoolExecutor
+
+import pytest
+
+from rac import RandomManifest, sample_rows, verify_reproducibility
+from rac.rng import Stream, stream_id, uniform_at
+
+ROWS = [{"id": i, "weight": 1.0} for i in range(400)]
+
+
+def _sample_in_chunks(seed: int, n_threads: int) -> tuple[list[int], RandomManifest]:
+    manifest = RandomManifest()
+    chunks = [ROWS[i : i + 37] for i in range(0, len(ROWS), 37)]
+    with ThreadPoolExecutor(max_workers=n_threads) as pool:
+        parts = pool.map(lambda c: sample_rows(c, 0.3, seed=seed, manifest=manifest), chunks)
+        ids = sorted(r["id"] for part in parts for r in part)
+    return ids, manifest
+
+
+class TestStream:
+    def test_draws_are_pure_functions(self):
+        a = Stream(1, "feature", "k")
+        b = Stream(1, "feature", "k")
+        assert [a.uniform() for _ in range(5)] == [b.uniform() for _ in range(5)]
+
+    def test_counter_advances(self):
+        s = Stream(1, "feature", "k")
+        first, second = s.uniform(), s.uniform()
+        assert first != second
+        assert second == uniform_at(stream_id(1, "feature", "k"), 1)
+
+    def test_streams_are_distinct(self):
+        assert stream_id(1, "f", "a") != stream_id(1, "f", "b")
+        assert stream_id(1, "f", "a") != stream_id(2, "f", "a")
+        assert stream_id(1, "f", "a") != stream_id(1, "g", "a")
+
+    def test_uniform_range(self):
+        s = Stream(3, "f", 0)
+        assert all(0.0 <= s.uniform() < 1.0 for _ in range(1000))
+
+
+class TestManifest:
+    def test_records_streams_and_draws(self):
+        manifest = RandomManifest()
+        sample_rows(ROWS[:10], 0.5, seed=4, manifest=manifest)
+        assert manifest.seed == 4
+        assert len(manifest.streams) == 10
+        assert manifest.summary() == {"sample_rows": {"streams": 10, "draws": 10}}
+        assert {e.key for e in manifest.streams.values()} == {repr(i) for i in range(10)}
+
+    @pytest.mark.parametrize("n_threads", [2, 4, 16])
+    def test_identical_across_thread_counts(self, n_threads):
+        ids_1, manifest_1 = _sample_in_chunks(seed=7, n_threads=1)
+        ids_n, manifest_n = _sample_in_chunks(seed=7, n_threads=n_threads)
+        assert ids_1 == ids_n
+        assert verify_reproducibility(manifest_1, manifest_n) == []
+
+    def test_differs_when_seed_changes(self):
+        _, a = _sample_in_chunks(seed=7, n_threads=4)
+        _, b = _sample_in_chunks(seed=8, n_threads=4)
+        problems = verify_reproducibility(a, b)
+        assert problems[0] == "master seed differs: 7 != 8"
+        assert any("only in first run" in p for p in problems)
+
+    def test_detects_draw_count_mismatch(self):
+        a, b = RandomManifest(), RandomManifest()
+        Stream(1, "takeup", 5, a).uniform()
+        s = Stream(1, "takeup", 5, b)
+        s.uniform()
+        s.uniform()
+        problems = verify_reproducibility(a, b)
+        assert len(problems) == 1
+        assert "draws differ: 1 != 2" in problems[0]
+
+    def test_entity_sampling_records_feature(self):
+        from datetime import date
+
+        from rac import compile, parse, sample_entities
+
+        schema = compile(
+            [parse("entity household:\n    weight: float\n")], as_of=date(2024, 1, 1)
+        ).schema_
+        manifest = RandomManifest()
+        sample_entities({"household": ROWS[:5]}, schema, "household", 0.5, manifest=manifest)
+        assert manifest.summary() == {"sample_entities": {"streams": 5, "draws": 5}}
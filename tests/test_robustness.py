@@ -0,0 +1,79 @@
+"""Input perturbation bands: output sensitivity near discontinuities."""
+
+from datetime import date
+
+import pytest
+
+from rac import PerturbationError, RandomManifest, compile, parse, perturb
+
+# A benefit that stops dead at 20000
+CLIFF = """
+    entity person:
+        income: float
+        band: str
+    variable person/benefit:
+        entity: person
+        from 2024-01-01: if income < 20000: 1000 else: 0
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * 0.1
+    variable person/label:
+        entity: person
+        from 2024-01-01: band
+"""
+
+DATA = {
+    "person": [
+        {"id": 1, "income": 19990.0, "band": "a"},  # 10 under the cliff
+        {"id": 2, "income": 5000.0, "band": "b"},  # far below it
+        {"id": 3, "income": 40000.0, "band": "c"},  # far above it
+    ]
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(CLIFF)], as_of=date(2024, 1, 1))
+
+
+class TestPerturb:
+    def test_cliff_rows_vary_most(self, ir):
+        out = perturb(ir, DATA, "person", {"income": 50}, draws=40, seed=7)
+        benefit = out["person/benefit"]
+        assert benefit.spread == [1000, 0, 0]
+        assert benefit.stddev[0] > 0 and benefit.stddev[1:] == [0, 0]
+        # A smooth output moves with the band everywhere, but never by more than it allows
+        assert all(0 < s <= 2 * 50 * 0.1 for s in out["person/tax"].spread)
+
+    def test_non_numeric_outputs_are_skipped(self, ir):
+        out = perturb(ir, DATA, "person", {"income": 50}, draws=2)
+        assert set(out) == {"person/benefit", "person/tax"}
+        with pytest.raises(PerturbationError, match="non-numeric"):
+            perturb(ir, DATA, "person", {"income": 50}, draws=2, outputs=["person/label"])
+
+    def test_seeded(self, ir):
+        first = perturb(ir, DATA, "person", {"income": 50}, seed=3)
+        assert perturb(ir, DATA, "person", {"income": 50}, seed=3) == first
+        assert perturb(ir, DATA, "person", {"income": 50}, seed=4) != first
+
+    def test_draws_are_recorded(self, ir):
+        manifest = RandomManifest()
+        perturb(ir, DATA, "person", {"income": 50}, draws=5, manifest=manifest)
+        assert manifest.summary() == {"perturb": {"streams": 3, "draws": 15}}
+
+    def test_zero_band_changes_nothing(self, ir):
+        out = perturb(ir, DATA, "person", {"income": 0}, draws=3)
+        assert out["person/tax"].spread == [0, 0, 0]
+
+    @pytest.mark.parametrize(
+        "kwargs, message",
+        [
+            ({"bands": {"income": -1}}, "non-negative"),
+            ({"bands": {"band": 1}}, "band is not a number"),
+            ({"bands": {}, "draws": 0}, "at least 1"),
+            ({"bands": {}, "outputs": ["gov/x"]}, "not a variable of entity person"),
+        ],
+    )
+    def test_invalid(self, ir, kwargs, message):
+        with pytest.raises(PerturbationError, match=message):
+            perturb(ir, DATA, "person", **kwargs)
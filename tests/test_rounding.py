@@ -0,0 +1,181 @@
+"""Per-variable rounding: round_to and rounding applied to each result."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    ParseError,
+    compile,
+    dump_ir,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    load_ir,
+    parse,
+    run,
+)
+
+SOURCE = """
+    entity person:
+        income: float?
+
+    variable gov/weekly_rate:
+        round_to: 0.01
+        from 2024-01-01: 123.456
+
+    variable person/credit:
+        entity: person
+        round_to: 0.01
+        from 2024-01-01: income * 0.1
+
+    variable person/taxable:
+        entity: person
+        round_to: 1
+        rounding: "down"
+        from 2024-01-01: income
+
+    variable person/award:
+        entity: person
+        round_to: 5
+        rounding: "up"
+        from 2024-01-01: income / 10
+"""
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+INCOMES = [1234.5, 2.9, 0.0, -1234.5, None]
+EXPECTED = {
+    "person/credit": [123.45, 0.29, 0.0, -123.45, None],
+    "person/taxable": [1234.0, 2.0, 0.0, -1235.0, None],
+    "person/award": [125.0, 5.0, 0.0, -120.0, None],
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _data(incomes):
+    return {"person": [{"id": i, "income": income} for i, income in enumerate(incomes)]}
+
+
+def _compile(metadata, formula="income"):
+    source = f"""
+        entity person:
+            income: float
+            name: str
+        variable person/out:
+            entity: person
+            {metadata}
+            from 2024-01-01: {formula}
+    """
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+class TestExecute:
+    def test_directions(self, ir):
+        for config in CONFIGS:
+            result = run(ir, _data(INCOMES), config=config)
+            assert result.scalars["gov/weekly_rate"] == 123.46
+            for path, expected in EXPECTED.items():
+                assert result.entities["person"][path] == expected, (path, config)
+
+    def test_halves_round_up(self):
+        ir = _compile("round_to: 1")
+        values = run(ir, _data([2.5, -2.5, 0.5])).entities["person"]["person/out"]
+        assert values == [3, -2, 1]
+
+    def test_nan_and_infinity_pass_through(self, ir):
+        for config in CONFIGS:
+            credit = run(ir, _data([math.nan, math.inf]), config=config).entities["person"]
+            assert math.isnan(credit["person/credit"][0])
+            assert credit["person/credit"][1] == math.inf
+
+    def test_later_variables_read_the_rounded_value(self):
+        module = parse("""
+            variable gov/a:
+                round_to: 0.01
+                from 2024-01-01: 1 / 3
+            variable gov/b:
+                from 2024-01-01: gov/a * 3
+        """)
+        assert run(compile([module], as_of=date(2024, 1, 1)), {}).scalars["gov/b"] == 0.99
+
+    def test_exact(self):
+        module = parse("""
+            variable gov/a:
+                arithmetic: "exact"
+                round_to: 0.01
+                from 2024-01-01: 2 / 3
+        """)
+        assert run(compile([module], as_of=date(2024, 1, 1)), {}).scalars["gov/a"] == 0.67
+
+    def test_missing_default_is_not_rounded(self):
+        ir = _compile('round_to: 10\n            missing: "default"\n            default: 3')
+        assert run(ir, _data([None, 14])).entities["person"]["person/out"] == [3, 10]
+
+
+class TestValidation:
+    @pytest.mark.parametrize(
+        "metadata, message",
+        [
+            ("round_to: 0", "round_to must be a positive number"),
+            ("round_to: -1", "round_to must be a positive number"),
+            ('round_to: "1"', "round_to must be a positive number"),
+            ('round_to: 1\n            rounding: "half-even"', "rounding must be one of"),
+        ],
+    )
+    def test_rejected_at_parse(self, metadata, message):
+        with pytest.raises(ParseError, match=message):
+            _compile(metadata)
+
+    def test_rounding_needs_round_to(self):
+        with pytest.raises(CompileError, match="rounding needs a round_to"):
+            _compile('rounding: "up"')
+
+    def test_strings_are_not_rounded(self):
+        with pytest.raises(CompileError, match="round_to needs numbers, got str"):
+            _compile("round_to: 1", formula="name")
+
+    @pytest.mark.parametrize(
+        "update, message",
+        [
+            ({"round_to": 0}, "round_to must be a positive number"),
+            ({"rounding": "sideways"}, "unknown rounding direction sideways"),
+            ({"round_to": None}, "rounding needs a round_to"),
+        ],
+    )
+    def test_loaded_ir_is_checked(self, ir, update, message):
+        data = ir.model_dump(by_alias=True)
+        data["variables"]["person/taxable"].update(update)
+        with pytest.raises(CompileError, match=f"person/taxable: {message}"):
+            load_ir(data)
+
+    def test_round_trips(self, ir):
+        loaded = load_ir(dump_ir(ir))
+        assert loaded.variables["person/award"].round_to == 5
+        assert loaded.variables["person/award"].rounding == "up"
+
+
+class TestCodegen:
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        assert scalars["gov_weekly_rate"] == 123.46
+        for i, income in enumerate(INCOMES[:-1]):  # generated arithmetic has no None
+            out = namespace["compute_person"](namespace["PersonInput"](income=income), scalars)
+            for path, expected in EXPECTED.items():
+                assert out[path.replace("/", "_")] == expected[i], (path, income)
+
+    def test_javascript_and_rust(self, ir):
+        js, rust = generate_javascript(ir), generate_rust(ir)
+        assert 'racRoundTo(input.income, 1, 0, "down")' in js
+        assert 'racRoundTo((input.income / 10), 5, 0, "up")' in js
+        assert 'rac_round_to((input.income) as f64, 1.0_f64, 0, "down")' in rust
+        assert 'rac_round_to((123.456_f64) as f64, 0.01_f64, 2, "nearest")' in rust
@@ -0,0 +1,110 @@
+"""Generated Rust, compiled and run with cargo."""
+
+import shutil
+import subprocess
+from datetime import date
+
+import pytest
+
+from rac import compile, generate_rust, parse
+
+pytestmark = pytest.mark.skipif(not shutil.which("cargo"), reason="Rust toolchain not available")
+
+MANIFEST = '[package]\nname = "rac_check"\nversion = "0.1.0"\nedition = "2021"\n'
+
+
+def run_rust(source: str, main: str, tmp_path) -> list[str]:
+    """Compile ``source``'s generated Rust with ``main`` as the body of main; its output lines."""
+    ir = compile([parse(source)], as_of=date(2024, 1, 1))
+    (tmp_path / "src").mkdir()
+    (tmp_path / "Cargo.toml").write_text(MANIFEST)
+    (tmp_path / "src" / "main.rs").write_text(f"{generate_rust(ir)}\nfn main() {{\n{main}\n}}\n")
+    result = subprocess.run(
+        ["cargo", "run", "--offline", "--quiet"], cwd=tmp_path, capture_output=True, text=True
+    )
+    assert result.returncode == 0, result.stderr
+    return result.stdout.split()
+
+
+class TestMembership:
+    SOURCE = """
+    entity person:
+        region: category
+        filing_status: str
+        age: int
+    variable person/scottish:
+        entity: person
+        from 2024-01-01: region == "SCOTLAND"
+    variable person/devolved:
+        entity: person
+        from 2024-01-01: region in ["SCOTLAND", "WALES", "NORTHERN_IRELAND"]
+    variable person/joint:
+        entity: person
+        from 2024-01-01: filing_status not in ["single", "head_of_household"]
+    variable person/milestone:
+        entity: person
+        from 2024-01-01: age in [18, 65]
+"""
+
+    def test_strings_categories_and_ints(self, tmp_path):
+        main = """
+    let scalars = Scalars::compute();
+    for (region, status, age) in [("WALES", "single", 65), ("ENGLAND", "married_joint", 40)] {
+        let input = PersonInput {
+            region: region.to_string(), filing_status: status.to_string(), age,
+        };
+        let out = PersonOutput::compute(&input, &scalars);
+        println!("{} {} {} {}", out.person_scottish, out.person_devolved,
+            out.person_joint, out.person_milestone);
+    }
+"""
+        assert run_rust(self.SOURCE, main, tmp_path) == "0 1 0 1 0 0 1 0".split()
+
+
+class TestMatch:
+    SOURCE = """
+    entity person:
+        band: category
+        tier: int
+    variable person/rate:
+        entity: person
+        from 2024-01-01: match band: "A" => 0.1 "B" => 0.2
+    variable person/bonus:
+        entity: person
+        from 2024-01-01: match tier: 1 => 10 2 => 20
+"""
+
+    def test_unmatched_panics(self, tmp_path):
+        main = """
+    let scalars = Scalars::compute();
+    let out = PersonOutput::compute(&PersonInput { band: "B".to_string(), tier: 1 }, &scalars);
+    println!("{} {}", out.person_rate, out.person_bonus);
+    let input = PersonInput { band: "C".to_string(), tier: 1 };
+    let missed = std::panic::catch_unwind(|| PersonOutput::compute(&input, &scalars));
+    println!("{}", missed.is_err());
+"""
+        assert run_rust(self.SOURCE, main, tmp_path) == ["0.2", "10", "true"]
+
+
+class TestMissing:
+    SOURCE = """
+    entity person:
+        income: float?
+        age: int
+    variable person/no_income:
+        entity: person
+        from 2024-01-01: is_missing(income)
+    variable person/no_age:
+        entity: person
+        from 2024-01-01: is_missing(age)
+"""
+
+    def test_is_missing(self, tmp_path):
+        main = """
+    let scalars = Scalars::compute();
+    for income in [f64::NAN, 0.0] {
+        let out = PersonOutput::compute(&PersonInput { income, age: 30 }, &scalars);
+        println!("{} {}", out.person_no_income, out.person_no_age);
+    }
+"""
+        assert run_rust(self.SOURCE, main, tmp_path) == ["1", "0", "0", "0"]
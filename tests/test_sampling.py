@@ -0,0 +1,127 @@
+"""Tests for reproducible row and entity-aware sampling."""
+
+from datetime import date
+
+import pytest
+
+from rac import SamplingError, compile, parse, sample_entities, sample_rows
+
+SCHEMA_SOURCE = """
+    entity household:
+        weight: float
+
+    entity person:
+        income: float
+        household: -> household
+
+    entity benefit_unit:
+        person: -> person
+"""
+
+
+@pytest.fixture
+def schema():
+    return compile([parse(SCHEMA_SOURCE)], as_of=date(2024, 1, 1)).schema_
+
+
+def make_population(n_households: int) -> dict[str, list[dict]]:
+    households = [{"id": h, "weight": 100.0 + h % 7} for h in range(n_households)]
+    persons = []
+    for h in range(n_households):
+        for k in range(1 + h % 3):
+            persons.append({"id": f"{h}-{k}", "household": h, "income": 1000.0 * k})
+    units = [{"id": f"u{p['id']}", "person": p["id"]} for p in persons[::2]]
+    return {"household": households, "person": persons, "benefit_unit": units}
+
+
+class TestSampleRows:
+    def test_deterministic(self):
+        rows = [{"id": i, "weight": 1.0} for i in range(1000)]
+        assert sample_rows(rows, 0.3, seed=7) == sample_rows(rows, 0.3, seed=7)
+
+    def test_seed_changes_sample(self):
+        rows = [{"id": i, "weight": 1.0} for i in range(1000)]
+        assert sample_rows(rows, 0.3, seed=1) != sample_rows(rows, 0.3, seed=2)
+
+    def test_order_independent(self):
+        rows = [{"id": i, "weight": 1.0} for i in range(500)]
+        forward = sample_rows(rows, 0.5, seed=3)
+        backward = sample_rows(list(reversed(rows)), 0.5, seed=3)
+        assert sorted(r["id"] for r in forward) == sorted(r["id"] for r in backward)
+
+    def test_weights_scaled(self):
+        rows = [{"id": i, "weight": 2.0} for i in range(100)]
+        assert all(r["weight"] == 8.0 for r in sample_rows(rows, 0.25, seed=0))
+
+    def test_input_not_mutated(self):
+        rows = [{"id": i, "weight": 2.0} for i in range(100)]
+        sample_rows(rows, 0.25, seed=0)
+        assert all(r["weight"] == 2.0 for r in rows)
+
+    def test_approximately_unbiased(self):
+        rows = [{"id": i, "weight": 1.0 + i % 5} for i in range(20000)]
+        total = sum(r["weight"] for r in rows)
+        sampled = sum(r["weight"] for r in sample_rows(rows, 0.1, seed=11))
+        assert sampled == pytest.approx(total, rel=0.05)
+
+    def test_full_fraction_keeps_everything(self):
+        rows = [{"id": i, "weight": 1.0} for i in range(50)]
+        assert sample_rows(rows, 1.0) == rows
+
+    @pytest.mark.parametrize("fraction", [0.0, -0.1, 1.5])
+    def test_invalid_fraction(self, fraction):
+        with pytest.raises(SamplingError, match="fraction"):
+            sample_rows([{"id": 1, "weight": 1.0}], fraction)
+
+    def test_missing_weight_column(self):
+        with pytest.raises(SamplingError, match="missing weight column"):
+            sample_rows([{"id": 1}], 1.0)
+
+
+class TestSampleEntities:
+    def test_linkage_integrity(self, schema):
+        sample = sample_entities(make_population(500), schema, "household", 0.2, seed=5)
+        household_ids = {h["id"] for h in sample["household"]}
+        person_ids = {p["id"] for p in sample["person"]}
+        assert household_ids
+        assert all(p["household"] in household_ids for p in sample["person"])
+        assert all(u["person"] in person_ids for u in sample["benefit_unit"])
+
+    def test_keeps_whole_households(self, schema):
+        population = make_population(500)
+        sample = sample_entities(population, schema, "household", 0.2, seed=5)
+        household_ids = {h["id"] for h in sample["household"]}
+        expected = [p for p in population["person"] if p["household"] in household_ids]
+        assert sample["person"] == expected
+
+    def test_only_sampled_entity_reweighted(self, schema):
+        population = make_population(200)
+        sample = sample_entities(population, schema, "household", 0.5, seed=1)
+        original = {h["id"]: h["weight"] for h in population["household"]}
+        assert all(h["weight"] == original[h["id"]] * 2 for h in sample["household"])
+        assert all("weight" not in p for p in sample["person"])
+
+    def test_deterministic(self, schema):
+        population = make_population(300)
+        a = sample_entities(population, schema, "household", 0.3, seed=9)
+        b = sample_entities(population, schema, "household", 0.3, seed=9)
+        c = sample_entities(population, schema, "household", 0.3, seed=10)
+        assert a == b
+        assert a != c
+
+    def test_approximately_unbiased(self, schema):
+        population = make_population(20000)
+        total = sum(h["weight"] for h in population["household"])
+        sample = sample_entities(population, schema, "household", 0.1, seed=2)
+        assert sum(h["weight"] for h in sample["household"]) == pytest.approx(total, rel=0.05)
+
+    def test_accepts_data_object(self, schema):
+        from rac import Data
+
+        population = make_population(50)
+        sample = sample_entities(Data(tables=population), schema, "household", 1.0)
+        assert len(sample["person"]) == len(population["person"])
+
+    def test_unknown_entity(self, schema):
+        with pytest.raises(SamplingError, match="no data"):
+            sample_entities({}, schema, "household", 0.5)
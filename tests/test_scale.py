@@ -0,0 +1,161 @@
+"""Scale expressions: bracketed rate schedules."""
+
+import math
+from datetime import date
+
+import pytest
+
+from rac import (
+    IR,
+    CompileError,
+    EngineConfig,
+    Scale,
+    compile,
+    execute,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    load_ir,
+    parse,
+    run,
+)
+from rac.ast import Literal, to_source
+from rac.executor import ExecutionError
+
+SOURCE = """
+    entity person:
+        income: float
+    variable gov/basic_rate:
+        from 2024-01-01: 0.2
+    variable gov/higher_threshold:
+        from 2024-01-01: 50000
+    variable person/tax:
+        entity: person
+        from 2024-01-01:
+            scale income:
+                12500 => gov/basic_rate
+                gov/higher_threshold => 0.4
+                150000 => 0.45
+    variable person/flat_tax:
+        entity: person
+        from 2024-01-01:
+            scale average income:
+                0 => 0.1
+                20000 => 0.2
+"""
+
+INCOMES = [0.0, 10000.0, 30000.0, 80000.0, 200000.0]
+
+DATA = {"person": [{"id": i, "income": income} for i, income in enumerate(INCOMES)]}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def _expr(ir, path):
+    return ir.variables[path].expr
+
+
+class TestParse:
+    def test_marginal_by_default(self, ir):
+        expr = _expr(ir, "person/tax")
+        assert isinstance(expr, Scale)
+        assert expr.mode == "marginal"
+        assert len(expr.brackets) == 3
+
+    def test_average_mode(self, ir):
+        assert _expr(ir, "person/flat_tax").mode == "average"
+
+    def test_scale_is_still_a_name(self):
+        module = parse(
+            """
+            variable gov/factor:
+                from 2024-01-01: scale * 2
+            """
+        )
+        assert to_source(module.variables[0].values[0].expr) == "(scale * 2)"
+
+    def test_round_trips_through_source(self, ir):
+        source = to_source(_expr(ir, "person/flat_tax"))
+        assert source == "scale average income: 0 => 0.1; 20000 => 0.2"
+
+    def test_thresholds_are_deps(self, ir):
+        assert {"gov/basic_rate", "gov/higher_threshold"} <= ir.variables["person/tax"].deps
+
+
+class TestExecute:
+    def test_marginal(self, ir):
+        tax = execute(ir, DATA).entities["person"]["person/tax"]
+        assert tax == pytest.approx(
+            [
+                0,
+                0,
+                17500 * 0.2,
+                37500 * 0.2 + 30000 * 0.4,
+                37500 * 0.2 + 100000 * 0.4 + 50000 * 0.45,
+            ]
+        )
+
+    def test_average(self, ir):
+        tax = execute(ir, DATA).entities["person"]["person/flat_tax"]
+        assert tax == pytest.approx([0, 1000, 6000, 16000, 40000])
+
+    def test_row_at_a_time_matches_columnar(self, ir):
+        rows = run(ir, DATA, config=EngineConfig(columnar=False)).entities["person"]
+        assert rows == run(ir, DATA).entities["person"]
+
+    def test_nan_propagates(self, ir):
+        data = {"person": [{"id": 1, "income": math.nan}]}
+        assert math.isnan(execute(ir, data).entities["person"]["person/tax"][0])
+
+    def test_descending_thresholds_rejected(self):
+        source = """
+            entity person:
+                income: float
+            variable person/tax:
+                entity: person
+                from 2024-01-01:
+                    scale income:
+                        100 => 0.1
+                        50 => 0.2
+        """
+        ir = compile([parse(source)], as_of=date(2024, 1, 1))
+        with pytest.raises(ExecutionError, match="must ascend"):
+            execute(ir, {"person": [{"id": 1, "income": 10.0}]})
+
+
+class TestLoadIR:
+    def test_unknown_mode_rejected(self, ir):
+        data = ir.model_dump(by_alias=True)
+        data["variables"]["person/tax"]["expr"]["mode"] = "progressive"
+        with pytest.raises(CompileError, match="unknown scale mode"):
+            load_ir(data)
+
+    def test_scale_round_trips(self, ir):
+        loaded = IR.model_validate_json(ir.model_dump_json(by_alias=True))
+        assert loaded.variables["person/tax"].expr == _expr(ir, "person/tax")
+        assert isinstance(loaded.variables["person/tax"].expr.brackets[0][0], Literal)
+
+
+class TestCodegen:
+    def test_generated_python_matches(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        expected = execute(ir, DATA).entities["person"]
+        for i, income in enumerate(INCOMES):
+            out = namespace["compute_person"](namespace["PersonInput"](income=income), scalars)
+            assert out["person_tax"] == pytest.approx(expected["person/tax"][i])
+            assert out["person_flat_tax"] == pytest.approx(expected["person/flat_tax"][i])
+
+    def test_rust(self, ir):
+        code = generate_rust(ir)
+        assert "fn rac_scale(" in code
+        assert "rac_scale(true, " in code
+
+    def test_javascript(self, ir):
+        code = generate_javascript(ir)
+        assert "function racScale(" in code
+        assert 'racScale("marginal", ' in code
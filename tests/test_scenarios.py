@@ -0,0 +1,473 @@
+"""Tests for scenario analysis helpers."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    Axis,
+    Result,
+    ScenarioError,
+    apply_overrides,
+    classify_changes,
+    compare_scenarios,
+    compile,
+    cross_join,
+    decompose,
+    execute,
+    expand_axes,
+    parse,
+    sweep,
+    winners_losers,
+)
+from rac.scenarios import total
+
+SOURCE = """
+    entity person:
+        income: float
+        weight: float
+
+    variable gov/allowance:
+        from 2024-01-01: 10000
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: max(0, income - gov/allowance) * gov/rate
+"""
+
+DATA = {
+    "person": [
+        {"id": 1, "income": 20000.0, "weight": 2.0},
+        {"id": 2, "income": 50000.0, "weight": 1.0},
+        {"id": 3, "income": 5000.0, "weight": 3.0},
+    ]
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestApplyOverrides:
+    def test_override_scalar(self, ir):
+        reform = apply_overrides(ir, {"gov/rate": 0.25})
+        assert execute(reform, DATA).entities["person"]["person/tax"] == [2500.0, 10000.0, 0.0]
+
+    def test_original_untouched(self, ir):
+        apply_overrides(ir, {"gov/rate": 0.25})
+        assert execute(ir, DATA).entities["person"]["person/tax"] == [2000.0, 8000.0, 0.0]
+
+    def test_unknown_variable(self, ir):
+        with pytest.raises(ScenarioError, match="unknown variable"):
+            apply_overrides(ir, {"gov/nope": 1})
+
+
+class TestDecompose:
+    STEPS = [("gov/rate", 0.25), ("gov/allowance", 12000)]
+
+    def test_sequential_contributions_sum_to_change(self, ir):
+        d = decompose(ir, DATA, self.STEPS, "person/tax", weight_path="weight")
+        # baseline: 2*2000 + 8000 = 12000; reform: 2*2000 + 9500 = 13500
+        assert d.baseline == pytest.approx(12000.0)
+        assert d.reform == pytest.approx(13500.0)
+        assert d.contributions["gov/rate"] == pytest.approx(3000.0)
+        assert d.contributions["gov/allowance"] == pytest.approx(-1500.0)
+        assert sum(d.contributions.values()) == pytest.approx(d.change)
+
+    def test_sequential_depends_on_order(self, ir):
+        d = decompose(ir, DATA, list(reversed(self.STEPS)), "person/tax", weight_path="weight")
+        assert d.contributions["gov/allowance"] == pytest.approx(-1200.0)
+        assert d.contributions["gov/rate"] == pytest.approx(2700.0)
+
+    def test_shapley_is_order_independent(self, ir):
+        a = decompose(ir, DATA, self.STEPS, "person/tax", "weight", method="shapley")
+        b = decompose(ir, DATA, list(reversed(self.STEPS)), "person/tax", "weight", "shapley")
+        assert a.contributions == pytest.approx(b.contributions)
+        assert a.contributions["gov/rate"] == pytest.approx(2850.0)
+        assert sum(a.contributions.values()) == pytest.approx(a.change)
+
+    def test_scalar_output(self, ir):
+        d = decompose(ir, DATA, [("gov/rate", 0.3)], "gov/rate")
+        assert d.contributions == {"gov/rate": pytest.approx(0.1)}
+
+    def test_unweighted(self, ir):
+        d = decompose(ir, DATA, [("gov/rate", 0.25)], "person/tax")
+        assert d.change == pytest.approx(2500.0)
+
+    def test_unknown_output(self, ir):
+        with pytest.raises(ScenarioError, match="unknown output"):
+            decompose(ir, DATA, self.STEPS, "person/nope")
+
+    def test_unknown_method(self, ir):
+        with pytest.raises(ScenarioError, match="unknown method"):
+            decompose(ir, DATA, self.STEPS, "person/tax", method="banzhaf")
+
+    def test_duplicate_parameter(self, ir):
+        with pytest.raises(ScenarioError, match="only one step"):
+            decompose(ir, DATA, [("gov/rate", 0.1), ("gov/rate", 0.2)], "person/tax")
+
+    def test_total_skips_missing_rows(self):
+        taxes = {"person": {"person/tax": [2000.0, None, 8000.0]}}
+        result = Result(scalars={"gov/rate": None}, entities=taxes)
+        assert total(result, DATA, "person/tax") == 10000.0
+        assert total(result, DATA, "person/tax", weight_path="weight") == 28000.0
+        with pytest.raises(ScenarioError, match="gov/rate is missing"):
+            total(result, DATA, "gov/rate")
+
+
+LINKED_SOURCE = """
+    entity household:
+        region: int
+
+    entity person:
+        household: -> household
+        income: float
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: income * gov/rate
+
+    variable household/tax:
+        entity: household
+        from 2024-01-01: sum_over(person, person/tax)
+"""
+
+LINKED = {
+    "household": [{"id": 1, "region": 1}, {"id": 2, "region": 2}],
+    "person": [
+        {"id": 10, "household": 1, "income": 1000.0},
+        {"id": 11, "household": 1, "income": 2000.0},
+        {"id": 12, "household": 2, "income": 1000.0},
+    ],
+}
+
+
+class TestSweep:
+    def test_rate_sweep_monotonic(self, ir):
+        rates = [0.0, 0.1, 0.2, 0.3, 0.4]
+        totals = sweep(ir, DATA, "gov/rate", rates, "person/tax", "weight")
+        assert totals[0] == 0.0
+        assert all(a < b for a, b in zip(totals, totals[1:]))
+        # Taxable income (weighted): 2 * 10000 + 40000 = 60000
+        assert totals == pytest.approx([60000 * r for r in rates])
+
+    def test_matches_individual_runs(self, ir):
+        allowances = [0, 15000, 60000]
+        totals = sweep(ir, DATA, "gov/allowance", allowances, "person/tax", max_workers=2)
+        for allowance, swept in zip(allowances, totals):
+            single = execute(apply_overrides(ir, {"gov/allowance": allowance}), DATA)
+            assert swept == sum(single.entities["person"]["person/tax"])
+        assert totals[-1] == 0.0
+
+    def test_unknown_parameter(self, ir):
+        with pytest.raises(ScenarioError, match="unknown variable"):
+            sweep(ir, DATA, "gov/missing", [1], "person/tax")
+
+    def test_aggregates_over_links(self):
+        ir = compile([parse(LINKED_SOURCE)], as_of=date(2024, 1, 1))
+        totals = sweep(ir, LINKED, "gov/rate", [0.1, 0.5], "household/tax")
+        assert totals == pytest.approx([400.0, 2000.0])
+
+
+HOUSEHOLD_SOURCE = """
+    entity household:
+        income: float
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable household/tax:
+        entity: household
+        from 2024-01-01: income * gov/rate
+"""
+
+HOUSEHOLDS = {"household": [{"id": "a", "income": 1000.0}, {"id": "b", "income": 3000.0}]}
+
+SCENARIOS = {"base": {}, "low": {"gov/rate": 0.1}, "high": {"gov/rate": 0.5}}
+
+
+class TestCrossJoin:
+    @pytest.fixture
+    def household_ir(self):
+        return compile([parse(HOUSEHOLD_SOURCE)], as_of=date(2024, 1, 1))
+
+    def test_flat(self, household_ir):
+        rows = cross_join(household_ir, HOUSEHOLDS, SCENARIOS, "household", max_workers=3)
+        assert len(rows) == 6
+        taxes = {(r["scenario"], r["id"]): r["household/tax"] for r in rows}
+        assert taxes == pytest.approx(
+            {
+                ("base", "a"): 200.0,
+                ("base", "b"): 600.0,
+                ("low", "a"): 100.0,
+                ("low", "b"): 300.0,
+                ("high", "a"): 500.0,
+                ("high", "b"): 1500.0,
+            }
+        )
+        assert [r["scenario"] for r in rows] == ["base", "base", "low", "low", "high", "high"]
+
+    def test_nested(self, household_ir):
+        nested = cross_join(household_ir, HOUSEHOLDS, SCENARIOS, "household", layout="nested")
+        assert list(nested) == ["base", "low", "high"]
+        assert nested["high"]["b"]["household/tax"] == pytest.approx(1500.0)
+
+    def test_list_labels(self, household_ir):
+        rows = cross_join(household_ir, HOUSEHOLDS, [{}, {"gov/rate": 0.0}], "household")
+        assert {r["scenario"] for r in rows} == {"0", "1"}
+
+    def test_aggregates_over_links(self):
+        ir = compile([parse(LINKED_SOURCE)], as_of=date(2024, 1, 1))
+        nested = cross_join(ir, LINKED, SCENARIOS, "household", layout="nested")
+        assert nested["low"][1]["household/tax"] == pytest.approx(300.0)
+        assert nested["high"][2]["household/tax"] == pytest.approx(500.0)
+
+    def test_unknown_layout(self, household_ir):
+        with pytest.raises(ScenarioError, match="unknown layout"):
+            cross_join(household_ir, HOUSEHOLDS, SCENARIOS, "household", layout="wide")
+
+
+class TestExpandAxes:
+    @pytest.fixture
+    def household_ir(self):
+        return compile([parse(HOUSEHOLD_SOURCE)], as_of=date(2024, 1, 1))
+
+    def test_cross(self, household_ir):
+        axes = [
+            Axis(field="income", values=[0.0, 500.0, 1000.0]),
+            Axis(field="rent", values=[0, 10, 20, 30]),
+        ]
+        rows = list(expand_axes(household_ir, HOUSEHOLDS, "household", axes, chunk_size=5))
+        assert len(rows) == 2 * 3 * 4
+        keys = {(r["id"], r["income_index"], r["rent_index"]) for r in rows}
+        assert keys == {(h, i, j) for h in "ab" for i in range(3) for j in range(4)}
+        for r in rows:
+            assert r["income"] == axes[0].values[r["income_index"]]
+            assert r["rent"] == axes[1].values[r["rent_index"]]
+            assert r["household/tax"] == pytest.approx(r["income"] * 0.2)
+
+    def test_zip(self, household_ir):
+        axes = [
+            Axis(field="income", values=[100.0, 200.0]),
+            Axis(field="rent", values=[1, 2]),
+        ]
+        rows = list(expand_axes(household_ir, HOUSEHOLDS, "household", axes, mode="zip"))
+        assert [(r["id"], r["income"], r["rent"]) for r in rows] == [
+            ("a", 100.0, 1),
+            ("a", 200.0, 2),
+            ("b", 100.0, 1),
+            ("b", 200.0, 2),
+        ]
+
+    def test_linked_entity_reads_its_parent(self):
+        ir = compile(
+            [
+                parse("""
+                    entity household:
+                        size: int
+                    entity person:
+                        household: -> household
+                        income: float
+                    variable household/members:
+                        entity: household
+                        from 2024-01-01: size
+                    variable person/share:
+                        entity: person
+                        from 2024-01-01: income / household/members
+                """)
+            ],
+            as_of=date(2024, 1, 1),
+        )
+        data = {
+            "household": [{"id": 1, "size": 2}, {"id": 2, "size": 4}],
+            "person": [{"id": 10, "household": 1}, {"id": 11, "household": 2}],
+        }
+        axes = [Axis(field="income", values=[100.0, 200.0])]
+        rows = list(expand_axes(ir, data, "person", axes))
+        assert [r["person/share"] for r in rows] == [50.0, 100.0, 25.0, 50.0]
+        households = list(expand_axes(ir, data, "household", [Axis(field="size", values=[1])]))
+        assert [r["id"] for r in households] == [1, 2]
+
+    def test_zip_needs_equal_lengths(self, household_ir):
+        axes = [Axis(field="income", values=[1.0]), Axis(field="rent", values=[1, 2])]
+        with pytest.raises(ScenarioError, match="same number of values"):
+            list(expand_axes(household_ir, HOUSEHOLDS, "household", axes, mode="zip"))
+
+
+BENEFIT_SOURCE = """
+    entity household:
+        earnings: float
+        weight: float
+
+    variable gov/amount:
+        from 2024-01-01: 1000
+
+    variable gov/taper:
+        from 2024-01-01: 0.5
+
+    variable household/benefit:
+        entity: household
+        from 2024-01-01: max(0, gov/amount - earnings * gov/taper)
+"""
+
+MIXED = {
+    "household": [
+        {"id": 1, "earnings": 0.0, "weight": 100.0},
+        {"id": 2, "earnings": 1500.0, "weight": 50.0},
+        {"id": 3, "earnings": 1005.0, "weight": 20.0},
+        {"id": 4, "earnings": 5000.0, "weight": 30.0},
+    ]
+}
+
+
+class TestWinnersLosers:
+    def test_mixed_population(self):
+        ir = compile([parse(BENEFIT_SOURCE)], as_of=date(2024, 1, 1))
+        baseline = execute(ir, MIXED)
+        # Reform benefit is 1100 - 0.6 * earnings; changes: +100, -50, -0.5, 0
+        reform = execute(apply_overrides(ir, {"gov/amount": 1100, "gov/taper": 0.6}), MIXED)
+        wl = winners_losers(baseline, reform, MIXED, "household/benefit", "weight")
+        assert (wl.gain.count, wl.loss.count, wl.no_change.count) == (100.0, 50.0, 50.0)
+        assert wl.gain.total == pytest.approx(100 * 100)
+        assert wl.loss.total == pytest.approx(50 * -50)
+        assert wl.no_change.total == pytest.approx(20 * -0.5)
+
+        strict = winners_losers(baseline, reform, MIXED, "household/benefit", tolerance=0.0)
+        assert (strict.gain.count, strict.loss.count, strict.no_change.count) == (1, 2, 1)
+        assert strict.loss.total == pytest.approx(-50.5)
+
+    def test_classify_changes(self):
+        wl = classify_changes([5.0, -3.0, 0.5, -10.0], [1.0, 2.0, None, 3.0])
+        assert (wl.gain.count, wl.loss.count, wl.no_change.count) == (1.0, 5.0, 0.0)
+        assert wl.loss.total == -36.0
+
+    def test_totals_compensated(self):
+        # A plain running sum rounds 1e16 + 3 + 3 to 1e16 + 8
+        assert classify_changes([1e16, 3.0, 3.0]).gain.total == 1e16 + 6
+
+    def test_nan_change_left_out(self):
+        wl = classify_changes([5.0, float("nan"), 0.5, None])
+        assert (wl.gain.count, wl.loss.count, wl.no_change.count) == (1.0, 0.0, 1.0)
+        assert wl.no_change.total == 0.5
+
+    def test_missing_output_left_out(self):
+        # Row 2 is missing after the reform, row 3 before it
+        before = {"household": {"household/benefit": [1000.0, 250.0, None, 0.0]}}
+        after = {"household": {"household/benefit": [1100.0, None, 500.0, 0.0]}}
+        baseline = Result(scalars={}, entities=before)
+        reform = Result(scalars={}, entities=after)
+        wl = winners_losers(baseline, reform, MIXED, "household/benefit", "weight")
+        assert (wl.gain.count, wl.loss.count, wl.no_change.count) == (100.0, 0.0, 30.0)
+        assert wl.gain.total == 100 * 100
+
+    def test_unknown_output(self):
+        ir = compile([parse(BENEFIT_SOURCE)], as_of=date(2024, 1, 1))
+        result = execute(ir, MIXED)
+        with pytest.raises(ScenarioError, match="unknown entity output"):
+            winners_losers(result, result, MIXED, "gov/amount")
+
+
+COMPARE_SOURCE = """
+    entity person:
+        income: float
+        weight: float
+
+    variable gov/allowance:
+        from 2024-01-01: 10000
+
+    variable gov/rate:
+        from 2024-01-01: 0.2
+
+    variable gov/child_amount:
+        from 2024-01-01: 500
+
+    variable person/tax:
+        entity: person
+        from 2024-01-01: max(0, income - gov/allowance) * gov/rate
+
+    variable person/benefit:
+        entity: person
+        from 2024-01-01: gov/child_amount
+"""
+
+
+class TestCompareScenarios:
+    @pytest.fixture
+    def compare_ir(self):
+        return compile([parse(COMPARE_SOURCE)], as_of=date(2024, 1, 1))
+
+    def test_long_format_and_deltas(self, compare_ir):
+        scenarios = {
+            "Current law": {},
+            "Rate 25%": {"gov/rate": 0.25},
+            "Bigger benefit (+£100)": {"gov/child_amount": 600},
+        }
+        comparison = compare_scenarios(
+            compare_ir,
+            DATA,
+            "weight",
+            scenarios,
+            ["person/tax", "person/benefit", "gov/rate"],
+            ["sum", "mean"],
+        )
+        assert comparison.baseline == "Current law"
+        assert [r["scenario"] for r in comparison.records[::5]] == list(scenarios)
+        values = {
+            (r["scenario"], r["variable"], r["aggregate"]): r["value"] for r in comparison.records
+        }
+        # Taxable income (weighted): 2 * 10000 + 40000 = 60000 over weight 6
+        assert values[("Current law", "person/tax", "sum")] == pytest.approx(12000)
+        assert values[("Rate 25%", "person/tax", "mean")] == pytest.approx(15000 / 6)
+        assert values[("Bigger benefit (+£100)", "person/benefit", "sum")] == 3600
+        assert values[("Rate 25%", "gov/rate", "value")] == 0.25
+
+        deltas = {
+            (d["scenario"], d["variable"], d["aggregate"]): d["delta"] for d in comparison.deltas
+        }
+        assert len(deltas) == 2 * 5
+        assert deltas[("Rate 25%", "person/tax", "sum")] == pytest.approx(3000)
+        assert deltas[("Rate 25%", "person/benefit", "sum")] == 0
+        assert deltas[("Bigger benefit (+£100)", "person/benefit", "sum")] == 600
+        assert deltas[("Bigger benefit (+£100)", "person/tax", "sum")] == 0
+
+    def test_only_affected_variables_evaluated(self, compare_ir):
+        comparison = compare_scenarios(
+            compare_ir,
+            DATA,
+            "weight",
+            {"base": {}, "rate": {"gov/rate": 0.3}, "benefit": {"gov/child_amount": 0}},
+            ["person/tax"],
+        )
+        assert comparison.evaluated == {
+            "base": [],
+            "rate": ["gov/rate", "person/tax"],
+            "benefit": ["gov/child_amount", "person/benefit"],
+        }
+
+    def test_designated_baseline(self, compare_ir):
+        comparison = compare_scenarios(
+            compare_ir,
+            DATA,
+            None,
+            {"low": {"gov/rate": 0.1}, "high": {"gov/rate": 0.3}},
+            ["person/tax"],
+            baseline="high",
+        )
+        assert [d["scenario"] for d in comparison.deltas] == ["low"]
+        # Unweighted taxable income: 10000 + 40000
+        assert comparison.deltas[0]["delta"] == pytest.approx(-10000)
+
+    def test_errors(self, compare_ir):
+        with pytest.raises(ScenarioError, match="unknown aggregate"):
+            compare_scenarios(compare_ir, DATA, None, {"a": {}}, ["person/tax"], ["median"])
+        with pytest.raises(ScenarioError, match="unknown baseline"):
+            compare_scenarios(compare_ir, DATA, None, {"a": {}}, ["person/tax"], baseline="b")
+        with pytest.raises(ScenarioError, match="unknown output"):
+            compare_scenarios(compare_ir, DATA, None, {"a": {}}, ["person/missing"])
@@ -0,0 +1,392 @@
+"""Tests for weighted statistics over execution results."""
+
+from datetime import date
+
+import numpy as np
+import pytest
+
+from rac import (
+    ColumnStats,
+    ExecutionError,
+    RandomManifest,
+    StatsError,
+    capped_allocate,
+    compile,
+    crosstab,
+    gini,
+    group_argmax,
+    group_argmin,
+    group_keys,
+    group_sum,
+    parse,
+    run,
+    verify_reproducibility,
+)
+
+
+class TestGini:
+    def test_perfect_equality_is_zero(self):
+        data = {"income": [100.0, 100.0, 100.0, 100.0]}
+        assert gini(data, "income") == pytest.approx(0.0)
+
+    def test_cumulative_sums_compensated(self):
+        # Plain running sums of 0.1 drift far enough to give about 1e-12
+        assert gini({"income": [0.1] * 100_000}, "income") == 0.0
+
+    def test_one_holds_everything(self):
+        # n - 1 people with nothing: G = (n - 1) / n
+        data = {"income": [0.0, 0.0, 0.0, 100.0]}
+        assert gini(data, "income") == pytest.approx(0.75)
+
+    def test_uniform_distribution(self):
+        # Values 1..n: G = (n - 1) / (3n) for a discrete uniform distribution
+        n = 100
+        data = {"income": [float(i) for i in range(1, n + 1)]}
+        assert gini(data, "income") == pytest.approx((n - 1) / (3 * n))
+
+    def test_weights_match_replicated_rows(self):
+        weighted = {"income": [10.0, 50.0], "weight": [3.0, 1.0]}
+        replicated = {"income": [10.0, 10.0, 10.0, 50.0]}
+        assert gini(weighted, "income", "weight") == pytest.approx(gini(replicated, "income"))
+
+    def test_row_data(self):
+        rows = [{"income": 0.0, "w": 1.0}, {"income": 10.0, "w": 1.0}]
+        assert gini(rows, "income", "w") == pytest.approx(0.5)
+
+    def test_zero_weight_rows_ignored(self):
+        data = {"income": [100.0, 100.0, 1e9], "weight": [1.0, 1.0, 0.0]}
+        assert gini(data, "income", "weight") == pytest.approx(0.0)
+
+    def test_zero_total_weight(self):
+        data = {"income": [1.0, 2.0], "weight": [0.0, 0.0]}
+        assert gini(data, "income", "weight") == 0.0
+
+    def test_all_zero_values(self):
+        assert gini({"income": [0.0, 0.0]}, "income") == 0.0
+
+    def test_empty(self):
+        assert gini({"income": []}, "income") == 0.0
+
+    def test_missing_values_left_out(self):
+        data = {"income": [0.0, None, 100.0], "w": [1.0, 5.0, 1.0]}
+        assert gini(data, "income", "w") == pytest.approx(0.5)
+
+    def test_nan_values_left_out(self):
+        data = {"income": [float("nan"), 1.0, 2.0, 3.0]}
+        assert gini(data, "income") == pytest.approx(2 / 9)
+
+    def test_negative_value_raises(self):
+        with pytest.raises(StatsError, match="negative values"):
+            gini({"income": [-5.0, 10.0]}, "income")
+
+    def test_negative_weight_raises(self):
+        with pytest.raises(StatsError, match="negative weights"):
+            gini({"income": [5.0, 10.0], "w": [1.0, -1.0]}, "income", "w")
+
+    def test_unknown_column_raises(self):
+        with pytest.raises(StatsError, match="unknown column"):
+            gini({"income": [1.0]}, "wealth")
+
+
+class TestGroupArg:
+    DATA = {
+        "id": [1, 2, 3, 4, 5],
+        "household": [10, 10, 10, 20, 20],
+        "income": [500.0, 900.0, 900.0, 100.0, 50.0],
+    }
+
+    def test_argmax(self):
+        assert group_argmax(self.DATA, "household", "income") == {10: 2, 20: 4}
+
+    def test_argmin(self):
+        assert group_argmin(self.DATA, "household", "income") == {10: 1, 20: 5}
+
+    def test_row_data(self):
+        rows = [dict(zip(self.DATA, vals)) for vals in zip(*self.DATA.values())]
+        assert group_argmax(rows, "household", "income") == {10: 2, 20: 4}
+
+    def test_tie_lowest_id_without_seed(self):
+        data = {"id": [7, 3, 5], "g": [1, 1, 1], "v": [1.0, 1.0, 1.0]}
+        assert group_argmax(data, "g", "v") == {1: 3}
+
+    def test_seeded_tie_break_reproducible(self):
+        for seed in range(20):
+            first = group_argmax(self.DATA, "household", "income", seed=seed)
+            again = group_argmax(self.DATA, "household", "income", seed=seed)
+            assert first == again
+            assert first[10] in (2, 3)
+
+    def test_different_seeds_can_pick_other_member(self):
+        picks = {group_argmax(self.DATA, "household", "income", seed=s)[10] for s in range(20)}
+        assert picks == {2, 3}
+
+    def test_seed_does_not_affect_strict_winner(self):
+        for seed in range(5):
+            assert group_argmin(self.DATA, "household", "income", seed=seed) == {10: 1, 20: 5}
+
+    def test_custom_id_column(self):
+        data = {"pid": ["a", "b"], "g": [1, 1], "v": [2.0, 3.0]}
+        assert group_argmax(data, "g", "v", id_path="pid") == {1: "b"}
+
+
+class TestCompositeGroups:
+    # Region x household type: rows 0 and 2 share both keys, row 1 only the region
+    DATA = {
+        "region": ["north", "north", "north", "south"],
+        "kind": ["family", "single", "family", "family"],
+        "income": [100.0, 200.0, 300.0, 400.0],
+        "adult": [True, True, False, True],
+        "id": [1, 2, 3, 4],
+        "cap": [250.0, 1000.0, 250.0, 1000.0],
+    }
+    KEY = ["region", "kind"]
+
+    def test_keys_are_tuples(self):
+        assert group_keys(self.DATA, self.KEY)[:2] == [("north", "family"), ("north", "single")]
+        assert group_keys(self.DATA, "region") == self.DATA["region"]
+        with pytest.raises(StatsError, match="at least one column"):
+            group_keys(self.DATA, [])
+
+    def test_grouped_only_when_both_keys_match(self):
+        assert group_sum(self.DATA, self.KEY, "income") == [400.0, 200.0, 400.0, 400.0]
+        assert group_sum(self.DATA, "region", "income") == [600.0, 600.0, 600.0, 400.0]
+
+    def test_conditional_sum_broadcasts_to_every_member(self):
+        assert group_sum(self.DATA, self.KEY, "income", where_path="adult") == [
+            100.0,
+            200.0,
+            100.0,
+            400.0,
+        ]
+
+    def test_argmax_and_allocation(self):
+        assert group_argmax(self.DATA, self.KEY, "income") == {
+            ("north", "family"): 3,
+            ("north", "single"): 2,
+            ("south", "family"): 4,
+        }
+        result = capped_allocate(self.DATA, self.KEY, "income", "cap")
+        assert result.granted == pytest.approx([62.5, 200.0, 187.5, 400.0])
+        assert result.residual[("north", "family")] == 0.0
+
+    def test_row_data(self):
+        rows = [dict(zip(self.DATA, values)) for values in zip(*self.DATA.values())]
+        assert group_sum(rows, self.KEY, "income") == group_sum(self.DATA, self.KEY, "income")
+
+
+class TestCrosstab:
+    DATA = {
+        "state": ["CA", "NY", "CA", "TX", None, "NY"],
+        "snap": ["yes", "no", "no", "no", "yes", None],
+        "weight": [2.0, 1.0, 3.0, 0.0, 1.5, 4.0],
+    }
+
+    def test_one_way(self):
+        table = crosstab(self.DATA, "state", weight_path="weight")
+        assert table == {"CA": 5.0, "NY": 5.0, "TX": 0.0, None: 1.5}
+        assert list(table) == ["CA", "NY", "TX", None]
+
+    def test_one_way_unweighted_total_share(self):
+        table = crosstab(self.DATA, "snap", normalize="total")
+        assert table == pytest.approx({"yes": 2 / 6, "no": 3 / 6, None: 1 / 6})
+
+    def test_two_way(self):
+        table = crosstab(self.DATA, "state", "snap", weight_path="weight")
+        assert table == {
+            "CA": {"yes": 2.0, "no": 3.0, None: 0.0},
+            "NY": {"yes": 0.0, "no": 1.0, None: 4.0},
+            "TX": {"yes": 0.0, "no": 0.0, None: 0.0},
+            None: {"yes": 1.5, "no": 0.0, None: 0.0},
+        }
+
+    def test_declared_order_and_zero_weight_categories(self):
+        table = crosstab(
+            self.DATA,
+            "state",
+            "snap",
+            weight_path="weight",
+            categories={"state": ["TX", "WA", "NY"], "snap": ["no", "yes"]},
+        )
+        assert list(table) == ["TX", "WA", "NY", "CA", None]
+        assert list(table["WA"]) == ["no", "yes", None]
+        assert table["WA"] == {"no": 0.0, "yes": 0.0, None: 0.0}
+
+    def test_normalize_row(self):
+        table = crosstab(self.DATA, "state", "snap", weight_path="weight", normalize="row")
+        assert table["CA"] == pytest.approx({"yes": 0.4, "no": 0.6, None: 0.0})
+        assert table["TX"] == {"yes": 0.0, "no": 0.0, None: 0.0}
+
+    def test_normalize_column(self):
+        table = crosstab(self.DATA, "state", "snap", weight_path="weight", normalize="column")
+        assert sum(table[r]["no"] for r in table) == pytest.approx(1.0)
+        assert table["CA"]["yes"] == pytest.approx(2.0 / 3.5)
+
+    def test_normalize_total(self):
+        table = crosstab(self.DATA, "state", "snap", weight_path="weight", normalize="total")
+        assert sum(sum(cells.values()) for cells in table.values()) == pytest.approx(1.0)
+
+    def test_deterministic_order(self):
+        a = crosstab(self.DATA, "state", "snap", weight_path="weight")
+        b = crosstab(self.DATA, "state", "snap", weight_path="weight")
+        assert [list(v) for v in a.values()] == [list(v) for v in b.values()]
+
+    def test_invalid_normalize(self):
+        with pytest.raises(StatsError, match="invalid normalize"):
+            crosstab(self.DATA, "state", "snap", normalize="diagonal")
+        with pytest.raises(StatsError, match="one-way"):
+            crosstab(self.DATA, "state", normalize="row")
+
+
+class TestCappedAllocate:
+    def test_demand_below_cap(self):
+        data = {"g": [1, 1], "demand": [100.0, 200.0], "cap": [500.0, 500.0]}
+        result = capped_allocate(data, "g", "demand", "cap")
+        assert result.granted == [100.0, 200.0]
+        assert result.residual == {1: 200.0}
+
+    def test_demand_equal_to_cap(self):
+        data = {"g": [1, 1], "demand": [200.0, 300.0], "cap": [500.0, 500.0]}
+        result = capped_allocate(data, "g", "demand", "cap")
+        assert result.granted == [200.0, 300.0]
+        assert result.residual == {1: 0.0}
+
+    def test_demand_above_cap_scales_proportionally(self):
+        data = {"g": [1, 1, 1], "demand": [100.0, 300.0, 600.0], "cap": [500.0] * 3}
+        result = capped_allocate(data, "g", "demand", "cap")
+        assert result.granted == pytest.approx([50.0, 150.0, 300.0])
+        assert sum(result.granted) == pytest.approx(500.0)
+        assert result.residual == {1: 0.0}
+
+    def test_groups_independent(self):
+        data = {
+            "g": ["a", "b", "a", "b"],
+            "demand": [10.0, 10.0, 30.0, 10.0],
+            "cap": [20.0, 100.0, 20.0, 100.0],
+        }
+        result = capped_allocate(data, "g", "demand", "cap")
+        assert result.granted == pytest.approx([5.0, 10.0, 15.0, 10.0])
+        assert result.residual == {"a": 0.0, "b": 80.0}
+
+    def test_zero_demand_group(self):
+        data = {"g": [1], "demand": [0.0], "cap": [50.0]}
+        result = capped_allocate(data, "g", "demand", "cap")
+        assert result.granted == [0.0]
+        assert result.residual == {1: 50.0}
+
+    def test_inconsistent_cap(self):
+        data = {"g": [1, 1], "demand": [1.0, 1.0], "cap": [5.0, 6.0]}
+        with pytest.raises(StatsError, match="inconsistent cap"):
+            capped_allocate(data, "g", "demand", "cap")
+
+    def test_negative_demand(self):
+        with pytest.raises(StatsError, match="negative"):
+            capped_allocate({"g": [1], "d": [-1.0], "c": [5.0]}, "g", "d", "c")
+
+
+class TestColumnStats:
+    VALUES = [3.0, -1.5, 8.25, 0.0, 12.0, 4.5, None, float("nan"), 7.0]
+
+    def accumulate(self, values):
+        acc = ColumnStats()
+        for v in values:
+            acc.add(v)
+        return acc
+
+    def test_matches_numpy(self):
+        acc = self.accumulate(self.VALUES)
+        present = np.array([v for v in self.VALUES if v is not None], dtype=float)
+        assert acc.count == 7
+        assert acc.nulls == 2
+        assert acc.min == np.nanmin(present)
+        assert acc.max == np.nanmax(present)
+        assert acc.mean == pytest.approx(np.nanmean(present))
+        assert acc.stddev == pytest.approx(np.nanstd(present))
+
+    def test_merge_matches_single_pass(self):
+        whole = self.accumulate(self.VALUES)
+        merged = self.accumulate(self.VALUES[:4]).merge(self.accumulate(self.VALUES[4:]))
+        assert merged.count == whole.count and merged.nulls == whole.nulls
+        assert (merged.min, merged.max) == (whole.min, whole.max)
+        assert merged.mean == pytest.approx(whole.mean)
+        assert merged.variance == pytest.approx(whole.variance)
+
+    def test_merge_with_empty(self):
+        acc = self.accumulate([1.0, 2.0])
+        nulls = self.accumulate([None])
+        assert acc.merge(nulls) == acc.model_copy(update={"nulls": 1})
+        assert ColumnStats().merge(acc) == acc
+
+    def test_empty(self):
+        acc = self.accumulate([None])
+        assert acc.count == 0 and acc.nulls == 1
+        assert acc.min is None and acc.stddev is None
+
+    def test_rejects_strings(self):
+        with pytest.raises(StatsError, match="need numbers"):
+            ColumnStats().add("a")
+
+
+class TestCollectStats:
+    SOURCE = """
+        entity person:
+            income: float
+            age: int
+        variable gov/rate:
+            from 2024-01-01: 0.2
+        variable person/tax:
+            entity: person
+            from 2024-01-01: income * gov/rate
+        variable person/credit:
+            entity: person
+            from 2024-01-01: if age < 18: 100 else: 0
+        variable person/net:
+            entity: person
+            from 2024-01-01: income - person/tax + person/credit
+    """
+    DATA = {
+        "person": [
+            {"id": i, "income": 1000.0 * i + 37.5 * (i % 3), "age": 10 + 7 * i}
+            for i in range(1, 9)
+        ]
+    }
+
+    def run(self, collect_stats):
+        ir = compile([parse(self.SOURCE)], as_of=date(2024, 1, 1))
+        return run(ir, self.DATA, collect_stats=collect_stats)
+
+    def test_against_numpy_on_returned_columns(self):
+        result = self.run(["person/tax", "person/net"])
+        assert list(result.column_stats) == ["person/tax", "person/net"]
+        for path, stats in result.column_stats.items():
+            column = np.array(result.entities["person"][path], dtype=float)
+            assert stats.count == len(column) and stats.nulls == 0
+            assert stats.min == column.min() and stats.max == column.max()
+            assert stats.mean == pytest.approx(column.mean())
+            assert stats.stddev == pytest.approx(column.std())
+
+    def test_globs(self):
+        result = self.run(["person/*", "gov/rate"])
+        assert list(result.column_stats) == [
+            "gov/rate",
+            "person/tax",
+            "person/credit",
+            "person/net",
+        ]
+        assert result.column_stats["gov/rate"].mean == pytest.approx(0.2)
+        assert self.run(["nobody/*"]).column_stats == {}
+
+    def test_only_monitored_variables(self):
+        assert self.run(None).column_stats == {}
+        assert list(self.run(["person/credit"]).column_stats) == ["person/credit"]
+
+    def test_unknown_path(self):
+        with pytest.raises(ExecutionError, match="unknown variable: person/nope"):
+            self.run(["person/nope"])
+
+    def test_recorded_in_manifest_comparison(self):
+        stats = self.run(["person/net"]).column_stats
+        drifted = {"person/net": stats["person/net"].model_copy(update={"max": 0.0})}
+        a = RandomManifest(seed=1, column_stats=stats)
+        assert verify_reproducibility(a, a.model_copy()) == []
+        b = RandomManifest(seed=1, column_stats=drifted)
+        assert verify_reproducibility(a, b) == ["column statistics of person/net differ"]
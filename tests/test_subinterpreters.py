@@ -0,0 +1,75 @@
+"""Tests that module state does not leak between Python subinterpreters."""
+
+import sys
+import textwrap
+
+import pytest
+
+si = pytest.importorskip("_xxsubinterpreters")
+
+import rac  # noqa: E402  (imported first, so native caches belong to this interpreter)
+
+PRELUDE = f"""
+import sys
+sys.path[:0] = {sys.path!r}
+from datetime import date
+import rac
+IR = rac.compile(
+    [rac.parse("entity person:\\n    x: float\\nvariable person/y:\\n    entity: person\\n"
+               "    from 2024-01-01: f(x)\\n")],
+    as_of=date(2024, 1, 1),
+)
+def y():
+    return rac.execute(IR, {{"person": [{{"id": 1, "x": 2.0}}]}}).entities["person"]["person/y"]
+"""
+
+
+def run(interp, code: str) -> None:
+    si.run_string(interp, textwrap.dedent(code))
+
+
+@pytest.fixture
+def interpreters():
+    created = []
+
+    def make():
+        interp = si.create()
+        created.append(interp)
+        try:
+            run(interp, PRELUDE)
+        except si.RunFailedError as e:
+            pytest.skip(f"rac cannot be imported in a subinterpreter here: {e}")
+        return interp
+
+    yield make
+    for interp in created:
+        si.destroy(interp)
+
+
+class TestSubinterpreters:
+    def test_registry_and_config_are_per_interpreter(self, interpreters):
+        a, b = interpreters(), interpreters()
+        run(a, """
+            rac.register_function("f", lambda x: x * 10)
+            rac.set_default_config({"strict": False})
+            assert y() == [20.0]
+        """)
+        run(b, """
+            from rac.functions import get_function
+            assert get_function("f") is None
+            assert rac.get_default_config() is None
+            rac.register_function("f", lambda x: x + 1)
+            assert y() == [3.0]
+        """)
+        run(a, "assert y() == [20.0]")
+        assert rac.functions.get_function("f") is None
+        assert rac.get_default_config() is None
+
+    def test_reimport_after_teardown(self, interpreters):
+        for _ in range(2):
+            interp = si.create()
+            try:
+                run(interp, PRELUDE + "rac.register_function('f', abs)\nassert y() == [2.0]\n")
+            finally:
+                si.destroy(interp)
+        run(interpreters(), "assert rac.functions.get_function('f') is None")
@@ -0,0 +1,121 @@
+"""Compensated, fixed-order summation for sum, sum_over and the stats totals."""
+
+import math
+import subprocess
+from datetime import date
+from fractions import Fraction
+
+import pytest
+
+from rac import (
+    EngineConfig,
+    Fixed,
+    compensated_sum,
+    compile,
+    generate_javascript,
+    generate_python,
+    group_sum,
+    parse,
+    run,
+    to_fixed,
+)
+
+SOURCE = """
+    entity household:
+        size: int
+
+    entity person:
+        household: -> household
+        income: float
+
+    variable person/net:
+        entity: person
+        from 2024-01-01: income
+
+    variable household/income:
+        entity: household
+        from 2024-01-01: sum_over(person, person/net)
+"""
+
+# A list-valued row field; bracketed list literals can't be summed
+LIST_SOURCE = """
+    entity household:
+        size: int
+
+    variable household/total:
+        entity: household
+        from 2024-01-01: sum(amounts)
+"""
+
+CONFIGS = (None, EngineConfig(columnar=False), EngineConfig(bytecode=True, columnar=False))
+
+
+class TestCompensatedSum:
+    def test_cancellation(self):
+        assert compensated_sum([1e16, 1.0, -1e16]) == 1.0
+        assert sum([1e16, 1.0, -1e16]) == 0.0
+
+    def test_many_small_amounts(self):
+        assert compensated_sum([0.1] * 1_000_000) == 100_000.0
+        assert sum([0.1] * 1_000_000) != 100_000.0
+
+    def test_types_are_kept(self):
+        assert compensated_sum([1, 2, 3]) == 6 and isinstance(compensated_sum([1, 2]), int)
+        assert compensated_sum([Fraction(1, 3)] * 3) == 1
+        total = compensated_sum([to_fixed(0.1, 3)] * 3)
+        assert isinstance(total, Fixed) and str(total) == "0.300"
+        assert compensated_sum([]) == 0
+
+    def test_nan_and_infinity(self):
+        assert math.isnan(compensated_sum([1.0, math.nan, 2.0]))
+        assert compensated_sum([1.0, math.inf, 0.5]) == math.inf
+        assert compensated_sum([1e308, 1e308]) == math.inf
+
+    def test_group_sum(self):
+        data = {"group": ["a", "a", "a", "b"], "income": [1e16, 1.0, -1e16, 0.5]}
+        assert group_sum(data, "group", "income") == [1.0, 1.0, 1.0, 0.5]
+
+
+class TestExecute:
+    def test_sum_over(self):
+        ir = compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+        data = {
+            "household": [{"id": 1, "size": 3}, {"id": 2, "size": 1}],
+            "person": [
+                {"id": 1, "household": 1, "income": 1e16},
+                {"id": 2, "household": 2, "income": 0.1},
+                {"id": 3, "household": 1, "income": 1.0},
+                {"id": 4, "household": 1, "income": -1e16},
+            ],
+        }
+        for config in CONFIGS:
+            result = run(ir, data, config=config)
+            assert result.entities["household"]["household/income"] == [1.0, 0.1], config
+
+    def test_sum_builtin(self):
+        ir = compile([parse(LIST_SOURCE)], as_of=date(2024, 1, 1))
+        data = {"household": [{"id": 1, "size": 3, "amounts": [1e16, 1.0, -1e16]}]}
+        for config in CONFIGS:
+            assert run(ir, data, config=config).entities["household"]["household/total"] == [1.0]
+
+
+class TestCodegen:
+    @pytest.fixture
+    def ir(self):
+        return compile([parse(LIST_SOURCE)], as_of=date(2024, 1, 1))
+
+    def test_python(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        assert namespace["_sum"]([1e16, 1.0, -1e16]) == 1.0
+
+    def test_javascript(self, ir, tmp_path):
+        js = generate_javascript(ir)
+        assert "racSum(input.amounts)" in js
+        path = tmp_path / "model.js"
+        path.write_text(js + "\nconsole.log(racSum([1e16, 1, -1e16]));\n")
+        try:
+            out = subprocess.run(["node", str(path)], capture_output=True, text=True, check=True)
+        except FileNotFoundError:
+            pytest.skip("node is not installed")
+        assert out.stdout.strip() == "1"
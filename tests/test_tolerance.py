@@ -0,0 +1,134 @@
+"""Comparison tolerance: the engine epsilon and per-comparison ``within``."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    EngineConfig,
+    ParseError,
+    compile,
+    dump_ir,
+    execute,
+    generate_javascript,
+    generate_python,
+    generate_rust,
+    load_ir,
+    parse,
+    run,
+)
+from rac.ast import to_source
+
+SOURCE = """
+    entity household:
+        income: float
+        rate: float
+    variable household/income_matches:
+        entity: household
+        from 2024-01-01: income == 30000 within 1
+    variable household/rate_matches:
+        entity: household
+        from 2024-01-01: rate == 0.25
+    variable household/rate_differs:
+        entity: household
+        from 2024-01-01: rate != 0.25 within 0.001
+"""
+
+DATA = {
+    "household": [
+        {"id": 1, "income": 30000.4, "rate": 0.2500004},
+        {"id": 2, "income": 30002.0, "rate": 0.25},
+    ]
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+def outputs(result):
+    return result.entities["household"]
+
+
+class TestParse:
+    def test_within_sets_tolerance(self):
+        module = parse(SOURCE)
+        exprs = [v.values[0].expr for v in module.variables]
+        assert [e.tolerance for e in exprs] == [1.0, None, 0.001]
+        assert to_source(exprs[0]) == "(income == 30000 within 1.0)"
+
+    def test_within_needs_a_number(self):
+        with pytest.raises(ParseError, match="expected a number after 'within'"):
+            parse("variable gov/a:\n    from 2024-01-01: 1 == 2 within gov/b\n")
+
+    def test_within_only_on_equality(self):
+        with pytest.raises(ParseError):
+            parse("variable gov/a:\n    from 2024-01-01: 1 < 2 within 1\n")
+
+    def test_within_is_still_a_name(self):
+        module = parse("""
+            entity person:
+                within: float
+            variable person/a:
+                entity: person
+                from 2024-01-01: within * 2
+        """)
+        assert module.variables[0].values[0].expr.left.path == "within"
+
+
+class TestExecute:
+    def test_sites_are_independent(self, ir):
+        result = outputs(execute(ir, DATA))
+        # The loose money comparison absorbs 0.4 but not 2.0
+        assert result["household/income_matches"] == [True, False]
+        # The rate comparison keeps the default: exact equality
+        assert result["household/rate_matches"] == [False, True]
+        assert result["household/rate_differs"] == [False, False]
+
+    def test_global_epsilon_applies_where_no_tolerance(self, ir):
+        result = outputs(run(ir, DATA, config=EngineConfig(epsilon=1e-6)))
+        assert result["household/rate_matches"] == [True, True]
+        # The explicit tolerance still wins over the looser global epsilon
+        loose = outputs(run(ir, DATA, config=EngineConfig(epsilon=10.0)))
+        assert loose["household/income_matches"] == [True, False]
+
+    def test_nan_is_never_equal(self):
+        ir = compile(
+            [
+                parse("""
+                    entity person:
+                        x: float
+                    variable person/eq:
+                        entity: person
+                        from 2024-01-01: x == x within 1
+                    variable person/ne:
+                        entity: person
+                        from 2024-01-01: x != x within 1
+                """)
+            ],
+            as_of=date(2024, 1, 1),
+        )
+        result = execute(ir, {"person": [{"id": 1, "x": float("nan")}]}).entities["person"]
+        assert result == {"person/eq": [False], "person/ne": [True]}
+
+    def test_round_trip(self, ir):
+        loaded = load_ir(dump_ir(ir))
+        assert loaded == ir
+        assert outputs(execute(loaded, DATA)) == outputs(execute(ir, DATA))
+
+
+class TestCodegen:
+    def test_python_matches_executor(self, ir):
+        namespace: dict = {}
+        exec(generate_python(ir), namespace)
+        scalars = namespace["compute_scalars"]()
+        for i, row in enumerate(DATA["household"]):
+            inputs = {k: v for k, v in row.items() if k != "id"}
+            out = namespace["compute_household"](namespace["HouseholdInput"](**inputs), scalars)
+            assert out["household_income_matches"] == [True, False][i]
+            assert out["household_rate_differs"] is False
+
+    def test_rust_and_javascript(self, ir):
+        assert ".abs() <= 1.0_f64" in generate_rust(ir)
+        assert "!(Math.abs(" in generate_javascript(ir)
@@ -0,0 +1,101 @@
+"""trace(): one row's evaluation tree."""
+
+from datetime import date
+
+import pytest
+
+from rac import TraceError, compile, parse, trace
+
+SOURCE = """
+    entity household:
+        id: int
+        income: float
+        members: int
+    entity person:
+        household: -> household
+        age: int
+    variable gov/allowance:
+        from 2024-01-01: 10000
+    variable gov/rate:
+        from 2024-01-01: 0.2
+    variable gov/per_member:
+        from 2024-01-01: 1000
+    variable household/allowance:
+        entity: household
+        from 2024-01-01: gov/allowance + gov/per_member * members
+    variable household/taxable:
+        entity: household
+        from 2024-01-01: max(0, income - household/allowance)
+    variable household/tax:
+        entity: household
+        from 2024-01-01: household/taxable * gov/rate
+    variable household/size:
+        entity: household
+        from 2024-01-01: count_over(person)
+    variable person/pension:
+        entity: person
+        from 2024-01-01: where(age >= 40, gov/allowance, 0)
+"""
+
+DATA = {
+    "household": [
+        {"id": 1, "income": 5000.0, "members": 1},
+        {"id": 2, "income": 30000.0, "members": 3},
+    ],
+    "person": [{"id": 10, "household": 2, "age": 40}],
+}
+
+
+@pytest.fixture
+def ir():
+    return compile([parse(SOURCE)], as_of=date(2024, 1, 1))
+
+
+class TestTrace:
+    def test_tree(self, ir):
+        root = trace(ir, DATA, "household", 1, "household/tax")
+        assert (root.path, root.value, root.expr) == (
+            "household/tax",
+            pytest.approx(3400.0),
+            "(household/taxable * gov/rate)",
+        )
+        assert [dep.path for dep in root.deps] == ["gov/rate", "household/taxable"]
+        taxable = root.find("household/taxable")
+        assert taxable.value == 17000.0
+        assert taxable.inputs == {"income": 30000.0}
+        assert root.find("household/allowance").inputs == {"members": 3}
+
+    def test_parameters(self, ir):
+        root = trace(ir, DATA, "household", 0, "household/tax")
+        assert root.value == 0
+        assert root.parameters() == {
+            "gov/allowance": 10000,
+            "gov/per_member": 1000,
+            "gov/rate": 0.2,
+        }
+
+    def test_render(self, ir):
+        text = trace(ir, DATA, "household", 1, "household/taxable").render()
+        assert text.splitlines() == [
+            "household/taxable = 17000.0  "
+            "[max(0, (income - household/allowance)), income=30000.0]",
+            "  household/allowance = 13000  "
+            "[(gov/allowance + (gov/per_member * members)), members=3]",
+            "    gov/allowance = 10000  [10000]",
+            "    gov/per_member = 1000  [1000]",
+        ]
+
+    def test_linked_row(self, ir):
+        root = trace(ir, DATA, "person", 0, "person/pension")
+        assert root.value == 10000
+        assert root.inputs == {"age": 40}
+
+    def test_errors(self, ir):
+        with pytest.raises(TraceError, match="unknown variable"):
+            trace(ir, DATA, "household", 0, "household/missing")
+        with pytest.raises(TraceError, match="no row 5"):
+            trace(ir, DATA, "household", 5, "household/tax")
+        with pytest.raises(TraceError, match="not a variable of entity person"):
+            trace(ir, DATA, "person", 0, "household/tax")
+        with pytest.raises(TraceError, match="reads another entity"):
+            trace(ir, DATA, "household", 0, "household/size")
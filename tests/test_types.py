@@ -0,0 +1,137 @@
+"""Value types: bool, int, float, str and date inferred and checked at compile time."""
+
+from datetime import date
+
+import pytest
+
+from rac import (
+    CompileError,
+    EngineConfig,
+    column_problems,
+    compile,
+    dump_ir,
+    load_ir,
+    parse,
+    run,
+    variable_types,
+)
+
+SOURCE = """
+    entity person:
+        is_disabled: bool
+        num_children: int
+        income: float?
+        region: str
+    variable gov/child_amount:
+        from 2024-01-01: 1000
+    variable person/eligible:
+        entity: person
+        from 2024-01-01: is_disabled or num_children > 0
+    variable person/children_plus_one:
+        entity: person
+        from 2024-01-01: num_children + 1
+    variable person/child_benefit:
+        entity: person
+        from 2024-01-01: num_children * gov/child_amount
+    variable person/share:
+        entity: person
+        from 2024-01-01: income / 2
+    variable person/scottish:
+        entity: person
+        from 2024-01-01: region == "SCOTLAND"
+    variable person/three_children:
+        entity: person
+        from 2024-01-01: num_children == 3
+"""
+
+
+def _compile(source):
+    return compile([parse(source)], as_of=date(2024, 1, 1))
+
+
+@pytest.fixture
+def ir():
+    return _compile(SOURCE)
+
+
+class TestInference:
+    def test_types(self, ir):
+        types, problems = variable_types(ir.variables, ir.schema_, ir.order)
+        assert problems == []
+        assert types == {
+            "gov/child_amount": "int",
+            "person/eligible": "bool",
+            "person/children_plus_one": "int",
+            "person/child_benefit": "int",
+            "person/share": "float",
+            "person/scottish": "bool",
+            "person/three_children": "bool",
+        }
+
+    def test_values_keep_their_types(self, ir):
+        data = {
+            "person": [
+                {"id": 1, "is_disabled": False, "num_children": 2, "income": 10.0, "region": "X"}
+            ]
+        }
+        columns = run(ir, data).entities["person"]
+        assert columns["person/eligible"] == [True]
+        assert type(columns["person/children_plus_one"][0]) is int
+
+
+class TestCompileErrors:
+    @pytest.mark.parametrize(
+        "formula, message",
+        [
+            ("region + 1", "needs numbers, got str"),
+            ("income > region", "compares different types"),
+            ('num_children == "3"', "compares different types"),
+            ("-region", "needs numbers, got str"),
+        ],
+    )
+    def test_rejected(self, formula, message):
+        source = SOURCE + f"""
+    variable person/bad:
+        entity: person
+        from 2024-01-01: {formula}
+"""
+        with pytest.raises(CompileError, match=f"person/bad: .*{message}"):
+            _compile(source)
+
+    def test_loaded_ir_is_checked(self, ir):
+        data = dump_ir(ir).replace('"value":1000', '"value":"1000"')
+        with pytest.raises(CompileError, match="person/child_benefit: .*needs numbers"):
+            load_ir(data)
+
+
+class TestIntegerEquality:
+    def test_epsilon_does_not_apply_to_counts(self, ir):
+        data = {
+            "person": [
+                {"id": 1, "is_disabled": False, "num_children": 4, "income": 0.0, "region": "X"}
+            ]
+        }
+        loose = EngineConfig(epsilon=1.5)
+        columns = run(ir, data, config=loose, outputs=["person/three_children"])
+        assert columns.entities["person"]["person/three_children"] == [False]
+
+
+class TestColumns:
+    def test_mistyped_values(self, ir):
+        tables = {
+            "person": [
+                {"id": 1, "is_disabled": 1.0, "num_children": 2.0, "income": 3, "region": "X"},
+                {"id": 2, "is_disabled": True, "num_children": 2.5, "income": None, "region": 7},
+            ]
+        }
+        assert column_problems(ir.schema_, tables) == [
+            "person row 0: is_disabled should be bool, got float 1.0",
+            "person row 1: num_children should be int, got float 2.5",
+            "person row 1: region should be str, got int 7",
+        ]
+
+    def test_missing_values_need_nullable(self, ir):
+        row = {"id": 1, "is_disabled": None, "num_children": 0, "income": None, "region": "X"}
+        assert column_problems(ir.schema_, {"person": [row]}) == [
+            "person row 0: is_disabled is missing but not declared nullable"
+        ]
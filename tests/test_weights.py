@@ -0,0 +1,129 @@
+"""Tests for the weight policy across weighted statistics."""
+
+import pytest
+
+from rac import (
+    Result,
+    SamplingError,
+    StatsError,
+    WeightPolicy,
+    crosstab,
+    gini,
+    quantile_groups,
+    sample_rows,
+    weighted_quantile,
+)
+from rac.scenarios import total
+
+# Replicate-style weights: one negative, one zero
+DATA = {
+    "income": [10.0, 20.0, 30.0, 40.0],
+    "region": ["a", "b", "a", "c"],
+    "w": [2.0, -1.0, 0.0, 1.0],
+}
+ROWS = [
+    {"id": i, "income": x, "weight": w}
+    for i, (x, w) in enumerate(zip(DATA["income"], DATA["w"]))
+]
+RESULT = Result(scalars={}, entities={"p": {"income": DATA["income"]}})
+TABLES = {"p": [{"w": w} for w in DATA["w"]]}
+
+ALLOW_ALL = WeightPolicy(allow_negative=True)
+CLAMP = WeightPolicy(on_invalid="clamp")
+SKIP = WeightPolicy(on_invalid="skip")
+NO_ZERO_SKIP = WeightPolicy(allow_zero=False, allow_negative=True, on_invalid="skip")
+
+
+class TestPolicy:
+    def test_apply(self):
+        weights = [1.0, -1.0, 0.0]
+        assert ALLOW_ALL.apply(weights) == [1.0, -1.0, 0.0]
+        assert CLAMP.apply(weights) == [1.0, 0.0, 0.0]
+        assert SKIP.apply(weights) == [1.0, None, 0.0]
+        assert NO_ZERO_SKIP.apply(weights) == [1.0, -1.0, None]
+
+    def test_default_rejects_negative(self):
+        with pytest.raises(StatsError, match="negative weights not allowed"):
+            WeightPolicy().apply([1.0, -1.0])
+
+    def test_zero_disallowed_error(self):
+        with pytest.raises(StatsError, match="zero weights not allowed"):
+            WeightPolicy(allow_zero=False).apply([0.0])
+
+
+class TestAggregates:
+    """Totals and frequency tables are defined under negative weights."""
+
+    @pytest.mark.parametrize(
+        "policy, expected",
+        [(ALLOW_ALL, 40.0), (CLAMP, 60.0), (SKIP, 60.0), (NO_ZERO_SKIP, 40.0)],
+    )
+    def test_total(self, policy, expected):
+        assert total(RESULT, TABLES, "income", "w", policy) == expected
+
+    def test_total_default_errors(self):
+        with pytest.raises(StatsError, match="negative"):
+            total(RESULT, TABLES, "income", "w")
+
+    @pytest.mark.parametrize(
+        "policy, expected",
+        [
+            (ALLOW_ALL, {"a": 2.0, "b": -1.0, "c": 1.0}),
+            (CLAMP, {"a": 2.0, "b": 0.0, "c": 1.0}),
+            (SKIP, {"a": 2.0, "c": 1.0}),
+            (NO_ZERO_SKIP, {"a": 2.0, "b": -1.0, "c": 1.0}),
+        ],
+    )
+    def test_crosstab(self, policy, expected):
+        assert crosstab(DATA, "region", weight_path="w", weight_policy=policy) == expected
+
+    def test_crosstab_default_errors(self):
+        with pytest.raises(StatsError, match="negative"):
+            crosstab(DATA, "region", weight_path="w")
+
+
+class TestUndefinedUnderNegative:
+    """Gini and quantiles must error if a negative weight remains."""
+
+    @pytest.mark.parametrize(
+        "stat",
+        [
+            lambda p: gini(DATA, "income", "w", weight_policy=p),
+            lambda p: weighted_quantile(DATA, "income", 0.5, "w", weight_policy=p),
+            lambda p: weighted_quantile(DATA, "income", 0.5, "w", approx=True, weight_policy=p),
+            lambda p: quantile_groups(DATA, "income", 2, "w", weight_policy=p),
+        ],
+    )
+    def test_errors_even_when_allowed(self, stat):
+        for policy in (WeightPolicy(), ALLOW_ALL, NO_ZERO_SKIP):
+            with pytest.raises(StatsError, match="negative weights"):
+                stat(policy)
+
+    @pytest.mark.parametrize("policy", [CLAMP, SKIP])
+    def test_gini_clamp_or_skip(self, policy):
+        # Both leave weights 2 on 10 and 1 on 40
+        expected = gini({"income": [10.0, 40.0], "w": [2.0, 1.0]}, "income", "w")
+        assert gini(DATA, "income", "w", weight_policy=policy) == pytest.approx(expected)
+
+    @pytest.mark.parametrize("policy", [CLAMP, SKIP])
+    def test_quantile_clamp_or_skip(self, policy):
+        assert weighted_quantile(DATA, "income", 0.5, "w", weight_policy=policy) == 10.0
+        assert weighted_quantile(DATA, "income", 0.9, "w", weight_policy=policy) == 40.0
+
+
+class TestSampling:
+    def test_default_errors(self):
+        with pytest.raises(SamplingError, match="negative"):
+            sample_rows(ROWS, 1.0)
+
+    def test_allow_negative(self):
+        rows = sample_rows(ROWS, 0.5, seed=3, weight_policy=ALLOW_ALL)
+        assert all(r["weight"] == ROWS[r["id"]]["weight"] * 2 for r in rows)
+
+    def test_clamp(self):
+        rows = sample_rows(ROWS, 1.0, weight_policy=CLAMP)
+        assert [r["weight"] for r in rows] == [2.0, 0.0, 0.0, 1.0]
+
+    def test_skip(self):
+        rows = sample_rows(ROWS, 1.0, weight_policy=NO_ZERO_SKIP)
+        assert [r["id"] for r in rows] == [0, 1, 3]